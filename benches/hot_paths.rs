@@ -0,0 +1,68 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use std::hint::black_box;
+
+use kakukuma::app::App;
+use kakukuma::canvas::{Canvas, MAX_DIMENSION};
+use kakukuma::cell::{blocks, nearest_256, Cell, Rgb};
+use kakukuma::export::{self, ColorFormat};
+use kakukuma::tools;
+
+/// A 128x128 canvas with a varied mix of blocks and colors.
+fn busy_canvas() -> Canvas {
+    let mut canvas = Canvas::new_with_size(MAX_DIMENSION, MAX_DIMENSION);
+    for y in 0..MAX_DIMENSION {
+        for x in 0..MAX_DIMENSION {
+            let fg = Rgb::new((x * 2) as u8, (y * 2) as u8, ((x + y) % 256) as u8);
+            let bg = ((x + y) % 3 == 0).then(|| Rgb::new((y * 2) as u8, 40, (x * 2) as u8));
+            let ch = blocks::ALL[(x * 7 + y) % blocks::ALL.len()];
+            canvas.set(x, y, Cell { ch, fg: Some(fg), bg });
+        }
+    }
+    canvas
+}
+
+fn bench_flood_fill(c: &mut Criterion) {
+    let canvas = Canvas::new_with_size(MAX_DIMENSION, MAX_DIMENSION);
+    c.bench_function("flood_fill 128x128", |b| {
+        b.iter(|| tools::flood_fill(black_box(&canvas), 64, 64, blocks::FULL, Some(Rgb::WHITE), None, tools::FillMode::Cell))
+    });
+}
+
+fn bench_export(c: &mut Criterion) {
+    let canvas = busy_canvas();
+    c.bench_function("to_ansi truecolor 128x128", |b| {
+        b.iter(|| export::to_ansi(black_box(&canvas), ColorFormat::TrueColor))
+    });
+    c.bench_function("to_ansi 16-color 128x128", |b| {
+        b.iter(|| export::to_ansi(black_box(&canvas), ColorFormat::Color16))
+    });
+}
+
+fn bench_nearest_color(c: &mut Criterion) {
+    let colors: Vec<Rgb> = (0..4096u32)
+        .map(|i| Rgb::new((i * 37) as u8, (i * 91) as u8, (i * 13) as u8))
+        .collect();
+    c.bench_function("nearest_256 x4096", |b| {
+        b.iter(|| colors.iter().map(|c| nearest_256(black_box(c)) as u32).sum::<u32>())
+    });
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut app = App::new();
+    app.canvas = busy_canvas();
+    let mut terminal = Terminal::new(TestBackend::new(200, 80)).unwrap();
+    c.bench_function("render full frame 200x80", |b| {
+        b.iter(|| {
+            terminal
+                .draw(|f| {
+                    black_box(kakukuma::ui::render(f, &app));
+                })
+                .unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, bench_flood_fill, bench_export, bench_nearest_color, bench_render);
+criterion_main!(benches);
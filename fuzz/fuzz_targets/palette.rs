@@ -0,0 +1,10 @@
+#![no_main]
+
+use kakukuma::palette;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = palette::load_palette_from_str(text);
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use kakukuma::export::{self, ColorFormat};
+use kakukuma::project::Project;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    // Anything that loads must also be safe to render.
+    if let Ok(project) = Project::load_from_str(text) {
+        let _ = export::to_ansi(&project.canvas, ColorFormat::Color16);
+        let _ = export::to_plain_text(&project.canvas);
+    }
+});
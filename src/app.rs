@@ -1,38 +1,120 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::canvas::{self, Canvas};
-use crate::cell::{blocks, Rgb, next_primary, next_shade};
+use crate::cell::{self, blocks, Rgb, next_primary, next_shade, shade_step};
 use crate::export::{self, ColorFormat};
-use crate::history::{CellMutation, History};
-use crate::project::Project;
+use crate::history::{self, Action, CellMutation, History};
+use crate::import;
+use crate::journal;
+use crate::keymap::{HelpSection, Keymap};
+use crate::listing::ListView;
+use crate::net;
+use crate::project::{self, Project};
+use crate::selection::Selection;
+use crate::session::SessionLog;
+use crate::settings::Settings;
 use crate::symmetry::{self, SymmetryMode};
 use crate::palette::{self, HueGroup, PaletteItem, PaletteSection};
 use crate::theme::{Theme, THEMES};
-use crate::tools::{self, ToolKind, ToolState};
+use crate::update;
+use crate::tools::{self, BrushPattern, FillMode, LineStyle, ToolKind, ToolState};
+use crate::trash;
+use crate::versions::{self, Version};
+
+/// The line an axis-locked stroke is held to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StrokeAxis {
+    Row,
+    Column,
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum AppMode {
     Normal,
     ExportDialog,
     FileDialog,
+    FileRename,
+    FileDeleteConfirm,
     SaveAs,
     ExportFile,
     Help,
     Quitting,
+    RevertConfirm,
     Recovery,
     ColorSliders,
     PaletteDialog,
     PaletteNameInput,
+    LospecSlugInput,
+    ReleaseNotes,
     PaletteRename,
     PaletteExport,
     NewCanvas,
     HexColorInput,
     BlockPicker,
+    RunInput,
+    RectSizeInput,
+    ImportDialog,
+    SymmetryPicker,
+    HistoryScrubber,
+    Versions,
+    Inspector,
+    ResizeCanvas,
+}
+
+/// How long a status message stays visible.
+const STATUS_DURATION: Duration = Duration::from_secs(3);
+/// How long the canvas may stay dirty before an autosave.
+const AUTO_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the spray tool paints while the mouse is held still.
+const SPRAY_INTERVAL: Duration = Duration::from_millis(100);
+/// Shortest time between two versions taken at autosaves.
+const VERSION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Screen rows per canvas row; zoom 4 draws each cell two rows tall.
+fn rows_per_cell(zoom: u8) -> usize {
+    if zoom == 4 { 2 } else { 1 }
+}
+
+/// Event loop timings, shown in the debug overlay.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PerfStats {
+    /// How long the last frame took to render
+    pub render: Duration,
+    /// How long the last batch of input took to handle
+    pub events: Duration,
 }
 
 pub struct StatusMessage {
     pub text: String,
-    pub ticks_remaining: u16,
+    pub expires_at: Instant,
+}
+
+/// A file-level operation that can be reverted with undo.
+pub enum FileOp {
+    /// A palette file was moved to the trash.
+    PaletteDeleted { original: PathBuf, trashed: PathBuf },
+    /// A project file and its operation log were moved to the trash, as
+    /// (original, trashed) pairs. `was_open` is the project path of the open
+    /// document, if it was the deleted file.
+    ProjectDeleted { files: Vec<(PathBuf, PathBuf)>, was_open: Option<String> },
+    /// The document that "New Canvas" replaced.
+    NewCanvas(Box<Document>),
+    /// The custom palette before marked colors were removed from it.
+    PaletteColors(palette::CustomPalette),
+}
+
+/// A project's canvas, undo history and file, while another one is being
+/// edited: in another tab, or replaced by New Canvas.
+pub struct Document {
+    pub canvas: Canvas,
+    pub history: History,
+    pub project_name: Option<String>,
+    pub project_path: Option<String>,
+    pub dirty: bool,
+    pub last_saved: Option<SystemTime>,
 }
 
 pub struct PaletteSectionState {
@@ -57,10 +139,29 @@ pub struct App {
     pub project_name: Option<String>,
     pub project_path: Option<String>,
     pub filled_rect: bool,
+    pub fill_mode: FillMode,
+    pub line_style: LineStyle,
+    pub brush_pattern: BrushPattern,
     // File dialog state
     pub file_dialog_files: Vec<String>,
     pub file_dialog_selected: usize,
-    // Export dialog state: 0=PlainText, 1=ANSI
+    pub file_dialog_view: ListView,
+    // Headers of files highlighted in the open dialog
+    pub file_dialog_info: RefCell<project::InfoCache>,
+    // Import dialog state: images in the working directory
+    pub import_dialog_files: Vec<String>,
+    pub import_dialog_selected: usize,
+    pub import_dither: bool,
+    // History scrubber: the history position it was opened at, and cells
+    // changed while stepping, for the journal
+    pub scrub_start: usize,
+    pub scrub_cells: Vec<(usize, usize)>,
+    // Symmetry picker: index into SymmetryMode::ALL
+    pub symmetry_picker_selected: usize,
+    // Versions browser: snapshots taken at autosaves, newest first
+    pub versions: Vec<Version>,
+    pub versions_selected: usize,
+    // Export dialog state: 0=PlainText, 1=ANSI, 2=HTML, 3=Markdown, 4=Markdown with HTML
     pub export_format: usize,
     // Export dialog state: 0=Clipboard, 1=File
     pub export_dest: usize,
@@ -68,18 +169,40 @@ pub struct App {
     pub export_cursor: usize,
     // Export color format: 0=24bit, 1=256, 2=16 (only used when ANSI)
     pub export_color_format: usize,
+    // Plain exports write each column twice, keeping square art square
+    pub export_double_width: bool,
+    // Ordered dithering for 256- and 16-color ANSI exports
+    pub export_dither: bool,
+    // How transparent cells are written in ANSI exports
+    pub export_ansi: export::AnsiOptions,
+    // Color count against the export depth, once asked for with A
+    pub export_analysis: Option<export::DepthReport>,
+    // The export file prompt writes an animation of the undo history
+    pub export_gif: bool,
     // Shared text input for SaveAs and ExportFile modes
     pub text_input: String,
-    // Auto-save tick counter (increments each tick, resets on save)
-    pub auto_save_ticks: u16,
-    // Path of autosave file found on startup
+    // Start of the current autosave interval (reset on save, and while clean)
+    pub auto_save_since: Instant,
+    // When the project was last saved by hand (or modified on disk, when
+    // opened) and last autosaved, for the header
+    pub last_saved: Option<SystemTime>,
+    pub last_autosave: Option<SystemTime>,
+    // When this session last added to the versions file
+    last_version: Option<Instant>,
+    // Path of autosave file or journal found on startup
     pub recovery_path: Option<String>,
+    // Crash-recovery journal: off until the TUI turns it on; `journal` is
+    // None until the first change after a save, load or autosave
+    pub journaling: bool,
+    pub journal: Option<journal::Journal>,
     // Recent colors (auto-tracked, last 8 unique)
     pub recent_colors: Vec<Rgb>,
     // Palette browser state
     pub hue_groups: Vec<HueGroup>,
     pub palette_scroll: usize,
     pub palette_cursor: usize,
+    // Page of the Colors box shown while the palette cursor is elsewhere
+    pub colors_page: usize,
     // HSL slider state
     pub slider_h: u16,
     pub slider_s: u8,
@@ -87,12 +210,28 @@ pub struct App {
     pub slider_active: u8, // 0=H, 1=S, 2=L
     // Custom palette state
     pub custom_palette: Option<palette::CustomPalette>,
+    // Indices of custom palette colors marked for a group delete or move
+    pub palette_marks: BTreeSet<usize>,
     pub palette_dialog_files: Vec<String>,
     pub palette_dialog_selected: usize,
+    pub palette_dialog_view: ListView,
+    // Palette dialog is showing the trash instead of the working directory
+    pub palette_dialog_trash: bool,
+    /// A Lospec palette being downloaded
+    pub palette_download: Option<net::Pending<Result<palette::CustomPalette, String>>>,
+    /// The startup check for a newer release, while it runs
+    pub update_check: Option<net::Pending<Result<Option<update::Release>, String>>>,
+    /// A newer release found at startup, and how far its notes are scrolled
+    pub available_update: Option<update::Release>,
+    pub release_notes_scroll: u16,
+    // Swatch strips for the palette dialog, filled in as rows are drawn
+    pub palette_swatches: RefCell<palette::SwatchCache>,
     // Active block character for drawing
     pub active_block: char,
     // Palette section collapse state
     pub palette_sections: PaletteSectionState,
+    // Sort section colors by saturation and lightness instead of index
+    pub palette_perceptual: bool,
     // Flattened palette layout for cursor navigation
     pub palette_layout: Vec<PaletteItem>,
     // Theme index (0=Warm, 1=Neon, 2=Dark)
@@ -101,6 +240,17 @@ pub struct App {
     pub new_canvas_width: usize,
     pub new_canvas_height: usize,
     pub new_canvas_cursor: u8, // 0=width, 1=height
+    // Resize dialog: target size, where the art lands, and the focused row
+    // (0=width, 1=height, 2=anchor)
+    pub resize_width: usize,
+    pub resize_height: usize,
+    pub resize_anchor: canvas::Anchor,
+    pub resize_cursor: u8,
+    // Cell inspector: the cell being edited, its character, fg and bg as
+    // typed, and which of them has focus
+    pub inspector_pos: (usize, usize),
+    pub inspector_fields: [String; 3],
+    pub inspector_field: usize,
     // Keyboard canvas cursor
     pub canvas_cursor: (usize, usize),
     pub canvas_cursor_active: bool,
@@ -109,9 +259,72 @@ pub struct App {
     pub viewport_y: usize,
     pub viewport_w: usize,
     pub viewport_h: usize,
+    /// Draw a minimap over the canvas when it doesn't all fit in view
+    pub show_minimap: bool,
+    /// Surround the canvas with copies of itself, to check that it tiles
+    pub tile_preview: bool,
+    /// Middle-button grab: screen position and viewport offset at the press
+    pub view_grab: Option<(u16, u16, usize, usize)>,
     // Block picker dialog cursor
     pub block_picker_row: usize,
     pub block_picker_col: usize,
+    // Last undoable file operation (cleared by the next canvas edit)
+    pub last_file_op: Option<FileOp>,
+    // Projects open in the other tabs, in tab order; the one being edited
+    // sits at `active_document` between them
+    pub documents: Vec<Document>,
+    pub active_document: usize,
+    // Opt-in command recording (--record-session)
+    pub session_log: Option<SessionLog>,
+    // Normal-mode key bindings, including the user's remaps
+    pub keymap: Keymap,
+    // Help overlay: first visible line, search text, and rows shown at the
+    // last render (for paging)
+    pub help_scroll: usize,
+    pub help_query: String,
+    pub help_searching: bool,
+    pub help_page: Cell<usize>,
+    // Colors the terminal can display; the editor quantizes to match
+    pub color_support: ColorFormat,
+    // Screen reader mode (--screen-reader): plain glyphs, a non-color cursor,
+    // and state changes kept on the status line until the next one
+    pub screen_reader: bool,
+    pub announcement: Option<String>,
+    // Monochrome mode (--monochrome or NO_COLOR): state is marked with
+    // characters as well as color
+    pub monochrome: bool,
+    // Safe mode (--safe): no autosave, clipboard or theme changes, and the
+    // user's settings and key map are ignored
+    pub safe_mode: bool,
+    // User preferences from settings.json
+    pub settings: Settings,
+    // Axis lock: strokes stay on the row or column they started on, chosen
+    // by the first move away from `stroke_origin`
+    pub axis_lock: bool,
+    pub stroke_origin: Option<(usize, usize)>,
+    // Pixel-perfect pencil: L-shaped corners are taken out of each stroke
+    // when it ends, using the points it passed through
+    pub pixel_perfect: bool,
+    pub stroke_path: Vec<(usize, usize)>,
+    pub stroke_axis: Option<StrokeAxis>,
+    // Alt+click: the current click or stroke ignores symmetry
+    pub unmirrored: bool,
+    // Cells targeted by bulk edits (shade, recolor, erase)
+    pub selection: Option<Selection>,
+    // Cells copied from a selection, relative to its top-left corner; kept
+    // across tabs so they can be pasted into another project
+    pub copied_cells: Vec<(usize, usize, cell::Cell)>,
+    // Whether the editor receives mouse events; off lets the terminal
+    // select text. The event loop applies changes to the terminal.
+    pub mouse_capture: bool,
+    // Set by the Suspend command; the event loop stops the process
+    pub suspend_requested: bool,
+    // Debug overlay (F12) and the timings it shows
+    pub debug_overlay: bool,
+    pub perf: PerfStats,
+    // Spray tool randomness, and when it last painted
+    pub spray_rng: tools::Scatter,
+    pub last_spray: Instant,
 }
 
 impl App {
@@ -132,27 +345,60 @@ impl App {
             project_name: None,
             project_path: None,
             filled_rect: false,
+            fill_mode: FillMode::default(),
+            line_style: LineStyle::default(),
+            brush_pattern: BrushPattern::default(),
             file_dialog_files: Vec::new(),
             file_dialog_selected: 0,
+            file_dialog_view: ListView::default(),
+            file_dialog_info: RefCell::new(project::InfoCache::default()),
+            import_dialog_files: Vec::new(),
+            import_dialog_selected: 0,
+            import_dither: false,
+            symmetry_picker_selected: 0,
+            versions: Vec::new(),
+            versions_selected: 0,
+            scrub_start: 0,
+            scrub_cells: Vec::new(),
             export_format: 0,
             export_dest: 0,
             export_cursor: 0,
             export_color_format: 0,
+            export_double_width: false,
+            export_dither: false,
+            export_gif: false,
+            export_ansi: export::AnsiOptions::default(),
+            export_analysis: None,
             text_input: String::new(),
-            auto_save_ticks: 0,
+            auto_save_since: Instant::now(),
+            last_saved: None,
+            last_autosave: None,
+            last_version: None,
             recovery_path: None,
+            journaling: false,
+            journal: None,
             recent_colors: Vec::new(),
             hue_groups: palette::build_hue_groups(),
             palette_scroll: 0,
             palette_cursor: 0,
+            colors_page: 0,
             slider_h: 0,
             slider_s: 0,
             slider_l: 50,
             slider_active: 0,
             custom_palette: None,
+            palette_marks: BTreeSet::new(),
             palette_dialog_files: Vec::new(),
             palette_dialog_selected: 0,
+            palette_dialog_view: ListView::default(),
+            palette_dialog_trash: false,
+            palette_download: None,
+            update_check: None,
+            available_update: None,
+            release_notes_scroll: 0,
+            palette_swatches: RefCell::new(palette::SwatchCache::default()),
             active_block: blocks::FULL,
+            palette_perceptual: true,
             palette_sections: PaletteSectionState {
                 standard_expanded: false,
                 hue_expanded: false,
@@ -163,14 +409,53 @@ impl App {
             new_canvas_width: canvas::DEFAULT_WIDTH,
             new_canvas_height: canvas::DEFAULT_HEIGHT,
             new_canvas_cursor: 0,
+            resize_width: canvas::DEFAULT_WIDTH,
+            resize_height: canvas::DEFAULT_HEIGHT,
+            resize_anchor: canvas::Anchor::default(),
+            resize_cursor: 0,
+            inspector_pos: (0, 0),
+            inspector_fields: Default::default(),
+            inspector_field: 0,
             canvas_cursor: (0, 0),
             canvas_cursor_active: false,
             viewport_x: 0,
             viewport_y: 0,
             viewport_w: 48,
             viewport_h: 32,
+            show_minimap: false,
+            tile_preview: false,
+            view_grab: None,
             block_picker_row: 0,
             block_picker_col: 0,
+            last_file_op: None,
+            documents: Vec::new(),
+            active_document: 0,
+            session_log: None,
+            keymap: Keymap::default(),
+            help_scroll: 0,
+            help_query: String::new(),
+            help_searching: false,
+            help_page: Cell::new(0),
+            color_support: ColorFormat::TrueColor,
+            screen_reader: false,
+            announcement: None,
+            monochrome: false,
+            safe_mode: false,
+            settings: Settings::default(),
+            axis_lock: false,
+            stroke_origin: None,
+            pixel_perfect: false,
+            stroke_path: Vec::new(),
+            stroke_axis: None,
+            unmirrored: false,
+            selection: None,
+            copied_cells: Vec::new(),
+            mouse_capture: true,
+            suspend_requested: false,
+            debug_overlay: false,
+            perf: PerfStats::default(),
+            spray_rng: tools::Scatter::from_time(),
+            last_spray: Instant::now(),
         };
         app.rebuild_palette_layout();
         app
@@ -192,40 +477,70 @@ impl App {
             }
         }
 
+        let perceptual = self.palette_perceptual;
+        let push_colors = |layout: &mut Vec<PaletteItem>, mut colors: Vec<Rgb>| {
+            if perceptual {
+                palette::sort_perceptual(&mut colors);
+            }
+            layout.extend(colors.into_iter().map(PaletteItem::Color));
+        };
+
         // Standard 16 section
         layout.push(PaletteItem::SectionHeader(PaletteSection::Standard));
         if self.palette_sections.standard_expanded {
-            for i in 0..16u8 {
-                layout.push(PaletteItem::Color(crate::cell::color256_to_rgb(i)));
-            }
+            push_colors(&mut layout, (0..16u8).map(crate::cell::color256_to_rgb).collect());
         }
 
         // Hue Groups section
         layout.push(PaletteItem::SectionHeader(PaletteSection::HueGroups));
         if self.palette_sections.hue_expanded {
             for group in &self.hue_groups {
-                for &c in &group.colors {
-                    layout.push(PaletteItem::Color(c));
-                }
+                push_colors(&mut layout, group.colors.clone());
             }
         }
 
         // Grayscale section
         layout.push(PaletteItem::SectionHeader(PaletteSection::Grayscale));
         if self.palette_sections.grayscale_expanded {
-            for i in 232..=255u8 {
-                layout.push(PaletteItem::Color(crate::cell::color256_to_rgb(i)));
-            }
+            push_colors(&mut layout, (232..=255u8).map(crate::cell::color256_to_rgb).collect());
         }
 
         self.palette_layout = layout;
     }
 
+    /// Terminal color for `color`, quantized to what the terminal supports.
+    pub fn display_color(&self, color: Rgb) -> ratatui::style::Color {
+        color.to_ratatui_as(self.color_support)
+    }
+
+    /// Warn when the canvas holds colors the terminal can only approximate.
+    pub fn check_color_support(&mut self) {
+        let format = self.color_support;
+        let approximated = (0..self.canvas.height).any(|y| {
+            (0..self.canvas.width).any(|x| {
+                self.canvas.get(x, y).is_some_and(|cell| {
+                    !cell.is_empty()
+                        && [cell.fg, cell.bg].iter().flatten().any(|c| !c.is_exact_in(format))
+                })
+            })
+        });
+        if approximated {
+            self.set_status(&format!(
+                "{} terminal: some canvas colors are approximated",
+                crate::terminal::color_support_label(format)
+            ));
+        }
+    }
+
     pub fn theme(&self) -> &Theme {
         &THEMES[self.theme_index]
     }
 
     pub fn cycle_theme(&mut self) {
+        if self.safe_mode {
+            self.set_status("Themes are off in safe mode");
+            return;
+        }
         self.theme_index = (self.theme_index + 1) % THEMES.len();
         self.set_status(&format!("Theme: {}", self.theme().name));
     }
@@ -239,6 +554,54 @@ impl App {
         self.set_status(&format!("Zoom: {}x", self.zoom));
     }
 
+    /// Step the zoom level in (positive) or out, without wrapping, and scroll
+    /// so canvas cell (x, y) stays `column` and `row` screen cells into the
+    /// canvas.
+    pub fn zoom_at(&mut self, x: usize, y: usize, column: u16, row: u16, step: isize) {
+        const LEVELS: [u8; 3] = [1, 2, 4];
+        let level = LEVELS.iter().position(|&z| z == self.zoom).unwrap_or(0);
+        let zoom = LEVELS[(level as isize + step).clamp(0, LEVELS.len() as isize - 1) as usize];
+        if zoom == self.zoom {
+            return;
+        }
+        let screen_w = self.viewport_w * self.zoom as usize;
+        let screen_h = self.viewport_h * rows_per_cell(self.zoom);
+        self.zoom = zoom;
+        self.viewport_w = screen_w / zoom as usize;
+        self.viewport_h = screen_h / rows_per_cell(zoom);
+        let left = x as isize - (column / zoom as u16) as isize;
+        let top = y as isize - (row as usize / rows_per_cell(zoom)) as isize;
+        self.scroll_view_to(left, top);
+        self.set_status(&format!("Zoom: {}x", self.zoom));
+    }
+
+    /// Turn the tile preview on or off. It only shows while the whole canvas
+    /// is in view, so turning it on zooms out until the canvas fits.
+    pub fn toggle_tile_preview(&mut self) {
+        self.tile_preview = !self.tile_preview;
+        if !self.tile_preview {
+            self.set_status("Tile preview: Off");
+            return;
+        }
+        let screen_w = self.viewport_w * self.zoom as usize;
+        let screen_h = self.viewport_h * rows_per_cell(self.zoom);
+        let fits = [4, 2, 1].into_iter().filter(|&z| z <= self.zoom).find(|&z| {
+            self.canvas.width <= screen_w / z as usize && self.canvas.height <= screen_h / rows_per_cell(z)
+        });
+        let Some(zoom) = fits else {
+            self.set_status("Tile preview: On, once the whole canvas fits in the window");
+            return;
+        };
+        if zoom != self.zoom {
+            self.zoom = zoom;
+            self.viewport_w = screen_w / zoom as usize;
+            self.viewport_h = screen_h / rows_per_cell(zoom);
+        }
+        self.viewport_x = 0;
+        self.viewport_y = 0;
+        self.set_status(&format!("Tile preview: On (zoom {}x)", self.zoom));
+    }
+
     /// Returns the effective cursor position: keyboard canvas cursor if active,
     /// otherwise the mouse hover cursor.
     pub fn effective_cursor(&self) -> Option<(usize, usize)> {
@@ -264,6 +627,102 @@ impl App {
         }
     }
 
+    /// Scroll the view by (dx, dy) canvas cells, stopping at the canvas
+    /// edges. The keyboard cursor stays put, even if it scrolls out of view.
+    pub fn pan(&mut self, dx: isize, dy: isize) {
+        self.scroll_view_to(self.viewport_x as isize + dx, self.viewport_y as isize + dy);
+    }
+
+    /// Move the view with a middle-button drag, so the grabbed canvas point
+    /// follows the pointer.
+    pub fn drag_view(&mut self, column: u16, row: u16) {
+        let Some((grab_col, grab_row, vx, vy)) = self.view_grab else {
+            return;
+        };
+        let zoom = self.zoom as isize;
+        let rows_per_cell = if self.zoom == 4 { 2 } else { 1 };
+        let dx = (grab_col as isize - column as isize) / zoom;
+        let dy = (grab_row as isize - row as isize) / rows_per_cell;
+        self.scroll_view_to(vx as isize + dx, vy as isize + dy);
+    }
+
+    /// Scroll the view to put canvas cell (x, y) in the middle.
+    pub fn center_view(&mut self, x: usize, y: usize) {
+        self.scroll_view_to(
+            x as isize - (self.viewport_w / 2) as isize,
+            y as isize - (self.viewport_h / 2) as isize,
+        );
+    }
+
+    fn scroll_view_to(&mut self, x: isize, y: isize) {
+        let max_x = self.canvas.width.saturating_sub(self.viewport_w) as isize;
+        let max_y = self.canvas.height.saturating_sub(self.viewport_h) as isize;
+        self.viewport_x = x.clamp(0, max_x) as usize;
+        self.viewport_y = y.clamp(0, max_y) as usize;
+    }
+
+    /// Number of colors in the Colors box (the curated or custom palette).
+    pub fn colors_count(&self) -> usize {
+        self.palette_layout
+            .iter()
+            .take_while(|item| matches!(item, PaletteItem::Color(_)))
+            .count()
+    }
+
+    pub fn colors_page_count(&self) -> usize {
+        self.colors_count().div_ceil(palette::COLORS_PER_PAGE).max(1)
+    }
+
+    /// The page of the Colors box on screen: the one holding the palette
+    /// cursor, or the last one shown if the cursor is in the sections below.
+    pub fn colors_page(&self) -> usize {
+        if self.palette_cursor < self.colors_count() {
+            self.palette_cursor / palette::COLORS_PER_PAGE
+        } else {
+            self.colors_page.min(self.colors_page_count() - 1)
+        }
+    }
+
+    /// Show another page of the Colors box. A palette cursor in the box
+    /// moves to the first color of the new page.
+    pub fn flip_colors_page(&mut self, delta: isize) {
+        let pages = self.colors_page_count();
+        if pages == 1 {
+            return;
+        }
+        let page = self.colors_page().saturating_add_signed(delta).min(pages - 1);
+        if self.palette_cursor < self.colors_count() {
+            self.palette_cursor = page * palette::COLORS_PER_PAGE;
+            if let Some(PaletteItem::Color(color)) = self.palette_layout.get(self.palette_cursor) {
+                self.color = *color;
+            }
+        }
+        self.colors_page = page;
+        self.set_status(&format!("Colors page {}/{}", page + 1, pages));
+    }
+
+    /// Switch the palette sections between index and perceptual order,
+    /// keeping the palette cursor on the same color.
+    pub fn toggle_palette_order(&mut self) {
+        // A color can appear in more than one section; find the same occurrence
+        let current = self.palette_layout.get(self.palette_cursor).copied();
+        let occurrence = self.palette_layout[..self.palette_cursor.min(self.palette_layout.len())]
+            .iter()
+            .filter(|&&it| Some(it) == current)
+            .count();
+        self.palette_perceptual = !self.palette_perceptual;
+        self.rebuild_palette_layout();
+        let found = self.palette_layout.iter().enumerate().filter(|&(_, &it)| Some(it) == current).nth(occurrence);
+        if let Some((i, _)) = found {
+            self.palette_cursor = i;
+        }
+        self.set_status(if self.palette_perceptual {
+            "Palette order: Perceptual"
+        } else {
+            "Palette order: Index"
+        });
+    }
+
     /// Quick-pick the Nth curated palette color (0-indexed).
     /// Returns true if a color was picked.
     pub fn quick_pick_color(&mut self, n: usize) -> bool {
@@ -287,20 +746,83 @@ impl App {
     pub fn set_status(&mut self, msg: &str) {
         self.status_message = Some(StatusMessage {
             text: msg.to_string(),
-            ticks_remaining: 30, // ~3 seconds at 10 ticks/sec
+            expires_at: Instant::now() + STATUS_DURATION,
         });
+        self.announcement = Some(msg.to_string());
+    }
+
+    /// Whether the cursor, symmetry axes and tool previews are drawn with
+    /// marker characters rather than by color alone.
+    pub fn char_markers(&self) -> bool {
+        self.monochrome || self.screen_reader
+    }
+
+    /// Announce tool and color changes that the rest of the UI only shows
+    /// visually. Only used in screen reader mode.
+    pub fn announce_changes(&mut self, tool: ToolKind, color: Rgb) {
+        if self.active_tool != tool {
+            self.set_status(&format!("Tool: {}", self.active_tool.name()));
+        } else if self.color != color {
+            self.set_status(&format!("Color: {}", self.color.name()));
+        }
+    }
+
+    /// The single plain-text status line shown in screen reader mode: the
+    /// last announcement followed by the current tool, color and cursor.
+    pub fn screen_reader_status(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(ref text) = self.announcement {
+            parts.push(text.clone());
+        }
+        parts.push(format!("Tool {}", self.active_tool.name()));
+        parts.push(format!("Color {}", self.color.name()));
+        parts.push(format!("Block {}", self.active_block));
+        if let Some((x, y)) = self.effective_cursor() {
+            parts.push(format!("Cursor {},{}", x, y));
+        }
+        parts.join(". ")
+    }
+
+    /// "saved 2m ago", plus when autosave last ran if that's more recent.
+    pub fn save_indicator(&self, now: SystemTime) -> Option<String> {
+        let ago = |t: SystemTime| format_age(now.duration_since(t).unwrap_or_default());
+        match (self.shown_autosave(), self.last_saved) {
+            (Some(a), Some(s)) => Some(format!("autosaved {}, saved {}", ago(a), ago(s))),
+            (Some(a), None) => Some(format!("autosaved {}", ago(a))),
+            (None, Some(s)) => Some(format!("saved {}", ago(s))),
+            (None, None) => None,
+        }
     }
 
     pub fn tick_status(&mut self) {
-        if let Some(ref mut msg) = self.status_message {
-            if msg.ticks_remaining > 0 {
-                msg.ticks_remaining -= 1;
-            } else {
+        if let Some(ref msg) = self.status_message {
+            if Instant::now() >= msg.expires_at {
                 self.status_message = None;
             }
         }
     }
 
+    /// The autosave time the save indicator shows: only one newer than the
+    /// last save.
+    fn shown_autosave(&self) -> Option<SystemTime> {
+        self.last_autosave.filter(|&a| self.last_saved.is_none_or(|s| a > s))
+    }
+
+    /// The next moment a timer needs servicing, if any is pending. That
+    /// includes the save indicator's text going stale, e.g. "45s ago"
+    /// becoming "46s ago".
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let status = self.status_message.as_ref().map(|m| m.expires_at);
+        let auto_save = (self.dirty && !self.safe_mode).then(|| self.auto_save_since + AUTO_SAVE_INTERVAL);
+        let now = SystemTime::now();
+        let indicator = [self.shown_autosave(), self.last_saved]
+            .into_iter()
+            .flatten()
+            .map(|t| Instant::now() + age_changes_in(now.duration_since(t).unwrap_or_default()))
+            .min();
+        [status, auto_save, indicator].into_iter().flatten().min()
+    }
+
     /// Ensure palette_scroll keeps the cursor visible in a given viewport height.
     pub fn ensure_palette_cursor_visible(&mut self, viewport_height: usize) {
         // Approximate: each color row holds COLS=6 items, plus section headers.
@@ -358,19 +880,92 @@ impl App {
         self.recent_colors.truncate(8);
     }
 
+    /// Constrain a drag point to the stroke's row or column. The axis is
+    /// fixed by the first point that leaves the starting cell.
+    pub fn lock_to_axis(&mut self, x: usize, y: usize) -> (usize, usize) {
+        let Some((ox, oy)) = self.stroke_origin else {
+            return (x, y);
+        };
+        let axis = match self.stroke_axis {
+            Some(axis) => axis,
+            None if (x, y) == (ox, oy) => return (x, y),
+            None => {
+                let axis = if x.abs_diff(ox) >= y.abs_diff(oy) { StrokeAxis::Row } else { StrokeAxis::Column };
+                self.stroke_axis = Some(axis);
+                axis
+            }
+        };
+        match axis {
+            StrokeAxis::Row => (x, oy),
+            StrokeAxis::Column => (ox, y),
+        }
+    }
+
+    /// What the pencil would change if clicked at the cursor, including
+    /// symmetry mirrors. Empty unless the pencil is idle over the canvas.
+    pub fn pencil_ghost(&self) -> Vec<CellMutation> {
+        if !self.settings.ghost_preview
+            || self.active_tool != ToolKind::Pencil
+            || self.mode != AppMode::Normal
+            || self.history.is_stroke_active()
+        {
+            return Vec::new();
+        }
+        let Some((x, y)) = self.effective_cursor() else {
+            return Vec::new();
+        };
+        if !self.brush_pattern.covers(x, y) {
+            return Vec::new();
+        }
+        let mutations = tools::pencil(&self.canvas, x, y, self.active_block, Some(self.color), None);
+        symmetry::apply_symmetry(mutations, self.symmetry, self.canvas.width, self.canvas.height)
+    }
+
+    /// Keep spraying while the mouse is held still. Call each event loop
+    /// iteration; returns whether anything was painted.
+    pub fn tick_spray(&mut self) -> bool {
+        if self.active_tool != ToolKind::Spray || !self.history.is_stroke_active() {
+            return false;
+        }
+        let now = Instant::now();
+        if now.duration_since(self.last_spray) < SPRAY_INTERVAL {
+            return false;
+        }
+        self.last_spray = now;
+        match self.cursor {
+            Some((x, y)) => {
+                self.apply_tool(x, y);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Apply a tool action at (x, y), handling symmetry and history.
     pub fn apply_tool(&mut self, x: usize, y: usize) {
         let fg = Some(self.color);
         let bg = None;
         let mutations = match self.active_tool {
+            ToolKind::Pencil if !self.brush_pattern.covers(x, y) => Vec::new(),
             ToolKind::Pencil => {
                 self.track_recent_color(self.color);
+                if self.history.is_stroke_active() {
+                    self.stroke_path.push((x, y));
+                }
                 tools::pencil(&self.canvas, x, y, self.active_block, fg, bg)
             }
             ToolKind::Eraser => tools::eraser(&self.canvas, x, y),
+            ToolKind::Spray => {
+                self.track_recent_color(self.color);
+                self.last_spray = Instant::now();
+                let points = tools::spray_points(
+                    x, y, self.settings.spray_radius, self.settings.spray_density, &mut self.spray_rng,
+                );
+                tools::plot(&self.canvas, &points, self.active_block, fg, bg)
+            }
             ToolKind::Fill => {
                 self.track_recent_color(self.color);
-                tools::flood_fill(&self.canvas, x, y, self.active_block, fg, bg)
+                tools::flood_fill(&self.canvas, x, y, self.active_block, fg, bg, self.fill_mode)
             }
             ToolKind::Eyedropper => {
                 if let Some((picked_fg, _bg, ch)) = tools::eyedropper(&self.canvas, x, y) {
@@ -395,7 +990,7 @@ impl App {
                     ToolState::LineStart { x: x0, y: y0 } => {
                         self.tool_state = ToolState::Idle;
                         self.track_recent_color(self.color);
-                        tools::line(&self.canvas, x0, y0, x, y, self.active_block, fg, bg)
+                        self.line_mutations(x0, y0, x, y)
                     }
                     _ => return,
                 }
@@ -410,18 +1005,66 @@ impl App {
                     ToolState::RectStart { x: x0, y: y0 } => {
                         self.tool_state = ToolState::Idle;
                         self.track_recent_color(self.color);
-                        tools::rectangle(
-                            &self.canvas, x0, y0, x, y, self.active_block, fg, bg,
-                            self.filled_rect,
-                        )
+                        self.rect_mutations(x0, y0, x, y)
                     }
                     _ => return,
                 }
             }
         };
 
+        self.commit_mutations(self.active_tool.name(), mutations);
+    }
+
+    /// Mask a line or outline path with the active line style.
+    fn styled(&self, points: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        match self.settings.pattern(self.line_style) {
+            Some(pattern) => pattern.mask(points),
+            None => points,
+        }
+    }
+
+    fn line_mutations(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> Vec<CellMutation> {
+        let points = self.styled(tools::bresenham_line(x0, y0, x1, y1));
+        tools::plot(&self.canvas, &points, self.active_block, Some(self.color), None)
+    }
+
+    fn rect_mutations(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> Vec<CellMutation> {
+        if self.filled_rect || self.line_style == LineStyle::Solid {
+            return tools::rectangle(
+                &self.canvas, x0, y0, x1, y1, self.active_block, Some(self.color), None,
+                self.filled_rect,
+            );
+        }
+        let points = self.styled(tools::rect_outline_points(x0, y0, x1, y1));
+        tools::plot(&self.canvas, &points, self.active_block, Some(self.color), None)
+    }
+
+    /// Cells the line or rectangle in progress would cover if finished at
+    /// the cursor (outline only for rectangles), mirrors included.
+    pub fn tool_preview_points(&self) -> Vec<(usize, usize)> {
+        let Some((cx, cy)) = self.effective_cursor() else {
+            return Vec::new();
+        };
+        let points = match self.tool_state {
+            ToolState::LineStart { x, y } => self.styled(tools::bresenham_line(x, y, cx, cy)),
+            ToolState::RectStart { x, y } if self.filled_rect => tools::rect_outline_points(x, y, cx, cy),
+            ToolState::RectStart { x, y } => self.styled(tools::rect_outline_points(x, y, cx, cy)),
+            ToolState::Idle => return Vec::new(),
+        };
+        symmetry::mirror_points(points, self.symmetry, self.canvas.width, self.canvas.height)
+    }
+
+    /// Mirror, apply and record a tool's mutations.
+    fn commit_mutations(&mut self, label: &str, mutations: Vec<CellMutation>) {
         // Apply symmetry
-        let mutations = symmetry::apply_symmetry(mutations, self.symmetry, self.canvas.width, self.canvas.height);
+        let mode = if self.unmirrored { SymmetryMode::Off } else { self.symmetry };
+        let mutations = symmetry::apply_symmetry(mutations, mode, self.canvas.width, self.canvas.height);
+        self.record_mutations(label, mutations);
+    }
+
+    /// Apply and record mutations as given, without symmetry, as one undo
+    /// step (or as part of the stroke in progress).
+    fn record_mutations(&mut self, label: &str, mutations: Vec<CellMutation>) {
 
         if mutations.is_empty() {
             return;
@@ -447,154 +1090,1019 @@ impl App {
         for m in &mutations {
             self.canvas.set(m.x, m.y, m.new);
         }
+        self.journal_cells(mutations.iter().map(|m| (m.x, m.y)).collect());
 
         // Record in history
-        for m in mutations {
-            self.history.push_mutation(m);
-        }
+        self.history.transaction(label, |history| {
+            for m in mutations {
+                history.push_mutation(m);
+            }
+        });
 
+        self.last_file_op = None;
         self.dirty = true;
     }
 
-    pub fn begin_stroke(&mut self) {
-        self.history.begin_stroke();
+    /// Paint `count` cells in a straight run from the keyboard cursor with the
+    /// active block and color, as one undo step. The run stops at the canvas
+    /// edge and the cursor ends on its last cell.
+    pub fn paint_run(&mut self, count: usize, dx: isize, dy: isize) {
+        if count == 0 {
+            return;
+        }
+        let (x0, y0) = self.canvas_cursor;
+        // No run is longer than the canvas, so larger counts can't overflow
+        let count = count.min(self.canvas.width.max(self.canvas.height));
+        let steps = count as isize - 1;
+        let max_x = self.canvas.width.saturating_sub(1) as isize;
+        let max_y = self.canvas.height.saturating_sub(1) as isize;
+        let x1 = (x0 as isize).saturating_add(dx.saturating_mul(steps)).clamp(0, max_x) as usize;
+        let y1 = (y0 as isize).saturating_add(dy.saturating_mul(steps)).clamp(0, max_y) as usize;
+
+        self.track_recent_color(self.color);
+        let mutations = tools::line(&self.canvas, x0, y0, x1, y1, self.active_block, Some(self.color), None);
+        self.commit_mutations("Run", mutations);
+
+        self.canvas_cursor = (x1, y1);
+        self.canvas_cursor_active = true;
+        self.ensure_cursor_in_viewport(x1, y1, self.viewport_w, self.viewport_h);
+        let painted = x0.abs_diff(x1).max(y0.abs_diff(y1)) + 1;
+        self.set_status(&format!("Run: {} cells", painted));
     }
 
-    pub fn end_stroke(&mut self) {
-        self.history.end_stroke();
+    /// Draw a `width` x `height` rectangle (filled or outlined as toggled)
+    /// extending right and down from the anchor: the first corner of a
+    /// rectangle in progress, or else the keyboard cursor.
+    pub fn paint_rect(&mut self, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (x0, y0) = match self.tool_state {
+            ToolState::RectStart { x, y } => (x, y),
+            _ => self.canvas_cursor,
+        };
+        self.tool_state = ToolState::Idle;
+        let width = width.min(self.canvas.width);
+        let height = height.min(self.canvas.height);
+        let x1 = x0.saturating_add(width - 1).min(self.canvas.width.saturating_sub(1));
+        let y1 = y0.saturating_add(height - 1).min(self.canvas.height.saturating_sub(1));
+
+        self.track_recent_color(self.color);
+        let mutations = self.rect_mutations(x0, y0, x1, y1);
+        self.commit_mutations("Rectangle", mutations);
+
+        self.canvas_cursor = (x1, y1);
+        self.canvas_cursor_active = true;
+        self.ensure_cursor_in_viewport(x1, y1, self.viewport_w, self.viewport_h);
+        self.set_status(&format!("Rect: {}x{}", x1 - x0 + 1, y1 - y0 + 1));
     }
 
-    pub fn undo(&mut self) {
-        if self.history.undo(&mut self.canvas) {
-            self.dirty = true;
-            self.set_status("Undo");
+    /// Select every cell drawn with the active block.
+    pub fn select_active_block(&mut self) {
+        let selection = Selection::of_block(&self.canvas, self.active_block);
+        let count = selection.len();
+        if count == 0 {
+            self.selection = None;
+            self.set_status(&format!("No {} cells", self.active_block));
+        } else {
+            self.selection = Some(selection);
+            self.set_status(&format!("Selected {} {} cells", count, self.active_block));
         }
     }
 
-    pub fn redo(&mut self) {
-        if self.history.redo(&mut self.canvas) {
-            self.dirty = true;
-            self.set_status("Redo");
+    /// Select every cell showing the active color and jump the cursor to
+    /// the first, to hunt down strays of a color.
+    pub fn find_color(&mut self) {
+        let selection = Selection::of_color(&self.canvas, self.color);
+        if selection.is_empty() {
+            self.selection = None;
+            self.set_status(&format!("No cells use {}", self.color.name()));
+            return;
         }
+        let count = selection.len();
+        self.selection = Some(selection);
+        self.canvas_cursor_active = false;
+        self.jump_to_selected(1);
+        let next = self.keymap.key_label(&crate::command::Command::JumpToSelected(1)).unwrap_or_default();
+        self.set_status(&format!("Found {} cells of {}, {} jumps to the next", count, self.color.name(), next));
     }
 
-    pub fn cancel_tool(&mut self) {
-        self.tool_state = ToolState::Idle;
+    /// Move the keyboard cursor to the next (positive) or previous selected
+    /// cell, wrapping around, and scroll it into view.
+    pub fn jump_to_selected(&mut self, step: isize) {
+        let Some(selection) = &self.selection else {
+            self.set_status("Nothing selected");
+            return;
+        };
+        let from = self.canvas_cursor_active.then_some(self.canvas_cursor);
+        let Some((index, (cx, cy))) = selection.step_from(from, step) else {
+            return;
+        };
+        let count = selection.len();
+        self.canvas_cursor = (cx, cy);
+        self.canvas_cursor_active = true;
+        self.ensure_cursor_in_viewport(cx, cy, self.viewport_w, self.viewport_h);
+        self.set_status(&format!("Selected cell {}/{} at ({},{})", index + 1, count, cx, cy));
     }
 
-    /// Open the custom palette dialog, scanning for .palette files.
-    pub fn open_palette_dialog(&mut self) {
-        let cwd = std::env::current_dir().unwrap_or_default();
-        self.palette_dialog_files = palette::list_palette_files(&cwd);
-        self.palette_dialog_selected = 0;
-        self.mode = AppMode::PaletteDialog;
+    /// Rewrite each selected cell with `edit` as one undo step. Returns how
+    /// many cells changed.
+    fn edit_selection(&mut self, label: &str, edit: impl Fn(cell::Cell) -> cell::Cell) -> usize {
+        let Some(ref selection) = self.selection else {
+            self.set_status("Nothing selected");
+            return 0;
+        };
+        let mutations: Vec<CellMutation> = selection
+            .iter()
+            .filter_map(|(x, y)| {
+                let old = self.canvas.get(x, y)?;
+                let new = edit(old);
+                (new != old).then_some(CellMutation { x, y, old, new })
+            })
+            .collect();
+        let count = mutations.len();
+        self.record_mutations(label, mutations);
+        count
     }
 
-    /// Load the currently selected palette from the dialog.
-    pub fn load_selected_palette(&mut self) {
-        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
-            match palette::load_palette(Path::new(&filename)) {
-                Ok(cp) => {
-                    self.set_status(&format!("Loaded palette: {}", cp.name));
-                    self.custom_palette = Some(cp);
-                    self.mode = AppMode::Normal;
-                }
-                Err(e) => {
-                    self.set_status(&format!("Load failed: {}", e));
-                }
-            }
+    /// Make selected shade cells denser (positive) or lighter (negative).
+    pub fn shade_selection(&mut self, delta: isize) {
+        if self.selection.is_none() {
+            self.set_status("Nothing selected");
+            return;
         }
+        let label = if delta > 0 { "Darken" } else { "Lighten" };
+        let count = self.edit_selection(label, |c| match shade_step(c.ch, delta) {
+            Some(ch) => cell::Cell { ch, ..c },
+            None => c,
+        });
+        let verb = if delta > 0 { "Darkened" } else { "Lightened" };
+        self.set_status(&format!("{} {} cells", verb, count));
     }
 
-    /// Delete the currently selected palette file.
-    pub fn delete_selected_palette(&mut self) {
-        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
-            match std::fs::remove_file(&filename) {
-                Ok(()) => {
-                    self.set_status(&format!("Deleted: {}", filename));
-                    // If this was the loaded palette, unload it
-                    if let Some(ref cp) = self.custom_palette {
-                        let expected = format!("{}.palette", cp.name);
-                        if filename == expected {
-                            self.custom_palette = None;
-                        }
-                    }
-                    // Refresh file list
-                    let cwd = std::env::current_dir().unwrap_or_default();
-                    self.palette_dialog_files = palette::list_palette_files(&cwd);
-                    if self.palette_dialog_selected >= self.palette_dialog_files.len() && self.palette_dialog_selected > 0 {
-                        self.palette_dialog_selected -= 1;
-                    }
-                }
-                Err(e) => {
-                    self.set_status(&format!("Delete failed: {}", e));
-                }
-            }
+    /// Give the selected cells the active color, keeping their characters.
+    pub fn recolor_selection(&mut self) {
+        if self.selection.is_none() {
+            self.set_status("Nothing selected");
+            return;
         }
+        let color = self.color;
+        let count = self.edit_selection("Recolor", |c| cell::Cell { fg: Some(color), ..c });
+        self.set_status(&format!("Recolored {} cells", count));
     }
 
-    /// Rename the selected palette file.
-    pub fn rename_selected_palette(&mut self, new_name: &str) {
-        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
-            let new_filename = format!("{}.palette", new_name);
-            if Path::new(&new_filename).exists() {
-                self.set_status("Palette already exists");
-                return;
-            }
-            // Load, rename, save to new file, delete old
-            match palette::load_palette(Path::new(&filename)) {
-                Ok(mut cp) => {
-                    cp.name = new_name.to_string();
-                    match palette::save_palette(&cp, Path::new(&new_filename)) {
-                        Ok(()) => {
-                            let _ = std::fs::remove_file(&filename);
-                            self.set_status(&format!("Renamed to: {}", new_name));
-                            // Update loaded palette if it was the renamed one
-                            if let Some(ref mut loaded) = self.custom_palette {
-                                let expected = filename.clone();
-                                if format!("{}.palette", loaded.name) == expected {
-                                    loaded.name = new_name.to_string();
-                                }
-                            }
-                            // Refresh
-                            let cwd = std::env::current_dir().unwrap_or_default();
-                            self.palette_dialog_files = palette::list_palette_files(&cwd);
-                            self.palette_dialog_selected = self.palette_dialog_selected.min(
-                                self.palette_dialog_files.len().saturating_sub(1),
-                            );
-                        }
-                        Err(e) => self.set_status(&format!("Rename failed: {}", e)),
-                    }
-                }
-                Err(e) => self.set_status(&format!("Rename failed: {}", e)),
-            }
-        }
-        self.mode = AppMode::PaletteDialog;
+    /// Copy the selected cells, to paste here or in another tab.
+    pub fn copy_cells(&mut self) {
+        let Some(ref selection) = self.selection else {
+            self.set_status("Nothing selected");
+            return;
+        };
+        let cells: Vec<(usize, usize, cell::Cell)> =
+            selection.iter().filter_map(|(x, y)| Some((x, y, self.canvas.get(x, y)?))).collect();
+        let left = cells.iter().map(|&(x, _, _)| x).min().unwrap_or(0);
+        let top = cells.iter().map(|&(_, y, _)| y).min().unwrap_or(0);
+        self.copied_cells = cells.into_iter().map(|(x, y, c)| (x - left, y - top, c)).collect();
+        self.set_status(&format!("Copied {} cells", self.copied_cells.len()));
     }
 
-    /// Duplicate the selected palette with "(Copy)" suffix.
-    pub fn duplicate_selected_palette(&mut self) {
-        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
-            match palette::load_palette(Path::new(&filename)) {
-                Ok(mut cp) => {
-                    cp.name = format!("{} (Copy)", cp.name);
-                    let new_filename = format!("{}.palette", cp.name);
-                    match palette::save_palette(&cp, Path::new(&new_filename)) {
-                        Ok(()) => {
-                            self.set_status(&format!("Duplicated: {}", cp.name));
-                            let cwd = std::env::current_dir().unwrap_or_default();
-                            self.palette_dialog_files = palette::list_palette_files(&cwd);
-                        }
-                        Err(e) => self.set_status(&format!("Duplicate failed: {}", e)),
-                    }
-                }
-                Err(e) => self.set_status(&format!("Duplicate failed: {}", e)),
-            }
+    /// Paste the copied cells with their top-left corner at the cursor, as
+    /// one undo step. Cells past the canvas edge are dropped.
+    pub fn paste_cells(&mut self) {
+        if self.copied_cells.is_empty() {
+            self.set_status("Nothing copied (select cells, then Y copies them)");
+            return;
         }
+        let Some((cx, cy)) = self.effective_cursor() else {
+            self.set_status("No cell under the cursor");
+            return;
+        };
+        let mutations: Vec<CellMutation> = self
+            .copied_cells
+            .iter()
+            .filter_map(|&(dx, dy, new)| {
+                let (x, y) = (cx + dx, cy + dy);
+                let old = self.canvas.get(x, y)?;
+                Some(CellMutation { x, y, old, new })
+            })
+            .collect();
+        let count = mutations.len();
+        self.record_mutations("Paste", mutations);
+        self.set_status(&format!("Pasted {} cells", count));
     }
 
-    /// Export the selected palette to a user-specified path.
-    pub fn export_selected_palette(&mut self, dest: &str) {
+    /// Swap the foreground and background of the cell under the cursor as
+    /// one undo step.
+    pub fn swap_cell_colors(&mut self) {
+        let Some((x, y)) = self.effective_cursor() else {
+            self.set_status("No cell under the cursor");
+            return;
+        };
+        let Some(old) = self.canvas.get(x, y) else {
+            return;
+        };
+        let new = cell::swap_colors(old);
+        if new == old {
+            self.set_status("Nothing to swap");
+            return;
+        }
+        self.record_mutations("Swap colors", vec![CellMutation { x, y, old, new }]);
+        self.set_status(&format!("Swapped colors at ({},{})", x, y));
+    }
+
+    /// Move the whole canvas by `(dx, dy)` cells as one undo step, wrapping
+    /// art around the edges or letting it fall off.
+    pub fn shift_canvas(&mut self, dx: isize, dy: isize, wrap: bool) {
+        let shifted = self.canvas.shifted(dx, dy, wrap);
+        let mut mutations = Vec::new();
+        for y in 0..self.canvas.height {
+            for x in 0..self.canvas.width {
+                let (Some(old), Some(new)) = (self.canvas.get(x, y), shifted.get(x, y)) else {
+                    continue;
+                };
+                if old != new {
+                    mutations.push(CellMutation { x, y, old, new });
+                }
+            }
+        }
+        if mutations.is_empty() {
+            self.set_status("Nothing to shift");
+            return;
+        }
+        self.record_mutations("Shift canvas", mutations);
+        let direction = match (dx.signum(), dy.signum()) {
+            (1, _) => "right",
+            (-1, _) => "left",
+            (_, 1) => "down",
+            _ => "up",
+        };
+        let wraps = if wrap { ", wrapping" } else { "" };
+        self.set_status(&format!("Shifted canvas {}{}", direction, wraps));
+    }
+
+    /// Open the inspector on the cell under the cursor, its fields filled
+    /// in from the cell.
+    pub fn open_inspector(&mut self) {
+        let Some((x, y)) = self.effective_cursor() else {
+            self.set_status("No cell under the cursor");
+            return;
+        };
+        let Some(cell) = self.canvas.get(x, y) else {
+            return;
+        };
+        let hex = |c: Option<Rgb>| c.map(|c| c.name()).unwrap_or_default();
+        self.inspector_pos = (x, y);
+        self.inspector_fields = [format!("U+{:04X}", cell.ch as u32), hex(cell.fg), hex(cell.bg)];
+        self.inspector_field = 0;
+        self.mode = AppMode::Inspector;
+    }
+
+    /// The cell the inspector fields describe. An empty color field is
+    /// transparent.
+    pub fn inspector_cell(&self) -> Result<cell::Cell, String> {
+        let [ch, fg, bg] = &self.inspector_fields;
+        let ch = cell::parse_codepoint(ch.trim()).ok_or("Invalid character (use U+XXXX)")?;
+        let color = |field: &str| match field.trim() {
+            "" => Ok(None),
+            hex => cell::parse_hex_color(hex).map(Some).ok_or("Invalid color (use #RRGGBB, or empty)"),
+        };
+        Ok(cell::Cell { ch, fg: color(fg)?, bg: color(bg)? })
+    }
+
+    /// Replace one cell exactly, as one undo step.
+    pub fn edit_cell(&mut self, x: usize, y: usize, new: cell::Cell) {
+        let Some(old) = self.canvas.get(x, y) else {
+            self.set_status("Cell is outside the canvas");
+            return;
+        };
+        if old == new {
+            self.set_status(&format!("Cell ({},{}) unchanged", x, y));
+            return;
+        }
+        self.record_mutations("Edit cell", vec![CellMutation { x, y, old, new }]);
+        self.set_status(&format!("Edited cell ({},{})", x, y));
+    }
+
+    /// Clear the selected cells.
+    pub fn erase_selection(&mut self) {
+        if self.selection.is_none() {
+            self.set_status("Nothing selected");
+            return;
+        }
+        let count = self.edit_selection("Erase selection", |_| cell::Cell::default());
+        self.selection = None;
+        self.set_status(&format!("Erased {} cells", count));
+    }
+
+    pub fn begin_stroke(&mut self) {
+        self.stroke_path.clear();
+        self.history.begin_stroke(self.active_tool.name());
+    }
+
+    pub fn end_stroke(&mut self) {
+        if self.pixel_perfect && self.active_tool == ToolKind::Pencil {
+            self.clean_stroke_corners();
+        }
+        self.history.end_stroke();
+    }
+
+    /// Put back what the pencil stroke in progress painted over at its
+    /// L-shaped corners, and at their mirror images, so the line is one
+    /// cell wide. Cells another point of the stroke also painted stay.
+    fn clean_stroke_corners(&mut self) {
+        let path = std::mem::take(&mut self.stroke_path);
+        let kept = tools::pixel_perfect(&path);
+        let mode = if self.unmirrored { SymmetryMode::Off } else { self.symmetry };
+        let (w, h) = (self.canvas.width, self.canvas.height);
+        let mirrored = |points: &[(usize, usize)]| -> BTreeSet<(usize, usize)> {
+            let marks = points
+                .iter()
+                .map(|&(x, y)| CellMutation { x, y, old: cell::Cell::default(), new: cell::Cell::default() })
+                .collect();
+            symmetry::apply_symmetry(marks, mode, w, h).into_iter().map(|m| (m.x, m.y)).collect()
+        };
+        let keep = mirrored(&kept);
+        let dropped: Vec<(usize, usize)> = path.into_iter().filter(|p| !kept.contains(p)).collect();
+        let mut restored = Vec::new();
+        for (x, y) in mirrored(&dropped).difference(&keep).copied() {
+            let (Some(original), Some(current)) = (self.history.pending_original(x, y), self.canvas.get(x, y)) else {
+                continue;
+            };
+            if original != current {
+                self.canvas.set(x, y, original);
+                self.history.push_mutation(CellMutation { x, y, old: current, new: original });
+                restored.push((x, y));
+            }
+        }
+        self.journal_cells(restored);
+    }
+
+    pub fn undo(&mut self) {
+        if self.undo_file_op() {
+            return;
+        }
+        let cells = action_cells(self.history.peek_undo());
+        let reshapes = self.history.peek_undo().is_some_and(Action::reshapes);
+        let label = self.history.peek_undo().map(|a| a.label.clone());
+        if self.history.undo(&mut self.canvas) {
+            if reshapes {
+                self.canvas_reshaped();
+            }
+            self.journal_cells(cells);
+            self.dirty = true;
+            self.set_status(&format!("Undo: {}", label.unwrap_or_default()));
+        }
+    }
+
+    pub fn redo(&mut self) {
+        let cells = action_cells(self.history.peek_redo());
+        let reshapes = self.history.peek_redo().is_some_and(Action::reshapes);
+        let label = self.history.peek_redo().map(|a| a.label.clone());
+        if self.history.redo(&mut self.canvas) {
+            if reshapes {
+                self.canvas_reshaped();
+            }
+            self.journal_cells(cells);
+            self.dirty = true;
+            self.set_status(&format!("Redo: {}", label.unwrap_or_default()));
+        }
+    }
+
+    /// Step through history with a live preview, from the current state.
+    pub fn open_history_scrubber(&mut self) {
+        if self.history.is_empty() {
+            self.set_status("No history yet");
+            return;
+        }
+        self.scrub_start = self.history.position();
+        self.scrub_cells.clear();
+        self.mode = AppMode::HistoryScrubber;
+    }
+
+    /// Move the scrubber `delta` states, applying each to the canvas.
+    pub fn scrub_history(&mut self, delta: isize) {
+        for _ in 0..delta.unsigned_abs() {
+            let action = if delta < 0 { self.history.peek_undo() } else { self.history.peek_redo() };
+            let cells = action_cells(action);
+            let reshapes = action.is_some_and(Action::reshapes);
+            let moved = if delta < 0 {
+                self.history.undo(&mut self.canvas)
+            } else {
+                self.history.redo(&mut self.canvas)
+            };
+            if !moved {
+                break;
+            }
+            if reshapes {
+                self.canvas_reshaped();
+            }
+            self.scrub_cells.extend(cells);
+        }
+    }
+
+    /// Leave the scrubber: keep the state it shows with `jump`, otherwise
+    /// go back to where it started.
+    pub fn finish_history_scrub(&mut self, jump: bool) {
+        self.mode = AppMode::Normal;
+        let position = self.history.position();
+        if !jump {
+            self.scrub_history(self.scrub_start as isize - position as isize);
+            self.scrub_cells.clear();
+            return;
+        }
+        if position != self.scrub_start {
+            let cells = std::mem::take(&mut self.scrub_cells);
+            self.journal_cells(cells);
+            self.dirty = true;
+            let label = self.history.label_at(position).unwrap_or("Start");
+            self.set_status(&format!("History: step {}/{}, {}", position, self.history.len(), label));
+        }
+    }
+
+    /// Where the crash-recovery journal for the current document lives.
+    pub fn journal_path(&self) -> PathBuf {
+        match &self.project_path {
+            Some(p) => journal::journal_path(Path::new(p)),
+            None => PathBuf::from("untitled.kaku.journal"),
+        }
+    }
+
+    /// Buffer the current contents of `cells` in the journal, starting a
+    /// fresh one with a canvas snapshot if needed. They reach the disk at
+    /// the next `sync_journal`.
+    fn journal_cells(&mut self, cells: Vec<(usize, usize)>) {
+        if !self.journaling {
+            return;
+        }
+        let result = match self.journal {
+            Some(ref mut journal) => journal.append(&journal::Entry::Cells(
+                cells.into_iter().filter_map(|(x, y)| Some((x, y, self.canvas.get(x, y)?))).collect(),
+            )),
+            None => journal::Journal::create(&self.journal_path(), &self.canvas).map(|j| self.journal = Some(j)),
+        };
+        if let Err(e) = result {
+            self.disable_journal(e);
+        }
+    }
+
+    /// Flush buffered journal entries to disk. The event loop calls this
+    /// once per batch of input, so a drag costs one fsync per frame rather
+    /// than one per cell.
+    pub fn sync_journal(&mut self) {
+        if let Some(Err(e)) = self.journal.as_mut().map(journal::Journal::sync) {
+            self.disable_journal(e);
+        }
+    }
+
+    fn disable_journal(&mut self, e: std::io::Error) {
+        self.journaling = false;
+        self.journal = None;
+        self.set_status(&format!("Journal disabled: {}", e));
+    }
+
+    /// Drop the journal once its changes are safely saved (or discarded).
+    pub fn end_journal(&mut self) {
+        self.journal = None;
+        if self.journaling {
+            let _ = std::fs::remove_file(self.journal_path());
+        }
+    }
+
+    /// Revert the last file operation, if it is still the most recent action.
+    pub fn undo_file_op(&mut self) -> bool {
+        match self.last_file_op.take() {
+            Some(FileOp::PaletteDeleted { original, trashed }) => {
+                match trash::restore(&trashed, &original) {
+                    Ok(()) => {
+                        let name = original.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        self.set_status(&format!("Restored: {}", name));
+                        self.refresh_palette_dialog();
+                    }
+                    Err(e) => {
+                        self.set_status(&format!("Restore failed: {}", e));
+                    }
+                }
+                true
+            }
+            Some(FileOp::ProjectDeleted { files, was_open }) => {
+                let restored = files.iter().rev().map(|(original, trashed)| trash::restore(trashed, original)).collect::<Result<Vec<()>, _>>();
+                match restored {
+                    Ok(_) => {
+                        let name = files[0].0.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        self.set_status(&format!("Restored: {}", name));
+                        if was_open.is_some() && self.project_path.is_none() {
+                            self.project_path = was_open;
+                        }
+                        self.refresh_file_dialog();
+                    }
+                    Err(e) => self.set_status(&format!("Restore failed: {}", e)),
+                }
+                true
+            }
+            Some(FileOp::NewCanvas(prev)) => {
+                self.put_document(*prev);
+                if self.dirty {
+                    self.journal_cells(Vec::new());
+                }
+                self.set_status("Restored previous canvas");
+                true
+            }
+            Some(FileOp::PaletteColors(prev)) => {
+                let msg = format!("Restored colors in {}", prev.name);
+                self.set_custom_palette(Some(prev));
+                self.save_custom_palette(&msg);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the document with a blank canvas. The previous canvas stays
+    /// retrievable with one undo.
+    pub fn new_canvas(&mut self, width: usize, height: usize) {
+        self.end_journal();
+        let previous = self.take_document(Canvas::new_with_size(width, height));
+        self.last_file_op = Some(FileOp::NewCanvas(Box::new(previous)));
+        self.set_status(&format!("New canvas {}x{} (Ctrl+Z to undo)", width, height));
+    }
+
+    /// Resize the canvas, keeping its art at `anchor`.
+    pub fn resize_canvas(&mut self, width: usize, height: usize, anchor: canvas::Anchor) {
+        let mut resized = self.canvas.clone();
+        resized.resize_anchored(width, height, anchor);
+        if resized == self.canvas {
+            self.set_status("Canvas is already that size");
+            return;
+        }
+        self.reshape_canvas("Resize", resized);
+        self.set_status(&format!(
+            "Resized to {}x{}, {} (Ctrl+Z to undo)",
+            self.canvas.width,
+            self.canvas.height,
+            anchor.name()
+        ));
+    }
+
+    /// Shrink the canvas to the bounding box of its art, as far as the
+    /// minimum size allows.
+    pub fn crop_to_content(&mut self) {
+        let Some(bounds) = crate::export::bounding_box(&self.canvas) else {
+            self.set_status("Nothing to crop to");
+            return;
+        };
+        let cropped = self.canvas.cropped(bounds);
+        if cropped == self.canvas {
+            self.set_status("Canvas is already cropped");
+            return;
+        }
+        self.reshape_canvas("Crop", cropped);
+        self.set_status(&format!(
+            "Cropped to {}x{} (Ctrl+Z to undo)",
+            self.canvas.width, self.canvas.height
+        ));
+    }
+
+    /// Swap in a different-sized copy of the canvas as one undo step that
+    /// keeps the whole previous canvas, since cell edits can't undo a size
+    /// change.
+    fn reshape_canvas(&mut self, label: &str, reshaped: Canvas) {
+        self.history.end_stroke();
+        let previous = std::mem::replace(&mut self.canvas, reshaped);
+        self.history.commit(Action::reshape(label, previous));
+        self.canvas_reshaped();
+        self.journal_cells(Vec::new());
+        self.last_file_op = None;
+        self.dirty = true;
+    }
+
+    /// The canvas changed size: views and tool state made for the old one
+    /// go, and the journal restarts from a snapshot.
+    fn canvas_reshaped(&mut self) {
+        self.reset_view();
+        self.journal = None;
+    }
+
+    /// Swap the document being edited for a blank `canvas` and return it.
+    /// Its journal stays on disk until it is saved or closed.
+    fn take_document(&mut self, canvas: Canvas) -> Document {
+        let document = Document {
+            canvas: std::mem::replace(&mut self.canvas, canvas),
+            history: std::mem::take(&mut self.history),
+            project_name: self.project_name.take(),
+            project_path: self.project_path.take(),
+            dirty: std::mem::take(&mut self.dirty),
+            last_saved: self.last_saved.take(),
+        };
+        self.last_autosave = None;
+        self.auto_save_since = Instant::now();
+        self.journal = None;
+        self.last_file_op = None;
+        self.reset_view();
+        document
+    }
+
+    /// Edit `document`, dropping the one being edited.
+    fn put_document(&mut self, document: Document) {
+        self.canvas = document.canvas;
+        self.history = document.history;
+        self.project_name = document.project_name;
+        self.project_path = document.project_path;
+        self.dirty = document.dirty;
+        self.last_saved = document.last_saved;
+        self.last_autosave = None;
+        self.auto_save_since = Instant::now();
+        self.journal = None;
+        self.reset_view();
+    }
+
+    /// Open projects in tab order: the name, marked `*` when unsaved, and
+    /// whether it is the one being edited.
+    pub fn tab_labels(&self) -> Vec<(String, bool)> {
+        let label = |name: Option<&str>, dirty: bool| {
+            format!("{}{}", name.unwrap_or("untitled"), if dirty { "*" } else { "" })
+        };
+        let mut tabs: Vec<(String, bool)> = self
+            .documents
+            .iter()
+            .map(|d| (label(d.project_name.as_deref(), d.dirty), false))
+            .collect();
+        tabs.insert(self.active_document, (label(self.project_name.as_deref(), self.dirty), true));
+        tabs
+    }
+
+    /// Open `filename` in a new tab after the current one, or switch to its
+    /// tab if it is already open.
+    pub fn open_in_new_tab(&mut self, filename: &str) {
+        if self.project_path.as_deref() == Some(filename) {
+            self.set_status(&format!("Already editing {}", filename));
+            return;
+        }
+        if let Some(i) = self.documents.iter().position(|d| d.project_path.as_deref() == Some(filename)) {
+            let tab = if i < self.active_document { i } else { i + 1 };
+            self.switch_tab(tab);
+            return;
+        }
+        let previous = self.take_document(Canvas::new());
+        if self.load_project(filename) {
+            self.documents.insert(self.active_document, previous);
+            self.active_document += 1;
+        } else {
+            // Keep the status explaining why
+            let status = self.status_message.take();
+            self.put_document(previous);
+            self.status_message = status;
+        }
+    }
+
+    /// Edit the next tab, wrapping around after the last.
+    pub fn next_tab(&mut self) {
+        if self.documents.is_empty() {
+            self.set_status("Only one project is open (T in the Open dialog adds a tab)");
+            return;
+        }
+        self.switch_tab((self.active_document + 1) % (self.documents.len() + 1));
+    }
+
+    /// Edit the project in tab `tab`, counting from 0.
+    fn switch_tab(&mut self, tab: usize) {
+        let current = self.take_document(Canvas::new());
+        self.documents.insert(self.active_document, current);
+        let document = self.documents.remove(tab);
+        self.active_document = tab;
+        self.put_document(document);
+        let count = self.documents.len() + 1;
+        let name = self.project_name.clone().unwrap_or_else(|| "untitled".to_string());
+        self.set_status(&format!("Tab {}/{}: {}", tab + 1, count, name));
+    }
+
+    /// Close the tab being edited, discarding unsaved changes, and edit the
+    /// one after it. Closing the last tab quits.
+    pub fn close_document(&mut self) {
+        self.mode = AppMode::Normal;
+        if self.documents.is_empty() {
+            self.running = false;
+            return;
+        }
+        self.end_journal();
+        let tab = self.active_document.min(self.documents.len() - 1);
+        let document = self.documents.remove(tab);
+        self.active_document = tab;
+        self.put_document(document);
+        self.last_file_op = None;
+        let name = self.project_name.clone().unwrap_or_else(|| "untitled".to_string());
+        self.set_status(&format!("Closed tab, now editing {}", name));
+    }
+
+    fn reset_view(&mut self) {
+        self.selection = None;
+        self.cursor = None;
+        self.canvas_cursor = (0, 0);
+        self.canvas_cursor_active = false;
+        self.viewport_x = 0;
+        self.viewport_y = 0;
+        self.view_grab = None;
+        self.tool_state = ToolState::Idle;
+    }
+
+    pub fn cancel_tool(&mut self) {
+        self.tool_state = ToolState::Idle;
+    }
+
+    /// Open the custom palette dialog, scanning for .palette files.
+    pub fn open_palette_dialog(&mut self) {
+        self.palette_dialog_trash = false;
+        self.palette_dialog_view.clear_filter();
+        self.palette_dialog_selected = 0;
+        self.refresh_palette_dialog();
+        self.mode = AppMode::PaletteDialog;
+    }
+
+    /// Switch the palette dialog between the working directory and the trash.
+    pub fn toggle_palette_trash(&mut self) {
+        self.palette_dialog_trash = !self.palette_dialog_trash;
+        self.palette_dialog_view.clear_filter();
+        self.palette_dialog_selected = 0;
+        self.refresh_palette_dialog();
+    }
+
+    /// Rescan the palette dialog's directory, applying its filter and sort.
+    pub fn refresh_palette_dialog(&mut self) {
+        let files = if self.palette_dialog_trash {
+            trash::trash_dir()
+                .map(|dir| trash::list_trash(&dir, "palette"))
+                .unwrap_or_default()
+        } else {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            palette::list_palette_files(&cwd)
+        };
+        let dir = self.palette_dialog_path("");
+        self.palette_dialog_files = self.palette_dialog_view.apply(&dir, files);
+        self.palette_dialog_selected = self.palette_dialog_selected.min(
+            self.palette_dialog_files.len().saturating_sub(1),
+        );
+    }
+
+    /// Where a file listed in the palette dialog lives.
+    pub fn palette_dialog_path(&self, filename: &str) -> PathBuf {
+        let dir = if self.palette_dialog_trash {
+            trash::trash_dir().unwrap_or_default()
+        } else {
+            std::env::current_dir().unwrap_or_default()
+        };
+        dir.join(filename)
+    }
+
+    /// Restore the selected trashed palette to where it was deleted from,
+    /// or into the working directory if that wasn't recorded.
+    pub fn restore_selected_palette(&mut self) {
+        let Some(trash_dir) = trash::trash_dir() else {
+            return;
+        };
+        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
+            let trashed = trash_dir.join(&filename);
+            let dest = trash::original_path(&trashed)
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_default().join(&filename));
+            match trash::restore(&trashed, &dest) {
+                Ok(()) => {
+                    self.set_status(&format!("Restored: {}", dest.display()));
+                    self.refresh_palette_dialog();
+                }
+                Err(e) => {
+                    self.set_status(&format!("Restore failed: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Load the currently selected palette from the dialog.
+    pub fn load_selected_palette(&mut self) {
+        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
+            match palette::load_palette(Path::new(&filename)) {
+                Ok(cp) => {
+                    self.set_status(&format!("Loaded palette: {}", cp.name));
+                    self.set_custom_palette(Some(cp));
+                    self.mode = AppMode::Normal;
+                }
+                Err(e) => {
+                    self.set_status(&format!("Load failed: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Move the currently selected palette file to the trash.
+    pub fn delete_selected_palette(&mut self) {
+        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
+            let Some(trash_dir) = trash::trash_dir() else {
+                self.set_status("Delete failed: no trash directory");
+                return;
+            };
+            let original = std::env::current_dir().unwrap_or_default().join(&filename);
+            match trash::move_to_trash(&original, &trash_dir) {
+                Ok(trashed) => {
+                    self.last_file_op = Some(FileOp::PaletteDeleted { original, trashed });
+                    self.set_status(&format!("Deleted: {} (Z to undo)", filename));
+                    // If this was the loaded palette, unload it
+                    if let Some(ref cp) = self.custom_palette {
+                        let expected = format!("{}.palette", cp.name);
+                        if filename == expected {
+                            self.set_custom_palette(None);
+                        }
+                    }
+                    self.refresh_palette_dialog();
+                }
+                Err(e) => {
+                    self.set_status(&format!("Delete failed: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Rename the selected palette file.
+    pub fn rename_selected_palette(&mut self, new_name: &str) {
+        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
+            let new_filename = format!("{}.palette", new_name);
+            if Path::new(&new_filename).exists() {
+                self.set_status("Palette already exists");
+                return;
+            }
+            // Load, rename, save to new file, delete old
+            match palette::load_palette(Path::new(&filename)) {
+                Ok(mut cp) => {
+                    cp.name = new_name.to_string();
+                    match palette::save_palette(&cp, Path::new(&new_filename)) {
+                        Ok(()) => {
+                            let _ = std::fs::remove_file(&filename);
+                            self.set_status(&format!("Renamed to: {}", new_name));
+                            // Update loaded palette if it was the renamed one
+                            if let Some(ref mut loaded) = self.custom_palette {
+                                let expected = filename.clone();
+                                if format!("{}.palette", loaded.name) == expected {
+                                    loaded.name = new_name.to_string();
+                                }
+                            }
+                            self.refresh_palette_dialog();
+                        }
+                        Err(e) => self.set_status(&format!("Rename failed: {}", e)),
+                    }
+                }
+                Err(e) => self.set_status(&format!("Rename failed: {}", e)),
+            }
+        }
+        self.mode = AppMode::PaletteDialog;
+    }
+
+    /// Duplicate the selected palette with "(Copy)" suffix.
+    pub fn duplicate_selected_palette(&mut self) {
+        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
+            match palette::load_palette(Path::new(&filename)) {
+                Ok(mut cp) => {
+                    cp.name = format!("{} (Copy)", cp.name);
+                    let new_filename = format!("{}.palette", cp.name);
+                    match palette::save_palette(&cp, Path::new(&new_filename)) {
+                        Ok(()) => {
+                            self.set_status(&format!("Duplicated: {}", cp.name));
+                            self.refresh_palette_dialog();
+                        }
+                        Err(e) => self.set_status(&format!("Duplicate failed: {}", e)),
+                    }
+                }
+                Err(e) => self.set_status(&format!("Duplicate failed: {}", e)),
+            }
+        }
+    }
+
+    /// Copy the selected palette to the clipboard as a `kakupal:` string.
+    pub fn share_selected_palette(&mut self) {
+        if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
+            match palette::load_palette(Path::new(&filename)) {
+                Ok(cp) => {
+                    let shared = palette::to_share_string(&cp);
+                    self.copy_to_clipboard(&shared, &format!("Copied {} as a palette string", cp.name));
+                }
+                Err(e) => self.set_status(&format!("Share failed: {}", e)),
+            }
+        }
+    }
+
+    /// Import a palette string or hex list from the clipboard.
+    pub fn paste_shared_palette(&mut self) {
+        if self.safe_mode {
+            self.set_status("Clipboard is off in safe mode");
+            return;
+        }
+        match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+            Ok(text) => self.import_shared_palette(&text),
+            Err(e) => self.set_status(&format!("Clipboard unavailable: {}", e)),
+        }
+    }
+
+    /// Save a shared palette as a new file and select it.
+    pub fn import_shared_palette(&mut self, text: &str) {
+        match palette::from_share_string(text, "Pasted") {
+            Ok(cp) => self.add_palette_file(cp),
+            Err(e) => self.set_status(&format!("Import failed: {}", e)),
+        }
+    }
+
+    /// Start downloading the Lospec palette with `slug`. It's saved as a
+    /// new palette file when it arrives; see `tick_downloads`.
+    pub fn download_lospec_palette(&mut self, slug: &str) {
+        self.mode = AppMode::PaletteDialog;
+        if !self.settings.allow_network {
+            self.set_status("Network access is off. Set allow_network in settings.json");
+            return;
+        }
+        if !palette::is_lospec_slug(slug) {
+            self.set_status("Lospec names look like sweetie-16");
+            return;
+        }
+        if self.palette_download.is_some() {
+            self.set_status("A download is already running");
+            return;
+        }
+        let slug = slug.to_string();
+        self.set_status(&format!("Downloading {} from Lospec...", slug));
+        self.palette_download = Some(net::Pending::spawn(move || palette::fetch_lospec(&slug)));
+    }
+
+    /// Look for a newer release in the background, if the settings allow.
+    pub fn start_update_check(&mut self) {
+        if self.settings.check_updates && self.settings.allow_network && !self.safe_mode {
+            self.update_check = Some(net::Pending::spawn(update::check));
+        }
+    }
+
+    /// Save a finished palette download and note a newer release. Returns
+    /// whether anything finished.
+    pub fn tick_downloads(&mut self) -> bool {
+        let mut finished = false;
+        if let Some(result) = self.palette_download.as_ref().and_then(|d| d.poll()) {
+            self.palette_download = None;
+            match result {
+                Ok(cp) => self.add_palette_file(cp),
+                Err(e) => self.set_status(&e),
+            }
+            finished = true;
+        }
+        if let Some(result) = self.update_check.as_ref().and_then(|d| d.poll()) {
+            self.update_check = None;
+            // A failed check isn't worth interrupting anyone for
+            if let Ok(Some(release)) = result {
+                let hint = self.keymap.key_label(&crate::command::Command::OpenReleaseNotes)
+                    .map(|key| format!(" ({} for release notes)", key))
+                    .unwrap_or_default();
+                self.set_status(&format!("kakukuma {} is out{}", release.version, hint));
+                self.available_update = Some(release);
+            }
+            finished = true;
+        }
+        finished
+    }
+
+    pub fn open_release_notes(&mut self) {
+        if self.available_update.is_none() {
+            self.set_status(if self.settings.check_updates {
+                "No newer release found"
+            } else {
+                "Update checks are off. Set check_updates in settings.json"
+            });
+            return;
+        }
+        self.release_notes_scroll = 0;
+        self.mode = AppMode::ReleaseNotes;
+    }
+
+    /// Save a palette from elsewhere as a new file and select it. The name
+    /// comes from someone else, so it can't pick the directory or
+    /// overwrite a palette that's already here.
+    fn add_palette_file(&mut self, mut cp: palette::CustomPalette) {
+        let name: String = cp.name.chars()
+            .map(|c| if c == '/' || c == '\\' || c.is_control() { '-' } else { c })
+            .collect();
+        let name = name.trim();
+        let name = if name.is_empty() { "Pasted" } else { name };
+        cp.name = name.to_string();
+        let mut n = 2;
+        while self.palette_dialog_path(&format!("{}.palette", cp.name)).exists() {
+            cp.name = format!("{} ({})", name, n);
+            n += 1;
+        }
+        let filename = format!("{}.palette", cp.name);
+        match palette::save_palette(&cp, &self.palette_dialog_path(&filename)) {
+            Ok(()) => {
+                self.set_status(&format!("Added palette: {} ({} colors)", cp.name, cp.colors.len()));
+                self.palette_dialog_view.clear_filter();
+                self.refresh_palette_dialog();
+                if let Some(i) = self.palette_dialog_files.iter().position(|f| *f == filename) {
+                    self.palette_dialog_selected = i;
+                }
+            }
+            Err(e) => self.set_status(&format!("Save failed: {}", e)),
+        }
+    }
+
+    /// Export the selected palette to a user-specified path.
+    pub fn export_selected_palette(&mut self, dest: &str) {
         if let Some(filename) = self.palette_dialog_files.get(self.palette_dialog_selected).cloned() {
             match std::fs::copy(&filename, dest) {
                 Ok(_) => {
@@ -618,7 +2126,7 @@ impl App {
         match palette::save_palette(&cp, Path::new(&filename)) {
             Ok(()) => {
                 self.set_status(&format!("Created palette: {}", name));
-                self.custom_palette = Some(cp);
+                self.set_custom_palette(Some(cp));
                 self.mode = AppMode::Normal;
             }
             Err(e) => {
@@ -637,7 +2145,11 @@ impl App {
                     cp.colors.push(color);
                     let filename = format!("{}.palette", cp.name);
                     let _ = palette::save_palette(cp, Path::new(&filename));
-                    let msg = format!("Added {} to {}", color.name(), cp.name);
+                    let mut msg = format!("Added {} to {}", color.name(), cp.name);
+                    self.rebuild_palette_layout();
+                    if self.colors_count() % palette::COLORS_PER_PAGE == 1 && self.colors_page_count() > 1 {
+                        msg.push_str(&format!(" (now {} pages, PgUp/PgDn to flip)", self.colors_page_count()));
+                    }
                     self.set_status(&msg);
                 } else {
                     self.set_status("Color already in palette");
@@ -649,6 +2161,121 @@ impl App {
         }
     }
 
+    /// Swap in a custom palette (or the curated one), dropping any marks.
+    fn set_custom_palette(&mut self, cp: Option<palette::CustomPalette>) {
+        self.custom_palette = cp;
+        self.palette_marks.clear();
+        self.rebuild_palette_layout();
+        self.palette_cursor = self.palette_cursor.min(self.palette_layout.len().saturating_sub(1));
+    }
+
+    /// The custom palette colors a group edit applies to: the marked ones,
+    /// or else the one under the palette cursor.
+    fn palette_targets(&self) -> Vec<usize> {
+        let len = self.custom_palette.as_ref().map_or(0, |cp| cp.colors.len());
+        if !self.palette_marks.is_empty() {
+            self.palette_marks.iter().copied().filter(|&i| i < len).collect()
+        } else if self.palette_cursor < len {
+            vec![self.palette_cursor]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Mark or unmark the custom palette color under the palette cursor.
+    pub fn toggle_palette_mark(&mut self) {
+        let len = self.custom_palette.as_ref().map_or(0, |cp| cp.colors.len());
+        if len == 0 || self.palette_cursor >= len {
+            self.set_status("Marking works on custom palette colors");
+            return;
+        }
+        let i = self.palette_cursor;
+        if !self.palette_marks.remove(&i) {
+            self.palette_marks.insert(i);
+        }
+        self.set_status(&format!("{} marked", self.palette_marks.len()));
+    }
+
+    /// Remove the marked colors from the custom palette and save it. Colors
+    /// must be marked first, so a stray Shift+X can't delete the one under
+    /// the cursor; one undo puts them back.
+    pub fn delete_palette_colors(&mut self) {
+        let len = self.custom_palette.as_ref().map_or(0, |cp| cp.colors.len());
+        let targets: Vec<usize> = self.palette_marks.iter().copied().filter(|&i| i < len).collect();
+        let Some(ref mut cp) = self.custom_palette else {
+            self.set_status("No palette loaded. Press C to open palettes.");
+            return;
+        };
+        if targets.is_empty() {
+            self.set_status("No palette colors marked (k marks one)");
+            return;
+        }
+        let before = cp.clone();
+        for &i in targets.iter().rev() {
+            cp.colors.remove(i);
+        }
+        let noun = if targets.len() == 1 { "color" } else { "colors" };
+        let msg = format!("Removed {} {} from {} (Ctrl+Z to undo)", targets.len(), noun, cp.name);
+        self.save_custom_palette(&msg);
+        self.last_file_op = Some(FileOp::PaletteColors(before));
+        self.palette_marks.clear();
+        self.rebuild_palette_layout();
+        let len = self.custom_palette.as_ref().map_or(0, |cp| cp.colors.len());
+        self.palette_cursor = targets[0].min(len.saturating_sub(1));
+    }
+
+    /// Move the marked colors (or the one under the cursor) one place
+    /// earlier (`delta` < 0) or later, keeping the gaps between them, and
+    /// save the palette. Nothing moves if a color is already at the end.
+    pub fn move_palette_colors(&mut self, delta: isize) {
+        let mut targets = self.palette_targets();
+        let Some(ref mut cp) = self.custom_palette else {
+            self.set_status("No palette loaded. Press C to open palettes.");
+            return;
+        };
+        let (Some(&first), Some(&last)) = (targets.first(), targets.last()) else {
+            self.set_status("No palette colors marked");
+            return;
+        };
+        if (delta < 0 && first == 0) || (delta > 0 && last + 1 >= cp.colors.len()) || delta == 0 {
+            return;
+        }
+        if delta < 0 {
+            for i in targets.iter_mut() {
+                cp.colors.swap(*i - 1, *i);
+                *i -= 1;
+            }
+        } else {
+            for i in targets.iter_mut().rev() {
+                cp.colors.swap(*i, *i + 1);
+                *i += 1;
+            }
+        }
+        let noun = if targets.len() == 1 { "color" } else { "colors" };
+        let msg = format!("Moved {} {} in {}", targets.len(), noun, cp.name);
+        self.save_custom_palette(&msg);
+        let cursor_moved = self.palette_marks.is_empty() || self.palette_marks.contains(&self.palette_cursor);
+        if !self.palette_marks.is_empty() {
+            self.palette_marks = targets.into_iter().collect();
+        }
+        if cursor_moved {
+            self.palette_cursor = self.palette_cursor.saturating_add_signed(delta.signum());
+        }
+        self.rebuild_palette_layout();
+    }
+
+    /// Write the custom palette back to its file, reporting `msg` on success.
+    fn save_custom_palette(&mut self, msg: &str) {
+        let Some(ref cp) = self.custom_palette else {
+            return;
+        };
+        let filename = format!("{}.palette", cp.name);
+        match palette::save_palette(cp, Path::new(&filename)) {
+            Ok(()) => self.set_status(msg),
+            Err(e) => self.set_status(&format!("Palette save failed: {}", e)),
+        }
+    }
+
     /// Save the current project to its path. If no path, returns false (need SaveAs).
     pub fn save_project(&mut self) -> bool {
         let path = match &self.project_path {
@@ -662,13 +2289,23 @@ impl App {
             self.color,
             self.symmetry,
         );
+        project.recent_colors = self.recent_colors.clone();
         match project.save_to_file(&path) {
             Ok(()) => {
                 self.dirty = false;
-                self.auto_save_ticks = 0;
+                self.auto_save_since = Instant::now();
+                self.last_saved = Some(SystemTime::now());
+                self.last_autosave = None;
                 // Delete autosave file if it exists
-                let autosave = format!("{}.autosave", path.display());
-                let _ = std::fs::remove_file(&autosave);
+                let _ = std::fs::remove_file(project::autosave_path(&path));
+                self.end_journal();
+                if self.settings.persist_history {
+                    let sidecar = history::sidecar_path(&path);
+                    if let Err(e) = self.history.save_sidecar(&sidecar, &self.canvas) {
+                        self.set_status(&format!("Saved, but not history: {}", e));
+                        return true;
+                    }
+                }
                 self.set_status("Saved!");
                 true
             }
@@ -681,46 +2318,241 @@ impl App {
 
     /// Save with a specific name (from SaveAs dialog).
     pub fn save_as(&mut self, name: &str) {
-        let filename = if name.ends_with(".kaku") {
+        let has_ext = name.to_ascii_lowercase().ends_with(".kaku");
+        let filename = if has_ext {
             name.to_string()
         } else {
             format!("{}.kaku", name)
         };
-        self.project_name = Some(name.trim_end_matches(".kaku").to_string());
+        let stem = strip_kaku_ext(name);
+        // The journal follows the document to its new path
+        self.end_journal();
+        let old_autosave = self.autosave_file();
+        self.project_name = Some(stem.to_string());
         self.project_path = Some(filename);
-        self.save_project();
+        if self.save_project() {
+            // The saved file supersedes the autosave kept under the old name
+            let _ = std::fs::remove_file(old_autosave);
+        }
+    }
+
+    /// Reload the project from disk, dropping unsaved changes and history.
+    pub fn revert_to_saved(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some(path) = self.project_path.clone() else {
+            return;
+        };
+        self.load_project(&path);
+        if !self.dirty {
+            self.end_journal();
+            self.reset_view();
+            self.set_status(&format!("Reverted to saved {}", path));
+        }
     }
 
-    /// Load a project from a .kaku file.
-    pub fn load_project(&mut self, filename: &str) {
+    /// Load a project from a .kaku file. Returns whether it loaded.
+    pub fn load_project(&mut self, filename: &str) -> bool {
         let path = Path::new(filename);
         match Project::load_from_file(path) {
             Ok(project) => {
                 self.canvas = project.canvas;
                 self.color = project.color;
                 self.symmetry = project.symmetry;
+                self.recent_colors = project.recent_colors;
                 self.project_name = Some(project.name);
                 self.project_path = Some(filename.to_string());
                 self.dirty = false;
                 self.history = History::new();
-                self.auto_save_ticks = 0;
+                if self.settings.persist_history {
+                    let sidecar = history::sidecar_path(path);
+                    if let Some(history) = History::load_sidecar(&sidecar, &self.canvas) {
+                        self.history = history;
+                    }
+                }
+                self.selection = None;
+                self.last_file_op = None;
+                self.auto_save_since = Instant::now();
+                self.last_saved = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                self.last_autosave = None;
+                self.journal = None;
                 self.set_status(&format!("Opened: {}", filename));
+                self.check_color_support();
+                true
             }
             Err(e) => {
                 self.set_status(&format!("Load failed: {}", e));
+                false
+            }
+        }
+    }
+
+    pub fn open_help(&mut self) {
+        self.help_scroll = 0;
+        self.help_query.clear();
+        self.help_searching = false;
+        self.mode = AppMode::Help;
+    }
+
+    /// Help sections matching the current search.
+    pub fn help_sections(&self) -> Vec<HelpSection> {
+        self.keymap.sections_matching(&self.help_query)
+    }
+
+    /// Lines in the help overlay: per section a title, a rule, its rows and a gap.
+    pub fn help_line_count(&self) -> usize {
+        self.help_sections().iter().map(|s| s.rows.len() + 3).sum()
+    }
+
+    /// Scroll the help overlay, stopping at the last full page.
+    pub fn scroll_help(&mut self, delta: isize) {
+        let max = self.help_line_count().saturating_sub(self.help_page.get().max(1));
+        self.help_scroll = self.help_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Populate file dialog with .kaku files from current directory.
+    pub fn open_file_dialog(&mut self) {
+        self.file_dialog_view.clear_filter();
+        self.file_dialog_selected = 0;
+        self.refresh_file_dialog();
+        if self.file_dialog_files.is_empty() {
+            self.set_status("No .kaku files found");
+        } else {
+            self.mode = AppMode::FileDialog;
+        }
+    }
+
+    /// Rescan the working directory for the open dialog, applying its filter and sort.
+    pub fn refresh_file_dialog(&mut self) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let files = project::list_kaku_files(&cwd);
+        self.file_dialog_files = self.file_dialog_view.apply(&cwd, files);
+        self.file_dialog_selected = self.file_dialog_selected.min(
+            self.file_dialog_files.len().saturating_sub(1),
+        );
+    }
+
+    /// Populate the import dialog with images from the current directory.
+    pub fn open_import_dialog(&mut self) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.import_dialog_files = import::list_image_files(&cwd);
+        self.import_dialog_selected = 0;
+        if self.import_dialog_files.is_empty() {
+            self.set_status("No PNG or JPEG files found");
+        } else {
+            self.mode = AppMode::ImportDialog;
+        }
+    }
+
+    /// Convert the image selected in the import dialog to half blocks,
+    /// scaled to fit the canvas and matched to the active palette, and
+    /// replace the canvas with it as one undo step.
+    pub fn import_selected_image(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some(filename) = self.import_dialog_files.get(self.import_dialog_selected).cloned() else {
+            return;
+        };
+        let img = match import::load_image(Path::new(&filename)) {
+            Ok(img) => img,
+            Err(e) => {
+                self.set_status(&format!("Import failed: {}", e));
+                return;
+            }
+        };
+        let opts = import::ImportOptions {
+            width: self.canvas.width,
+            height: self.canvas.height,
+            palette: self.custom_palette.as_ref().map(|cp| cp.colors.clone()),
+            dither: self.import_dither,
+            ..Default::default()
+        };
+        let imported = import::import_image(&img, &opts);
+        let mut mutations = Vec::new();
+        for y in 0..self.canvas.height {
+            for x in 0..self.canvas.width {
+                let old = self.canvas.get(x, y).unwrap_or_default();
+                let new = imported.get(x, y).unwrap_or_default();
+                if old != new {
+                    mutations.push(CellMutation { x, y, old, new });
+                }
+            }
+        }
+        self.record_mutations("Import image", mutations);
+        self.set_status(&format!("Imported {}", filename));
+    }
+
+    /// Rename the project file selected in the open dialog.
+    pub fn rename_selected_project(&mut self, new_name: &str) {
+        if let Some(filename) = self.file_dialog_files.get(self.file_dialog_selected).cloned() {
+            let new_name = strip_kaku_ext(new_name);
+            let cwd = std::env::current_dir().unwrap_or_default();
+            match project::rename_project_file(&cwd, &filename, new_name) {
+                Ok(new_filename) => {
+                    // Keep saving to the right place if it was the open project
+                    if self.project_path.as_deref() == Some(filename.as_str()) {
+                        self.project_name = Some(new_name.to_string());
+                        self.project_path = Some(new_filename.clone());
+                    }
+                    self.set_status(&format!("Renamed to: {}", new_filename));
+                    self.refresh_file_dialog();
+                }
+                Err(e) => self.set_status(&format!("Rename failed: {}", e)),
+            }
+        }
+        self.mode = AppMode::FileDialog;
+    }
+
+    /// Duplicate the project file selected in the open dialog.
+    pub fn duplicate_selected_project(&mut self) {
+        if let Some(filename) = self.file_dialog_files.get(self.file_dialog_selected).cloned() {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            match project::duplicate_project_file(&cwd, &filename) {
+                Ok(new_filename) => {
+                    self.set_status(&format!("Duplicated: {}", new_filename));
+                    self.refresh_file_dialog();
+                }
+                Err(e) => self.set_status(&format!("Duplicate failed: {}", e)),
             }
         }
     }
 
-    /// Populate file dialog with .kaku files from current directory.
-    pub fn open_file_dialog(&mut self) {
-        let cwd = std::env::current_dir().unwrap_or_default();
-        self.file_dialog_files = crate::project::list_kaku_files(&cwd);
-        self.file_dialog_selected = 0;
-        if self.file_dialog_files.is_empty() {
-            self.set_status("No .kaku files found");
-        } else {
-            self.mode = AppMode::FileDialog;
+    /// Move the project file selected in the open dialog, and its operation
+    /// log, to the trash. Called once the user has confirmed.
+    pub fn delete_selected_project(&mut self) {
+        self.mode = AppMode::FileDialog;
+        let Some(filename) = self.file_dialog_files.get(self.file_dialog_selected).cloned() else {
+            return;
+        };
+        let Some(trash_dir) = trash::trash_dir() else {
+            self.set_status("Delete failed: no trash directory");
+            return;
+        };
+        self.trash_project(&filename, &trash_dir);
+    }
+
+    /// Move project `filename` and its operation log into `trash_dir`,
+    /// undoable with Z in the open dialog or Ctrl+Z.
+    fn trash_project(&mut self, filename: &str, trash_dir: &Path) {
+        let path = std::env::current_dir().unwrap_or_default().join(filename);
+        match trash::move_to_trash(&path, trash_dir) {
+            Ok(trashed) => {
+                let mut files = vec![(path.clone(), trashed)];
+                let log = crate::oplog::log_path(&path);
+                if log.exists() {
+                    if let Ok(trashed_log) = trash::move_to_trash(&log, trash_dir) {
+                        files.push((log, trashed_log));
+                    }
+                }
+                // The open document survives in memory, but has no file any more
+                let mut was_open = None;
+                if self.project_path.as_deref() == Some(filename) {
+                    was_open = self.project_path.take();
+                    self.dirty = true;
+                }
+                self.last_file_op = Some(FileOp::ProjectDeleted { files, was_open });
+                self.set_status(&format!("Moved to trash: {} (Z to undo)", filename));
+                self.refresh_file_dialog();
+            }
+            Err(e) => self.set_status(&format!("Delete failed: {}", e)),
         }
     }
 
@@ -733,35 +2565,86 @@ impl App {
         }
     }
 
+    /// Step the export dialog's transparency option: keep, reset, then a
+    /// solid background in the active color.
+    pub fn cycle_export_transparency(&mut self) {
+        self.export_ansi.transparent = match self.export_ansi.transparent {
+            export::Transparency::Keep => export::Transparency::Reset,
+            export::Transparency::Reset => export::Transparency::Solid(self.color),
+            export::Transparency::Solid(_) => export::Transparency::Keep,
+        };
+    }
+
+    /// Count the canvas's colors against the export dialog's depth.
+    pub fn analyze_export_depth(&mut self) {
+        let report = export::depth_report(&self.canvas, self.color_format(), &self.settings.terminal_palette);
+        self.export_analysis = Some(report);
+    }
+
+    /// The canvas as text, per the export dialog's format, depth and dither.
+    fn export_content(&self) -> String {
+        match self.export_format {
+            0 => export::to_plain_text_with(&self.canvas, self.export_double_width),
+            1 => self.ansi_content(&self.canvas),
+            2 => export::to_html(&self.canvas),
+            3 => export::to_markdown(&self.canvas, self.project_name.as_deref()),
+            _ => export::to_markdown_html(&self.canvas, self.project_name.as_deref()),
+        }
+    }
+
+    /// `canvas` as ANSI at the export dialog's depth, dither and options.
+    fn ansi_content(&self, canvas: &Canvas) -> String {
+        let format = self.color_format();
+        let terminal = &self.settings.terminal_palette;
+        let reduced = export::reduce_colors(canvas, format, self.export_dither, terminal);
+        export::to_ansi_with(&reduced, format, &self.export_ansi)
+    }
+
+    /// Copy the selected cells, cropped to their bounds, to the clipboard
+    /// as ANSI. Unselected cells inside the bounds are left blank.
+    pub fn copy_selection_ansi(&mut self) {
+        let Some(selection) = self.selection.as_ref().filter(|s| !s.is_empty()) else {
+            self.set_status("No selection to copy");
+            return;
+        };
+        let content = self.ansi_content(&selection.extract(&self.canvas));
+        self.copy_to_clipboard(&content, "Copied selection");
+    }
+
+    /// Put export `content` on the clipboard, reporting its size after
+    /// `done` in the status bar.
+    fn copy_to_clipboard(&mut self, content: &str, done: &str) {
+        if self.safe_mode {
+            self.set_status("Clipboard is off in safe mode. Use File export.");
+            return;
+        }
+        match arboard::Clipboard::new() {
+            Ok(mut clipboard) => match clipboard.set_text(clipboard_text(content)) {
+                Ok(()) => {
+                    let stats = export::ExportStats::of(content);
+                    self.set_status(&format!("{} ({})", done, stats.summary(self.settings.export_budget)));
+                }
+                Err(e) => self.set_status(&format!("Clipboard error: {}", e)),
+            },
+            Err(e) => self.set_status(&format!("Clipboard unavailable: {}. Use File export.", e)),
+        }
+    }
+
     /// Execute the current export dialog selection.
     pub fn do_export(&mut self) {
-        let content = if self.export_format == 0 {
-            export::to_plain_text(&self.canvas)
-        } else {
-            export::to_ansi(&self.canvas, self.color_format())
-        };
+        let content = self.export_content();
 
         if self.export_dest == 0 {
-            // Clipboard
-            match arboard::Clipboard::new() {
-                Ok(mut clipboard) => match clipboard.set_text(&content) {
-                    Ok(()) => {
-                        self.set_status("Copied to clipboard!");
-                        self.mode = AppMode::Normal;
-                    }
-                    Err(e) => {
-                        self.set_status(&format!("Clipboard error: {}", e));
-                        self.mode = AppMode::Normal;
-                    }
-                },
-                Err(e) => {
-                    self.set_status(&format!("Clipboard unavailable: {}. Use File export.", e));
-                    self.mode = AppMode::Normal;
-                }
-            }
+            self.copy_to_clipboard(&content, "Copied to clipboard!");
+            self.mode = AppMode::Normal;
         } else {
             // File — switch to text input for filename
-            let ext = if self.export_format == 0 { "txt" } else { "ans" };
+            let ext = match self.export_format {
+                0 => "txt",
+                1 => "ans",
+                2 => "html",
+                _ => "md",
+            };
             let base = self
                 .project_name
                 .as_deref()
@@ -771,38 +2654,97 @@ impl App {
         }
     }
 
+    /// Ask where to write the undo history as an animated GIF.
+    pub fn start_gif_export(&mut self) {
+        if self.history.is_empty() {
+            self.set_status("No history to animate yet");
+            return;
+        }
+        let base = self.project_name.as_deref().unwrap_or("untitled");
+        self.text_input = format!("{}.gif", base);
+        self.export_gif = true;
+        self.mode = AppMode::ExportFile;
+    }
+
+    /// Animate every undo step up to the current state, holding the last
+    /// frame ten times as long.
+    fn history_gif(&self) -> std::io::Result<Vec<u8>> {
+        let delay = self.settings.gif_frame_ms;
+        let states = self.history.states(&self.canvas);
+        let last = states.len() - 1;
+        let frames: Vec<(Canvas, u32)> = states
+            .into_iter()
+            .enumerate()
+            .map(|(i, state)| (state, if i == last { delay.saturating_mul(10) } else { delay }))
+            .collect();
+        export::to_gif(&frames, self.settings.cell_aspect.raster_cell())
+    }
+
     /// Write export content to a file.
     pub fn export_to_file(&mut self, filename: &str) {
-        let content = if self.export_format == 0 {
-            export::to_plain_text(&self.canvas)
-        } else {
-            export::to_ansi(&self.canvas, self.color_format())
-        };
+        if self.export_gif {
+            self.export_gif = false;
+            match self.history_gif().and_then(|gif| std::fs::write(filename, &gif).map(|()| gif.len())) {
+                Ok(bytes) => self.set_status(&format!(
+                    "Exported {} steps to {} ({})",
+                    self.history.position(),
+                    filename,
+                    export::format_bytes(bytes)
+                )),
+                Err(e) => self.set_status(&format!("Export failed: {}", e)),
+            }
+            self.mode = AppMode::Normal;
+            return;
+        }
+        let content = self.export_content();
         match std::fs::write(filename, &content) {
-            Ok(()) => self.set_status(&format!("Exported to {}", filename)),
+            Ok(()) => {
+                let stats = export::ExportStats::of(&content);
+                let summary = stats.summary(self.settings.export_budget);
+                self.set_status(&format!("Exported to {} ({})", filename, summary));
+            }
             Err(e) => self.set_status(&format!("Export failed: {}", e)),
         }
         self.mode = AppMode::Normal;
     }
 
-    /// Auto-save tick. Call each event loop iteration (~100ms).
-    /// Triggers auto-save after 600 ticks (60 seconds) if dirty.
+    /// Auto-save tick. Call each event loop iteration; autosaves once the
+    /// canvas has been dirty for `AUTO_SAVE_INTERVAL`.
     pub fn tick_auto_save(&mut self) {
-        if !self.dirty {
+        let now = Instant::now();
+        if !self.dirty || self.safe_mode {
+            self.auto_save_since = now;
             return;
         }
-        self.auto_save_ticks += 1;
-        if self.auto_save_ticks >= 600 {
-            self.auto_save_ticks = 0;
+        if now.duration_since(self.auto_save_since) >= AUTO_SAVE_INTERVAL {
+            self.auto_save_since = now;
             self.do_auto_save();
         }
     }
 
+    /// What a crash bundle should say about the app.
+    pub fn crash_state(&self) -> crate::crash::AppState {
+        crate::crash::AppState {
+            mode: format!("{:?}", self.mode),
+            tool: format!("{:?}", self.active_tool),
+            canvas_size: (self.canvas.width, self.canvas.height),
+            zoom: self.zoom,
+            dirty: self.dirty,
+            tabs: self.documents.len() + 1,
+            project_path: self.project_path.clone(),
+            autosave_path: self.autosave_file().display().to_string(),
+        }
+    }
+
+    fn autosave_file(&self) -> PathBuf {
+        match &self.project_path {
+            Some(p) => project::autosave_path(Path::new(p)),
+            None => PathBuf::from("untitled.kaku.autosave"),
+        }
+    }
+
     fn do_auto_save(&mut self) {
-        let path = match &self.project_path {
-            Some(p) => format!("{}.autosave", p),
-            None => "untitled.kaku.autosave".to_string(),
-        };
+        let path = self.autosave_file();
         let name = self.project_name.clone().unwrap_or_else(|| "untitled".to_string());
         let mut project = Project::new(
             &name,
@@ -810,34 +2752,146 @@ impl App {
             self.color,
             self.symmetry,
         );
-        if project.save_to_file(Path::new(&path)).is_ok() {
+        project.recent_colors = self.recent_colors.clone();
+        if project.save_to_file(&path).is_ok() {
+            if self.last_version.is_none_or(|t| t.elapsed() >= VERSION_INTERVAL)
+                && versions::record(&self.versions_file(), &self.canvas).unwrap_or(false)
+            {
+                self.last_version = Some(Instant::now());
+            }
+            // Keep the journal short: the next change restarts it
+            self.journal = None;
+            self.last_autosave = Some(SystemTime::now());
             self.set_status("Auto-saved");
         }
     }
 
-    /// Check for autosave files on startup and prompt recovery.
+    fn versions_file(&self) -> PathBuf {
+        match &self.project_path {
+            Some(p) => versions::versions_path(Path::new(p)),
+            None => PathBuf::from("untitled.kaku.versions"),
+        }
+    }
+
+    /// Open the versions browser on the snapshots taken at autosaves.
+    pub fn open_versions(&mut self) {
+        self.versions = versions::load(&self.versions_file());
+        if self.versions.is_empty() {
+            self.set_status("No versions yet: they're taken at each autosave");
+            return;
+        }
+        self.versions.reverse();
+        self.versions_selected = 0;
+        self.mode = AppMode::Versions;
+    }
+
+    /// Replace the canvas with the selected version as one undo step.
+    pub fn restore_version(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some(version) = self.versions.get(self.versions_selected).cloned() else {
+            return;
+        };
+        let label = version.label();
+        if (version.canvas.width, version.canvas.height) != (self.canvas.width, self.canvas.height) {
+            self.reshape_canvas(&format!("Restore {}", label), version.canvas);
+        } else {
+            let mut mutations = Vec::new();
+            for y in 0..self.canvas.height {
+                for x in 0..self.canvas.width {
+                    let old = self.canvas.get(x, y).unwrap_or_default();
+                    let new = version.canvas.get(x, y).unwrap_or_default();
+                    if old != new {
+                        mutations.push(CellMutation { x, y, old, new });
+                    }
+                }
+            }
+            if mutations.is_empty() {
+                self.set_status(&format!("Canvas already matches version {}", label));
+                return;
+            }
+            for m in &mutations {
+                self.canvas.set(m.x, m.y, m.new);
+            }
+            self.journal_cells(mutations.iter().map(|m| (m.x, m.y)).collect());
+            self.history.commit(Action { label: format!("Restore {}", label), mutations, canvas: None });
+        }
+        self.last_file_op = None;
+        self.dirty = true;
+        self.set_status(&format!("Restored version {}", label));
+    }
+
+    /// Check for a journal or autosave left by a crash and prompt recovery.
+    /// With a file open only its own journal counts; otherwise the working
+    /// directory is searched, journals first since they are more recent.
     pub fn check_recovery(&mut self) {
-        let cwd = std::env::current_dir().unwrap_or_default();
-        if let Some(autosave_name) = crate::project::find_autosave(&cwd) {
-            self.recovery_path = Some(autosave_name);
+        let found = match &self.project_path {
+            Some(_) => Some(self.journal_path())
+                .filter(|p| p.exists())
+                .map(|p| p.to_string_lossy().into_owned()),
+            None => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                journal::find_journal(&cwd).or_else(|| crate::project::find_autosave(&cwd))
+            }
+        };
+        if let Some(path) = found {
+            self.recovery_path = Some(path);
             self.mode = AppMode::Recovery;
+        } else {
+            self.sweep_stale_autosaves();
+        }
+    }
+
+    /// Delete old superseded autosaves next to the project (or in the
+    /// working directory). Runs only once recovery has been offered, so it
+    /// never removes one the user hasn't had the chance to recover.
+    fn sweep_stale_autosaves(&self) {
+        let days = self.settings.autosave_retention_days;
+        if days == 0 {
+            return;
         }
+        let dir = match &self.project_path {
+            Some(p) => Path::new(p).parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        let dir = if dir.as_os_str().is_empty() { PathBuf::from(".") } else { dir };
+        project::remove_stale_autosaves(&dir, Duration::from_secs(days * 86400));
+    }
+
+    /// Turn down the recovery offer, leaving the file on disk.
+    pub fn decline_recovery(&mut self) {
+        self.recovery_path = None;
+        self.mode = AppMode::Normal;
+        self.sweep_stale_autosaves();
+    }
+
+    /// Whether the pending recovery replays a journal rather than an autosave.
+    pub fn recovering_journal(&self) -> bool {
+        self.recovery_path
+            .as_deref()
+            .is_some_and(|p| journal::journal_target(Path::new(p)).is_some())
     }
 
-    /// Recover from an autosave file.
+    /// Recover from a journal or autosave file.
     pub fn recover_autosave(&mut self) {
+        if self.recovering_journal() {
+            self.recover_journal();
+            return;
+        }
         if let Some(ref autosave) = self.recovery_path.clone() {
             let path = Path::new(autosave);
             match Project::load_from_file(path) {
                 Ok(project) => {
                     self.canvas = project.canvas;
+                    self.selection = None;
                     self.color = project.color;
                     self.symmetry = project.symmetry;
+                    self.recent_colors = project.recent_colors;
                     self.project_name = Some(project.name);
                     // Derive the real save path from autosave name
-                    let real_path = autosave.trim_end_matches(".autosave");
-                    if !real_path.is_empty() && real_path != "untitled.kaku" {
-                        self.project_path = Some(real_path.to_string());
+                    if let Some(real_path) = project::autosave_target(path) {
+                        if real_path != Path::new("untitled.kaku") {
+                            self.project_path = Some(real_path.to_string_lossy().into_owned());
+                        }
                     }
                     self.dirty = true; // Mark dirty so user knows to save properly
                     self.set_status("Recovered from autosave");
@@ -849,18 +2903,97 @@ impl App {
         }
         self.recovery_path = None;
         self.mode = AppMode::Normal;
+        self.sweep_stale_autosaves();
+    }
+
+    /// Replay the journal onto a fresh document. The journal stays on disk
+    /// until the next change restarts it, in case of another crash.
+    fn recover_journal(&mut self) {
+        let Some(journal) = self.recovery_path.take().map(PathBuf::from) else {
+            return;
+        };
+        match journal::replay(&journal) {
+            Ok(canvas) => {
+                self.canvas = canvas;
+                self.history = History::new();
+                self.last_file_op = None;
+                self.reset_view();
+                if let Some(real_path) = journal::journal_target(&journal) {
+                    if real_path != Path::new("untitled.kaku") {
+                        let name = real_path.file_stem().and_then(|n| n.to_str()).unwrap_or("untitled");
+                        self.project_name = Some(name.to_string());
+                        self.project_path = Some(real_path.to_string_lossy().into_owned());
+                    }
+                }
+                self.journal = None;
+                self.dirty = true;
+                self.set_status("Recovered unsaved changes");
+            }
+            Err(e) => self.set_status(&format!("Recovery failed: {}", e)),
+        }
+        self.mode = AppMode::Normal;
+        self.sweep_stale_autosaves();
     }
 }
 
+/// Cells an undo or redo of `action` will touch.
+fn action_cells(action: Option<&Action>) -> Vec<(usize, usize)> {
+    action.map_or_else(Vec::new, |a| a.mutations.iter().map(|m| (m.x, m.y)).collect())
+}
+
 impl Default for App {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// A project name without its ".kaku" extension, matched case-insensitively.
+fn strip_kaku_ext(name: &str) -> &str {
+    if name.to_ascii_lowercase().ends_with(".kaku") {
+        &name[..name.len() - ".kaku".len()]
+    } else {
+        name
+    }
+}
+
+/// Windows apps expect CRLF line endings in pasted text.
+fn clipboard_text(content: &str) -> String {
+    if cfg!(windows) {
+        content.replace('\n', "\r\n")
+    } else {
+        content.to_string()
+    }
+}
+
+/// A coarse age: "just now", "45s ago", "2m ago", "3h ago", "2d ago".
+fn format_age(d: Duration) -> String {
+    match d.as_secs() {
+        0..5 => "just now".to_string(),
+        s @ 5..60 => format!("{}s ago", s),
+        s @ 60..3600 => format!("{}m ago", s / 60),
+        s @ 3600..86400 => format!("{}h ago", s / 3600),
+        s => format!("{}d ago", s / 86400),
+    }
+}
+
+/// How long until `format_age` says something different for an age that
+/// is `d` now.
+fn age_changes_in(d: Duration) -> Duration {
+    let step = match d.as_secs() {
+        0..5 => return Duration::from_secs(5) - d,
+        5..60 => 1,
+        60..3600 => 60,
+        3600..86400 => 3600,
+        _ => 86400,
+    };
+    let into = Duration::from_secs(d.as_secs() % step) + Duration::from_nanos(d.subsec_nanos() as u64);
+    Duration::from_secs(step) - into
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command::Command;
 
     #[test]
     fn test_cycle_zoom() {
@@ -873,4 +3006,426 @@ mod tests {
         app.cycle_zoom();
         assert_eq!(app.zoom, 1);
     }
+
+    #[test]
+    fn test_edit_cell_without_change_is_not_an_undo_step() {
+        let mut app = App::new();
+        let same = app.canvas.get(2, 3).unwrap();
+        app.edit_cell(2, 3, same);
+        assert!(app.history.is_empty());
+        assert!(!app.dirty);
+
+        let red = cell::Cell { ch: blocks::FULL, fg: Some(Rgb::new(205, 0, 0)), bg: None };
+        app.edit_cell(2, 3, red);
+        assert_eq!(app.canvas.get(2, 3), Some(red));
+        assert!(app.dirty);
+        assert!(!app.history.is_empty());
+    }
+
+    #[test]
+    fn test_tile_preview_zooms_out_to_fit() {
+        let mut app = App::new();
+        app.canvas = Canvas::new_with_size(32, 24);
+        // An 80x32 screen area
+        app.zoom = 4;
+        (app.viewport_w, app.viewport_h) = (20, 16);
+        (app.viewport_x, app.viewport_y) = (5, 5);
+        app.toggle_tile_preview();
+        assert!(app.tile_preview);
+        assert_eq!((app.zoom, app.viewport_w, app.viewport_h), (2, 40, 32));
+        assert_eq!((app.viewport_x, app.viewport_y), (0, 0));
+        app.toggle_tile_preview();
+        assert!(!app.tile_preview);
+
+        // Too big even at 1x: say why nothing shows
+        app.canvas = Canvas::new_with_size(128, 128);
+        app.toggle_tile_preview();
+        assert!(app.tile_preview);
+        assert_eq!(app.zoom, 2);
+        assert!(app.status_message.as_ref().unwrap().text.contains("once the whole canvas fits"));
+    }
+
+    #[test]
+    fn test_new_canvas_undo_restores_previous() {
+        let mut app = App::new();
+        app.apply_tool(1, 1);
+        app.project_path = Some("art.kaku".to_string());
+        let before = app.canvas.get(1, 1);
+
+        app.new_canvas(16, 16);
+        assert_eq!(app.canvas.width, 16);
+        assert!(app.project_path.is_none());
+
+        app.undo();
+        assert_eq!(app.canvas.width, canvas::DEFAULT_WIDTH);
+        assert_eq!(app.canvas.get(1, 1), before);
+        assert_eq!(app.project_path.as_deref(), Some("art.kaku"));
+        assert!(app.history.can_undo());
+    }
+
+    #[test]
+    fn test_restoring_other_size_version_is_undoable() {
+        let mut app = App::new();
+        app.apply_tool(1, 1);
+        let painted = app.canvas.clone();
+        app.versions = vec![Version::new(&Canvas::new_with_size(16, 16))];
+        app.versions_selected = 0;
+
+        app.restore_version();
+        assert_eq!(app.canvas.width, 16);
+        app.undo();
+        assert_eq!(app.canvas, painted);
+        assert!(app.history.can_undo(), "earlier steps survive the restore");
+        app.redo();
+        assert_eq!(app.canvas.width, 16);
+    }
+
+    #[test]
+    fn test_copied_cells_paste_into_another_tab() {
+        let dir = std::env::temp_dir().join("kaku_test_tab_paste");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("other.kaku").to_string_lossy().into_owned();
+        Project::new("other", Canvas::new_with_size(16, 16), Rgb::WHITE, SymmetryMode::Off)
+            .save_to_file(Path::new(&path))
+            .unwrap();
+
+        let mut app = App::new();
+        app.execute(Command::Paste);
+        assert!(app.history.is_empty());
+        app.apply_tool(3, 2);
+        app.apply_tool(4, 3);
+        let drawn = app.canvas.get(3, 2).unwrap();
+        app.execute(Command::SelectActiveBlock);
+        app.execute(Command::Copy);
+        assert_eq!(app.copied_cells.len(), 2);
+
+        app.execute(Command::OpenFileInTab(path));
+        app.execute(Command::Hover(Some((14, 0))));
+        app.execute(Command::Paste);
+        assert_eq!(app.canvas.get(14, 0), Some(drawn));
+        assert_eq!(app.canvas.get(15, 1), Some(drawn));
+        assert!(app.canvas.get(15, 0).unwrap().is_empty());
+        // One undo step takes the whole paste back
+        app.undo();
+        assert!(app.canvas.get(14, 0).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tabs_keep_each_document() {
+        let dir = std::env::temp_dir().join("kaku_test_tabs");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("other.kaku").to_string_lossy().into_owned();
+        Project::new("other", Canvas::new_with_size(16, 16), Rgb::WHITE, SymmetryMode::Off)
+            .save_to_file(Path::new(&path))
+            .unwrap();
+
+        let mut app = App::new();
+        app.apply_tool(1, 1);
+        app.execute(Command::OpenFileInTab(path.clone()));
+        assert_eq!(app.canvas.width, 16);
+        assert_eq!(app.tab_labels(), vec![("untitled*".to_string(), false), ("other".to_string(), true)]);
+
+        // Each tab keeps its own canvas, history and unsaved state
+        app.execute(Command::NextTab);
+        assert_eq!(app.active_document, 0);
+        assert!(app.dirty && app.history.can_undo());
+        assert!(!app.canvas.get(1, 1).unwrap().is_empty());
+        app.execute(Command::OpenFileInTab(path.clone()));
+        assert_eq!(app.active_document, 1, "an open file switches to its tab");
+        assert!(!app.history.can_undo());
+
+        // Quit closes the tab; the last one quits
+        app.execute(Command::Quit);
+        assert!(app.running && app.documents.is_empty());
+        assert_eq!(app.canvas.width, canvas::DEFAULT_WIDTH);
+        app.execute(Command::Quit);
+        assert_eq!(app.mode, AppMode::Quitting);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_new_canvas_undo_expires_after_edit() {
+        let mut app = App::new();
+        app.new_canvas(16, 16);
+        app.apply_tool(0, 0);
+        assert!(app.last_file_op.is_none());
+
+        app.undo();
+        assert_eq!(app.canvas.width, 16);
+        assert_eq!(app.canvas.get(0, 0), Some(crate::cell::Cell::default()));
+    }
+
+    #[test]
+    fn test_bulk_edits_are_single_labeled_undo_steps() {
+        let mut app = App::new();
+        app.active_tool = ToolKind::Line;
+        app.apply_tool(0, 0);
+        app.apply_tool(5, 0);
+        app.select_active_block();
+        app.color = crate::cell::Rgb::new(0, 200, 0);
+        app.recolor_selection();
+        assert_eq!(app.history.len(), 2);
+        assert_eq!(app.history.peek_undo().unwrap().label, "Recolor");
+
+        app.active_tool = ToolKind::Fill;
+        app.apply_tool(0, 5);
+        assert_eq!(app.history.len(), 3, "a fill is one step, not one per cell");
+
+        // Bulk edits made mid-stroke join the stroke
+        app.active_tool = ToolKind::Pencil;
+        app.color = crate::cell::Rgb::new(200, 0, 0);
+        app.begin_stroke();
+        app.recolor_selection();
+        app.paint_run(3, 0, 1);
+        app.end_stroke();
+        assert_eq!(app.history.len(), 4);
+
+        app.undo();
+        assert_eq!(app.status_message.as_ref().unwrap().text, "Undo: Pencil");
+        app.undo();
+        assert_eq!(app.status_message.as_ref().unwrap().text, "Undo: Fill");
+    }
+
+    #[test]
+    fn test_journal_replays_edits_and_clears_on_save() {
+        let dir = std::env::temp_dir().join("kaku_test_app_journal");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("art.kaku").to_string_lossy().into_owned();
+
+        let mut app = App::new();
+        app.journaling = true;
+        app.project_path = Some(path.clone());
+        app.apply_tool(1, 1);
+        app.apply_tool(2, 1);
+        app.undo();
+        app.redo();
+        app.undo();
+        app.apply_tool(3, 3);
+        let journal = app.journal_path();
+        app.sync_journal();
+        assert_eq!(journal::replay(&journal).unwrap(), app.canvas);
+
+        // A new session on the same file offers the journal back
+        let mut after_crash = App::new();
+        after_crash.project_path = Some(path.clone());
+        after_crash.check_recovery();
+        assert_eq!(after_crash.mode, AppMode::Recovery);
+        assert!(after_crash.recovering_journal());
+        after_crash.recover_autosave();
+        assert_eq!(after_crash.canvas, app.canvas);
+        assert_eq!(after_crash.project_path.as_deref(), Some(path.as_str()));
+        assert!(after_crash.dirty);
+
+        assert!(app.save_project());
+        assert!(!journal.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_deleted_project_and_log_come_back_with_undo() {
+        let dir = std::env::temp_dir().join("kaku_test_app_trash_project");
+        let _ = std::fs::remove_dir_all(&dir);
+        let trash_dir = dir.join("trash");
+        for sub in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+            std::fs::write(dir.join(sub).join("art.kaku"), sub).unwrap();
+        }
+        let path = dir.join("a").join("art.kaku");
+        std::fs::write(crate::oplog::log_path(&path), "log").unwrap();
+        let filename = path.to_string_lossy().into_owned();
+
+        let mut app = App::new();
+        app.trash_project(&dir.join("b").join("art.kaku").to_string_lossy(), &trash_dir);
+        app.project_path = Some(filename.clone());
+        app.trash_project(&filename, &trash_dir);
+        assert!(!path.exists());
+        assert!(app.project_path.is_none());
+
+        app.undo();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+        assert_eq!(std::fs::read_to_string(crate::oplog::log_path(&path)).unwrap(), "log");
+        assert_eq!(app.project_path.as_deref(), Some(filename.as_str()));
+        // The same-named project trashed first is still there
+        assert_eq!(trash::list_trash(&trash_dir, "kaku"), vec!["art.kaku"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stale_autosaves_are_swept_only_after_recovery_is_offered() {
+        let dir = std::env::temp_dir().join("kaku_test_app_sweep");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("art.kaku").to_string_lossy().into_owned();
+        let old = SystemTime::now() - Duration::from_secs(30 * 86400);
+        for name in ["old.kaku.autosave", "untitled.kaku.autosave"] {
+            let f = std::fs::File::create(dir.join(name)).unwrap();
+            f.set_modified(old).unwrap();
+        }
+        std::fs::write(dir.join("old.kaku"), "{}").unwrap();
+
+        let mut app = App::new();
+        app.journaling = true;
+        app.project_path = Some(path.clone());
+        app.apply_tool(1, 1);
+
+        let mut after_crash = App::new();
+        after_crash.project_path = Some(path);
+        after_crash.check_recovery();
+        assert_eq!(after_crash.mode, AppMode::Recovery);
+        assert!(dir.join("old.kaku.autosave").exists(), "not swept before the prompt");
+        after_crash.decline_recovery();
+        assert!(!dir.join("old.kaku.autosave").exists());
+        assert!(dir.join("untitled.kaku.autosave").exists(), "never-saved work is kept");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_indicator() {
+        let mut app = App::new();
+        let now = SystemTime::now();
+        assert_eq!(app.save_indicator(now), None);
+        app.last_saved = Some(now - Duration::from_secs(150));
+        assert_eq!(app.save_indicator(now).as_deref(), Some("saved 2m ago"));
+        app.last_autosave = Some(now - Duration::from_secs(2));
+        assert_eq!(app.save_indicator(now).as_deref(), Some("autosaved just now, saved 2m ago"));
+        app.last_saved = Some(now);
+        assert_eq!(app.save_indicator(now).as_deref(), Some("saved just now"));
+        assert_eq!(format_age(Duration::from_secs(3 * 86400)), "3d ago");
+    }
+
+    #[test]
+    fn test_selection_ansi_is_cropped_to_selected_cells() {
+        let mut app = App::new();
+        app.copy_selection_ansi();
+        assert_eq!(app.status_message.as_ref().unwrap().text, "No selection to copy");
+
+        let red = Some(Rgb::new(205, 0, 0));
+        app.canvas.set(2, 1, cell::Cell { ch: blocks::FULL, fg: red, bg: None });
+        app.canvas.set(9, 5, cell::Cell { ch: blocks::SHADE_LIGHT, fg: red, bg: None });
+        app.active_block = blocks::FULL;
+        app.select_active_block();
+        let region = app.selection.as_ref().unwrap().extract(&app.canvas);
+        let ansi = app.ansi_content(&region);
+        assert_eq!(ansi.lines().count(), 1);
+        assert!(ansi.contains(blocks::FULL) && !ansi.contains(blocks::SHADE_LIGHT));
+    }
+
+    #[test]
+    fn test_safe_mode_skips_autosave_clipboard_and_themes() {
+        let mut app = App::new();
+        app.safe_mode = true;
+        app.dirty = true;
+        app.auto_save_since = Instant::now() - AUTO_SAVE_INTERVAL;
+        app.tick_auto_save();
+        assert!(app.last_autosave.is_none());
+        assert!(app.next_deadline().is_none());
+
+        app.cycle_theme();
+        assert_eq!(app.theme_index, 0);
+        app.copy_to_clipboard("x", "Copied");
+        assert!(app.status_message.as_ref().unwrap().text.contains("off in safe mode"));
+    }
+
+    #[test]
+    fn test_status_expires_by_time() {
+        let mut app = App::new();
+        app.set_status("hello");
+        app.tick_status();
+        assert!(app.status_message.is_some());
+
+        app.status_message.as_mut().unwrap().expires_at = Instant::now();
+        app.tick_status();
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        let mut app = App::new();
+        assert!(app.next_deadline().is_none());
+
+        app.dirty = true;
+        app.tick_auto_save();
+        assert_eq!(app.next_deadline(), Some(app.auto_save_since + AUTO_SAVE_INTERVAL));
+
+        app.set_status("saved");
+        let expires = app.status_message.as_ref().unwrap().expires_at;
+        assert_eq!(app.next_deadline(), Some(expires));
+
+        // An idle app still wakes when "saved 2m ago" would go stale
+        let mut app = App::new();
+        app.last_saved = Some(SystemTime::now() - Duration::from_secs(150));
+        let wait = app.next_deadline().unwrap() - Instant::now();
+        assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30), "{:?}", wait);
+    }
+
+    #[test]
+    fn test_age_changes_in() {
+        assert_eq!(age_changes_in(Duration::from_secs(2)), Duration::from_secs(3));
+        assert_eq!(age_changes_in(Duration::from_millis(7_250)), Duration::from_millis(750));
+        assert_eq!(age_changes_in(Duration::from_secs(150)), Duration::from_secs(30));
+        assert_eq!(age_changes_in(Duration::from_secs(3600)), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_marked_palette_colors_move_and_delete_together() {
+        let file = std::env::temp_dir().join(format!("kaku_test_marks_{}", std::process::id()));
+        let colors: Vec<Rgb> = (0..5).map(|i| Rgb::new(i * 10, 0, 0)).collect();
+        let mut app = App::new();
+        app.set_custom_palette(Some(palette::CustomPalette {
+            name: file.to_string_lossy().into_owned(),
+            colors: colors.clone(),
+        }));
+
+        app.palette_cursor = 1;
+        app.toggle_palette_mark();
+        app.palette_cursor = 3;
+        app.toggle_palette_mark();
+        app.move_palette_colors(1);
+        let moved = &app.custom_palette.as_ref().unwrap().colors;
+        assert_eq!(moved, &[colors[0], colors[2], colors[1], colors[4], colors[3]]);
+        assert_eq!(app.palette_marks, BTreeSet::from([2, 4]));
+        assert_eq!(app.palette_cursor, 4);
+
+        // The last color can't move further, so neither does the group
+        app.move_palette_colors(1);
+        assert_eq!(app.palette_marks, BTreeSet::from([2, 4]));
+
+        app.delete_palette_colors();
+        assert_eq!(app.custom_palette.as_ref().unwrap().colors, [colors[0], colors[2], colors[4]]);
+        assert!(app.palette_marks.is_empty());
+        let saved = palette::load_palette(&file.with_extension("palette")).unwrap();
+        assert_eq!(saved.colors.len(), 3);
+
+        // Without marks nothing is deleted, not even the color under the cursor
+        app.delete_palette_colors();
+        assert_eq!(app.custom_palette.as_ref().unwrap().colors.len(), 3);
+
+        // Undo brings the removed colors back, on disk too
+        app.undo();
+        let restored = &app.custom_palette.as_ref().unwrap().colors;
+        assert_eq!(restored, &[colors[0], colors[2], colors[1], colors[4], colors[3]]);
+        let saved = palette::load_palette(&file.with_extension("palette")).unwrap();
+        assert_eq!(saved.colors.len(), 5);
+        let _ = std::fs::remove_file(file.with_extension("palette"));
+    }
+
+    #[test]
+    fn test_toggle_palette_order_keeps_cursor_color() {
+        let mut app = App::new();
+        app.palette_sections.hue_expanded = true;
+        app.rebuild_palette_layout();
+        let start = app.palette_layout.iter().position(|&it| it == PaletteItem::SectionHeader(PaletteSection::HueGroups)).unwrap();
+        app.palette_cursor = start + 3;
+        let item = app.palette_layout[app.palette_cursor];
+
+        app.toggle_palette_order();
+        assert!(!app.palette_perceptual);
+        assert_eq!(app.palette_layout[app.palette_cursor], item);
+        assert!(app.palette_cursor > start);
+    }
 }
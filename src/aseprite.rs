@@ -0,0 +1,529 @@
+//! Aseprite `.ase`/`.aseprite` files, flattened: each frame's visible
+//! layers are composited into one image that the regular image import then
+//! converts. Blend modes other than normal are drawn as normal, and tilemap
+//! layers are skipped.
+
+use std::io::Read;
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+const FILE_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_BACKGROUND: u16 = 8;
+const LAYER_REFERENCE: u16 = 64;
+const LAYER_GROUP: u16 = 1;
+/// Header flag: layer opacity is stored, rather than always opaque.
+const HAS_LAYER_OPACITY: u32 = 1;
+
+/// Largest sprite or cel side accepted, so a corrupt header can't ask for
+/// gigabytes of pixels.
+const MAX_SIDE: u32 = 8192;
+
+/// Whether a path looks like an Aseprite file.
+pub fn is_aseprite(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("ase") || e.eq_ignore_ascii_case("aseprite"))
+}
+
+/// How many frames an Aseprite file has, from its header alone.
+pub fn frame_count(path: &Path) -> Result<usize, String> {
+    let mut header = [0u8; 8];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map_err(|e| format!("Read error: {}", e))?;
+    if u16::from_le_bytes([header[4], header[5]]) != FILE_MAGIC {
+        return Err("Not an Aseprite file".to_string());
+    }
+    Ok(u16::from_le_bytes([header[6], header[7]]) as usize)
+}
+
+/// Every frame of an Aseprite file, with its layers flattened.
+pub fn load(path: &Path) -> Result<Vec<RgbaImage>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Read error: {}", e))?;
+    parse(&data)
+}
+
+struct Layer {
+    /// Visible itself and inside visible groups
+    visible: bool,
+    background: bool,
+    opacity: u8,
+}
+
+#[derive(Clone)]
+struct Cel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    z_index: i16,
+    width: u32,
+    /// Pixels in the file's color depth, row by row
+    pixels: Vec<u8>,
+}
+
+/// Flatten every frame of an Aseprite file held in memory.
+pub fn parse(data: &[u8]) -> Result<Vec<RgbaImage>, String> {
+    let mut header = Reader::new(data.get(..HEADER_SIZE).ok_or("Aseprite file is truncated")?);
+    header.skip(4)?;
+    if header.u16()? != FILE_MAGIC {
+        return Err("Not an Aseprite file".to_string());
+    }
+    let frames = header.u16()? as usize;
+    let width = header.u16()? as u32;
+    let height = header.u16()? as u32;
+    let depth = header.u16()?;
+    let flags = header.u32()?;
+    header.skip(10)?;
+    let transparent_index = header.u8()?;
+    let bytes_per_pixel = match depth {
+        32 => 4,
+        16 => 2,
+        8 => 1,
+        _ => return Err(format!("Unsupported Aseprite color depth {}", depth)),
+    };
+    if width == 0 || height == 0 || width > MAX_SIDE || height > MAX_SIDE {
+        return Err(format!("Unsupported Aseprite sprite size {}x{}", width, height));
+    }
+
+    let mut layers: Vec<Layer> = Vec::new();
+    // Visibility of the groups enclosing the next layer, by nesting level
+    let mut groups: Vec<bool> = Vec::new();
+    let mut palette = vec![[0u8; 4]; 256];
+    let mut new_palette = false;
+    let mut frame_cels: Vec<Vec<Cel>> = Vec::with_capacity(frames);
+    let mut images = Vec::with_capacity(frames);
+    let mut rest = &data[HEADER_SIZE..];
+
+    for _ in 0..frames {
+        let mut frame = Reader::new(rest.get(..FRAME_HEADER_SIZE).ok_or("Aseprite frame is truncated")?);
+        let frame_size = frame.u32()? as usize;
+        if frame.u16()? != FRAME_MAGIC || frame_size < FRAME_HEADER_SIZE {
+            return Err("Aseprite frame header is corrupt".to_string());
+        }
+        let old_chunks = frame.u16()? as usize;
+        frame.skip(4)?;
+        let chunks = match frame.u32()? as usize {
+            0 => old_chunks,
+            n => n,
+        };
+        let body = rest.get(FRAME_HEADER_SIZE..frame_size).ok_or("Aseprite frame is truncated")?;
+        rest = &rest[frame_size..];
+
+        let mut cels = Vec::new();
+        let mut chunk_data = body;
+        for _ in 0..chunks {
+            let mut chunk = Reader::new(chunk_data);
+            let size = chunk.u32()? as usize;
+            let kind = chunk.u16()?;
+            let content = chunk_data.get(6..size).ok_or("Aseprite chunk is truncated")?;
+            chunk_data = &chunk_data[size..];
+            let mut r = Reader::new(content);
+            match kind {
+                CHUNK_LAYER => {
+                    let layer_flags = r.u16()?;
+                    let kind = r.u16()?;
+                    let level = r.u16()? as usize;
+                    r.skip(6)?;
+                    let opacity = r.u8()?;
+                    groups.truncate(level);
+                    let visible = layer_flags & LAYER_VISIBLE != 0
+                        && layer_flags & LAYER_REFERENCE == 0
+                        && groups.iter().all(|&v| v);
+                    if kind == LAYER_GROUP {
+                        groups.resize(level, true);
+                        groups.push(visible);
+                    }
+                    layers.push(Layer {
+                        visible: visible && kind != LAYER_GROUP,
+                        background: layer_flags & LAYER_BACKGROUND != 0,
+                        opacity: if flags & HAS_LAYER_OPACITY != 0 { opacity } else { 255 },
+                    });
+                }
+                CHUNK_CEL => {
+                    if let Some(cel) = read_cel(&mut r, bytes_per_pixel, &frame_cels)? {
+                        cels.push(cel);
+                    }
+                }
+                CHUNK_PALETTE => {
+                    new_palette = true;
+                    let size = r.u32()? as usize;
+                    let first = r.u32()? as usize;
+                    let last = r.u32()? as usize;
+                    r.skip(8)?;
+                    palette.resize(size.clamp(256, 65536), [0; 4]);
+                    for i in first..=last {
+                        let entry_flags = r.u16()?;
+                        let color = [r.u8()?, r.u8()?, r.u8()?, r.u8()?];
+                        if let Some(slot) = palette.get_mut(i) {
+                            *slot = color;
+                        }
+                        if entry_flags & 1 != 0 {
+                            let len = r.u16()? as usize;
+                            r.skip(len)?;
+                        }
+                    }
+                }
+                // Written for older readers; the new chunk has alpha
+                CHUNK_OLD_PALETTE if !new_palette => {
+                    let mut index = 0;
+                    for _ in 0..r.u16()? {
+                        index += r.u8()? as usize;
+                        let count = match r.u8()? {
+                            0 => 256,
+                            n => n as usize,
+                        };
+                        for _ in 0..count {
+                            let color = [r.u8()?, r.u8()?, r.u8()?, 255];
+                            if let Some(slot) = palette.get_mut(index) {
+                                *slot = color;
+                            }
+                            index += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut image = RgbaImage::new(width, height);
+        let mut drawn: Vec<&Cel> = cels.iter().filter(|c| layers.get(c.layer).is_some_and(|l| l.visible)).collect();
+        drawn.sort_by_key(|c| (c.layer as i32 + c.z_index as i32, c.z_index));
+        for cel in drawn {
+            let layer = &layers[cel.layer];
+            let opacity = cel.opacity as u32 * layer.opacity as u32 / 255;
+            let clear = (depth == 8 && !layer.background).then_some(transparent_index);
+            draw_cel(&mut image, cel, bytes_per_pixel, opacity as u8, &palette, clear);
+        }
+        images.push(image);
+        frame_cels.push(cels);
+    }
+    if images.is_empty() {
+        return Err("Aseprite file has no frames".to_string());
+    }
+    Ok(images)
+}
+
+/// A cel chunk's pixels, or the cel it links to. Tilemap cels are skipped.
+fn read_cel(r: &mut Reader, bytes_per_pixel: usize, frames: &[Vec<Cel>]) -> Result<Option<Cel>, String> {
+    let layer = r.u16()? as usize;
+    let x = r.i16()? as i32;
+    let y = r.i16()? as i32;
+    let opacity = r.u8()?;
+    let kind = r.u16()?;
+    let z_index = r.i16()?;
+    r.skip(5)?;
+    let (width, height) = match kind {
+        0 | 2 => (r.u16()? as u32, r.u16()? as u32),
+        1 => {
+            let linked = r.u16()? as usize;
+            let cel = frames.get(linked).and_then(|cels| cels.iter().find(|c| c.layer == layer));
+            return Ok(cel.cloned());
+        }
+        _ => return Ok(None),
+    };
+    if width > MAX_SIDE || height > MAX_SIDE {
+        return Err(format!("Aseprite cel is too large: {}x{}", width, height));
+    }
+    let len = width as usize * height as usize * bytes_per_pixel;
+    let pixels = if kind == 0 {
+        r.bytes(len)?.to_vec()
+    } else {
+        let mut pixels = Vec::with_capacity(len);
+        flate2::read::ZlibDecoder::new(r.rest())
+            .take(len as u64)
+            .read_to_end(&mut pixels)
+            .map_err(|e| format!("Aseprite cel is corrupt: {}", e))?;
+        pixels
+    };
+    if pixels.len() != len {
+        return Err("Aseprite cel is truncated".to_string());
+    }
+    Ok(Some(Cel { layer, x, y, opacity, z_index, width, pixels }))
+}
+
+/// Blend a cel onto the frame with normal blending. `clear` is the palette
+/// index that stands for transparent in indexed sprites.
+fn draw_cel(image: &mut RgbaImage, cel: &Cel, bytes_per_pixel: usize, opacity: u8, palette: &[[u8; 4]], clear: Option<u8>) {
+    for (i, px) in cel.pixels.chunks_exact(bytes_per_pixel).enumerate() {
+        let x = cel.x + (i as u32 % cel.width) as i32;
+        let y = cel.y + (i as u32 / cel.width) as i32;
+        if x < 0 || y < 0 || x as u32 >= image.width() || y as u32 >= image.height() {
+            continue;
+        }
+        let src = match *px {
+            [r, g, b, a] => [r, g, b, a],
+            [v, a] => [v, v, v, a],
+            [index] if Some(index) == clear => continue,
+            [index] => palette.get(index as usize).copied().unwrap_or([0; 4]),
+            _ => continue,
+        };
+        let dst = image.get_pixel_mut(x as u32, y as u32);
+        *dst = blend(*dst, src, opacity);
+    }
+}
+
+/// `src` over `dst`, with straight alpha.
+fn blend(dst: Rgba<u8>, src: [u8; 4], opacity: u8) -> Rgba<u8> {
+    let sa = src[3] as f32 / 255.0 * opacity as f32 / 255.0;
+    let da = dst[3] as f32 / 255.0;
+    let out = sa + da * (1.0 - sa);
+    if out <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel = |c: usize| ((src[c] as f32 * sa + dst[c] as f32 * da * (1.0 - sa)) / out).round() as u8;
+    Rgba([channel(0), channel(1), channel(2), (out * 255.0).round() as u8])
+}
+
+/// Little-endian reads that fail on truncated data instead of panicking.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if n > self.data.len() {
+            return Err("Aseprite file is truncated".to_string());
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), String> {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.data)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Builds Aseprite files chunk by chunk, for tests.
+    struct Builder {
+        width: u16,
+        height: u16,
+        depth: u16,
+        frames: Vec<Vec<(u16, Vec<u8>)>>,
+    }
+
+    impl Builder {
+        fn new(width: u16, height: u16, depth: u16) -> Self {
+            Builder { width, height, depth, frames: Vec::new() }
+        }
+
+        fn frame(mut self) -> Self {
+            self.frames.push(Vec::new());
+            self
+        }
+
+        fn chunk(mut self, kind: u16, data: Vec<u8>) -> Self {
+            self.frames.last_mut().unwrap().push((kind, data));
+            self
+        }
+
+        /// A normal layer, or a group, at a nesting level.
+        fn layer(self, flags: u16, group: bool, level: u16, opacity: u8) -> Self {
+            let mut d = Vec::new();
+            d.extend(flags.to_le_bytes());
+            d.extend((group as u16).to_le_bytes());
+            d.extend(level.to_le_bytes());
+            d.extend([0; 6]);
+            d.push(opacity);
+            d.extend([0; 3]);
+            d.extend(4u16.to_le_bytes());
+            d.extend(b"name");
+            self.chunk(CHUNK_LAYER, d)
+        }
+
+        fn cel_header(layer: u16, x: i16, y: i16, kind: u16) -> Vec<u8> {
+            let mut d = Vec::new();
+            d.extend(layer.to_le_bytes());
+            d.extend(x.to_le_bytes());
+            d.extend(y.to_le_bytes());
+            d.push(255);
+            d.extend(kind.to_le_bytes());
+            d.extend(0i16.to_le_bytes());
+            d.extend([0; 5]);
+            d
+        }
+
+        /// A cel of raw pixels, or zlib-compressed ones.
+        fn cel(self, layer: u16, (x, y): (i16, i16), (w, h): (u16, u16), pixels: &[u8], compress: bool) -> Self {
+            let mut d = Self::cel_header(layer, x, y, if compress { 2 } else { 0 });
+            d.extend(w.to_le_bytes());
+            d.extend(h.to_le_bytes());
+            if compress {
+                let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                z.write_all(pixels).unwrap();
+                d.extend(z.finish().unwrap());
+            } else {
+                d.extend(pixels);
+            }
+            self.chunk(CHUNK_CEL, d)
+        }
+
+        fn linked_cel(self, layer: u16, frame: u16) -> Self {
+            let mut d = Self::cel_header(layer, 0, 0, 1);
+            d.extend(frame.to_le_bytes());
+            self.chunk(CHUNK_CEL, d)
+        }
+
+        fn palette(self, colors: &[[u8; 4]]) -> Self {
+            let mut d = Vec::new();
+            d.extend((colors.len() as u32).to_le_bytes());
+            d.extend(0u32.to_le_bytes());
+            d.extend((colors.len() as u32 - 1).to_le_bytes());
+            d.extend([0; 8]);
+            for c in colors {
+                d.extend(0u16.to_le_bytes());
+                d.extend(c);
+            }
+            self.chunk(CHUNK_PALETTE, d)
+        }
+
+        fn build(self) -> Vec<u8> {
+            let mut body = Vec::new();
+            for chunks in &self.frames {
+                let data: Vec<u8> = chunks
+                    .iter()
+                    .flat_map(|(kind, d)| {
+                        let mut c = ((d.len() + 6) as u32).to_le_bytes().to_vec();
+                        c.extend(kind.to_le_bytes());
+                        c.extend(d);
+                        c
+                    })
+                    .collect();
+                body.extend(((data.len() + FRAME_HEADER_SIZE) as u32).to_le_bytes());
+                body.extend(FRAME_MAGIC.to_le_bytes());
+                body.extend((chunks.len() as u16).to_le_bytes());
+                body.extend(100u16.to_le_bytes());
+                body.extend([0; 2]);
+                body.extend((chunks.len() as u32).to_le_bytes());
+                body.extend(data);
+            }
+            let mut file = Vec::new();
+            file.extend(((HEADER_SIZE + body.len()) as u32).to_le_bytes());
+            file.extend(FILE_MAGIC.to_le_bytes());
+            file.extend((self.frames.len() as u16).to_le_bytes());
+            file.extend(self.width.to_le_bytes());
+            file.extend(self.height.to_le_bytes());
+            file.extend(self.depth.to_le_bytes());
+            file.extend(HAS_LAYER_OPACITY.to_le_bytes());
+            file.resize(HEADER_SIZE, 0);
+            file.extend(body);
+            file
+        }
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    #[test]
+    fn test_flattens_visible_layers_in_order() {
+        let file = Builder::new(4, 2, 32)
+            .frame()
+            .layer(LAYER_VISIBLE, false, 0, 255)
+            .layer(LAYER_VISIBLE, false, 0, 255)
+            .layer(0, false, 0, 255)
+            .cel(0, (0, 0), (2, 1), &[RED, RED].concat(), false)
+            .cel(1, (1, 0), (1, 2), &[BLUE, BLUE].concat(), true)
+            .cel(2, (0, 0), (4, 2), &[[0, 255, 0, 255]; 8].concat(), false)
+            .build();
+        let frames = parse(&file).unwrap();
+        assert_eq!(frames.len(), 1);
+        let img = &frames[0];
+        assert_eq!(img.get_pixel(0, 0), &Rgba(RED));
+        // The upper layer covers the lower one
+        assert_eq!(img.get_pixel(1, 0), &Rgba(BLUE));
+        assert_eq!(img.get_pixel(1, 1), &Rgba(BLUE));
+        // The hidden layer isn't drawn
+        assert_eq!(img.get_pixel(3, 1), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_layer_opacity_and_hidden_groups() {
+        let file = Builder::new(2, 1, 32)
+            .frame()
+            .layer(LAYER_VISIBLE, false, 0, 255)
+            .layer(LAYER_VISIBLE, false, 0, 128)
+            .layer(0, true, 0, 255)
+            .layer(LAYER_VISIBLE, false, 1, 255)
+            .cel(0, (0, 0), (2, 1), &[[0, 0, 0, 255]; 2].concat(), false)
+            .cel(1, (0, 0), (1, 1), &[255, 255, 255, 255], false)
+            .cel(3, (1, 0), (1, 1), &RED, false)
+            .build();
+        let img = &parse(&file).unwrap()[0];
+        assert_eq!(img.get_pixel(0, 0), &Rgba([128, 128, 128, 255]));
+        // Inside a hidden group
+        assert_eq!(img.get_pixel(1, 0), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_indexed_frames_and_linked_cels() {
+        let file = Builder::new(2, 1, 8)
+            .frame()
+            .palette(&[[0, 0, 0, 255], RED, BLUE])
+            .layer(LAYER_VISIBLE, false, 0, 255)
+            .cel(0, (0, 0), (2, 1), &[1, 0], false)
+            .frame()
+            .linked_cel(0, 0)
+            .build();
+        let frames = parse(&file).unwrap();
+        assert_eq!(frames.len(), 2);
+        for img in &frames {
+            assert_eq!(img.get_pixel(0, 0), &Rgba(RED));
+            // Index 0 is transparent on a layer that isn't the background
+            assert_eq!(img.get_pixel(1, 0), &Rgba([0, 0, 0, 0]));
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_files() {
+        assert!(parse(b"not an aseprite file").is_err());
+        let file = Builder::new(2, 2, 32).frame().layer(LAYER_VISIBLE, false, 0, 255).cel(0, (0, 0), (2, 2), &RED, false).build();
+        assert_eq!(parse(&file).unwrap_err(), "Aseprite file is truncated");
+        let mut cut = Builder::new(2, 2, 32).frame().build();
+        cut.truncate(HEADER_SIZE + 8);
+        assert!(parse(&cut).is_err());
+        assert!(parse(&Builder::new(2, 2, 24).frame().build()).unwrap_err().contains("color depth"));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::cell::Cell;
+use crate::cell::{Cell, Rgb};
 
 pub const DEFAULT_WIDTH: usize = 48;
 pub const DEFAULT_HEIGHT: usize = 32;
@@ -10,7 +10,35 @@ pub const MAX_DIMENSION: usize = 128;
 fn default_width() -> usize { DEFAULT_WIDTH }
 fn default_height() -> usize { DEFAULT_HEIGHT }
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Where existing art lands when a canvas is resized: on each axis 0 keeps
+/// it at the left or top, 1 centers it, 2 keeps it at the right or bottom.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Anchor {
+    pub const CENTER: Anchor = Anchor { x: 1, y: 1 };
+
+    /// The anchor for a numeric keypad digit: 7 is top-left, 5 the center,
+    /// 3 bottom-right.
+    pub fn from_keypad(digit: u8) -> Option<Anchor> {
+        let i = digit.checked_sub(1).filter(|i| *i < 9)?;
+        Some(Anchor { x: i % 3, y: 2 - i / 3 })
+    }
+
+    pub fn name(self) -> &'static str {
+        const NAMES: [&str; 9] = [
+            "top-left", "top", "top-right",
+            "left", "center", "right",
+            "bottom-left", "bottom", "bottom-right",
+        ];
+        NAMES[(self.y.min(2) * 3 + self.x.min(2)) as usize]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Canvas {
     cells: Vec<Vec<Cell>>,
     #[serde(default = "default_width")]
@@ -48,25 +76,112 @@ impl Canvas {
         }
     }
 
+    /// Distinct colors of the drawn (non-blank) cells, in reading order.
+    pub fn used_colors(&self) -> Vec<Rgb> {
+        let mut colors = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for cell in self.cells.iter().flatten().filter(|c| !c.is_empty()) {
+            for color in [cell.fg, cell.bg].into_iter().flatten() {
+                if seen.insert((color.r, color.g, color.b)) {
+                    colors.push(color);
+                }
+            }
+        }
+        colors
+    }
+
+    /// Heap bytes held by the cell grid, including unused capacity.
+    pub fn allocated_bytes(&self) -> usize {
+        self.cells.capacity() * std::mem::size_of::<Vec<Cell>>()
+            + self.cells.iter().map(|row| row.capacity() * std::mem::size_of::<Cell>()).sum::<usize>()
+    }
+
     #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.cells = vec![vec![Cell::default(); self.width]; self.height];
     }
 
     /// Resize the canvas, preserving existing content where it overlaps.
-    #[allow(dead_code)]
     pub fn resize(&mut self, new_width: usize, new_height: usize) {
+        self.resize_anchored(new_width, new_height, Anchor::default());
+    }
+
+    /// Resize the canvas, keeping existing content at `anchor`. Growing adds
+    /// blank cells on the other sides; shrinking crops them.
+    pub fn resize_anchored(&mut self, new_width: usize, new_height: usize, anchor: Anchor) {
         let w = new_width.clamp(MIN_DIMENSION, MAX_DIMENSION);
         let h = new_height.clamp(MIN_DIMENSION, MAX_DIMENSION);
-        let mut new_cells = vec![vec![Cell::default(); w]; h];
-        let copy_w = w.min(self.width);
-        let copy_h = h.min(self.height);
-        for (y, new_row) in new_cells.iter_mut().enumerate().take(copy_h) {
-            new_row[..copy_w].copy_from_slice(&self.cells[y][..copy_w]);
+        // How far the old content moves, possibly past the new edges
+        let shift = |old: usize, new: usize, a: u8| (new as isize - old as isize) * a.min(2) as isize / 2;
+        let dx = shift(self.width, w, anchor.x);
+        let dy = shift(self.height, h, anchor.y);
+        let mut resized = Canvas::new_with_size(w, h);
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx >= 0 && ny >= 0 {
+                    resized.set(nx as usize, ny as usize, *cell);
+                }
+            }
+        }
+        *self = resized;
+    }
+
+    /// A copy with every cell moved `dx` right and `dy` down. Cells pushed
+    /// off one edge come back on the other with `wrap`, or are dropped.
+    pub fn shifted(&self, dx: isize, dy: isize, wrap: bool) -> Canvas {
+        let (w, h) = (self.width as isize, self.height as isize);
+        let mut shifted = Canvas::new_with_size(self.width, self.height);
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let (mut nx, mut ny) = (x as isize + dx, y as isize + dy);
+                if wrap {
+                    nx = nx.rem_euclid(w);
+                    ny = ny.rem_euclid(h);
+                }
+                if (0..w).contains(&nx) && (0..h).contains(&ny) {
+                    shifted.set(nx as usize, ny as usize, *cell);
+                }
+            }
+        }
+        shifted
+    }
+
+    /// A copy of the cells from `(min_x, min_y)` to `(max_x, max_y)`
+    /// inclusive. A region under the minimum size grows right and down, or
+    /// left and up near the far edges, to reach it.
+    pub fn cropped(&self, (min_x, min_y, max_x, max_y): (usize, usize, usize, usize)) -> Canvas {
+        let w = (max_x + 1 - min_x).clamp(MIN_DIMENSION, self.width);
+        let h = (max_y + 1 - min_y).clamp(MIN_DIMENSION, self.height);
+        let (x0, y0) = (min_x.min(self.width - w), min_y.min(self.height - h));
+        let mut cropped = Canvas::new_with_size(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                cropped.set(x, y, self.cells[y0 + y][x0 + x]);
+            }
         }
-        self.cells = new_cells;
-        self.width = w;
-        self.height = h;
+        cropped
+    }
+
+    /// Check that the stored grid matches the declared size. Deserialized
+    /// canvases come from untrusted files, and `get`/`set` index the grid
+    /// directly once a coordinate passes the bounds check.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_DIMENSION..=MAX_DIMENSION).contains(&self.width)
+            || !(MIN_DIMENSION..=MAX_DIMENSION).contains(&self.height)
+        {
+            return Err(format!(
+                "Canvas size {}x{} is outside {}-{}",
+                self.width, self.height, MIN_DIMENSION, MAX_DIMENSION
+            ));
+        }
+        if self.cells.len() != self.height || self.cells.iter().any(|row| row.len() != self.width) {
+            return Err(format!(
+                "Canvas cells don't match its {}x{} size",
+                self.width, self.height
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -84,6 +199,23 @@ mod tests {
     const RED: Option<Rgb> = Some(Rgb { r: 205, g: 0, b: 0 });
     const BLUE: Option<Rgb> = Some(Rgb { r: 0, g: 0, b: 238 });
 
+    #[test]
+    fn test_validate() {
+        assert!(Canvas::new().validate().is_ok());
+
+        let mut short = Canvas::new_with_size(8, 8);
+        short.cells.pop();
+        assert!(short.validate().is_err());
+
+        let mut ragged = Canvas::new_with_size(8, 8);
+        ragged.cells[3].push(Cell::default());
+        assert!(ragged.validate().is_err());
+
+        let mut huge = Canvas::new_with_size(8, 8);
+        huge.width = MAX_DIMENSION + 1;
+        assert!(huge.validate().is_err());
+    }
+
     #[test]
     fn test_new_canvas_is_empty() {
         let canvas = Canvas::new();
@@ -179,6 +311,56 @@ mod tests {
         assert_eq!(canvas.get(20, 20), Some(Cell::default()));
     }
 
+    #[test]
+    fn test_shifted_wraps_or_drops() {
+        let mut canvas = Canvas::new_with_size(8, 8);
+        let red = Cell { ch: blocks::FULL, fg: RED, bg: None };
+        canvas.set(7, 0, red);
+        let wrapped = canvas.shifted(1, -1, true);
+        assert_eq!(wrapped.get(0, 7), Some(red));
+        assert_eq!(wrapped.used_colors(), canvas.used_colors());
+        assert!(canvas.shifted(1, 0, false).used_colors().is_empty());
+        assert_eq!(canvas.shifted(-7, 3, false).get(0, 3), Some(red));
+    }
+
+    #[test]
+    fn test_cropped_keeps_minimum_size() {
+        let mut canvas = Canvas::new_with_size(32, 24);
+        let red = Cell { ch: blocks::FULL, fg: RED, bg: None };
+        canvas.set(30, 22, red);
+        canvas.set(4, 2, red);
+        let cropped = canvas.cropped((4, 2, 30, 22));
+        assert_eq!((cropped.width, cropped.height), (27, 21));
+        assert_eq!(cropped.get(0, 0), Some(red));
+        assert_eq!(cropped.get(26, 20), Some(red));
+
+        // A single cell near the corner grows back toward the art
+        let cropped = canvas.cropped((30, 22, 30, 22));
+        assert_eq!((cropped.width, cropped.height), (8, 8));
+        assert_eq!(cropped.get(6, 6), Some(red));
+    }
+
+    #[test]
+    fn test_resize_anchored() {
+        let cell = Cell { ch: blocks::FULL, fg: RED, bg: None };
+        let mut canvas = Canvas::new_with_size(16, 16);
+        canvas.set(0, 0, cell);
+        canvas.resize_anchored(32, 20, Anchor::CENTER);
+        assert_eq!((canvas.width, canvas.height), (32, 20));
+        assert_eq!(canvas.get(8, 2), Some(cell));
+
+        // Shrinking toward the bottom-right crops the top and left
+        canvas.resize_anchored(16, 16, Anchor::from_keypad(3).unwrap());
+        assert_eq!(canvas.get(0, 0), Some(Cell::default()));
+        canvas.set(15, 15, cell);
+        canvas.resize_anchored(8, 8, Anchor { x: 2, y: 2 });
+        assert_eq!(canvas.get(7, 7), Some(cell));
+
+        assert_eq!(Anchor::from_keypad(7), Some(Anchor { x: 0, y: 0 }));
+        assert_eq!(Anchor::from_keypad(6).unwrap().name(), "right");
+        assert_eq!(Anchor::from_keypad(0), None);
+    }
+
     #[test]
     fn test_resize_shrink() {
         let mut canvas = Canvas::new_with_size(32, 32);
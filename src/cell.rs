@@ -1,5 +1,13 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
 use ratatui::style::Color;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::export::ColorFormat;
+use crate::lab::{ciede2000, Lab};
 
 /// Block element constants (U+2580–259F) for readability.
 pub mod blocks {
@@ -72,6 +80,42 @@ pub fn is_half_block(ch: char) -> bool {
     is_vertical_half(ch) || is_horizontal_half(ch)
 }
 
+/// Swap a cell's foreground and background colors. A half block with a
+/// transparent other half flips to the opposite half instead, so the colored
+/// half still moves across without its color turning transparent.
+pub fn swap_colors(cell: Cell) -> Cell {
+    let flipped = match cell.ch {
+        blocks::UPPER_HALF => blocks::LOWER_HALF,
+        blocks::LOWER_HALF => blocks::UPPER_HALF,
+        blocks::LEFT_HALF => blocks::RIGHT_HALF,
+        blocks::RIGHT_HALF => blocks::LEFT_HALF,
+        _ => cell.ch,
+    };
+    if flipped != cell.ch && cell.bg.is_none() {
+        Cell { ch: flipped, ..cell }
+    } else {
+        Cell { fg: cell.bg, bg: cell.fg, ..cell }
+    }
+}
+
+/// Drawn in place of a character with no width of its own.
+pub const REPLACEMENT: char = '\u{FFFD}';
+
+/// `ch`, or `REPLACEMENT` for combining marks and control characters,
+/// which would otherwise merge into the previous cell or move the cursor.
+pub fn display_char(ch: char) -> char {
+    match unicode_width::UnicodeWidthChar::width(ch) {
+        Some(0) | None => REPLACEMENT,
+        Some(_) => ch,
+    }
+}
+
+/// Columns a terminal gives `ch` once drawn: 2 for wide characters such as
+/// CJK and most emoji, which cover the cell to their right, else 1.
+pub fn char_width(ch: char) -> usize {
+    unicode_width::UnicodeWidthChar::width(display_char(ch)).unwrap_or(1)
+}
+
 /// Result of resolving a half-block cell's transparency.
 /// `fg` and `bg` are `None` when that half is transparent.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -132,6 +176,14 @@ pub fn next_shade(ch: char) -> char {
     }
 }
 
+/// Step a shade along ░ ▒ ▓ █: positive is denser, negative lighter. Stops
+/// at either end; `None` for characters that aren't shades.
+pub fn shade_step(ch: char, delta: isize) -> Option<char> {
+    const LADDER: [char; 4] = [blocks::SHADE_LIGHT, blocks::SHADE_MEDIUM, blocks::SHADE_DARK, blocks::FULL];
+    let i = LADDER.iter().position(|&c| c == ch)? as isize;
+    Some(LADDER[(i + delta).clamp(0, LADDER.len() as isize - 1) as usize])
+}
+
 /// Parse a hex color string into an Rgb value.
 /// Accepts "#RRGGBB", "RRGGBB", case-insensitive.
 pub fn parse_hex_color(input: &str) -> Option<Rgb> {
@@ -145,6 +197,23 @@ pub fn parse_hex_color(input: &str) -> Option<Rgb> {
     Some(Rgb::new(r, g, b))
 }
 
+/// Parse a character entered as a codepoint (`U+2580`, `0x2580`, `2580`)
+/// or typed as itself. Zero-width and control characters are refused.
+pub fn parse_codepoint(input: &str) -> Option<char> {
+    let mut chars = input.chars();
+    let ch = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => {
+            let hex = ["U+", "u+", "0x", "0X"]
+                .iter()
+                .find_map(|p| input.strip_prefix(p))
+                .unwrap_or(input);
+            char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+        }
+    };
+    (display_char(ch) == ch).then_some(ch)
+}
+
 /// Convert a legacy BlockChar name to a char.
 fn legacy_block_to_char(name: &str) -> char {
     match name {
@@ -159,7 +228,7 @@ fn legacy_block_to_char(name: &str) -> char {
 }
 
 /// True-color RGB value.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Rgb {
     pub r: u8,
     pub g: u8,
@@ -179,6 +248,24 @@ impl Rgb {
         Color::Indexed(nearest_256(&self))
     }
 
+    /// Terminal color for a display that supports `format`.
+    pub fn to_ratatui_as(self, format: ColorFormat) -> Color {
+        match format {
+            ColorFormat::TrueColor => Color::Rgb(self.r, self.g, self.b),
+            ColorFormat::Color256 => self.to_ratatui(),
+            ColorFormat::Color16 => Color::Indexed(nearest_16(&self)),
+        }
+    }
+
+    /// Whether `format` can show this color exactly.
+    pub fn is_exact_in(self, format: ColorFormat) -> bool {
+        match format {
+            ColorFormat::TrueColor => true,
+            ColorFormat::Color256 => color256_to_rgb(nearest_256(&self)) == self,
+            ColorFormat::Color16 => color256_to_rgb(nearest_16(&self)) == self,
+        }
+    }
+
     /// Human-readable name. Returns hex string like "#FF0000".
     pub fn name(self) -> String {
         format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
@@ -309,24 +396,91 @@ pub fn color256_to_rgb(idx: u8) -> Rgb {
     Rgb { r, g, b }
 }
 
-/// Find the nearest xterm-256 color index for an Rgb value (Euclidean distance).
+/// How the nearest color is measured when quantizing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorDistance {
+    /// Euclidean distance between RGB values
+    #[default]
+    Rgb,
+    /// CIEDE2000 difference in CIELAB space: slower, but better at skin
+    /// tones and dark hues
+    Cielab,
+}
+
+static USE_CIELAB: AtomicBool = AtomicBool::new(false);
+
+/// Pick the distance `nearest_256`, `nearest_16` and `distance` use, for
+/// the whole process. Set once from the settings at startup.
+pub fn set_color_distance(metric: ColorDistance) {
+    USE_CIELAB.store(metric == ColorDistance::Cielab, Ordering::Relaxed);
+}
+
+pub fn color_distance() -> ColorDistance {
+    if USE_CIELAB.load(Ordering::Relaxed) { ColorDistance::Cielab } else { ColorDistance::Rgb }
+}
+
+/// How different two colors are under the configured distance. Only
+/// meaningful compared with other distances.
+pub fn distance(a: &Rgb, b: &Rgb) -> f64 {
+    match color_distance() {
+        ColorDistance::Rgb => rgb_distance(a, b) as f64,
+        ColorDistance::Cielab => ciede2000(Lab::from_rgb(*a), Lab::from_rgb(*b)),
+    }
+}
+
+fn rgb_distance(a: &Rgb, b: &Rgb) -> u32 {
+    let dr = a.r as i32 - b.r as i32;
+    let dg = a.g as i32 - b.g as i32;
+    let db = a.b as i32 - b.b as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+/// Find the nearest xterm-256 color index for an Rgb value.
 pub fn nearest_256(color: &Rgb) -> u8 {
-    let mut best_idx: u8 = 0;
-    let mut best_dist = u32::MAX;
-
-    for i in 0u16..=255 {
-        let c = color256_to_rgb(i as u8);
-        let dr = color.r as i32 - c.r as i32;
-        let dg = color.g as i32 - c.g as i32;
-        let db = color.b as i32 - c.b as i32;
-        let dist = (dr * dr + dg * dg + db * db) as u32;
-        if dist < best_dist {
-            best_dist = dist;
-            best_idx = i as u8;
+    nearest_256_by(color, color_distance())
+}
+
+/// Find the nearest ANSI 16 color index for an Rgb value.
+pub fn nearest_16(color: &Rgb) -> u8 {
+    nearest_16_by(color, color_distance())
+}
+
+pub fn nearest_256_by(color: &Rgb, metric: ColorDistance) -> u8 {
+    nearest_index(color, 256, metric)
+}
+
+pub fn nearest_16_by(color: &Rgb, metric: ColorDistance) -> u8 {
+    nearest_index(color, 16, metric)
+}
+
+/// Nearest of the first `count` xterm-256 colors. CIELAB matches are cached,
+/// since the renderer asks for the same few colors every frame.
+fn nearest_index(color: &Rgb, count: usize, metric: ColorDistance) -> u8 {
+    match metric {
+        ColorDistance::Rgb => (0..count)
+            .min_by_key(|&i| rgb_distance(color, &color256_to_rgb(i as u8)))
+            .unwrap_or(0) as u8,
+        ColorDistance::Cielab => {
+            thread_local! {
+                static CACHE: RefCell<HashMap<(Rgb, usize), u8>> = RefCell::new(HashMap::new());
+            }
+            static TABLE: OnceLock<Vec<Lab>> = OnceLock::new();
+            if let Some(idx) = CACHE.with(|c| c.borrow().get(&(*color, count)).copied()) {
+                return idx;
+            }
+            let table = TABLE.get_or_init(|| (0..=255).map(|i| Lab::from_rgb(color256_to_rgb(i))).collect());
+            let target = Lab::from_rgb(*color);
+            let idx = table[..count]
+                .iter()
+                .map(|&lab| ciede2000(target, lab))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map_or(0, |(i, _)| i as u8);
+            CACHE.with(|c| c.borrow_mut().insert((*color, count), idx));
+            idx
         }
     }
-
-    best_idx
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -433,6 +587,42 @@ impl<'de> serde::Deserialize<'de> for Cell {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_codepoint() {
+        assert_eq!(parse_codepoint("U+2580"), Some(blocks::UPPER_HALF));
+        assert_eq!(parse_codepoint("0x41"), Some('A'));
+        assert_eq!(parse_codepoint("2588"), Some(blocks::FULL));
+        // One character is itself, even a digit
+        assert_eq!(parse_codepoint("5"), Some('5'));
+        assert_eq!(parse_codepoint("\u{2591}"), Some(blocks::SHADE_LIGHT));
+        assert_eq!(parse_codepoint("U+D800"), None);
+        assert_eq!(parse_codepoint("U+0007"), None);
+        assert_eq!(parse_codepoint(""), None);
+    }
+
+    #[test]
+    fn test_swap_colors() {
+        let red = Some(Rgb { r: 205, g: 0, b: 0 });
+        let blue = Some(Rgb { r: 0, g: 0, b: 238 });
+        let two_color = Cell { ch: blocks::UPPER_HALF, fg: red, bg: blue };
+        assert_eq!(swap_colors(two_color), Cell { ch: blocks::UPPER_HALF, fg: blue, bg: red });
+        // The transparent half trades places by flipping the block
+        let one_color = Cell { ch: blocks::LEFT_HALF, fg: red, bg: None };
+        assert_eq!(swap_colors(one_color), Cell { ch: blocks::RIGHT_HALF, fg: red, bg: None });
+        assert_eq!(swap_colors(swap_colors(one_color)), one_color);
+        let full = Cell { ch: blocks::FULL, fg: red, bg: blue };
+        assert_eq!(swap_colors(full), Cell { ch: blocks::FULL, fg: blue, bg: red });
+    }
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width(blocks::FULL), 1);
+        assert_eq!(char_width('\u{5B57}'), 2); // 字
+        assert_eq!(display_char('\u{0301}'), REPLACEMENT); // combining acute
+        assert_eq!(display_char('\x1b'), REPLACEMENT);
+        assert_eq!(char_width('\x1b'), 1);
+    }
+
     #[test]
     fn test_color256_to_rgb_standard() {
         let c = color256_to_rgb(0);
@@ -455,6 +645,25 @@ mod tests {
         assert_eq!((c.r, c.g, c.b), (0, 255, 0));
     }
 
+    #[test]
+    fn test_is_exact_in() {
+        let odd = Rgb::new(201, 3, 7);
+        assert!(odd.is_exact_in(ColorFormat::TrueColor));
+        assert!(!odd.is_exact_in(ColorFormat::Color256));
+        let cube = color256_to_rgb(202);
+        assert!(cube.is_exact_in(ColorFormat::Color256));
+        assert!(!cube.is_exact_in(ColorFormat::Color16));
+        assert!(color256_to_rgb(9).is_exact_in(ColorFormat::Color16));
+    }
+
+    #[test]
+    fn test_to_ratatui_as() {
+        let red = Rgb::new(250, 10, 10);
+        assert_eq!(red.to_ratatui_as(ColorFormat::TrueColor), Color::Rgb(250, 10, 10));
+        assert_eq!(red.to_ratatui_as(ColorFormat::Color256), red.to_ratatui());
+        assert_eq!(red.to_ratatui_as(ColorFormat::Color16), Color::Indexed(9));
+    }
+
     #[test]
     fn test_color256_to_rgb_grayscale() {
         let c = color256_to_rgb(232);
@@ -481,6 +690,16 @@ mod tests {
         assert_eq!(nearest_256(&Rgb::BLACK), 0);
     }
 
+    #[test]
+    fn test_cielab_keeps_dark_hues() {
+        // RGB distance rounds a dark navy down to black; CIELAB keeps it blue
+        let navy = Rgb::new(0, 0, 105);
+        assert_eq!(nearest_16_by(&navy, ColorDistance::Rgb), 0);
+        assert_eq!(nearest_16_by(&navy, ColorDistance::Cielab), 4);
+        assert_eq!(nearest_256_by(&Rgb::new(255, 0, 0), ColorDistance::Cielab), 9);
+        assert_eq!(nearest_256_by(&Rgb::BLACK, ColorDistance::Cielab), 0);
+    }
+
     #[test]
     fn test_nearest_256_white() {
         let idx = nearest_256(&Rgb::new(255, 255, 255));
@@ -600,6 +819,15 @@ mod tests {
         assert_eq!(next_shade(blocks::SHADE_DARK), blocks::SHADE_LIGHT); // wraps
     }
 
+    #[test]
+    fn test_shade_step_clamps() {
+        assert_eq!(shade_step(blocks::SHADE_LIGHT, 1), Some(blocks::SHADE_MEDIUM));
+        assert_eq!(shade_step(blocks::SHADE_DARK, 1), Some(blocks::FULL));
+        assert_eq!(shade_step(blocks::FULL, 1), Some(blocks::FULL));
+        assert_eq!(shade_step(blocks::SHADE_LIGHT, -1), Some(blocks::SHADE_LIGHT));
+        assert_eq!(shade_step(blocks::UPPER_HALF, 1), None);
+    }
+
     #[test]
     fn test_next_shade_non_shade_input() {
         // Non-shade char → first shade (LIGHT)
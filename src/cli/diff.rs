@@ -1,7 +1,7 @@
 use std::io;
 
 use crate::cell::Cell;
-use crate::cli::load_project;
+use crate::cli::{cli_error, load_project};
 use crate::oplog;
 use crate::project::Project;
 
@@ -11,8 +11,7 @@ pub fn run(file1: &str, file2: Option<&str>, before: bool) -> io::Result<()> {
     } else if let Some(f2) = file2 {
         cmd_diff_files(file1, f2)
     } else {
-        eprintln!("Error: Specify a second file or use --before");
-        std::process::exit(1);
+        cli_error("Specify a second file or use --before");
     }
 }
 
@@ -30,8 +29,7 @@ fn cmd_diff_before(file: &str) -> io::Result<()> {
     let entries = oplog::active_entries(&log_path)?;
 
     if entries.is_empty() {
-        eprintln!("Error: No operations recorded — cannot diff against previous state");
-        std::process::exit(1);
+        cli_error("No operations recorded — cannot diff against previous state");
     }
 
     let last = &entries[entries.len() - 1];
@@ -82,8 +80,8 @@ fn diff_canvases(p1: &Project, p2: &Project) -> serde_json::Value {
 
     for y in 0..h {
         for x in 0..w {
-            let a = c1.get(x, y).unwrap_or(Cell::default());
-            let b = c2.get(x, y).unwrap_or(Cell::default());
+            let a = c1.get(x, y).unwrap_or_default();
+            let b = c2.get(x, y).unwrap_or_default();
             if a != b {
                 let a_empty = a.is_empty();
                 let b_empty = b.is_empty();
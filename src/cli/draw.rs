@@ -2,7 +2,7 @@ use std::io;
 use std::path::Path;
 
 use crate::cell::blocks;
-use crate::cli::{DrawOpts, DrawTool, atomic_save, cli_error, load_project, resolve_colors, to_symmetry_mode};
+use crate::cli::{CliFillMode, DrawOpts, DrawTool, StrokeOpts, atomic_save, cli_error, load_project, report, resolve_colors, to_dash_pattern, to_fill_mode, to_symmetry_mode};
 use crate::history::CellMutation;
 use crate::oplog;
 use crate::symmetry::apply_symmetry;
@@ -12,9 +12,9 @@ pub fn run(tool: DrawTool) -> io::Result<()> {
     match tool {
         DrawTool::Pencil { file, coord, opts } => cmd_pencil(&file, coord, &opts),
         DrawTool::Eraser { file, coord, region } => cmd_eraser(&file, coord, region),
-        DrawTool::Line { file, from, to, opts } => cmd_line(&file, from, to, &opts),
-        DrawTool::Rect { file, from, to, filled, opts } => cmd_rect(&file, from, to, filled, &opts),
-        DrawTool::Fill { file, coord, opts } => cmd_fill(&file, coord, &opts),
+        DrawTool::Line { file, from, to, stroke, opts } => cmd_line(&file, from, to, &stroke, &opts),
+        DrawTool::Rect { file, from, to, filled, stroke, opts } => cmd_rect(&file, from, to, filled, &stroke, &opts),
+        DrawTool::Fill { file, coord, match_mode, opts } => cmd_fill(&file, coord, &match_mode, &opts),
         DrawTool::Eyedropper { file, coord } => cmd_eyedropper(&file, coord),
     }
 }
@@ -66,7 +66,7 @@ fn apply_and_save(
         "tool": tool_name,
         "symmetry": sym_label,
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[path]);
     Ok(())
 }
 
@@ -105,29 +105,48 @@ fn cmd_eraser(file: &str, coord: (usize, usize), region: Option<(usize, usize, u
     apply_and_save(file, "eraser", mutations, None)
 }
 
-fn cmd_line(file: &str, from: (usize, usize), to: (usize, usize), opts: &DrawOpts) -> io::Result<()> {
+fn cmd_line(file: &str, from: (usize, usize), to: (usize, usize), stroke: &StrokeOpts, opts: &DrawOpts) -> io::Result<()> {
     let project = load_project(file);
     let (fg, bg) = resolve_colors(opts);
     let ch = opts.ch.unwrap_or(blocks::FULL);
 
-    let mutations = tools::line(&project.canvas, from.0, from.1, to.0, to.1, ch, fg, bg);
+    let mutations = match to_dash_pattern(stroke) {
+        Some(pattern) => {
+            let points = pattern.mask(tools::bresenham_line(from.0, from.1, to.0, to.1));
+            tools::plot(&project.canvas, &points, ch, fg, bg)
+        }
+        None => tools::line(&project.canvas, from.0, from.1, to.0, to.1, ch, fg, bg),
+    };
     drop(project);
 
     apply_and_save(file, "line", mutations, Some(opts))
 }
 
-fn cmd_rect(file: &str, from: (usize, usize), to: (usize, usize), filled: bool, opts: &DrawOpts) -> io::Result<()> {
+fn cmd_rect(
+    file: &str,
+    from: (usize, usize),
+    to: (usize, usize),
+    filled: bool,
+    stroke: &StrokeOpts,
+    opts: &DrawOpts,
+) -> io::Result<()> {
     let project = load_project(file);
     let (fg, bg) = resolve_colors(opts);
     let ch = opts.ch.unwrap_or(blocks::FULL);
 
-    let mutations = tools::rectangle(&project.canvas, from.0, from.1, to.0, to.1, ch, fg, bg, filled);
+    let mutations = match to_dash_pattern(stroke).filter(|_| !filled) {
+        Some(pattern) => {
+            let points = pattern.mask(tools::rect_outline_points(from.0, from.1, to.0, to.1));
+            tools::plot(&project.canvas, &points, ch, fg, bg)
+        }
+        None => tools::rectangle(&project.canvas, from.0, from.1, to.0, to.1, ch, fg, bg, filled),
+    };
     drop(project);
 
     apply_and_save(file, "rect", mutations, Some(opts))
 }
 
-fn cmd_fill(file: &str, coord: (usize, usize), opts: &DrawOpts) -> io::Result<()> {
+fn cmd_fill(file: &str, coord: (usize, usize), mode: &CliFillMode, opts: &DrawOpts) -> io::Result<()> {
     let project = load_project(file);
     let (fg, bg) = resolve_colors(opts);
     let ch = opts.ch.unwrap_or(blocks::FULL);
@@ -135,7 +154,7 @@ fn cmd_fill(file: &str, coord: (usize, usize), opts: &DrawOpts) -> io::Result<()
     let (x, y) = coord;
     validate_coords(x, y, &project.canvas);
 
-    let mutations = tools::flood_fill(&project.canvas, x, y, ch, fg, bg);
+    let mutations = tools::flood_fill(&project.canvas, x, y, ch, fg, bg, to_fill_mode(mode));
     drop(project);
 
     apply_and_save(file, "fill", mutations, Some(opts))
@@ -1,7 +1,7 @@
 use std::io;
 use std::path::Path;
 
-use crate::cli::{atomic_save, load_project};
+use crate::cli::{atomic_save, load_project, report};
 use crate::oplog;
 
 pub fn undo(file: &str, count: usize) -> io::Result<()> {
@@ -28,7 +28,7 @@ pub fn undo(file: &str, count: usize) -> io::Result<()> {
         "undone": undone.len(),
         "cells_restored": cells_restored,
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[path]);
     Ok(())
 }
 
@@ -56,7 +56,7 @@ pub fn redo(file: &str, count: usize) -> io::Result<()> {
         "redone": redone.len(),
         "cells_applied": cells_applied,
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[path]);
     Ok(())
 }
 
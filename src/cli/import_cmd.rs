@@ -0,0 +1,223 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::canvas::Canvas;
+use crate::cell::Rgb;
+use crate::cli::{cli_error, report, to_import_mode, CliImportMode, ImportArgs};
+use crate::aseprite;
+use crate::import::{self, ImportOptions};
+use crate::palette;
+use crate::project::Project;
+use crate::symmetry::SymmetryMode;
+
+/// Extensions handed to ffmpeg for frame extraction.
+const VIDEO_EXTENSIONS: [&str; 6] = ["mp4", "mov", "webm", "mkv", "avi", "gif"];
+
+pub fn run(args: &ImportArgs) -> io::Result<()> {
+    let src = Path::new(&args.image);
+    if !src.exists() {
+        cli_error(&format!("File not found: '{}'", args.image));
+    }
+    if args.ramp.is_some() && matches!(args.mode, CliImportMode::Edges) {
+        cli_error("--ramp colors by brightness, which edges mode doesn't use");
+    }
+
+    let opts = ImportOptions {
+        mode: to_import_mode(&args.mode),
+        width: args.width,
+        height: args.height,
+        ramp: args.ramp.as_deref().map(load_ramp),
+        palette: None,
+        dither: args.dither,
+    };
+
+    if src.is_dir() || is_video(src) || is_animation(src) {
+        return run_frames(args, src, &opts);
+    }
+
+    let out_path = Path::new(&args.output);
+    if out_path.exists() && !args.force {
+        cli_error(&format!("'{}' already exists. Use --force to overwrite.", args.output));
+    }
+    let img = import::load_image(src).unwrap_or_else(|e| cli_error(&e));
+    let canvas = import::import_image(&img, &opts);
+    let (w, h) = (canvas.width, canvas.height);
+    save_canvas(canvas, out_path)?;
+
+    let json = serde_json::json!({
+        "imported": args.image,
+        "output": args.output,
+        "width": w,
+        "height": h,
+        "mode": mode_str(&args.mode),
+        "ramp": args.ramp,
+        "dither": args.dither,
+    });
+    report(json, &[out_path]);
+    Ok(())
+}
+
+/// Import a directory of numbered PNGs, a video or an animated Aseprite
+/// file as a numbered sequence of .kaku files.
+fn run_frames(args: &ImportArgs, src: &Path, opts: &ImportOptions) -> io::Result<()> {
+    let extract_dir = std::env::temp_dir().join(format!("kakukuma-frames-{}", std::process::id()));
+    let mut frames = if src.is_dir() {
+        import::list_frame_files(src)
+    } else if aseprite::is_aseprite(src) {
+        extract_aseprite_frames(src, &extract_dir, args.max_frames)?
+    } else {
+        extract_video_frames(src, &extract_dir, args.max_frames)?
+    };
+    frames.truncate(args.max_frames as usize);
+    if frames.is_empty() {
+        let _ = std::fs::remove_dir_all(&extract_dir);
+        cli_error(&format!("No PNG frames found in '{}'", args.image));
+    }
+
+    let out_path = Path::new(&args.output);
+    let outputs: Vec<PathBuf> = (1..=frames.len()).map(|i| frame_path(out_path, i)).collect();
+    if !args.force {
+        if let Some(existing) = outputs.iter().find(|p| p.exists()) {
+            let _ = std::fs::remove_dir_all(&extract_dir);
+            cli_error(&format!(
+                "'{}' already exists. Use --force to overwrite.",
+                existing.display()
+            ));
+        }
+    }
+
+    let mut size = (0, 0);
+    for (frame, output) in frames.iter().zip(&outputs) {
+        let img = import::load_image(frame).unwrap_or_else(|e| {
+            let _ = std::fs::remove_dir_all(&extract_dir);
+            cli_error(&format!("{}: {}", frame.display(), e))
+        });
+        let canvas = import::import_image(&img, opts);
+        size = (canvas.width, canvas.height);
+        save_canvas(canvas, output)?;
+    }
+    let _ = std::fs::remove_dir_all(&extract_dir);
+
+    let files: Vec<_> = outputs.iter().map(|p| p.display().to_string()).collect();
+    let json = serde_json::json!({
+        "imported": args.image,
+        "frames": files.len(),
+        "files": files,
+        "width": size.0,
+        "height": size.1,
+        "mode": mode_str(&args.mode),
+        "ramp": args.ramp,
+    });
+    let written: Vec<&Path> = outputs.iter().map(PathBuf::as_path).collect();
+    report(json, &written);
+    Ok(())
+}
+
+fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.iter().any(|v| e.eq_ignore_ascii_case(v)))
+}
+
+/// An Aseprite file with more than one frame.
+fn is_animation(path: &Path) -> bool {
+    aseprite::is_aseprite(path) && aseprite::frame_count(path).unwrap_or_else(|e| cli_error(&e)) > 1
+}
+
+/// Flatten up to `max_frames` frames of an Aseprite file into PNGs.
+fn extract_aseprite_frames(src: &Path, dir: &Path, max_frames: u32) -> io::Result<Vec<PathBuf>> {
+    let frames = aseprite::load(src).unwrap_or_else(|e| cli_error(&e));
+    std::fs::create_dir_all(dir)?;
+    for (i, frame) in frames.iter().take(max_frames as usize).enumerate() {
+        if let Err(e) = frame.save(dir.join(format!("{:04}.png", i + 1))) {
+            let _ = std::fs::remove_dir_all(dir);
+            return Err(io::Error::other(e));
+        }
+    }
+    Ok(import::list_frame_files(dir))
+}
+
+/// Extract up to `max_frames` PNG frames from a video with ffmpeg.
+fn extract_video_frames(src: &Path, dir: &Path, max_frames: u32) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let status = std::process::Command::new("ffmpeg")
+        .args(["-loglevel", "error", "-i"])
+        .arg(src)
+        .args(["-frames:v", &max_frames.to_string()])
+        .arg(dir.join("%04d.png"))
+        .status();
+    match status {
+        Ok(s) if s.success() => Ok(import::list_frame_files(dir)),
+        Ok(s) => {
+            let _ = std::fs::remove_dir_all(dir);
+            cli_error(&format!("ffmpeg failed ({})", s))
+        }
+        Err(_) => {
+            let _ = std::fs::remove_dir_all(dir);
+            cli_error("Video import requires ffmpeg on PATH")
+        }
+    }
+}
+
+/// `anim.kaku` -> `anim-001.kaku`
+fn frame_path(output: &Path, index: usize) -> PathBuf {
+    let stem = output.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    output.with_file_name(format!("{}-{:03}.kaku", stem, index))
+}
+
+fn save_canvas(canvas: Canvas, path: &Path) -> io::Result<()> {
+    let mut project = Project::new(
+        path.file_stem().and_then(|s| s.to_str()).unwrap_or("untitled"),
+        canvas,
+        Rgb::WHITE,
+        SymmetryMode::Off,
+    );
+    project.save_to_file(path)
+        .map_err(io::Error::other)?;
+
+    let log = crate::oplog::log_path(path);
+    crate::oplog::init_log(&log)
+}
+
+fn mode_str(mode: &CliImportMode) -> &'static str {
+    match mode {
+        CliImportMode::Blocks => "blocks",
+        CliImportMode::Edges => "edges",
+        CliImportMode::Ascii => "ascii",
+        CliImportMode::Shades => "shades",
+        CliImportMode::Best => "best",
+    }
+}
+
+/// Resolve a palette by path, or by name in the working directory.
+fn load_ramp(name: &str) -> Vec<Rgb> {
+    let direct = PathBuf::from(name);
+    let path = if direct.exists() {
+        direct
+    } else {
+        std::env::current_dir()
+            .unwrap_or_default()
+            .join(format!("{}.palette", name))
+    };
+    let pal = palette::load_palette(&path).unwrap_or_else(|e| cli_error(&e));
+    if pal.colors.is_empty() {
+        cli_error(&format!("Palette '{}' has no colors", name));
+    }
+    pal.colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_path() {
+        assert_eq!(frame_path(Path::new("out/anim.kaku"), 7), PathBuf::from("out/anim-007.kaku"));
+    }
+
+    #[test]
+    fn test_is_video() {
+        assert!(is_video(Path::new("clip.MP4")));
+        assert!(!is_video(Path::new("still.png")));
+    }
+}
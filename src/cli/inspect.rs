@@ -1,6 +1,6 @@
 use std::io;
 
-use crate::cli::load_project;
+use crate::cli::{cli_error, load_project};
 
 pub fn run(
     file: &str,
@@ -15,8 +15,7 @@ pub fn run(
     if let Some((x, y)) = coord {
         // Single cell inspection
         if x >= canvas.width || y >= canvas.height {
-            eprintln!("Error: Position ({}, {}) exceeds canvas dimensions ({}x{})", x, y, canvas.width, canvas.height);
-            std::process::exit(1);
+            cli_error(&format!("Position ({}, {}) exceeds canvas dimensions ({}x{})", x, y, canvas.width, canvas.height));
         }
         let cell = canvas.get(x, y).unwrap();
         let json = serde_json::json!({
@@ -53,8 +52,7 @@ pub fn run(
     } else if let Some(r) = row {
         // Row inspection
         if r >= canvas.height {
-            eprintln!("Error: Row {} exceeds canvas height ({})", r, canvas.height);
-            std::process::exit(1);
+            cli_error(&format!("Row {} exceeds canvas height ({})", r, canvas.height));
         }
         let mut cells = Vec::new();
         for x in 0..canvas.width {
@@ -73,8 +71,7 @@ pub fn run(
     } else if let Some(c) = col {
         // Column inspection
         if c >= canvas.width {
-            eprintln!("Error: Column {} exceeds canvas width ({})", c, canvas.width);
-            std::process::exit(1);
+            cli_error(&format!("Column {} exceeds canvas width ({})", c, canvas.width));
         }
         let mut cells = Vec::new();
         for y in 0..canvas.height {
@@ -91,8 +88,7 @@ pub fn run(
         }
         println!("{}", serde_json::to_string(&cells).unwrap());
     } else {
-        eprintln!("Error: Specify a coordinate, --region, --row, or --col");
-        std::process::exit(1);
+        cli_error("Specify a coordinate, --region, --row, or --col");
     }
 
     Ok(())
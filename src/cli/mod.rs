@@ -5,24 +5,72 @@ pub mod diff;
 pub mod stats;
 pub mod history_cmd;
 pub mod palette_cmd;
+pub mod import_cmd;
+pub mod thumb;
 
 use std::io;
 use std::path::Path;
+use std::sync::OnceLock;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 
 use crate::canvas::Canvas;
 use crate::cell::{parse_hex_color, Rgb};
-use crate::export::ColorFormat;
+use crate::export::{CellAspect, ColorFormat, TerminalPalette, Transparency};
+use crate::import::ImportMode;
+use crate::keymap::{Keymap, SheetFormat};
 use crate::project::Project;
 use crate::symmetry::SymmetryMode;
+use crate::settings::Settings;
+use crate::tools::{DashPattern, FillMode, LineStyle};
 
 #[derive(Parser)]
-#[command(name = "kakukuma", about = "Terminal ANSI art editor")]
+#[command(name = "kakukuma", version, about = "Terminal ANSI art editor")]
 pub struct Cli {
     /// Open .kaku file in TUI editor
     pub file: Option<String>,
 
+    /// Longest wait between UI updates while busy, in milliseconds
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(10..=1000))]
+    pub tick_rate: u64,
+
+    /// Record every editor command to a session log (JSON lines), written as
+    /// you go so it survives a crash
+    #[arg(long, value_name = "LOG")]
+    pub record_session: Option<String>,
+
+    /// Terminal color depth (detected from COLORTERM/TERM when omitted)
+    #[arg(long, value_name = "DEPTH")]
+    pub colors: Option<CliColorFormat>,
+
+    /// Plain glyphs, a non-color cursor and a spoken-style status line
+    #[arg(long)]
+    pub screen_reader: bool,
+
+    /// Mark the cursor, symmetry axes and tool previews with characters, not
+    /// just color (also enabled by NO_COLOR)
+    #[arg(long)]
+    pub monochrome: bool,
+
+    /// Diagnostic mode for misbehaving terminals: no mouse capture, autosave,
+    /// clipboard or theme changes, and settings and key maps are ignored
+    #[arg(long)]
+    pub safe: bool,
+
+    /// Replay a session log headlessly and print the resulting canvas
+    #[arg(long, value_name = "LOG", conflicts_with = "record_session")]
+    pub replay: Option<String>,
+
+    /// Print nothing on success; errors still go to stderr
+    #[arg(long, global = true)]
+    pub quiet: bool,
+
+    /// Add bytes written, dimensions and colors used to status output, and
+    /// report errors as JSON on stderr
+    #[arg(long, global = true)]
+    pub json: bool,
+
     #[command(subcommand)]
     pub command: Option<Command>,
 }
@@ -88,17 +136,8 @@ pub enum Command {
 
     /// Export canvas to file
     Export {
-        /// Path to .kaku file
-        file: String,
-        /// Output file path
-        #[arg(long)]
-        output: String,
-        /// Export format
-        #[arg(long, default_value = "ansi")]
-        format: PreviewFormat,
-        /// Color depth for ANSI output
-        #[arg(long, default_value = "truecolor")]
-        color_format: CliColorFormat,
+        #[command(flatten)]
+        args: ExportArgs,
     },
 
     /// Compare two canvas files
@@ -112,6 +151,28 @@ pub enum Command {
         before: bool,
     },
 
+    /// Render a small PNG thumbnail of the canvas
+    Thumb {
+        /// Path to .kaku file
+        file: String,
+        /// Output PNG path
+        #[arg(short, long)]
+        output: String,
+        /// Longest side of the thumbnail in pixels
+        #[arg(long, default_value_t = 128, value_parser = clap::value_parser!(u32).range(8..=2048))]
+        size: u32,
+        /// Terminal cell width:height, or auto (defaults to the cell_aspect setting)
+        #[arg(long, value_name = "W:H", value_parser = CellAspect::parse)]
+        aspect: Option<CellAspect>,
+        /// Gamma adjustment, above 1 to brighten (defaults to the export_gamma setting)
+        #[arg(long, value_parser = parse_gamma)]
+        gamma: Option<f32>,
+        /// Draw shade patterns and text glyphs the way a terminal font does,
+        /// instead of mixing each cell's colors
+        #[arg(long)]
+        glyphs: bool,
+    },
+
     /// Canvas statistics
     Stats {
         /// Path to .kaku file
@@ -145,10 +206,45 @@ pub enum Command {
         full: bool,
     },
 
-    /// Palette management
+    /// Import a PNG/JPEG image into a new .kaku file
+    Import {
+        #[command(flatten)]
+        args: ImportArgs,
+    },
+
+    /// Palette management, or analyze the colors a canvas uses
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Palette {
+        /// .kaku file to analyze
+        #[arg(required = true)]
+        file: Option<String>,
+        /// Write the canvas's colors to a .palette file
+        #[arg(long, value_name = "PALETTE", required_unless_present = "report")]
+        extract: Option<String>,
+        /// Print how many cells use each color
+        #[arg(long)]
+        report: bool,
         #[command(subcommand)]
-        action: PaletteAction,
+        action: Option<PaletteAction>,
+    },
+
+    /// Write a cheat sheet of the effective key bindings, including remaps
+    Keys {
+        /// Sheet format
+        #[arg(long, default_value = "markdown")]
+        format: CliSheetFormat,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Generate shell completions or a man page from the CLI definition
+    Completions {
+        /// Shell to complete for, or `man` for a roff man page
+        target: CliCompletionTarget,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        output: Option<String>,
     },
 }
 
@@ -186,6 +282,8 @@ pub enum DrawTool {
         #[arg(value_parser = parse_coord)]
         to: (usize, usize),
         #[command(flatten)]
+        stroke: StrokeOpts,
+        #[command(flatten)]
         opts: DrawOpts,
     },
     /// Draw a rectangle
@@ -202,6 +300,8 @@ pub enum DrawTool {
         #[arg(long)]
         filled: bool,
         #[command(flatten)]
+        stroke: StrokeOpts,
+        #[command(flatten)]
         opts: DrawOpts,
     },
     /// Flood fill from a point
@@ -211,6 +311,9 @@ pub enum DrawTool {
         /// Start coordinate (x,y)
         #[arg(value_parser = parse_coord)]
         coord: (usize, usize),
+        /// Spread through identical cells, same character, or same colors
+        #[arg(long = "match", value_name = "MODE", default_value = "cell")]
+        match_mode: CliFillMode,
         #[command(flatten)]
         opts: DrawOpts,
     },
@@ -224,6 +327,17 @@ pub enum DrawTool {
     },
 }
 
+/// Dash options for lines and rectangle outlines
+#[derive(clap::Args)]
+pub struct StrokeOpts {
+    /// Line style
+    #[arg(long, default_value = "solid")]
+    pub style: CliLineStyle,
+    /// Dash pattern as ON,OFF cell counts (default 3,2 dashed, 1,1 dotted)
+    #[arg(long, value_parser = parse_pattern)]
+    pub pattern: Option<DashPattern>,
+}
+
 #[derive(clap::Args)]
 pub struct DrawOpts {
     /// Set foreground color (hex, e.g., "#FF0000")
@@ -246,11 +360,93 @@ pub struct DrawOpts {
     pub no_log: bool,
 }
 
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    /// Path to .kaku file
+    pub file: String,
+    /// Output file path
+    #[arg(long)]
+    pub output: String,
+    /// Export format
+    #[arg(long, default_value = "ansi")]
+    pub format: PreviewFormat,
+    /// Color depth for ANSI output
+    #[arg(long, default_value = "truecolor")]
+    pub color_format: CliColorFormat,
+    /// Ordered dithering for 256 and 16 colors, to keep gradients smooth
+    #[arg(long)]
+    pub dither: bool,
+    /// The destination terminal's 16 colors for --color-format 16: xterm,
+    /// solarized, dracula, or 16 comma-separated hex colors (default: the
+    /// terminal_palette setting)
+    #[arg(long, value_parser = TerminalPalette::parse)]
+    pub terminal_palette: Option<TerminalPalette>,
+    /// How transparent cells appear: keep (no background), reset (reset
+    /// to the default background at each line start), or a hex color to
+    /// fill them with
+    #[arg(long, default_value = "keep", value_parser = Transparency::parse)]
+    pub transparent: Transparency,
+    /// Skip long runs of transparent cells with cursor movement instead
+    /// of spaces, for much smaller files of sparse art
+    #[arg(long)]
+    pub cursor_forward: bool,
+    /// Warn when the output is larger than this many bytes (default: the
+    /// export_budget setting)
+    #[arg(long)]
+    pub budget: Option<u64>,
+    /// Plain format: write each column twice, as the editor draws cells at
+    /// 2x zoom, so square art doesn't come out half as wide
+    #[arg(long)]
+    pub double_width: bool,
+    /// Markdown formats: a note for an HTML comment above the art (and the
+    /// summary of the markdown-html details block)
+    #[arg(long)]
+    pub note: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct ImportArgs {
+    /// Source image (PNG, JPEG or Aseprite, with layers flattened), a
+    /// directory of numbered PNG frames, or a video file (requires ffmpeg);
+    /// an Aseprite file with several frames imports as a sequence
+    pub image: String,
+    /// Path for the new .kaku file (frames are written as NAME-001.kaku, ...)
+    pub output: String,
+    /// Maximum canvas width in cells
+    #[arg(long, default_value_t = 48)]
+    pub width: usize,
+    /// Maximum canvas height in cells
+    #[arg(long, default_value_t = 32)]
+    pub height: usize,
+    /// Conversion mode
+    #[arg(long, default_value = "blocks")]
+    pub mode: CliImportMode,
+    /// Map brightness onto a custom palette ramp (name or .palette path);
+    /// not for edges mode
+    #[arg(long)]
+    pub ramp: Option<String>,
+    /// Dither colors (blocks mode), trading flat areas for smoother gradients
+    #[arg(long)]
+    pub dither: bool,
+    /// Maximum number of frames to import from a directory or video
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_frames: u32,
+    /// Overwrite existing files
+    #[arg(long)]
+    pub force: bool,
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum PreviewFormat {
     Ansi,
     Json,
     Plain,
+    /// A `<pre>` block with inline color styles, for web pages
+    Html,
+    /// Plain text in a fenced code block, for READMEs
+    Markdown,
+    /// The HTML `<pre>` inside a Markdown `<details>` block
+    MarkdownHtml,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -262,6 +458,29 @@ pub enum CliColorFormat {
     Color16,
 }
 
+#[derive(ValueEnum, Clone, Debug)]
+pub enum CliSheetFormat {
+    Markdown,
+    Text,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum CliCompletionTarget {
+    Bash,
+    Zsh,
+    Fish,
+    Man,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum CliImportMode {
+    Blocks,
+    Edges,
+    Ascii,
+    Shades,
+    Best,
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum CliSymmetry {
     Off,
@@ -270,6 +489,20 @@ pub enum CliSymmetry {
     Quad,
 }
 
+#[derive(ValueEnum, Clone, Debug)]
+pub enum CliLineStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum CliFillMode {
+    Cell,
+    Char,
+    Color,
+}
+
 #[derive(Subcommand)]
 pub enum PaletteAction {
     /// List available .palette files
@@ -286,6 +519,24 @@ pub enum PaletteAction {
     },
     /// Add color to palette
     Add { name: String, color: String },
+    /// Remove a color from a palette
+    Remove { name: String, color: String },
+    /// Print a palette as a one-line kakupal: string for sharing
+    Share { name: String },
+    /// Save a shared kakupal: string or a list of hex colors as a palette
+    Import {
+        text: String,
+        /// Palette name; required for a hex list
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Download a palette from Lospec by its slug (needs allow_network)
+    Lospec {
+        slug: String,
+        /// Palette name; defaults to the slug
+        #[arg(long)]
+        name: Option<String>,
+    },
     /// List available themes
     Themes,
     /// Show colors in a theme
@@ -322,6 +573,21 @@ pub fn parse_region(s: &str) -> Result<(usize, usize, usize, usize), String> {
     Ok((x1, y1, x2, y2))
 }
 
+pub fn parse_pattern(s: &str) -> Result<DashPattern, String> {
+    let (on, off) = parse_coord(s).map_err(|_| format!("Expected ON,OFF format (e.g., 3,2), got '{}'", s))?;
+    if on == 0 {
+        return Err("Dash pattern needs at least one drawn cell".to_string());
+    }
+    Ok(DashPattern { on, off })
+}
+
+pub fn parse_gamma(s: &str) -> Result<f32, String> {
+    match s.trim().parse::<f32>() {
+        Ok(g) if (0.1..=10.0).contains(&g) => Ok(g),
+        _ => Err(format!("Expected a gamma between 0.1 and 10, got '{}'", s)),
+    }
+}
+
 pub fn parse_size(s: &str) -> Result<(usize, usize), String> {
     let parts: Vec<&str> = s.split('x').collect();
     if parts.len() != 2 {
@@ -368,6 +634,35 @@ pub fn to_symmetry_mode(s: &CliSymmetry) -> SymmetryMode {
     }
 }
 
+pub fn to_fill_mode(m: &CliFillMode) -> FillMode {
+    match m {
+        CliFillMode::Cell => FillMode::Cell,
+        CliFillMode::Char => FillMode::Char,
+        CliFillMode::Color => FillMode::Color,
+    }
+}
+
+/// The dash pattern for the stroke options; `None` draws solid.
+pub fn to_dash_pattern(stroke: &StrokeOpts) -> Option<DashPattern> {
+    let style = match stroke.style {
+        CliLineStyle::Solid => LineStyle::Solid,
+        CliLineStyle::Dashed => LineStyle::Dashed,
+        CliLineStyle::Dotted => LineStyle::Dotted,
+    };
+    let pattern = Settings::default().pattern(style)?;
+    Some(stroke.pattern.unwrap_or(pattern))
+}
+
+pub fn to_import_mode(m: &CliImportMode) -> ImportMode {
+    match m {
+        CliImportMode::Blocks => ImportMode::Blocks,
+        CliImportMode::Edges => ImportMode::Edges,
+        CliImportMode::Ascii => ImportMode::Ascii,
+        CliImportMode::Shades => ImportMode::Shades,
+        CliImportMode::Best => ImportMode::Best,
+    }
+}
+
 pub fn to_color_format(f: &CliColorFormat) -> ColorFormat {
     match f {
         CliColorFormat::Truecolor => ColorFormat::TrueColor,
@@ -376,14 +671,70 @@ pub fn to_color_format(f: &CliColorFormat) -> ColorFormat {
     }
 }
 
+/// Process exit codes for the headless subcommands.
+pub mod exit {
+    /// Bad input: a missing file, an out-of-range value, an unknown name
+    pub const INPUT: i32 = 1;
+    /// Unparseable arguments (clap exits with this itself)
+    pub const USAGE: i32 = 2;
+    /// A project file that exists but can't be read as a project
+    pub const CORRUPT: i32 = 3;
+    /// Reading or writing a file failed
+    pub const IO: i32 = 4;
+}
+
+/// How subcommands report results, from the global `--quiet`/`--json` flags.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OutputOpts {
+    pub quiet: bool,
+    pub json: bool,
+}
+
+static OUTPUT: OnceLock<OutputOpts> = OnceLock::new();
+
+fn output_opts() -> OutputOpts {
+    OUTPUT.get().copied().unwrap_or_default()
+}
+
+/// Print an error and exit with `code`.
+fn fail(code: i32, msg: &str) -> ! {
+    if output_opts().json {
+        let json = serde_json::json!({ "error": msg, "code": code });
+        eprintln!("{}", serde_json::to_string(&json).unwrap());
+    } else {
+        eprintln!("Error: {}", msg);
+    }
+    std::process::exit(code)
+}
+
 fn cli_error(msg: &str) -> ! {
-    eprintln!("Error: {}", msg);
-    std::process::exit(1)
+    fail(exit::INPUT, msg)
 }
 
-fn internal_error(msg: &str) -> ! {
-    eprintln!("Internal error: {}", msg);
-    std::process::exit(2)
+/// Print the status line of a command that wrote `written`. With `--json`
+/// it also says how many bytes were written and, for a project, its size
+/// and the number of colors it uses.
+fn report(mut status: serde_json::Value, written: &[&Path]) {
+    let opts = output_opts();
+    if opts.quiet {
+        return;
+    }
+    if let (true, Some(obj)) = (opts.json, status.as_object_mut()) {
+        let bytes: u64 = written.iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+        obj.insert("bytes_written".into(), bytes.into());
+        if let [path] = written {
+            if let Ok(project) = Project::load_from_file(path) {
+                let canvas = &project.canvas;
+                obj.entry("width").or_insert(canvas.width.into());
+                obj.entry("height").or_insert(canvas.height.into());
+                obj.insert("colors_used".into(), canvas.used_colors().len().into());
+            }
+        }
+    }
+    println!("{}", serde_json::to_string(&status).unwrap());
 }
 
 fn load_project(path: &str) -> Project {
@@ -392,19 +743,30 @@ fn load_project(path: &str) -> Project {
         cli_error(&format!("File not found: '{}'", path));
     }
     Project::load_from_file(p).unwrap_or_else(|e| {
-        internal_error(&format!("Failed to load '{}': {}", path, e));
+        fail(exit::CORRUPT, &format!("Failed to load '{}': {}", path, e));
     })
 }
 
 fn atomic_save(project: &mut Project, path: &Path) -> io::Result<()> {
     let tmp = path.with_extension("kaku.tmp");
     project.save_to_file(&tmp)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
     std::fs::rename(&tmp, path)
 }
 
+/// Run a CLI command, exiting with an `exit` code if it fails.
+pub fn run(cmd: Command, opts: OutputOpts) -> io::Result<()> {
+    let _ = OUTPUT.set(opts);
+    // Commands that need the rest of the settings report a broken file themselves
+    if let Ok(settings) = Settings::load_user() {
+        crate::cell::set_color_distance(settings.color_distance);
+    }
+    dispatch(cmd).unwrap_or_else(|e| fail(exit::IO, &e.to_string()));
+    Ok(())
+}
+
 /// Route a CLI command to the appropriate handler.
-pub fn run(cmd: Command) -> io::Result<()> {
+fn dispatch(cmd: Command) -> io::Result<()> {
     match cmd {
         Command::New { file, width, height, size, force } => {
             let (w, h) = size.unwrap_or((width, height));
@@ -420,15 +782,61 @@ pub fn run(cmd: Command) -> io::Result<()> {
         Command::Diff { file1, file2, before } => {
             diff::run(&file1, file2.as_deref(), before)
         }
+        Command::Thumb { file, output, size, aspect, gamma, glyphs } => {
+            thumb::run(&file, &output, size, aspect, gamma, glyphs)
+        }
         Command::Stats { file } => stats::run(&file),
         Command::Undo { file, count } => history_cmd::undo(&file, count),
         Command::Redo { file, count } => history_cmd::redo(&file, count),
         Command::History { file, full } => history_cmd::history(&file, full),
-        Command::Export { file, output, format, color_format } => {
-            preview::export_to_file(&file, &output, &format, &color_format)
+        Command::Export { args } => preview::export_to_file(&args),
+        Command::Import { args } => import_cmd::run(&args),
+        Command::Palette { action: Some(action), .. } => palette_cmd::run(action),
+        Command::Palette { action: None, file, extract, report } => {
+            palette_cmd::analyze(file.as_deref().unwrap_or_default(), extract.as_deref(), report)
+        }
+        Command::Keys { format, output } => cmd_keys(&format, output.as_deref()),
+        Command::Completions { target, output } => cmd_completions(&target, output.as_deref()),
+    }
+}
+
+fn cmd_keys(format: &CliSheetFormat, output: Option<&str>) -> io::Result<()> {
+    let keymap = Keymap::load_user().unwrap_or_else(|e| cli_error(&format!("Key map: {}", e)));
+    let sheet = keymap.cheat_sheet(match format {
+        CliSheetFormat::Markdown => SheetFormat::Markdown,
+        CliSheetFormat::Text => SheetFormat::Text,
+    });
+    match output {
+        Some(path) => {
+            std::fs::write(path, &sheet)?;
+            let json = serde_json::json!({
+                "written": path,
+                "bindings": keymap.bindings.len(),
+            });
+            report(json, &[Path::new(path)]);
         }
-        Command::Palette { action } => palette_cmd::run(action),
+        None => print!("{}", sheet),
     }
+    Ok(())
+}
+
+fn cmd_completions(target: &CliCompletionTarget, output: Option<&str>) -> io::Result<()> {
+    let mut cmd = Cli::command();
+    let mut script = Vec::new();
+    match target {
+        CliCompletionTarget::Bash => clap_complete::generate(Shell::Bash, &mut cmd, "kakukuma", &mut script),
+        CliCompletionTarget::Zsh => clap_complete::generate(Shell::Zsh, &mut cmd, "kakukuma", &mut script),
+        CliCompletionTarget::Fish => clap_complete::generate(Shell::Fish, &mut cmd, "kakukuma", &mut script),
+        CliCompletionTarget::Man => clap_mangen::Man::new(cmd).render(&mut script)?,
+    }
+    match output {
+        Some(path) => {
+            std::fs::write(path, &script)?;
+            report(serde_json::json!({ "written": path }), &[Path::new(path)]);
+        }
+        None => io::Write::write_all(&mut io::stdout(), &script)?,
+    }
+    Ok(())
 }
 
 fn cmd_new(file: &str, width: usize, height: usize, force: bool) -> io::Result<()> {
@@ -449,7 +857,7 @@ fn cmd_new(file: &str, width: usize, height: usize, force: bool) -> io::Result<(
     );
 
     project.save_to_file(path)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
 
     // Initialize empty log
     let log = crate::oplog::log_path(path);
@@ -460,7 +868,7 @@ fn cmd_new(file: &str, width: usize, height: usize, force: bool) -> io::Result<(
         "width": w,
         "height": h,
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[path]);
     Ok(())
 }
 
@@ -2,8 +2,9 @@ use std::io;
 use std::path::Path;
 
 use crate::cell::{parse_hex_color, Rgb};
-use crate::cli::{load_project, PaletteAction};
+use crate::cli::{cli_error, load_project, report, PaletteAction};
 use crate::palette::{self, CustomPalette, DEFAULT_PALETTE};
+use crate::settings::Settings;
 use crate::theme::THEMES;
 
 pub fn run(action: PaletteAction) -> io::Result<()> {
@@ -13,11 +14,88 @@ pub fn run(action: PaletteAction) -> io::Result<()> {
         PaletteAction::Create { name, file } => cmd_create(&name, &file),
         PaletteAction::Export { name, output } => cmd_export(&name, &output),
         PaletteAction::Add { name, color } => cmd_add(&name, &color),
+        PaletteAction::Remove { name, color } => cmd_remove(&name, &color),
+        PaletteAction::Share { name } => cmd_share(&name),
+        PaletteAction::Import { text, name } => cmd_import(&text, name.as_deref()),
+        PaletteAction::Lospec { slug, name } => cmd_lospec(&slug, name.as_deref()),
         PaletteAction::Themes => cmd_themes(),
         PaletteAction::Theme { name } => cmd_theme(&name),
     }
 }
 
+/// Extract a canvas's colors to a palette file and/or print how much each
+/// color is used.
+pub fn analyze(file: &str, extract: Option<&str>, with_report: bool) -> io::Result<()> {
+    let project = load_project(file);
+    let canvas = &project.canvas;
+    let colors = canvas.used_colors();
+
+    if let Some(output) = extract {
+        let out_path = Path::new(output);
+        let pal = CustomPalette {
+            name: out_path.file_stem().and_then(|s| s.to_str()).unwrap_or("extracted").to_string(),
+            colors: colors.clone(),
+        };
+        palette::save_palette(&pal, out_path)
+            .map_err(io::Error::other)?;
+        if !with_report {
+            let json = serde_json::json!({
+                "extracted": output,
+                "name": pal.name,
+                "colors_extracted": colors.len(),
+            });
+            report(json, &[out_path]);
+            return Ok(());
+        }
+    }
+
+    // Cells per color, counting a cell once even if it uses a color twice
+    let mut drawn = 0usize;
+    let mut usage = vec![(0usize, 0usize, 0usize); colors.len()];
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let Some(cell) = canvas.get(x, y).filter(|c| !c.is_empty()) else {
+                continue;
+            };
+            drawn += 1;
+            for (i, color) in colors.iter().enumerate() {
+                let fg = cell.fg == Some(*color);
+                let bg = cell.bg == Some(*color);
+                usage[i].0 += fg as usize;
+                usage[i].1 += bg as usize;
+                usage[i].2 += (fg || bg) as usize;
+            }
+        }
+    }
+
+    let mut rows: Vec<_> = colors.iter().zip(&usage).collect();
+    rows.sort_by_key(|(_, u)| std::cmp::Reverse(u.2));
+    let rows: Vec<_> = rows.iter()
+        .map(|(c, (fg, bg, cells))| {
+            let pct = if drawn > 0 { (*cells as f64 / drawn as f64) * 100.0 } else { 0.0 };
+            serde_json::json!({
+                "hex": c.name(),
+                "fg": fg,
+                "bg": bg,
+                "cells": cells,
+                "percent": (pct * 100.0).round() / 100.0,
+            })
+        })
+        .collect();
+
+    let mut json = serde_json::json!({
+        "file": file,
+        "drawn_cells": drawn,
+        "count": colors.len(),
+        "colors": rows,
+    });
+    if let Some(output) = extract {
+        json["extracted"] = output.into();
+    }
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    Ok(())
+}
+
 fn palette_dir() -> std::path::PathBuf {
     std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
 }
@@ -70,10 +148,7 @@ fn cmd_show(name: &str) -> io::Result<()> {
             println!("{}", serde_json::to_string_pretty(&json).unwrap());
             Ok(())
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
-        }
+        Err(e) => cli_error(&e),
     }
 }
 
@@ -81,26 +156,7 @@ fn cmd_create(name: &str, file: &str) -> io::Result<()> {
     let project = load_project(file);
     let canvas = &project.canvas;
 
-    // Extract unique colors from canvas
-    let mut colors = Vec::new();
-    let mut seen = std::collections::HashSet::new();
-
-    for y in 0..canvas.height {
-        for x in 0..canvas.width {
-            if let Some(cell) = canvas.get(x, y) {
-                if let Some(fg) = cell.fg {
-                    if seen.insert((fg.r, fg.g, fg.b)) {
-                        colors.push(fg);
-                    }
-                }
-                if let Some(bg) = cell.bg {
-                    if seen.insert((bg.r, bg.g, bg.b)) {
-                        colors.push(bg);
-                    }
-                }
-            }
-        }
-    }
+    let colors = canvas.used_colors();
 
     let pal = CustomPalette {
         name: name.to_string(),
@@ -109,22 +165,21 @@ fn cmd_create(name: &str, file: &str) -> io::Result<()> {
 
     let path = palette_dir().join(format!("{}.palette", name));
     palette::save_palette(&pal, &path)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
 
     let json = serde_json::json!({
         "created": format!("{}.palette", name),
         "name": name,
         "colors_extracted": colors.len(),
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[&path]);
     Ok(())
 }
 
 fn cmd_export(name: &str, output: &str) -> io::Result<()> {
     let src = palette_dir().join(format!("{}.palette", name));
     if !src.exists() {
-        eprintln!("Error: Palette '{}' not found", name);
-        std::process::exit(1);
+        cli_error(&format!("Palette '{}' not found", name));
     }
     std::fs::copy(&src, Path::new(output))?;
 
@@ -132,23 +187,20 @@ fn cmd_export(name: &str, output: &str) -> io::Result<()> {
         "exported": output,
         "source": format!("{}.palette", name),
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[Path::new(output)]);
     Ok(())
 }
 
 fn cmd_add(name: &str, color: &str) -> io::Result<()> {
     let rgb = match parse_hex_color(color) {
         Some(c) => c,
-        None => {
-            eprintln!("Error: Invalid hex color '{}'", color);
-            std::process::exit(1);
-        }
+        None => cli_error(&format!("Invalid hex color '{}'", color)),
     };
 
     let path = palette_dir().join(format!("{}.palette", name));
     let mut pal = if path.exists() {
         palette::load_palette(&path)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .map_err(io::Error::other)?
     } else {
         CustomPalette {
             name: name.to_string(),
@@ -158,7 +210,7 @@ fn cmd_add(name: &str, color: &str) -> io::Result<()> {
 
     pal.colors.push(rgb);
     palette::save_palette(&pal, &path)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
 
     let json = serde_json::json!({
         "ok": true,
@@ -166,7 +218,116 @@ fn cmd_add(name: &str, color: &str) -> io::Result<()> {
         "added": rgb.name(),
         "total_colors": pal.colors.len(),
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    report(json, &[&path]);
+    Ok(())
+}
+
+fn cmd_remove(name: &str, color: &str) -> io::Result<()> {
+    let rgb = match parse_hex_color(color) {
+        Some(c) => c,
+        None => cli_error(&format!("Invalid hex color '{}'", color)),
+    };
+
+    let path = palette_dir().join(format!("{}.palette", name));
+    if !path.exists() {
+        cli_error(&format!("Palette '{}' not found", name));
+    }
+    let mut pal = palette::load_palette(&path)
+        .map_err(io::Error::other)?;
+
+    let before = pal.colors.len();
+    pal.colors.retain(|&c| c != rgb);
+    if pal.colors.len() == before {
+        cli_error(&format!("{} is not in palette '{}'", rgb.name(), name));
+    }
+    palette::save_palette(&pal, &path)
+        .map_err(io::Error::other)?;
+
+    let json = serde_json::json!({
+        "ok": true,
+        "palette": name,
+        "removed": rgb.name(),
+        "total_colors": pal.colors.len(),
+    });
+    report(json, &[&path]);
+    Ok(())
+}
+
+fn cmd_share(name: &str) -> io::Result<()> {
+    let path = palette_dir().join(format!("{}.palette", name));
+    let pal = match palette::load_palette(&path) {
+        Ok(pal) => pal,
+        Err(e) => cli_error(&e),
+    };
+    let json = serde_json::json!({
+        "name": pal.name,
+        "count": pal.colors.len(),
+        "share": palette::to_share_string(&pal),
+    });
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    Ok(())
+}
+
+fn cmd_import(text: &str, name: Option<&str>) -> io::Result<()> {
+    let shared = text.trim_start().starts_with(palette::SHARE_PREFIX);
+    if !shared && name.is_none() {
+        cli_error("A hex color list needs --name");
+    }
+    let mut pal = match palette::from_share_string(text, name.unwrap_or_default()) {
+        Ok(pal) => pal,
+        Err(e) => cli_error(&e),
+    };
+    if let Some(name) = name {
+        pal.name = name.to_string();
+    }
+    check_palette_name(&pal.name);
+
+    let path = palette_dir().join(format!("{}.palette", pal.name));
+    if path.exists() {
+        cli_error(&format!("Palette '{}' already exists", pal.name));
+    }
+    palette::save_palette(&pal, &path)
+        .map_err(io::Error::other)?;
+
+    let json = serde_json::json!({
+        "imported": format!("{}.palette", pal.name),
+        "name": pal.name,
+        "count": pal.colors.len(),
+    });
+    report(json, &[&path]);
+    Ok(())
+}
+
+/// Exit with an error unless `name` can be a palette's file name.
+fn check_palette_name(name: &str) {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        cli_error(&format!("Invalid palette name '{}'; use --name", name));
+    }
+}
+
+fn cmd_lospec(slug: &str, name: Option<&str>) -> io::Result<()> {
+    let name = name.unwrap_or(slug);
+    check_palette_name(name);
+    let settings = Settings::load_user().unwrap_or_else(|e| cli_error(&format!("Settings: {}", e)));
+    if !settings.allow_network {
+        cli_error("Network access is off. Set \"allow_network\": true in settings.json");
+    }
+    let path = palette_dir().join(format!("{}.palette", name));
+    if path.exists() {
+        cli_error(&format!("Palette '{}' already exists", name));
+    }
+    let mut pal = palette::fetch_lospec(slug).unwrap_or_else(|e| cli_error(&e));
+    pal.name = name.to_string();
+    palette::save_palette(&pal, &path)
+        .map_err(io::Error::other)?;
+
+    let json = serde_json::json!({
+        "downloaded": format!("{}.palette", name),
+        "source": palette::lospec_url(slug),
+        "name": name,
+        "count": pal.colors.len(),
+    });
+    report(json, &[&path]);
     Ok(())
 }
 
@@ -202,11 +363,8 @@ fn cmd_theme(name: &str) -> io::Result<()> {
             println!("{}", serde_json::to_string_pretty(&json).unwrap());
             Ok(())
         }
-        None => {
-            eprintln!("Error: Theme '{}' not found. Available: {}", name,
-                THEMES.iter().map(|t| t.name).collect::<Vec<_>>().join(", "));
-            std::process::exit(1);
-        }
+        None => cli_error(&format!("Theme '{}' not found. Available: {}", name,
+            THEMES.iter().map(|t| t.name).collect::<Vec<_>>().join(", "))),
     }
 }
 
@@ -1,7 +1,10 @@
+use std::cell::OnceCell;
 use std::io;
+use std::path::Path;
 
-use crate::cli::{CliColorFormat, PreviewFormat, load_project, to_color_format};
-use crate::export;
+use crate::cli::{CliColorFormat, ExportArgs, PreviewFormat, cli_error, load_project, report, to_color_format};
+use crate::export::{self, AnsiOptions, ColorFormat, ExportStats, TerminalPalette};
+use crate::settings::Settings;
 
 pub fn run(
     file: &str,
@@ -36,43 +39,104 @@ pub fn run(
             print!("{}", output);
             Ok(())
         }
+        PreviewFormat::Html => {
+            let canvas = match region {
+                Some((x1, y1, x2, y2)) => sub_canvas(&project, x1, y1, x2, y2),
+                None => project.canvas,
+            };
+            let reduced = export::reduce_colors(&canvas, cf, false, &Default::default());
+            println!("{}", export::to_html(&reduced));
+            Ok(())
+        }
+        PreviewFormat::Markdown => {
+            let canvas = match region {
+                Some((x1, y1, x2, y2)) => sub_canvas(&project, x1, y1, x2, y2),
+                None => project.canvas,
+            };
+            print!("{}", export::to_markdown(&canvas, None));
+            Ok(())
+        }
+        PreviewFormat::MarkdownHtml => {
+            let canvas = match region {
+                Some((x1, y1, x2, y2)) => sub_canvas(&project, x1, y1, x2, y2),
+                None => project.canvas,
+            };
+            let reduced = export::reduce_colors(&canvas, cf, false, &Default::default());
+            print!("{}", export::to_markdown_html(&reduced, None));
+            Ok(())
+        }
     }
 }
 
-pub fn export_to_file(
-    file: &str,
-    output: &str,
-    format: &PreviewFormat,
-    color_format: &CliColorFormat,
-) -> io::Result<()> {
-    let project = load_project(file);
-    let cf = to_color_format(color_format);
+pub fn export_to_file(args: &ExportArgs) -> io::Result<()> {
+    let project = load_project(&args.file);
+    let cf = to_color_format(&args.color_format);
+    // Settings only fill in options not given on the command line, and are
+    // only read for an option the export uses
+    let loaded = OnceCell::new();
+    let settings = || loaded.get_or_init(|| Settings::load_user().unwrap_or_else(|e| cli_error(&format!("Settings: {}", e))));
+    // Only 16-color reduction matches against the terminal's palette
+    let reduced = !matches!(args.format, PreviewFormat::Plain | PreviewFormat::Json | PreviewFormat::Markdown);
+    let terminal = match args.terminal_palette {
+        Some(terminal) => terminal,
+        None if reduced && cf == ColorFormat::Color16 => settings().terminal_palette,
+        None => TerminalPalette::default(),
+    };
+    let budget = args.budget.unwrap_or_else(|| settings().export_budget);
+    let ansi = AnsiOptions { transparent: args.transparent, cursor_forward: args.cursor_forward };
 
-    let content = match format {
-        PreviewFormat::Ansi => export::to_ansi(&project.canvas, cf),
-        PreviewFormat::Plain => export::to_plain_text(&project.canvas),
+    let content = match args.format {
+        PreviewFormat::Ansi => {
+            let reduced = export::reduce_colors(&project.canvas, cf, args.dither, &terminal);
+            export::to_ansi_with(&reduced, cf, &ansi)
+        }
+        PreviewFormat::Plain => export::to_plain_text_with(&project.canvas, args.double_width),
         PreviewFormat::Json => json_preview(&project, None),
+        PreviewFormat::Html => {
+            let reduced = export::reduce_colors(&project.canvas, cf, args.dither, &terminal);
+            export::to_html(&reduced)
+        }
+        PreviewFormat::Markdown => export::to_markdown(&project.canvas, args.note.as_deref()),
+        PreviewFormat::MarkdownHtml => {
+            let reduced = export::reduce_colors(&project.canvas, cf, args.dither, &terminal);
+            export::to_markdown_html(&reduced, args.note.as_deref())
+        }
     };
 
-    std::fs::write(output, &content)?;
+    std::fs::write(&args.output, &content)?;
 
-    let format_str = match format {
+    let format_str = match args.format {
         PreviewFormat::Ansi => "ansi",
         PreviewFormat::Plain => "plain",
         PreviewFormat::Json => "json",
+        PreviewFormat::Html => "html",
+        PreviewFormat::Markdown => "markdown",
+        PreviewFormat::MarkdownHtml => "markdown-html",
     };
-    let cf_str = match color_format {
+    let cf_str = match args.color_format {
         CliColorFormat::Truecolor => "truecolor",
         CliColorFormat::Color256 => "256",
         CliColorFormat::Color16 => "16",
     };
 
-    let json = serde_json::json!({
-        "exported": output,
+    let stats = ExportStats::of(&content);
+    let mut json = serde_json::json!({
+        "exported": args.output,
         "format": format_str,
         "color_format": cf_str,
+        "dither": args.dither,
+        "double_width": args.double_width,
+        "transparent": ansi.transparent.label(),
+        "cursor_forward": ansi.cursor_forward,
+        "lines": stats.lines,
+        "escape_bytes": stats.escape_bytes,
+        "summary": stats.summary(budget),
     });
-    println!("{}", serde_json::to_string(&json).unwrap());
+    if budget > 0 {
+        json["budget"] = budget.into();
+        json["over_budget"] = stats.over_budget(budget).into();
+    }
+    report(json, &[Path::new(&args.output)]);
     Ok(())
 }
 
@@ -120,26 +184,21 @@ fn ansi_region(
     x1: usize, y1: usize, x2: usize, y2: usize,
     format: crate::export::ColorFormat,
 ) -> String {
-    // Create a sub-canvas from the region
-    let canvas = &project.canvas;
-    let mut sub = crate::canvas::Canvas::new_with_size(
-        (x2 - x1 + 1).max(8),
-        (y2 - y1 + 1).max(8),
-    );
-    for y in y1..=y2.min(canvas.height.saturating_sub(1)) {
-        for x in x1..=x2.min(canvas.width.saturating_sub(1)) {
-            if let Some(cell) = canvas.get(x, y) {
-                sub.set(x - x1, y - y1, cell);
-            }
-        }
-    }
-    export::to_ansi(&sub, format)
+    export::to_ansi(&sub_canvas(project, x1, y1, x2, y2), format)
 }
 
 fn plain_region(
     project: &crate::project::Project,
     x1: usize, y1: usize, x2: usize, y2: usize,
 ) -> String {
+    export::to_plain_text(&sub_canvas(project, x1, y1, x2, y2))
+}
+
+/// The region copied to the top left of a new canvas.
+fn sub_canvas(
+    project: &crate::project::Project,
+    x1: usize, y1: usize, x2: usize, y2: usize,
+) -> crate::canvas::Canvas {
     let canvas = &project.canvas;
     let mut sub = crate::canvas::Canvas::new_with_size(
         (x2 - x1 + 1).max(8),
@@ -152,5 +211,5 @@ fn plain_region(
             }
         }
     }
-    export::to_plain_text(&sub)
+    sub
 }
@@ -59,7 +59,7 @@ pub fn run(file: &str) -> io::Result<()> {
 
     // FG color distribution sorted by count descending
     let mut fg_sorted: Vec<_> = fg_colors.into_iter().collect();
-    fg_sorted.sort_by(|a, b| b.1.cmp(&a.1));
+    fg_sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
     let fg_dist: Vec<_> = fg_sorted.iter()
         .map(|(c, n)| {
             let pct = if non_empty > 0 { (*n as f64 / non_empty as f64) * 100.0 } else { 0.0 };
@@ -69,7 +69,7 @@ pub fn run(file: &str) -> io::Result<()> {
 
     // BG color distribution sorted by count descending
     let mut bg_sorted: Vec<_> = bg_colors.into_iter().collect();
-    bg_sorted.sort_by(|a, b| b.1.cmp(&a.1));
+    bg_sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
     let bg_dist: Vec<_> = bg_sorted.iter()
         .map(|(c, n)| {
             let pct = if non_empty > 0 { (*n as f64 / non_empty as f64) * 100.0 } else { 0.0 };
@@ -79,7 +79,7 @@ pub fn run(file: &str) -> io::Result<()> {
 
     // Character distribution sorted by count descending
     let mut char_sorted: Vec<_> = unique_chars.into_iter().collect();
-    char_sorted.sort_by(|a, b| b.1.cmp(&a.1));
+    char_sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
     let char_dist: Vec<_> = char_sorted.iter()
         .map(|(ch, n)| {
             let pct = if non_empty > 0 { (*n as f64 / non_empty as f64) * 100.0 } else { 0.0 };
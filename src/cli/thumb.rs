@@ -0,0 +1,44 @@
+use std::io;
+use std::path::Path;
+
+use image::imageops::FilterType;
+
+use crate::cli::{cli_error, load_project, report};
+use crate::export::{self, CellAspect};
+use crate::settings::Settings;
+
+/// Write a PNG of the canvas scaled to fit in a `size`-pixel square.
+pub fn run(
+    file: &str,
+    output: &str,
+    size: u32,
+    aspect: Option<CellAspect>,
+    gamma: Option<f32>,
+    glyphs: bool,
+) -> io::Result<()> {
+    let project = load_project(file);
+    let settings = Settings::load_user().unwrap_or_else(|e| cli_error(&format!("Settings: {}", e)));
+    let aspect = aspect.unwrap_or(settings.cell_aspect);
+    let full = if glyphs {
+        export::to_glyph_image(&project.canvas, aspect.raster_cell())
+    } else {
+        export::to_image(&project.canvas, aspect.raster_cell())
+    };
+
+    let (w, h) = full.dimensions();
+    let scale = size as f64 / w.max(h) as f64;
+    let tw = ((w as f64 * scale).round() as u32).max(1);
+    let th = ((h as f64 * scale).round() as u32).max(1);
+    // Enlarged glyphs keep hard pixel edges, like the terminal shows them
+    let filter = if glyphs && scale >= 1.0 { FilterType::Nearest } else { FilterType::Triangle };
+    let thumb = image::imageops::resize(&full, tw, th, filter);
+    export::write_png(&thumb, Path::new(output), gamma.unwrap_or(settings.export_gamma))?;
+
+    let json = serde_json::json!({
+        "written": output,
+        "width": tw,
+        "height": th,
+    });
+    report(json, &[Path::new(output)]);
+    Ok(())
+}
@@ -0,0 +1,955 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, AppMode};
+use crate::canvas::Anchor;
+use crate::cell::{Cell, Rgb};
+use crate::palette::{PaletteItem, PaletteSection};
+use crate::symmetry::SymmetryMode;
+use crate::tools::ToolKind;
+
+/// Editor actions, decoupled from the key or mouse event that triggered them.
+/// Input handling maps events to commands; `App::execute` applies them. Canvas
+/// coordinates are in cells, so a command sequence replays the same way
+/// whatever the terminal size or zoom.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    Undo,
+    Redo,
+    /// Step through history with a live preview of each state
+    OpenHistoryScrubber,
+    OpenVersions,
+    /// Save to the current path, or prompt for a name if there is none
+    Save,
+    Quit,
+    /// Reload the project from disk, after confirming, discarding unsaved changes
+    RevertToSaved,
+    SelectTool(ToolKind),
+    ToggleSymmetryHorizontal,
+    ToggleSymmetryVertical,
+    OpenSymmetryPicker,
+    CycleZoom,
+    CycleTheme,
+    CycleBlock,
+    CycleShade,
+    ToggleFilledRect,
+    /// Show or hide render timings and memory stats
+    ToggleDebugOverlay,
+    /// Hand the mouse back to the terminal for its own text selection
+    ToggleMouseCapture,
+    /// Hand the terminal back to the shell until resumed with `fg`
+    Suspend,
+    /// Cycle solid, dashed and dotted lines and rectangle outlines
+    CycleLineStyle,
+    /// Step the pencil through solid and dithered brush patterns
+    CycleBrushPattern,
+    /// Cycle what the fill tool matches: whole cell, character, or color
+    CycleFillMode,
+    /// Keep every pencil or eraser stroke on its starting row or column
+    ToggleAxisLock,
+    /// Take L-shaped corners out of pencil strokes as they end
+    TogglePixelPerfect,
+    SetColor(Rgb),
+    SetBlock(char),
+    /// Select one of the ten quick-pick palette slots
+    QuickPickColor(usize),
+    /// Move the palette cursor by a signed offset; ignored if it would leave the list
+    MovePaletteCursor(isize),
+    /// Show the previous (negative) or next page of the Colors box
+    FlipColorsPage(isize),
+    /// Sort palette sections by index or by saturation and lightness
+    TogglePaletteOrder,
+    /// Toggle the section header or pick the color under the palette cursor
+    ActivatePaletteItem,
+    AddColorToPalette,
+    /// Mark or unmark the custom palette color under the palette cursor
+    TogglePaletteMark,
+    /// Remove the marked custom palette colors; nothing happens without marks
+    DeletePaletteColors,
+    /// Move the marked custom palette colors, or the one under the cursor,
+    /// one place earlier (negative) or later
+    MovePaletteColors(isize),
+    /// Move the keyboard canvas cursor, activating it
+    MoveCanvasCursor { dx: isize, dy: isize },
+    /// Show or hide the minimap of a canvas larger than the view
+    ToggleMinimap,
+    ToggleTilePreview,
+    /// Scroll the view by canvas cells, leaving the cursor where it is
+    Pan { dx: isize, dy: isize },
+    /// Apply the active tool at the keyboard canvas cursor
+    ApplyAtCursor,
+    /// Paint `count` cells from the keyboard cursor, stepping by (dx, dy)
+    PaintRun { count: usize, dx: isize, dy: isize },
+    /// Draw a rectangle of exactly this size from the anchor corner
+    PaintRect { width: usize, height: usize },
+    /// Mouse button down on a canvas cell; `unmirrored` (Alt held) skips
+    /// symmetry for this click or stroke
+    Press {
+        x: usize,
+        y: usize,
+        #[serde(default)]
+        unmirrored: bool,
+    },
+    /// Mouse drag onto a canvas cell; `locked` (Shift held) keeps the stroke
+    /// on its starting row or column
+    Drag {
+        x: usize,
+        y: usize,
+        #[serde(default)]
+        locked: bool,
+    },
+    /// Mouse button released
+    Release,
+    /// Swap the colors of the cell under the cursor, flipping a half block
+    /// whose other half is transparent
+    SwapCellColors,
+    /// Inspect the cell under the cursor and edit its character and exact
+    /// colors
+    OpenInspector,
+    /// Replace a cell exactly
+    EditCell { x: usize, y: usize, cell: Cell },
+    /// Pick color and block from a cell without switching tools
+    PickAt { x: usize, y: usize },
+    /// Scroll the view so canvas cell (x, y) is in the middle, as far as
+    /// the edges allow
+    CenterView { x: usize, y: usize },
+    /// Middle button down at a screen position: start dragging the view
+    GrabView { column: u16, row: u16 },
+    /// Middle-button drag to a screen position: move the view with it
+    DragView { column: u16, row: u16 },
+    /// Mouse wheel: zoom in (positive) or out one level, keeping canvas
+    /// cell (x, y) under the pointer, `column` and `row` screen cells into
+    /// the canvas
+    ZoomAt { x: usize, y: usize, column: u16, row: u16, step: isize },
+    /// Pointer hover; `None` when the pointer leaves the canvas
+    Hover(Option<(usize, usize)>),
+    NewCanvas { width: usize, height: usize },
+    /// Resize the canvas, keeping its art at `anchor`
+    ResizeCanvas { width: usize, height: usize, anchor: Anchor },
+    OpenFile(String),
+    /// Open a project in a new tab, keeping the current one open
+    OpenFileInTab(String),
+    /// Edit the project in the next tab
+    NextTab,
+    /// Esc: drop the keyboard cursor, or cancel a multi-click tool
+    Cancel,
+    OpenFileDialog,
+    OpenNewCanvasDialog,
+    OpenResizeDialog,
+    CropToContent,
+    /// Move every cell; with `wrap`, cells leaving one edge enter the other
+    ShiftCanvas { dx: isize, dy: isize, wrap: bool },
+    OpenExportDialog,
+    /// Copy only the selected cells as ANSI, at the export dialog's depth
+    CopySelectionAnsi,
+    /// Copy the selected cells for `Paste`, in this tab or another
+    Copy,
+    /// Paste copied cells with their top-left corner at the cursor
+    Paste,
+    OpenImportDialog,
+    OpenColorSliders,
+    OpenHexInput,
+    OpenPaletteDialog,
+    OpenBlockPicker,
+    /// Prompt for a count and direction to paint with `PaintRun`
+    OpenRunPrompt,
+    /// Select every cell drawn with the active block
+    SelectActiveBlock,
+    /// Step selected shade cells denser (positive) or lighter (negative)
+    ShadeSelection(isize),
+    RecolorSelection,
+    /// Select every cell showing the active color and jump to the first
+    FindColor,
+    /// Move the keyboard cursor to the next (positive) or previous selected
+    /// cell
+    JumpToSelected(isize),
+    EraseSelection,
+    /// Prompt for a size to draw with `PaintRect`
+    OpenRectSizePrompt,
+    OpenHelp,
+    OpenReleaseNotes,
+}
+
+impl Command {
+    /// Whether the command reaches outside the editor: writing a file or
+    /// the clipboard rather than only changing the app.
+    pub fn has_side_effects(&self) -> bool {
+        matches!(
+            self,
+            Command::Save
+                | Command::AddColorToPalette
+                | Command::DeletePaletteColors
+                | Command::MovePaletteColors(_)
+                | Command::CopySelectionAnsi
+        )
+    }
+}
+
+/// Parse a rectangle size like `12x5` (or `12×5`).
+pub fn parse_rect_size(text: &str) -> Result<Command, String> {
+    let (width, height) = crate::cli::parse_size(&text.trim().replace('\u{d7}', "x"))?;
+    if width == 0 || height == 0 {
+        return Err("Size must be at least 1x1".to_string());
+    }
+    Ok(Command::PaintRect { width, height })
+}
+
+/// Parse a run like `8d` or `12 down`: a cell count followed by a direction,
+/// given as a cursor key (`w`/`a`/`s`/`d`), a word, or an arrow.
+pub fn parse_run(text: &str) -> Result<Command, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (count, dir) = text.split_at(split);
+    let count: usize = count.parse().map_err(|_| format!("Run needs a count first: {}", text))?;
+    let (dx, dy) = match dir.trim().to_lowercase().as_str() {
+        "w" | "up" | "\u{2191}" => (0, -1),
+        "s" | "down" | "\u{2193}" => (0, 1),
+        "a" | "left" | "\u{2190}" => (-1, 0),
+        "d" | "right" | "\u{2192}" => (1, 0),
+        other => return Err(format!("Unknown direction: {:?}", other)),
+    };
+    Ok(Command::PaintRun { count, dx, dy })
+}
+
+fn is_stroke_tool(tool: ToolKind) -> bool {
+    matches!(tool, ToolKind::Pencil | ToolKind::Eraser | ToolKind::Spray)
+}
+
+impl App {
+    pub fn execute(&mut self, cmd: Command) {
+        if let Some(ref mut log) = self.session_log {
+            log.record(&cmd);
+        }
+        crate::crash::record_command(&cmd);
+        let before = self.screen_reader.then_some((self.active_tool, self.color));
+        match cmd {
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::OpenHistoryScrubber => self.open_history_scrubber(),
+            Command::OpenVersions => self.open_versions(),
+            Command::Save => {
+                if !self.save_project() {
+                    // No path set — prompt for name
+                    self.text_input = self
+                        .project_name
+                        .clone()
+                        .unwrap_or_else(|| "untitled".to_string());
+                    self.mode = AppMode::SaveAs;
+                }
+            }
+            Command::Quit => {
+                if self.dirty {
+                    self.mode = AppMode::Quitting;
+                    if self.documents.is_empty() {
+                        self.set_status("Unsaved changes. Quit? (y/n)");
+                    } else {
+                        self.set_status("Unsaved changes. Close this tab? (y/n)");
+                    }
+                } else {
+                    self.close_document();
+                }
+            }
+            Command::RevertToSaved => {
+                if self.project_path.is_none() {
+                    self.set_status("Not saved yet, nothing to revert to");
+                } else if !self.dirty {
+                    self.set_status("No unsaved changes");
+                } else {
+                    self.mode = AppMode::RevertConfirm;
+                    self.set_status("Discard unsaved changes? (y/n)");
+                }
+            }
+            Command::SelectTool(tool) => {
+                self.active_tool = tool;
+                self.cancel_tool();
+            }
+            Command::ToggleSymmetryHorizontal => {
+                self.symmetry = self.symmetry.toggle_horizontal();
+                self.set_status(&format!("Symmetry: {}", self.symmetry.label()));
+            }
+            Command::ToggleSymmetryVertical => {
+                self.symmetry = self.symmetry.toggle_vertical();
+                self.set_status(&format!("Symmetry: {}", self.symmetry.label()));
+            }
+            Command::OpenSymmetryPicker => {
+                self.symmetry_picker_selected =
+                    SymmetryMode::ALL.iter().position(|&m| m == self.symmetry).unwrap_or(0);
+                self.mode = AppMode::SymmetryPicker;
+            }
+            Command::CycleZoom => self.cycle_zoom(),
+            Command::CycleTheme => self.cycle_theme(),
+            Command::CycleBlock => self.cycle_block(),
+            Command::CycleShade => self.cycle_shade(),
+            Command::ToggleFilledRect => {
+                self.filled_rect = !self.filled_rect;
+                self.set_status(if self.filled_rect { "Rect: Filled" } else { "Rect: Outline" });
+            }
+            Command::ToggleDebugOverlay => self.debug_overlay = !self.debug_overlay,
+            Command::ToggleMouseCapture => {
+                self.mouse_capture = !self.mouse_capture;
+                if self.mouse_capture {
+                    self.set_status("Mouse capture on");
+                } else {
+                    let key = self.keymap.key_label(&Command::ToggleMouseCapture).unwrap_or_default();
+                    self.set_status(&format!("Mouse capture off: select text freely, {} to draw again", key));
+                }
+            }
+            Command::Suspend => self.suspend_requested = true,
+            Command::CycleLineStyle => {
+                self.line_style = self.line_style.next();
+                self.set_status(&format!("Line style: {}", self.line_style.name()));
+            }
+            Command::CycleBrushPattern => {
+                self.brush_pattern = self.brush_pattern.next();
+                self.set_status(&format!("Brush: {}", self.brush_pattern.name()));
+            }
+            Command::CycleFillMode => {
+                self.fill_mode = self.fill_mode.next();
+                self.set_status(&format!("Fill: match {}", self.fill_mode.name()));
+            }
+            Command::TogglePixelPerfect => {
+                self.pixel_perfect = !self.pixel_perfect;
+                self.set_status(if self.pixel_perfect { "Pixel-perfect pencil: On" } else { "Pixel-perfect pencil: Off" });
+            }
+            Command::ToggleAxisLock => {
+                self.axis_lock = !self.axis_lock;
+                self.set_status(if self.axis_lock { "Axis lock: On" } else { "Axis lock: Off" });
+            }
+            Command::SetColor(color) => self.color = color,
+            Command::SetBlock(ch) => {
+                self.active_block = ch;
+                self.set_status(&format!("Block: {}", ch));
+            }
+            Command::QuickPickColor(n) => {
+                self.quick_pick_color(n);
+            }
+            Command::MovePaletteCursor(delta) => {
+                self.colors_page = self.colors_page();
+                let target = self.palette_cursor as isize + delta;
+                if target >= 0 && (target as usize) < self.palette_layout.len() {
+                    self.palette_cursor = target as usize;
+                    if let Some(PaletteItem::Color(color)) = self.palette_layout.get(self.palette_cursor) {
+                        self.color = *color;
+                    }
+                    self.ensure_palette_cursor_visible(15);
+                }
+            }
+            Command::FlipColorsPage(delta) => self.flip_colors_page(delta),
+            Command::TogglePaletteOrder => self.toggle_palette_order(),
+            Command::ActivatePaletteItem => self.activate_palette_item(),
+            Command::AddColorToPalette => self.add_color_to_custom_palette(),
+            Command::TogglePaletteMark => self.toggle_palette_mark(),
+            Command::DeletePaletteColors => self.delete_palette_colors(),
+            Command::MovePaletteColors(delta) => self.move_palette_colors(delta),
+            Command::MoveCanvasCursor { dx, dy } => {
+                let (cx, cy) = self.canvas_cursor;
+                let max_x = self.canvas.width.saturating_sub(1) as isize;
+                let max_y = self.canvas.height.saturating_sub(1) as isize;
+                self.canvas_cursor = (
+                    (cx as isize + dx).clamp(0, max_x) as usize,
+                    (cy as isize + dy).clamp(0, max_y) as usize,
+                );
+                self.canvas_cursor_active = true;
+                let (cx, cy) = self.canvas_cursor;
+                self.ensure_cursor_in_viewport(cx, cy, self.viewport_w, self.viewport_h);
+            }
+            Command::Pan { dx, dy } => self.pan(dx, dy),
+            Command::ToggleMinimap => {
+                self.show_minimap = !self.show_minimap;
+                self.set_status(if self.show_minimap { "Minimap: On" } else { "Minimap: Off" });
+            }
+            Command::ToggleTilePreview => self.toggle_tile_preview(),
+            Command::GrabView { column, row } => {
+                self.view_grab = Some((column, row, self.viewport_x, self.viewport_y));
+            }
+            Command::DragView { column, row } => self.drag_view(column, row),
+            Command::CenterView { x, y } => self.center_view(x, y),
+            Command::ZoomAt { x, y, column, row, step } => self.zoom_at(x, y, column, row, step),
+            Command::ApplyAtCursor => {
+                let (x, y) = self.canvas_cursor;
+                let stroke = is_stroke_tool(self.active_tool);
+                if stroke {
+                    self.begin_stroke();
+                }
+                self.apply_tool(x, y);
+                if stroke {
+                    self.end_stroke();
+                }
+            }
+            Command::PaintRun { count, dx, dy } => self.paint_run(count, dx, dy),
+            Command::PaintRect { width, height } => self.paint_rect(width, height),
+            Command::Press { x, y, unmirrored } => {
+                self.unmirrored = unmirrored;
+                self.stroke_origin = Some((x, y));
+                self.stroke_axis = None;
+                self.cursor = Some((x, y));
+                self.canvas_cursor = (x, y);
+                self.canvas_cursor_active = false;
+                // Start stroke for continuous tools
+                if is_stroke_tool(self.active_tool) {
+                    self.begin_stroke();
+                }
+                self.apply_tool(x, y);
+                if !self.history.is_stroke_active() {
+                    self.unmirrored = false;
+                }
+            }
+            Command::Drag { x, y, locked } => {
+                let (x, y) = if locked || self.axis_lock { self.lock_to_axis(x, y) } else { (x, y) };
+                self.cursor = Some((x, y));
+                if is_stroke_tool(self.active_tool) {
+                    self.apply_tool(x, y);
+                }
+            }
+            Command::Release => {
+                if self.history.is_stroke_active() {
+                    self.end_stroke();
+                }
+                self.unmirrored = false;
+            }
+            Command::SwapCellColors => self.swap_cell_colors(),
+            Command::OpenInspector => self.open_inspector(),
+            Command::EditCell { x, y, cell } => self.edit_cell(x, y, cell),
+            Command::PickAt { x, y } => {
+                if let Some((picked_fg, _bg, ch)) = crate::tools::eyedropper(&self.canvas, x, y) {
+                    if let Some(picked) = picked_fg {
+                        self.color = picked;
+                        self.set_status(&format!("Picked: {} {}", picked.name(), ch));
+                    }
+                    if ch != ' ' {
+                        self.active_block = ch;
+                    }
+                }
+            }
+            Command::Hover(pos) => {
+                self.cursor = pos;
+                if pos.is_some() {
+                    self.canvas_cursor_active = false;
+                }
+            }
+            Command::NewCanvas { width, height } => self.new_canvas(width, height),
+            Command::ResizeCanvas { width, height, anchor } => self.resize_canvas(width, height, anchor),
+            Command::CropToContent => self.crop_to_content(),
+            Command::ShiftCanvas { dx, dy, wrap } => self.shift_canvas(dx, dy, wrap),
+            Command::OpenFile(path) => {
+                self.load_project(&path);
+            }
+            Command::OpenFileInTab(path) => self.open_in_new_tab(&path),
+            Command::NextTab => self.next_tab(),
+            Command::SelectActiveBlock => self.select_active_block(),
+            Command::ShadeSelection(delta) => self.shade_selection(delta),
+            Command::RecolorSelection => self.recolor_selection(),
+            Command::FindColor => self.find_color(),
+            Command::JumpToSelected(step) => self.jump_to_selected(step),
+            Command::EraseSelection => self.erase_selection(),
+            Command::Cancel => {
+                if self.selection.is_some() {
+                    self.selection = None;
+                    self.set_status("Selection cleared");
+                } else if self.canvas_cursor_active {
+                    self.canvas_cursor_active = false;
+                    self.set_status("Canvas cursor off");
+                } else {
+                    self.cancel_tool();
+                    self.set_status("Cancelled");
+                }
+            }
+            Command::OpenFileDialog => self.open_file_dialog(),
+            Command::OpenImportDialog => self.open_import_dialog(),
+            Command::OpenNewCanvasDialog => {
+                self.new_canvas_width = self.canvas.width;
+                self.new_canvas_height = self.canvas.height;
+                self.new_canvas_cursor = 0;
+                self.mode = AppMode::NewCanvas;
+            }
+            Command::OpenResizeDialog => {
+                self.resize_width = self.canvas.width;
+                self.resize_height = self.canvas.height;
+                self.resize_cursor = 0;
+                self.mode = AppMode::ResizeCanvas;
+            }
+            Command::OpenExportDialog => {
+                self.export_format = 0;
+                self.export_dest = 0;
+                self.export_cursor = 0;
+                self.export_color_format = 0;
+                self.export_analysis = None;
+                self.export_gif = false;
+                self.mode = AppMode::ExportDialog;
+            }
+            Command::CopySelectionAnsi => self.copy_selection_ansi(),
+            Command::Copy => self.copy_cells(),
+            Command::Paste => self.paste_cells(),
+            Command::OpenColorSliders => {
+                let (h, s, l) = crate::palette::rgb_to_hsl(self.color.r, self.color.g, self.color.b);
+                self.slider_h = h;
+                self.slider_s = s;
+                self.slider_l = l;
+                self.slider_active = 0;
+                self.mode = AppMode::ColorSliders;
+            }
+            Command::OpenHexInput => {
+                self.text_input = String::new();
+                self.mode = AppMode::HexColorInput;
+            }
+            Command::OpenPaletteDialog => self.open_palette_dialog(),
+            Command::OpenBlockPicker => self.open_block_picker(),
+            Command::OpenRunPrompt => {
+                self.text_input = String::new();
+                self.mode = AppMode::RunInput;
+            }
+            Command::OpenRectSizePrompt => {
+                self.text_input = String::new();
+                self.mode = AppMode::RectSizeInput;
+            }
+            Command::OpenHelp => self.open_help(),
+            Command::OpenReleaseNotes => self.open_release_notes(),
+        }
+        if let Some((tool, color)) = before {
+            self.announce_changes(tool, color);
+        }
+    }
+
+    fn activate_palette_item(&mut self) {
+        let Some(item) = self.palette_layout.get(self.palette_cursor).copied() else {
+            return;
+        };
+        match item {
+            PaletteItem::SectionHeader(section) => {
+                let sections = &mut self.palette_sections;
+                match section {
+                    PaletteSection::Standard => sections.standard_expanded = !sections.standard_expanded,
+                    PaletteSection::HueGroups => sections.hue_expanded = !sections.hue_expanded,
+                    PaletteSection::Grayscale => sections.grayscale_expanded = !sections.grayscale_expanded,
+                }
+                self.rebuild_palette_layout();
+                // Clamp cursor if layout shrank
+                if self.palette_cursor >= self.palette_layout.len() {
+                    self.palette_cursor = self.palette_layout.len().saturating_sub(1);
+                }
+            }
+            PaletteItem::Color(color) => self.color = color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_press_drag_release_is_one_undo_step() {
+        let mut app = App::new();
+        app.execute(Command::SelectTool(ToolKind::Pencil));
+        app.execute(Command::Press { x: 1, y: 1, unmirrored: false });
+        app.execute(Command::Drag { x: 2, y: 1, locked: false });
+        app.execute(Command::Drag { x: 3, y: 1, locked: false });
+        app.execute(Command::Release);
+        assert!(!app.canvas.get(3, 1).unwrap().is_empty());
+
+        app.execute(Command::Undo);
+        for x in 1..=3 {
+            assert!(app.canvas.get(x, 1).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_screen_reader_announces_tool_and_color() {
+        let mut app = App::new();
+        app.execute(Command::SelectTool(ToolKind::Fill));
+        assert_eq!(app.announcement, None);
+        app.screen_reader = true;
+        app.execute(Command::SelectTool(ToolKind::Eraser));
+        assert_eq!(app.announcement.as_deref(), Some("Tool: Eraser"));
+        app.execute(Command::SetColor(Rgb::new(255, 0, 0)));
+        assert_eq!(app.announcement.as_deref(), Some("Color: #FF0000"));
+        assert!(app.screen_reader_status().starts_with("Color: #FF0000. Tool Eraser"));
+    }
+
+    #[test]
+    fn test_locked_drag_keeps_to_first_axis() {
+        let mut app = App::new();
+        app.execute(Command::Press { x: 5, y: 5, unmirrored: false });
+        app.execute(Command::Drag { x: 7, y: 6, locked: true });
+        app.execute(Command::Drag { x: 9, y: 9, locked: true });
+        app.execute(Command::Release);
+        assert!(!app.canvas.get(7, 5).unwrap().is_empty());
+        assert!(!app.canvas.get(9, 5).unwrap().is_empty());
+        assert!(app.canvas.get(9, 9).unwrap().is_empty());
+
+        app.execute(Command::ToggleAxisLock);
+        app.execute(Command::Press { x: 20, y: 2, unmirrored: false });
+        app.execute(Command::Drag { x: 20, y: 4, locked: false });
+        app.execute(Command::Drag { x: 23, y: 8, locked: false });
+        assert!(!app.canvas.get(20, 8).unwrap().is_empty());
+        assert!(app.canvas.get(23, 8).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pixel_perfect_stroke_drops_corners_and_mirrors() {
+        use crate::cell::{blocks, Cell};
+        let mut app = App::new();
+        let w = app.canvas.width;
+        app.canvas.set(1, 0, Cell { ch: blocks::SHADE_LIGHT, fg: None, bg: None });
+        app.execute(Command::TogglePixelPerfect);
+        app.execute(Command::ToggleSymmetryHorizontal);
+        app.execute(Command::Press { x: 0, y: 0, unmirrored: false });
+        for (x, y) in [(1, 0), (1, 1), (2, 1), (2, 2)] {
+            app.execute(Command::Drag { x, y, locked: false });
+        }
+        app.execute(Command::Release);
+        for x in [0, w - 1] {
+            assert!(!app.canvas.get(x, 0).unwrap().is_empty());
+        }
+        assert_eq!(app.canvas.get(1, 0).unwrap().ch, blocks::SHADE_LIGHT, "corner restored");
+        assert!(app.canvas.get(w - 2, 0).unwrap().is_empty(), "mirror corner restored");
+        assert!(app.canvas.get(2, 1).unwrap().is_empty());
+        assert!(!app.canvas.get(1, 1).unwrap().is_empty());
+
+        // One undo step takes the whole stroke back
+        app.undo();
+        assert_eq!(app.canvas.get(1, 0).unwrap().ch, blocks::SHADE_LIGHT);
+        assert!(app.canvas.get(1, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_run() {
+        assert_eq!(parse_run("8d"), Ok(Command::PaintRun { count: 8, dx: 1, dy: 0 }));
+        assert_eq!(parse_run(" 12 Up "), Ok(Command::PaintRun { count: 12, dx: 0, dy: -1 }));
+        assert!(parse_run("d8").is_err());
+        assert!(parse_run("3x").is_err());
+    }
+
+    #[test]
+    fn test_paint_run_stops_at_edge_as_one_undo_step() {
+        let mut app = App::new();
+        let w = app.canvas.width;
+        app.canvas_cursor = (w - 3, 4);
+        app.execute(Command::PaintRun { count: 10, dx: 1, dy: 0 });
+        assert_eq!(app.canvas_cursor, (w - 1, 4));
+        assert!(app.canvas_cursor_active);
+        assert!((w - 3..w).all(|x| !app.canvas.get(x, 4).unwrap().is_empty()));
+        assert_eq!(app.announcement.as_deref(), Some("Run: 3 cells"));
+        app.execute(Command::Undo);
+        assert!((w - 3..w).all(|x| app.canvas.get(x, 4).unwrap().is_empty()));
+    }
+
+    #[test]
+    fn test_paint_rect_from_pending_corner() {
+        let mut app = App::new();
+        assert_eq!(parse_rect_size("4\u{d7}3"), Ok(Command::PaintRect { width: 4, height: 3 }));
+        assert!(parse_rect_size("0x3").is_err());
+        app.execute(Command::SelectTool(ToolKind::Rectangle));
+        app.execute(Command::Press { x: 2, y: 2, unmirrored: false });
+        app.execute(Command::PaintRect { width: 4, height: 3 });
+        assert!(matches!(app.tool_state, crate::tools::ToolState::Idle));
+        assert!(!app.canvas.get(5, 4).unwrap().is_empty());
+        assert!(app.canvas.get(3, 3).unwrap().is_empty(), "outline leaves the inside empty");
+        assert!(app.canvas.get(6, 4).unwrap().is_empty());
+        assert_eq!(app.canvas_cursor, (5, 4));
+    }
+
+    #[test]
+    fn test_huge_rect_size_stops_at_the_edge() {
+        let mut app = App::new();
+        let w = app.canvas.width;
+        app.canvas_cursor = (5, 5);
+        app.execute(parse_rect_size("18446744073709551615x1").unwrap());
+        assert_eq!(app.canvas_cursor, (w - 1, 5));
+        assert_eq!(app.announcement.as_deref(), Some(format!("Rect: {}x1", w - 5).as_str()));
+    }
+
+    #[test]
+    fn test_huge_run_count_stops_at_the_edge() {
+        let mut app = App::new();
+        let w = app.canvas.width;
+        app.canvas_cursor = (5, 5);
+        app.execute(parse_run("9223372036854775808d").unwrap());
+        assert_eq!(app.canvas_cursor, (w - 1, 5), "not flipped to run left");
+        app.canvas_cursor = (5, 5);
+        app.execute(parse_run("18446744073709551615 up").unwrap());
+        assert_eq!(app.canvas_cursor, (5, 0));
+    }
+
+    #[test]
+    fn test_select_active_block_then_darken() {
+        use crate::cell::blocks;
+        let mut app = App::new();
+        app.execute(Command::SetBlock(blocks::SHADE_MEDIUM));
+        app.execute(Command::Press { x: 1, y: 1, unmirrored: false });
+        app.execute(Command::Release);
+        app.execute(Command::Press { x: 6, y: 2, unmirrored: false });
+        app.execute(Command::Release);
+        app.execute(Command::SetBlock(blocks::SHADE_LIGHT));
+        app.execute(Command::Press { x: 3, y: 3, unmirrored: false });
+        app.execute(Command::Release);
+
+        app.execute(Command::SetBlock(blocks::SHADE_MEDIUM));
+        app.execute(Command::SelectActiveBlock);
+        assert_eq!(app.selection.as_ref().map(|s| s.len()), Some(2));
+        app.execute(Command::ShadeSelection(1));
+        assert_eq!(app.canvas.get(6, 2).unwrap().ch, blocks::SHADE_DARK);
+        assert_eq!(app.canvas.get(3, 3).unwrap().ch, blocks::SHADE_LIGHT);
+        app.execute(Command::Undo);
+        assert_eq!(app.canvas.get(1, 1).unwrap().ch, blocks::SHADE_MEDIUM);
+        assert_eq!(app.canvas.get(6, 2).unwrap().ch, blocks::SHADE_MEDIUM);
+
+        app.execute(Command::Cancel);
+        assert!(app.selection.is_none());
+    }
+
+    #[test]
+    fn test_spray_scatters_around_cursor_and_undoes_as_one_stroke() {
+        let mut app = App::new();
+        app.spray_rng = crate::tools::Scatter::new(7);
+        app.execute(Command::SelectTool(ToolKind::Spray));
+        app.execute(Command::Press { x: 10, y: 10, unmirrored: false });
+        let painted = |app: &App| {
+            (0..app.canvas.height)
+                .flat_map(|y| (0..app.canvas.width).map(move |x| (x, y)))
+                .filter(|&(x, y)| !app.canvas.get(x, y).unwrap().is_empty())
+                .collect::<Vec<_>>()
+        };
+        let first = painted(&app).len();
+        assert!((1..=4).contains(&first));
+        assert!(painted(&app).iter().all(|&(x, y)| x.abs_diff(10) <= 3 && y.abs_diff(10) <= 3));
+
+        // Holding still keeps spraying once the interval has passed
+        assert!(!app.tick_spray());
+        app.last_spray -= std::time::Duration::from_secs(1);
+        assert!(app.tick_spray());
+        assert!(painted(&app).len() >= first);
+        app.execute(Command::Release);
+        assert!(!app.tick_spray());
+
+        app.execute(Command::Undo);
+        assert!(painted(&app).is_empty());
+    }
+
+    #[test]
+    fn test_unmirrored_press_skips_symmetry_for_one_stroke() {
+        let mut app = App::new();
+        app.execute(Command::ToggleSymmetryHorizontal);
+        let width = app.canvas.width;
+        let mirror = |x: usize| width - 1 - x;
+        app.execute(Command::Press { x: 1, y: 1, unmirrored: true });
+        app.execute(Command::Drag { x: 2, y: 1, locked: false });
+        app.execute(Command::Release);
+        assert!(!app.canvas.get(2, 1).unwrap().is_empty());
+        assert!(app.canvas.get(mirror(1), 1).unwrap().is_empty());
+        assert!(app.canvas.get(mirror(2), 1).unwrap().is_empty());
+        assert!(!app.unmirrored);
+
+        app.execute(Command::Press { x: 1, y: 3, unmirrored: false });
+        app.execute(Command::Release);
+        assert!(!app.canvas.get(mirror(1), 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dashed_line_and_outline() {
+        let mut app = App::new();
+        app.execute(Command::CycleLineStyle);
+        app.execute(Command::SelectTool(ToolKind::Line));
+        app.execute(Command::Press { x: 0, y: 0, unmirrored: false });
+        app.execute(Command::Press { x: 9, y: 0, unmirrored: false });
+        let drawn: Vec<usize> = (0..10).filter(|&x| !app.canvas.get(x, 0).unwrap().is_empty()).collect();
+        assert_eq!(drawn, vec![0, 1, 2, 5, 6, 7]);
+
+        app.execute(Command::CycleLineStyle);
+        app.execute(Command::SelectTool(ToolKind::Rectangle));
+        app.execute(Command::Press { x: 0, y: 2, unmirrored: false });
+        app.execute(Command::Hover(Some((4, 4))));
+        assert_eq!(app.tool_preview_points().len(), 6, "dotted preview of a 12-cell outline");
+        app.execute(Command::Press { x: 4, y: 4, unmirrored: false });
+        assert!(!app.canvas.get(0, 2).unwrap().is_empty());
+        assert!(app.canvas.get(1, 2).unwrap().is_empty());
+        assert!(!app.canvas.get(2, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_move_canvas_cursor_clamps_and_activates() {
+        let mut app = App::new();
+        app.execute(Command::MoveCanvasCursor { dx: -1, dy: -1 });
+        assert_eq!(app.canvas_cursor, (0, 0));
+        assert!(app.canvas_cursor_active);
+        app.execute(Command::MoveCanvasCursor { dx: 1000, dy: 2 });
+        assert_eq!(app.canvas_cursor, (app.canvas.width - 1, 2));
+    }
+
+    #[test]
+    fn test_pan_clamps_and_leaves_cursor() {
+        let mut app = App::new();
+        app.canvas = crate::canvas::Canvas::new_with_size(100, 50);
+        app.viewport_w = 40;
+        app.viewport_h = 20;
+        app.execute(Command::Pan { dx: 5, dy: 3 });
+        assert_eq!((app.viewport_x, app.viewport_y), (5, 3));
+        assert_eq!(app.canvas_cursor, (0, 0));
+        app.execute(Command::Pan { dx: -10, dy: 1000 });
+        assert_eq!((app.viewport_x, app.viewport_y), (0, 30));
+
+        // Middle-drag at 2x: dragging left by 6 columns scrolls right by 3 cells
+        app.zoom = 2;
+        app.execute(Command::GrabView { column: 50, row: 10 });
+        app.execute(Command::DragView { column: 44, row: 12 });
+        assert_eq!((app.viewport_x, app.viewport_y), (3, 28));
+    }
+
+    #[test]
+    fn test_resize_canvas_keeps_art_and_undoes() {
+        use crate::canvas::Anchor;
+        use crate::cell::{blocks, Cell};
+        let mut app = App::new();
+        app.canvas = crate::canvas::Canvas::new_with_size(16, 16);
+        let red = Cell { ch: blocks::FULL, fg: Some(Rgb { r: 205, g: 0, b: 0 }), bg: None };
+        app.canvas.set(0, 0, red);
+        app.execute(Command::ResizeCanvas { width: 20, height: 24, anchor: Anchor::from_keypad(3).unwrap() });
+        assert_eq!((app.canvas.width, app.canvas.height), (20, 24));
+        assert_eq!(app.canvas.get(4, 8), Some(red));
+        assert!(app.dirty);
+
+        app.execute(Command::Press { x: 1, y: 1, unmirrored: false });
+        app.execute(Command::Release);
+        app.execute(Command::Undo);
+        app.execute(Command::Undo);
+        assert_eq!((app.canvas.width, app.canvas.height), (16, 16));
+        assert_eq!(app.canvas.get(0, 0), Some(red));
+        app.execute(Command::Redo);
+        assert_eq!((app.canvas.width, app.canvas.height), (20, 24));
+    }
+
+    #[test]
+    fn test_shift_canvas_is_one_undo_step() {
+        use crate::cell::{blocks, Cell};
+        let mut app = App::new();
+        app.canvas = crate::canvas::Canvas::new_with_size(8, 8);
+        let red = Cell { ch: blocks::FULL, fg: Some(Rgb { r: 205, g: 0, b: 0 }), bg: None };
+        app.canvas.set(0, 0, red);
+        app.execute(Command::ShiftCanvas { dx: -1, dy: 0, wrap: true });
+        assert_eq!(app.canvas.get(7, 0), Some(red));
+        app.execute(Command::ShiftCanvas { dx: 0, dy: 1, wrap: false });
+        assert_eq!(app.canvas.get(7, 1), Some(red));
+
+        app.execute(Command::Undo);
+        app.execute(Command::Undo);
+        assert_eq!(app.canvas.get(0, 0), Some(red));
+        assert_eq!(app.canvas.get(7, 0), Some(Cell::default()));
+    }
+
+    #[test]
+    fn test_crop_to_content_undoes() {
+        use crate::cell::{blocks, Cell};
+        let mut app = App::new();
+        app.canvas = crate::canvas::Canvas::new_with_size(48, 32);
+        let red = Cell { ch: blocks::FULL, fg: Some(Rgb { r: 205, g: 0, b: 0 }), bg: None };
+        app.canvas.set(10, 5, red);
+        app.canvas.set(29, 16, red);
+        app.execute(Command::CropToContent);
+        assert_eq!((app.canvas.width, app.canvas.height), (20, 12));
+        assert_eq!(app.canvas.get(0, 0), Some(red));
+
+        app.execute(Command::CropToContent);
+        assert_eq!(app.status_message.as_ref().unwrap().text, "Canvas is already cropped");
+
+        // The crop is a step in the normal history, so edits after it don't
+        // strand the uncropped art
+        app.execute(Command::Press { x: 5, y: 5, unmirrored: false });
+        app.execute(Command::Release);
+        app.execute(Command::Undo);
+        app.execute(Command::Undo);
+        assert_eq!(app.status_message.as_ref().unwrap().text, "Undo: Crop");
+        assert_eq!((app.canvas.width, app.canvas.height), (48, 32));
+        assert_eq!(app.canvas.get(29, 16), Some(red));
+
+        app.execute(Command::Redo);
+        assert_eq!((app.canvas.width, app.canvas.height), (20, 12));
+        app.execute(Command::Redo);
+        assert!(!app.canvas.get(5, 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_swap_cell_colors_under_cursor_undoes() {
+        use crate::cell::{blocks, Cell};
+        let mut app = App::new();
+        let red = Some(Rgb { r: 205, g: 0, b: 0 });
+        app.canvas.set(2, 1, Cell { ch: blocks::UPPER_HALF, fg: red, bg: None });
+        app.execute(Command::SwapCellColors);
+        assert_eq!(app.canvas.get(2, 1).unwrap().ch, blocks::UPPER_HALF, "no cursor, no swap");
+
+        app.execute(Command::Hover(Some((2, 1))));
+        app.execute(Command::SwapCellColors);
+        assert_eq!(app.canvas.get(2, 1).unwrap(), Cell { ch: blocks::LOWER_HALF, fg: red, bg: None });
+        app.execute(Command::Undo);
+        assert_eq!(app.canvas.get(2, 1).unwrap().ch, blocks::UPPER_HALF);
+    }
+
+    #[test]
+    fn test_wheel_zoom_keeps_cell_under_pointer() {
+        let mut app = App::new();
+        app.canvas = crate::canvas::Canvas::new_with_size(128, 128);
+        app.viewport_w = 64;
+        app.viewport_h = 32;
+        app.viewport_x = 10;
+        app.viewport_y = 10;
+        // Cell (30, 20) is 20 columns and 10 rows into the canvas
+        app.execute(Command::ZoomAt { x: 30, y: 20, column: 20, row: 10, step: 1 });
+        assert_eq!(app.zoom, 2);
+        assert_eq!((app.viewport_x, app.viewport_y), (20, 10));
+        app.execute(Command::ZoomAt { x: 30, y: 20, column: 20, row: 10, step: 1 });
+        assert_eq!(app.zoom, 4);
+        assert_eq!((app.viewport_w, app.viewport_h), (16, 16));
+        assert_eq!((app.viewport_x, app.viewport_y), (25, 15));
+        // Past the last level nothing changes
+        app.execute(Command::ZoomAt { x: 30, y: 20, column: 20, row: 10, step: 1 });
+        assert_eq!((app.zoom, app.viewport_x), (4, 25));
+        app.execute(Command::ZoomAt { x: 30, y: 20, column: 20, row: 10, step: -2 });
+        assert_eq!(app.zoom, 1);
+        assert_eq!((app.viewport_x, app.viewport_y), (10, 10));
+    }
+
+    #[test]
+    fn test_find_color_selects_and_jumps() {
+        use crate::cell::{blocks, Cell};
+        let mut app = App::new();
+        let red = Rgb { r: 205, g: 0, b: 0 };
+        app.canvas.set(5, 1, Cell { ch: blocks::FULL, fg: Some(red), bg: None });
+        app.canvas.set(2, 3, Cell { ch: blocks::UPPER_HALF, fg: Some(Rgb::WHITE), bg: Some(red) });
+        app.color = red;
+        app.execute(Command::FindColor);
+        assert_eq!(app.selection.as_ref().map(|s| s.len()), Some(2));
+        assert_eq!(app.canvas_cursor, (5, 1));
+        assert!(app.canvas_cursor_active);
+        app.execute(Command::JumpToSelected(1));
+        assert_eq!(app.canvas_cursor, (2, 3));
+        app.execute(Command::JumpToSelected(1));
+        assert_eq!(app.canvas_cursor, (5, 1), "wraps to the first");
+        app.execute(Command::JumpToSelected(-1));
+        assert_eq!(app.canvas_cursor, (2, 3));
+
+        app.color = Rgb::BLACK;
+        app.execute(Command::FindColor);
+        assert!(app.selection.is_none());
+    }
+
+    #[test]
+    fn test_move_palette_cursor_ignores_out_of_range() {
+        let mut app = App::new();
+        app.palette_cursor = 0;
+        app.execute(Command::MovePaletteCursor(-1));
+        assert_eq!(app.palette_cursor, 0);
+        app.execute(Command::MovePaletteCursor(6));
+        assert_eq!(app.palette_cursor, 6);
+    }
+
+    #[test]
+    fn test_command_serde_roundtrip() {
+        let cmds = vec![
+            Command::SelectTool(ToolKind::Rectangle),
+            Command::SetColor(Rgb::new(1, 2, 3)),
+            Command::Press { x: 4, y: 5, unmirrored: false },
+            Command::Hover(None),
+        ];
+        let json = serde_json::to_string(&cmds).unwrap();
+        let back: Vec<Command> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, cmds);
+    }
+}
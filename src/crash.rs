@@ -0,0 +1,159 @@
+//! Crash bundles. When the editor panics, the panic hook writes what it was
+//! doing to a JSON file that can be attached to a bug report.
+
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::command::Command;
+
+/// Commands kept for a bundle, the newest last.
+pub const RECENT_COMMANDS: usize = 50;
+
+/// The app as of the last event it handled. The panic hook can't reach
+/// the app, so the event loop leaves this behind for it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AppState {
+    pub mode: String,
+    pub tool: String,
+    pub canvas_size: (usize, usize),
+    pub zoom: u8,
+    pub dirty: bool,
+    pub tabs: usize,
+    pub project_path: Option<String>,
+    pub autosave_path: String,
+}
+
+/// The last `RECENT_COMMANDS` commands, oldest first.
+#[derive(Debug, Default)]
+pub struct RecentCommands(VecDeque<Command>);
+
+impl RecentCommands {
+    pub const fn new() -> Self {
+        RecentCommands(VecDeque::new())
+    }
+
+    pub fn push(&mut self, cmd: &Command) {
+        if self.0.len() == RECENT_COMMANDS {
+            self.0.pop_front();
+        }
+        self.0.push_back(cmd.clone());
+    }
+
+    pub fn to_vec(&self) -> Vec<Command> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+static RECENT: Mutex<RecentCommands> = Mutex::new(RecentCommands::new());
+static STATE: Mutex<Option<AppState>> = Mutex::new(None);
+
+/// Remember `cmd` as one of the most recent commands.
+pub fn record_command(cmd: &Command) {
+    RECENT.lock().unwrap_or_else(|e| e.into_inner()).push(cmd);
+}
+
+pub fn set_state(state: AppState) {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = Some(state);
+}
+
+/// Where bundles are written (`~/.local/share/kakukuma/crashes` on Linux).
+pub fn crash_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|d| d.join("kakukuma").join("crashes"))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Bundle {
+    pub version: &'static str,
+    pub time: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: Vec<String>,
+    pub state: Option<AppState>,
+    pub recent_commands: Vec<Command>,
+}
+
+impl Bundle {
+    /// Gather a bundle for a panic with `message` from the state and
+    /// commands the app left behind. A lock held by the panicking code is
+    /// skipped rather than waited on.
+    pub fn capture(message: String, location: Option<String>) -> Bundle {
+        let state = STATE.try_lock().ok().and_then(|s| s.clone());
+        let recent = RECENT.try_lock().map(|r| r.to_vec()).unwrap_or_default();
+        Bundle::new(message, location, state, recent)
+    }
+
+    /// A bundle for a panic happening now, with a fresh backtrace.
+    pub fn new(message: String, location: Option<String>, state: Option<AppState>, recent_commands: Vec<Command>) -> Bundle {
+        let backtrace = Backtrace::force_capture().to_string();
+        Bundle {
+            version: env!("CARGO_PKG_VERSION"),
+            time: crate::project::now_iso8601(),
+            message,
+            location,
+            backtrace: backtrace.lines().map(str::to_string).collect(),
+            state,
+            recent_commands,
+        }
+    }
+
+    /// Write the bundle into `dir`, returning its path.
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("crash-{}.json", self.time.replace(':', "-")));
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+/// Write a bundle for the panic in `info` to `crash_dir()`.
+pub fn write_bundle(info: &PanicHookInfo) -> io::Result<PathBuf> {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string());
+    let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+    Bundle::capture(message, location).write_to(&crash_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_keeps_recent_commands_and_state() {
+        // An owned buffer: the global one also sees every other test's commands
+        let mut recent = RecentCommands::new();
+        recent.push(&Command::Redo);
+        for _ in 0..RECENT_COMMANDS - 1 {
+            recent.push(&Command::Undo);
+        }
+        recent.push(&Command::CropToContent);
+        let state = AppState { canvas_size: (48, 32), ..AppState::default() };
+
+        let bundle = Bundle::new("boom".to_string(), Some("src/app.rs:1:1".to_string()), Some(state), recent.to_vec());
+        assert_eq!(bundle.recent_commands.len(), RECENT_COMMANDS);
+        assert!(!bundle.recent_commands.contains(&Command::Redo), "the oldest is dropped");
+        assert_eq!(bundle.recent_commands.last(), Some(&Command::CropToContent));
+        assert_eq!(bundle.state.as_ref().unwrap().canvas_size, (48, 32));
+        assert!(!bundle.backtrace.is_empty());
+
+        let dir = std::env::temp_dir().join("kaku_test_crash_bundle");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = bundle.write_to(&dir).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["message"], "boom");
+        assert!(!path.file_name().unwrap().to_string_lossy().contains(':'));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,16 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
 use crate::canvas::Canvas;
-use crate::cell::{is_half_block, nearest_256, resolve_half_block, Rgb, ANSI_16_RGB};
+use crate::cell::{
+    self, blocks, char_width, color256_to_rgb, display_char, is_half_block, nearest_16, nearest_256, parse_hex_color,
+    resolve_half_block, Cell, Rgb,
+};
+use crate::font;
 
 /// ANSI color format for export.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -12,28 +23,9 @@ pub enum ColorFormat {
     Color16,
 }
 
-/// Find the nearest ANSI 16 color index for an Rgb value (Euclidean distance).
-fn nearest_16(color: &Rgb) -> u8 {
-    let mut best_idx: u8 = 0;
-    let mut best_dist = u32::MAX;
-
-    for (i, &(r, g, b)) in ANSI_16_RGB.iter().enumerate() {
-        let dr = color.r as i32 - r as i32;
-        let dg = color.g as i32 - g as i32;
-        let db = color.b as i32 - b as i32;
-        let dist = (dr * dr + dg * dg + db * db) as u32;
-        if dist < best_dist {
-            best_dist = dist;
-            best_idx = i as u8;
-        }
-    }
-
-    best_idx
-}
-
 /// Returns the bounding box of all non-empty cells as (min_x, min_y, max_x, max_y),
 /// or None if the canvas is entirely empty.
-fn bounding_box(canvas: &Canvas) -> Option<(usize, usize, usize, usize)> {
+pub(crate) fn bounding_box(canvas: &Canvas) -> Option<(usize, usize, usize, usize)> {
     let mut min_x = canvas.width;
     let mut min_y = canvas.height;
     let mut max_x = 0usize;
@@ -62,19 +54,26 @@ fn bounding_box(canvas: &Canvas) -> Option<(usize, usize, usize, usize)> {
 /// Export canvas as plain Unicode (block characters only, no color).
 /// Auto-crops to bounding box.
 pub fn to_plain_text(canvas: &Canvas) -> String {
+    to_plain_text_with(canvas, false)
+}
+
+/// `to_plain_text`, with each column written twice if `double_width`, as
+/// the editor draws cells at 2x zoom. Cells are about twice as tall as they
+/// are wide, so this keeps square art square where it's pasted.
+pub fn to_plain_text_with(canvas: &Canvas, double_width: bool) -> String {
     let (min_x, min_y, max_x, max_y) = match bounding_box(canvas) {
         Some(bb) => bb,
         None => return String::new(),
     };
 
+    let repeats = if double_width { 2 } else { 1 };
     let mut output = String::new();
     for y in min_y..=max_y {
-        let mut row = String::new();
-        for x in min_x..=max_x {
-            if let Some(cell) = canvas.get(x, y) {
-                row.push(cell.ch);
-            }
-        }
+        // A wide character is already two columns, so it repeats half as often
+        let row: String = row_cells(canvas, y, min_x, max_x)
+            .iter()
+            .flat_map(|c| std::iter::repeat_n(c.ch, (repeats / char_width(c.ch)).max(1)))
+            .collect();
         // Strip trailing spaces
         let trimmed = row.trim_end();
         output.push_str(trimmed);
@@ -86,6 +85,232 @@ pub fn to_plain_text(canvas: &Canvas) -> String {
     output
 }
 
+/// 4x4 Bayer matrix: the order in which cells of a block switch to the
+/// second color as a color moves towards it.
+const BAYER_4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// The 16 colors a terminal actually shows for the ANSI color indices.
+/// Written as a preset name in settings ("xterm", "solarized", "dracula"),
+/// or as 16 comma-separated hex colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TerminalPalette {
+    #[default]
+    Xterm,
+    Solarized,
+    Dracula,
+    Custom([Rgb; 16]),
+}
+
+const SOLARIZED: [&str; 16] = [
+    "#073642", "#DC322F", "#859900", "#B58900", "#268BD2", "#D33682", "#2AA198", "#EEE8D5",
+    "#002B36", "#CB4B16", "#586E75", "#657B83", "#839496", "#6C71C4", "#93A1A1", "#FDF6E3",
+];
+
+const DRACULA: [&str; 16] = [
+    "#21222C", "#FF5555", "#50FA7B", "#F1FA8C", "#BD93F9", "#FF79C6", "#8BE9FD", "#F8F8F2",
+    "#6272A4", "#FF6E6E", "#69FF94", "#FFFFA5", "#D6ACFF", "#FF92DF", "#A4FFFF", "#FFFFFF",
+];
+
+impl TerminalPalette {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "xterm" => return Ok(TerminalPalette::Xterm),
+            "solarized" => return Ok(TerminalPalette::Solarized),
+            "dracula" => return Ok(TerminalPalette::Dracula),
+            _ => {}
+        }
+        let colors: Vec<Rgb> = s
+            .split(',')
+            .map(|hex| parse_hex_color(hex.trim()).ok_or_else(|| format!("Invalid hex color '{}'", hex.trim())))
+            .collect::<Result<_, _>>()?;
+        let colors: [Rgb; 16] = colors.try_into().map_err(|_| {
+            format!("Expected xterm, solarized, dracula or 16 comma-separated colors, got '{}'", s)
+        })?;
+        Ok(TerminalPalette::Custom(colors))
+    }
+
+    pub fn colors(&self) -> [Rgb; 16] {
+        let hex = |table: [&str; 16]| table.map(|h| parse_hex_color(h).expect("preset colors are valid"));
+        match self {
+            TerminalPalette::Xterm => std::array::from_fn(|i| color256_to_rgb(i as u8)),
+            TerminalPalette::Solarized => hex(SOLARIZED),
+            TerminalPalette::Dracula => hex(DRACULA),
+            TerminalPalette::Custom(colors) => *colors,
+        }
+    }
+}
+
+impl TryFrom<String> for TerminalPalette {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> {
+        TerminalPalette::parse(&s)
+    }
+}
+
+impl From<TerminalPalette> for String {
+    fn from(p: TerminalPalette) -> String {
+        match p {
+            TerminalPalette::Xterm => "xterm".to_string(),
+            TerminalPalette::Solarized => "solarized".to_string(),
+            TerminalPalette::Dracula => "dracula".to_string(),
+            TerminalPalette::Custom(colors) => colors.map(|c| c.name()).join(","),
+        }
+    }
+}
+
+/// Prepare a canvas for a 256- or 16-color ANSI export. For 16 colors each
+/// color is matched against what the target terminal really shows for the
+/// ANSI indices, then swapped for the xterm value of the chosen index so
+/// `to_ansi` emits that index. With `dither`, colors are first spread over
+/// their two nearest palette colors. True color passes through unchanged.
+pub fn reduce_colors(canvas: &Canvas, format: ColorFormat, dither: bool, terminal: &TerminalPalette) -> Canvas {
+    match format {
+        ColorFormat::TrueColor => canvas.clone(),
+        ColorFormat::Color256 if dither => {
+            let palette: Vec<Rgb> = (0..=255).map(color256_to_rgb).collect();
+            ordered_dither(canvas, &palette)
+        }
+        ColorFormat::Color256 => canvas.clone(),
+        ColorFormat::Color16 => {
+            let palette = terminal.colors();
+            let mut out = if dither { ordered_dither(canvas, &palette) } else { canvas.clone() };
+            let to_index = |c: Rgb| {
+                let nearest = (0..16)
+                    .min_by(|&a, &b| cell::distance(&c, &palette[a]).total_cmp(&cell::distance(&c, &palette[b])))
+                    .unwrap_or(0);
+                color256_to_rgb(nearest as u8)
+            };
+            for y in 0..out.height {
+                for x in 0..out.width {
+                    let Some(cell) = out.get(x, y) else { continue };
+                    out.set(x, y, Cell { fg: cell.fg.map(to_index), bg: cell.bg.map(to_index), ..cell });
+                }
+            }
+            out
+        }
+    }
+}
+
+/// How many of the most used off-palette colors `depth_report` suggests
+/// merges for.
+pub const MAX_MERGES: usize = 5;
+
+/// How well a canvas's colors fit an export depth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthReport {
+    /// Distinct colors on the canvas
+    pub distinct: usize,
+    /// Distinct colors the depth can show exactly
+    pub limit: usize,
+    /// Canvas colors with no exact match at this depth
+    pub inexact: usize,
+    /// For 16 colors, the most used inexact colors and the terminal color
+    /// each will turn into
+    pub merges: Vec<ColorMerge>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMerge {
+    pub color: Rgb,
+    pub cells: usize,
+    pub into: Rgb,
+}
+
+/// Count a canvas's colors against an export depth, so lossy exports can
+/// be spotted before they are written.
+pub fn depth_report(canvas: &Canvas, format: ColorFormat, terminal: &TerminalPalette) -> DepthReport {
+    let mut cells: HashMap<Rgb, usize> = HashMap::new();
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let Some(cell) = canvas.get(x, y).filter(|c| !c.is_empty()) else { continue };
+            let mut colors = [cell.fg, cell.bg];
+            if colors[0] == colors[1] {
+                colors[1] = None;
+            }
+            for color in colors.into_iter().flatten() {
+                *cells.entry(color).or_default() += 1;
+            }
+        }
+    }
+
+    let palette: Vec<Rgb> = match format {
+        ColorFormat::TrueColor => Vec::new(),
+        ColorFormat::Color256 => (0..=255).map(color256_to_rgb).collect(),
+        ColorFormat::Color16 => terminal.colors().to_vec(),
+    };
+    let mut inexact: Vec<(Rgb, usize)> = cells
+        .iter()
+        .filter(|(c, _)| format != ColorFormat::TrueColor && !palette.contains(c))
+        .map(|(c, n)| (*c, *n))
+        .collect();
+    // Most used first, ties in a stable order
+    inexact.sort_by_key(|&(c, n)| (std::cmp::Reverse(n), c.r, c.g, c.b));
+
+    let merges = if format == ColorFormat::Color16 {
+        inexact
+            .iter()
+            .take(MAX_MERGES)
+            .map(|&(color, cells)| {
+                let into = palette
+                    .iter()
+                    .copied()
+                    .min_by(|a, b| cell::distance(&color, a).total_cmp(&cell::distance(&color, b)))
+                    .unwrap_or(color);
+                ColorMerge { color, cells, into }
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    DepthReport {
+        distinct: cells.len(),
+        limit: match format {
+            ColorFormat::TrueColor => 1 << 24,
+            ColorFormat::Color256 => 256,
+            ColorFormat::Color16 => 16,
+        },
+        inexact: inexact.len(),
+        merges,
+    }
+}
+
+/// Ordered dithering: each color becomes one of its two nearest palette
+/// colors, chosen per cell by a Bayer threshold in proportion to how close
+/// it sits to each, so gradients turn into patterns instead of flat bands.
+/// Colors already in the palette stay as they are.
+fn ordered_dither(canvas: &Canvas, palette: &[Rgb]) -> Canvas {
+    let mut out = canvas.clone();
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let Some(cell) = canvas.get(x, y) else { continue };
+            let threshold = (BAYER_4[y % 4][x % 4] as f64 + 0.5) / 16.0;
+            let pick = |c: Rgb| dither_color(c, palette, threshold);
+            out.set(x, y, Cell { fg: cell.fg.map(pick), bg: cell.bg.map(pick), ..cell });
+        }
+    }
+    out
+}
+
+/// The nearest palette color, or the second nearest if `color` is further
+/// than `threshold` of the way towards it.
+fn dither_color(color: Rgb, palette: &[Rgb], threshold: f64) -> Rgb {
+    let mut candidates = palette.to_vec();
+    candidates.sort_by(|a, b| cell::distance(&color, a).total_cmp(&cell::distance(&color, b)));
+    let near = candidates[0];
+    let Some(far) = candidates.iter().copied().find(|&c| c != near) else {
+        return near;
+    };
+
+    // Project onto the line between the two, in RGB
+    let axis = [far.r as f64 - near.r as f64, far.g as f64 - near.g as f64, far.b as f64 - near.b as f64];
+    let offset = [color.r as f64 - near.r as f64, color.g as f64 - near.g as f64, color.b as f64 - near.b as f64];
+    let length = axis.iter().map(|v| v * v).sum::<f64>();
+    let t = axis.iter().zip(offset).map(|(a, o)| a * o).sum::<f64>() / length;
+    if t > threshold { far } else { near }
+}
+
 /// Emit ANSI fg escape code for a color in the given format.
 fn emit_fg(color: &Rgb, format: ColorFormat) -> String {
     match format {
@@ -139,6 +364,8 @@ fn emit_cell_colors(
     }
 
     match (fg, bg) {
+        (Some(f), Some(_)) if !bg_changed => output.push_str(&emit_fg(&f, format)),
+        (Some(_), Some(b)) if !fg_changed => output.push_str(&emit_bg(&b, format)),
         (Some(f), Some(b)) => {
             output.push_str(&emit_fg_bg(&f, &b, format));
         }
@@ -163,50 +390,127 @@ fn emit_cell_colors(
     *prev_bg = bg;
 }
 
+/// How an ANSI export shows transparent cells and transparent cell halves.
+/// Written "keep", "reset" or a hex color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Transparency {
+    /// Spaces with no background, showing whatever the terminal has
+    #[default]
+    Keep,
+    /// Like `Keep`, but every line starts by resetting to the default
+    /// background, so color left over from earlier output can't show through
+    Reset,
+    /// Fill with a solid background color
+    Solid(Rgb),
+}
+
+impl Transparency {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "keep" => Ok(Transparency::Keep),
+            "reset" => Ok(Transparency::Reset),
+            other => parse_hex_color(other)
+                .map(Transparency::Solid)
+                .ok_or_else(|| format!("Expected keep, reset or a hex color, got '{}'", s)),
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Transparency::Keep => "keep".to_string(),
+            Transparency::Reset => "reset".to_string(),
+            Transparency::Solid(c) => c.name(),
+        }
+    }
+}
+
+/// Emit a transparent cell. Any active background is reset first so it
+/// doesn't bleed into the gap.
+fn emit_blank(output: &mut String, prev_bg: &mut Option<Rgb>) {
+    if prev_bg.is_some() {
+        output.push_str("\x1b[49m");
+        *prev_bg = None;
+    }
+    output.push(' ');
+}
+
 /// Export canvas as ANSI art (Unicode blocks with color escape codes).
 /// Auto-crops to bounding box. Applies half-block resolution for export fidelity.
 /// Color format determines escape sequence type (24-bit, 256-color, or 16-color).
 pub fn to_ansi(canvas: &Canvas, format: ColorFormat) -> String {
+    to_ansi_with(canvas, format, &AnsiOptions::default())
+}
+
+/// Options for `to_ansi_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AnsiOptions {
+    pub transparent: Transparency,
+    /// Skip runs of transparent cells with a cursor-forward sequence where
+    /// that is shorter than the spaces, and drop them at line ends
+    pub cursor_forward: bool,
+}
+
+/// `to_ansi`, with transparent cells written as `options` says.
+pub fn to_ansi_with(canvas: &Canvas, format: ColorFormat, options: &AnsiOptions) -> String {
     let (min_x, min_y, max_x, max_y) = match bounding_box(canvas) {
         Some(bb) => bb,
         None => return String::new(),
     };
+    let solid = match options.transparent {
+        Transparency::Solid(c) => Some(c),
+        _ => None,
+    };
 
     let mut output = String::new();
 
     for y in min_y..=max_y {
         let mut prev_fg: Option<Rgb> = None;
         let mut prev_bg: Option<Rgb> = None;
+        // Transparent cells not written yet, so a run can become one skip
+        let mut gap = 0;
+        if options.transparent == Transparency::Reset {
+            output.push_str("\x1b[49m");
+        }
 
-        for x in min_x..=max_x {
-            if let Some(cell) = canvas.get(x, y) {
-                if cell.is_empty() {
-                    output.push(' ');
-                    continue;
-                }
-
-                // Determine effective (ch, fg, bg) — half-block resolution or raw cell
-                let (out_ch, fg, bg) = if is_half_block(cell.ch) {
-                    let resolved = resolve_half_block(&cell).unwrap();
-                    (resolved.ch, resolved.fg, resolved.bg)
-                } else {
-                    (cell.ch, cell.fg, cell.bg)
-                };
-
-                if out_ch == ' ' {
-                    // Both halves transparent after resolution
-                    output.push(' ');
-                    prev_fg = None;
-                    prev_bg = None;
-                    continue;
+        for cell in row_cells(canvas, y, min_x, max_x) {
+            if cell.is_empty() {
+                match solid {
+                    Some(c) => {
+                        emit_cell_colors(&mut output, prev_fg, Some(c), &mut prev_fg, &mut prev_bg, format);
+                        output.push(' ');
+                    }
+                    None => gap += 1,
                 }
+                continue;
+            }
 
-                emit_cell_colors(&mut output, fg, bg, &mut prev_fg, &mut prev_bg, format);
-                output.push(out_ch);
+            // Determine effective (ch, fg, bg) — half-block resolution or raw cell
+            let (out_ch, fg, bg) = if is_half_block(cell.ch) {
+                let resolved = resolve_half_block(&cell).unwrap();
+                (resolved.ch, resolved.fg, resolved.bg)
+            } else {
+                (cell.ch, cell.fg, cell.bg)
+            };
+
+            if out_ch == ' ' && solid.is_none() {
+                // Both halves transparent after resolution
+                gap += 1;
+                continue;
             }
+
+            emit_gap(&mut output, gap, options.cursor_forward, &mut prev_bg);
+            gap = 0;
+            let bg = bg.or(solid);
+            emit_cell_colors(&mut output, fg, bg, &mut prev_fg, &mut prev_bg, format);
+            output.push(out_ch);
         }
 
-        output.push_str("\x1b[0m"); // Reset at end of line
+        if !options.cursor_forward {
+            emit_gap(&mut output, gap, false, &mut prev_bg);
+        }
+        if prev_fg.is_some() || prev_bg.is_some() {
+            output.push_str("\x1b[0m"); // Reset at end of line
+        }
         if y < max_y {
             output.push('\n');
         }
@@ -215,6 +519,454 @@ pub fn to_ansi(canvas: &Canvas, format: ColorFormat) -> String {
     output
 }
 
+/// Cells `min_x..=max_x` of row `y` as a terminal lays them out: a wide
+/// character covers the cell to its right, which is left out, so later
+/// columns don't shift. Characters with no width become `REPLACEMENT`.
+fn row_cells(canvas: &Canvas, y: usize, min_x: usize, max_x: usize) -> Vec<Cell> {
+    let mut cells = Vec::with_capacity(max_x + 1 - min_x);
+    let mut x = min_x;
+    while x <= max_x {
+        let mut cell = canvas.get(x, y).unwrap_or_default();
+        cell.ch = display_char(cell.ch);
+        x += char_width(cell.ch);
+        cells.push(cell);
+    }
+    cells
+}
+
+/// Export canvas as plain text in a Markdown fenced code block, to paste
+/// into a README. A `note`, if given, goes above it in an HTML comment,
+/// which renderers hide. Auto-crops to bounding box.
+pub fn to_markdown(canvas: &Canvas, note: Option<&str>) -> String {
+    let text = to_plain_text(canvas);
+    // A fence longer than any backtick run inside, so art can't end it
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}text\n{}\n{}\n", note_comment(note), fence, text, fence)
+}
+
+/// Export canvas as the colored `<pre>` of `to_html` inside a collapsed
+/// `<details>` block, summarized by `note`, for Markdown that allows HTML.
+pub fn to_markdown_html(canvas: &Canvas, note: Option<&str>) -> String {
+    let summary = note.unwrap_or("Colored version").replace('<', "&lt;");
+    format!("{}<details>\n<summary>{}</summary>\n\n{}\n\n</details>\n", note_comment(note), summary, to_html(canvas))
+}
+
+/// `<!-- note -->` and a newline, or nothing. `--` can't appear inside a
+/// comment, so it's spaced out.
+fn note_comment(note: Option<&str>) -> String {
+    note.map(|n| format!("<!-- {} -->\n", n.replace("--", "- -"))).unwrap_or_default()
+}
+
+/// Foreground and background of an HTML span.
+type HtmlStyle = (Option<Rgb>, Option<Rgb>);
+
+/// Export canvas as an HTML `<pre>` block, each run of same-colored cells a
+/// `<span>` with inline `color` and `background` styles, so the art can go
+/// in a web page as is. Transparent cells are unstyled spaces. Auto-crops
+/// to bounding box.
+pub fn to_html(canvas: &Canvas) -> String {
+    let (min_x, min_y, max_x, max_y) = match bounding_box(canvas) {
+        Some(bb) => bb,
+        None => return String::new(),
+    };
+
+    let mut output = String::from("<pre style=\"font-family:monospace;line-height:1\">");
+    for y in min_y..=max_y {
+        // Runs of one style and their text
+        let mut runs: Vec<(HtmlStyle, String)> = Vec::new();
+        for cell in row_cells(canvas, y, min_x, max_x) {
+            let (ch, fg, bg) = if cell.is_empty() {
+                (' ', None, None)
+            } else if is_half_block(cell.ch) {
+                let resolved = resolve_half_block(&cell).unwrap();
+                (resolved.ch, resolved.fg, resolved.bg)
+            } else {
+                (cell.ch, cell.fg, cell.bg)
+            };
+            // A space shows only its background
+            let style = if ch == ' ' { (None, bg) } else { (fg, bg) };
+            match runs.last_mut() {
+                Some((last, text)) if *last == style => text.push(ch),
+                _ => runs.push((style, ch.to_string())),
+            }
+        }
+        for ((fg, bg), text) in runs {
+            let text = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+            let mut style = Vec::new();
+            if let Some(fg) = fg {
+                style.push(format!("color:{}", fg.name()));
+            }
+            if let Some(bg) = bg {
+                style.push(format!("background:{}", bg.name()));
+            }
+            if style.is_empty() {
+                output.push_str(&text);
+            } else {
+                output.push_str(&format!("<span style=\"{}\">{}</span>", style.join(";"), text));
+            }
+        }
+        if y < max_y {
+            output.push('\n');
+        }
+    }
+    output.push_str("</pre>");
+    output
+}
+
+/// Write `len` transparent cells: as spaces, or with `cursor_forward` as a
+/// single skip when that is shorter.
+fn emit_gap(output: &mut String, len: usize, cursor_forward: bool, prev_bg: &mut Option<Rgb>) {
+    let skip = format!("\x1b[{}C", len);
+    if cursor_forward && skip.len() < len {
+        output.push_str(&skip);
+        return;
+    }
+    for _ in 0..len {
+        emit_blank(output, prev_bg);
+    }
+}
+
+/// Size of a text export, for art that has to fit somewhere small, like a
+/// MOTD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExportStats {
+    pub bytes: usize,
+    pub lines: usize,
+    /// Bytes spent on escape sequences rather than characters
+    pub escape_bytes: usize,
+}
+
+impl ExportStats {
+    pub fn of(content: &str) -> ExportStats {
+        let bytes = content.as_bytes();
+        let mut escape_bytes = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == 0x1b && bytes.get(i + 1) == Some(&b'[') {
+                // CSI: parameters, then one final byte in @..~
+                let end = bytes[i + 2..].iter().position(|b| (0x40..=0x7e).contains(b));
+                let len = end.map_or(bytes.len() - i, |e| e + 3);
+                escape_bytes += len;
+                i += len;
+            } else {
+                i += 1;
+            }
+        }
+        ExportStats { bytes: bytes.len(), lines: content.lines().count(), escape_bytes }
+    }
+
+    /// Share of the bytes that are escape sequences, as a whole percent.
+    pub fn escape_percent(&self) -> usize {
+        (self.escape_bytes * 100).checked_div(self.bytes).unwrap_or(0)
+    }
+
+    /// "3.2 KB, 12 lines, 61% escapes", plus a warning past `budget` bytes
+    /// (0 for no budget).
+    pub fn summary(&self, budget: u64) -> String {
+        let mut text = format!("{}, {} lines", format_bytes(self.bytes), self.lines);
+        if self.escape_bytes > 0 {
+            text.push_str(&format!(", {}% escapes", self.escape_percent()));
+        }
+        if self.over_budget(budget) {
+            text.push_str(&format!(" \u{2014} over the {} budget", format_bytes(budget as usize)));
+        }
+        text
+    }
+
+    pub fn over_budget(&self, budget: u64) -> bool {
+        budget > 0 && self.bytes as u64 > budget
+    }
+}
+
+/// "512 B", "1.5 KB", "3.0 MB".
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KB", b as f64 / (1 << 10) as f64),
+        b => format!("{} B", b),
+    }
+}
+
+/// Pixels per cell when rasterizing at the default 1:2 aspect. Terminal
+/// cells are about twice as tall as wide, and eight pixels resolve the 1/8
+/// fractional blocks exactly.
+pub const RASTER_CELL: (u32, u32) = (8, 16);
+
+/// Width:height of a terminal cell, so rasterized exports look the way the
+/// art does in the terminal. Written "1:2" in settings, or "auto" to ask
+/// the terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum CellAspect {
+    Auto,
+    Ratio(u32, u32),
+}
+
+impl Default for CellAspect {
+    fn default() -> Self {
+        CellAspect::Ratio(1, 2)
+    }
+}
+
+impl CellAspect {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(CellAspect::Auto);
+        }
+        let err = || format!("Expected W:H (e.g., 1:2) or auto, got '{}'", s);
+        let (w, h) = s.split_once(':').ok_or_else(err)?;
+        let w: u32 = w.trim().parse().map_err(|_| err())?;
+        let h: u32 = h.trim().parse().map_err(|_| err())?;
+        if w == 0 || h == 0 {
+            return Err(err());
+        }
+        Ok(CellAspect::Ratio(w, h))
+    }
+
+    /// Pixels per cell: eight wide, and as tall as the aspect makes that.
+    /// `Auto` falls back to 1:2 when the terminal doesn't report its size.
+    pub fn raster_cell(self) -> (u32, u32) {
+        let (w, h) = match self {
+            CellAspect::Ratio(w, h) => (w, h),
+            CellAspect::Auto => crate::terminal::detect_cell_size().unwrap_or((1, 2)),
+        };
+        let width = RASTER_CELL.0;
+        (width, ((width * h + w / 2) / w).clamp(1, 8 * width))
+    }
+}
+
+impl TryFrom<String> for CellAspect {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> {
+        CellAspect::parse(&s)
+    }
+}
+
+impl From<CellAspect> for String {
+    fn from(a: CellAspect) -> String {
+        match a {
+            CellAspect::Auto => "auto".to_string(),
+            CellAspect::Ratio(w, h) => format!("{}:{}", w, h),
+        }
+    }
+}
+
+/// Rasterize the canvas's content (cropped like the text exports), each cell
+/// as a `cell`-sized block of pixels. Uncolored areas are transparent.
+pub fn to_image(canvas: &Canvas, cell: (u32, u32)) -> RgbaImage {
+    rasterize(canvas, content_region(canvas), cell, |ch, (x, y), (w, h)| {
+        // Sample at the pixel's center, in 0..1 across the cell
+        let fx = (x as f32 + 0.5) / w as f32;
+        let fy = (y as f32 + 0.5) / h as f32;
+        coverage(ch, fx, fy)
+    })
+}
+
+/// Like `to_image`, but shades are drawn as the dot patterns terminal
+/// fonts use and ASCII as bitmap glyphs, instead of each as a flat mix of
+/// the cell's colors. Other characters are still mixed.
+pub fn to_glyph_image(canvas: &Canvas, cell: (u32, u32)) -> RgbaImage {
+    rasterize(canvas, content_region(canvas), cell, |ch, (x, y), (w, h)| {
+        let on = |ink: bool| if ink { 1.0 } else { 0.0 };
+        match ch {
+            blocks::SHADE_LIGHT => on((x + 2 * y) % 4 == 0),
+            blocks::SHADE_MEDIUM => on((x + y) % 2 == 0),
+            blocks::SHADE_DARK => on((x + 2 * y) % 4 != 2),
+            _ => {
+                // The glyph plus a blank column and row, stretched over the cell
+                let (gw, gh) = font::GLYPH_SIZE;
+                let col = x as usize * (gw + 1) / w as usize;
+                let row = y as usize * (gh + 1) / h as usize;
+                match font::glyph_pixel(ch, col, row) {
+                    Some(ink) => on(ink),
+                    None => coverage(ch, (x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32),
+                }
+            }
+        }
+    })
+}
+
+/// The cells the text exports keep: the drawn area, or all of an empty
+/// canvas.
+fn content_region(canvas: &Canvas) -> (usize, usize, usize, usize) {
+    bounding_box(canvas).unwrap_or((0, 0, canvas.width - 1, canvas.height - 1))
+}
+
+/// Paint each cell of `region` (min_x, min_y, max_x, max_y) as a
+/// `cell`-sized block of pixels, mixing its colors by how much of each
+/// pixel `ink(ch, pixel in cell, cell size)` says the foreground covers.
+fn rasterize(
+    canvas: &Canvas,
+    region: (usize, usize, usize, usize),
+    cell: (u32, u32),
+    ink: impl Fn(char, (u32, u32), (u32, u32)) -> f32,
+) -> RgbaImage {
+    let (min_x, min_y, max_x, max_y) = region;
+    let (cw, ch) = cell;
+    let cols = (max_x - min_x + 1) as u32;
+    let rows = (max_y - min_y + 1) as u32;
+    RgbaImage::from_fn(cols * cw, rows * ch, |px, py| {
+        let cell = canvas
+            .get(min_x + (px / cw) as usize, min_y + (py / ch) as usize)
+            .unwrap_or_default();
+        blend(&cell, ink(cell.ch, (px % cw, py % ch), (cw, ch)))
+    })
+}
+
+/// Render each frame, all cropped to the area any of them draws in, and
+/// assemble a looping animated GIF. Frames are `(canvas, delay in ms)`.
+/// One palette index is kept for transparency; if the frames use more than
+/// the other 255 colors, colors are snapped to a 6x6x6 cube.
+pub fn to_gif(frames: &[(Canvas, u32)], cell: (u32, u32)) -> io::Result<Vec<u8>> {
+    let Some((first, _)) = frames.first() else {
+        return Err(io::Error::other("no frames to animate"));
+    };
+    let region = frames
+        .iter()
+        .filter_map(|(canvas, _)| bounding_box(canvas))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .unwrap_or_else(|| content_region(first));
+    let images: Vec<RgbaImage> = frames
+        .iter()
+        .map(|(canvas, _)| {
+            rasterize(canvas, region, cell, |ch, (x, y), (w, h)| {
+                coverage(ch, (x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32)
+            })
+        })
+        .collect();
+    let (width, height) = images[0].dimensions();
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(io::Error::other("animation is too large for a GIF"));
+    }
+
+    // Index 0 is transparent; colors follow in order of first use
+    let opaque = |p: &Rgba<u8>| (p[3] >= 128).then_some([p[0], p[1], p[2]]);
+    let mut colors: Vec<[u8; 3]> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for pixel in images.iter().flat_map(|img| img.pixels()).filter_map(opaque) {
+        if seen.insert(pixel) {
+            colors.push(pixel);
+        }
+    }
+    let snap = colors.len() > 255;
+    let key = |c: [u8; 3]| if snap { c.map(|v| ((v as u32 + 25) / 51 * 51) as u8) } else { c };
+    let mut index: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut palette = vec![[0u8; 3]];
+    for c in colors.into_iter().map(key) {
+        index.entry(c).or_insert_with(|| {
+            palette.push(c);
+            (palette.len() - 1) as u8
+        });
+    }
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend((width as u16).to_le_bytes());
+    gif.extend((height as u16).to_le_bytes());
+    // A 256-entry global color table, no background color
+    gif.extend([0xF7, 0, 0]);
+    palette.resize(256, [0, 0, 0]);
+    gif.extend(palette.iter().flatten());
+    // Loop forever
+    gif.extend(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+    for (img, (_, delay_ms)) in images.iter().zip(frames) {
+        let delay = (delay_ms / 10).min(u16::MAX as u32) as u16;
+        // Clear to transparent between frames, with index 0 transparent
+        gif.extend([0x21, 0xF9, 0x04, 0x09]);
+        gif.extend(delay.to_le_bytes());
+        gif.extend([0, 0]);
+        gif.push(0x2C);
+        gif.extend([0, 0, 0, 0]);
+        gif.extend((width as u16).to_le_bytes());
+        gif.extend((height as u16).to_le_bytes());
+        gif.push(0);
+        let pixels: Vec<u8> = img.pixels().map(|p| opaque(p).map_or(0, |c| index[&key(c)])).collect();
+        let data = weezl::encode::Encoder::new(weezl::BitOrder::Lsb, 8)
+            .encode(&pixels)
+            .map_err(io::Error::other)?;
+        gif.push(8);
+        for block in data.chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3B);
+    Ok(gif)
+}
+
+/// Write an RGBA image as a PNG tagged sRGB, so viewers don't guess at the
+/// color space. `gamma` other than 1.0 brightens (above 1) or darkens the
+/// colors first, for displays that still render them off.
+pub fn write_png(img: &RgbaImage, path: &Path, gamma: f32) -> io::Result<()> {
+    let mut data = img.as_raw().clone();
+    if gamma != 1.0 {
+        let lut: Vec<u8> = (0..=255u8)
+            .map(|v| ((v as f32 / 255.0).powf(1.0 / gamma) * 255.0).round() as u8)
+            .collect();
+        for px in data.chunks_exact_mut(4) {
+            for c in &mut px[..3] {
+                *c = lut[*c as usize];
+            }
+        }
+    }
+    let file = io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, img.width(), img.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(&data).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+/// How much of the point (fx, fy) in a cell the foreground covers.
+fn coverage(ch: char, fx: f32, fy: f32) -> f32 {
+    let eighths = |n: u32| n as f32 / 8.0;
+    let lower = |n: u32| if fy >= 1.0 - eighths(n) { 1.0 } else { 0.0 };
+    let left = |n: u32| if fx < eighths(n) { 1.0 } else { 0.0 };
+    match ch {
+        ' ' => 0.0,
+        blocks::FULL => 1.0,
+        blocks::UPPER_HALF => if fy < 0.5 { 1.0 } else { 0.0 },
+        blocks::LOWER_HALF => lower(4),
+        blocks::LEFT_HALF => left(4),
+        blocks::RIGHT_HALF => if fx >= 0.5 { 1.0 } else { 0.0 },
+        blocks::LOWER_1_8 => lower(1),
+        blocks::LOWER_1_4 => lower(2),
+        blocks::LOWER_3_8 => lower(3),
+        blocks::LOWER_5_8 => lower(5),
+        blocks::LOWER_3_4 => lower(6),
+        blocks::LOWER_7_8 => lower(7),
+        blocks::LEFT_7_8 => left(7),
+        blocks::LEFT_3_4 => left(6),
+        blocks::LEFT_5_8 => left(5),
+        blocks::LEFT_3_8 => left(3),
+        blocks::LEFT_1_4 => left(2),
+        blocks::LEFT_1_8 => left(1),
+        blocks::SHADE_LIGHT => 0.25,
+        blocks::SHADE_MEDIUM => 0.5,
+        blocks::SHADE_DARK => 0.75,
+        // Text glyphs: about half the cell is ink
+        _ => 0.5,
+    }
+}
+
+/// Mix a cell's colors by foreground coverage. A missing color is
+/// transparent rather than black.
+fn blend(cell: &Cell, coverage: f32) -> Rgba<u8> {
+    let fg_weight = if cell.fg.is_some() { coverage } else { 0.0 };
+    let bg_weight = if cell.bg.is_some() { 1.0 - coverage } else { 0.0 };
+    let alpha = fg_weight + bg_weight;
+    if alpha == 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let fg = cell.fg.unwrap_or(Rgb::new(0, 0, 0));
+    let bg = cell.bg.unwrap_or(Rgb::new(0, 0, 0));
+    let mix = |f: u8, b: u8| ((f as f32 * fg_weight + b as f32 * bg_weight) / alpha).round() as u8;
+    Rgba([mix(fg.r, bg.r), mix(fg.g, bg.g), mix(fg.b, bg.b), (alpha * 255.0).round() as u8])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,6 +974,19 @@ mod tests {
 
     const RED: Option<Rgb> = Some(Rgb { r: 205, g: 0, b: 0 });
 
+    #[test]
+    fn test_ansi_blank_resets_background_only() {
+        let blue = Some(Rgb { r: 0, g: 0, b: 205 });
+        let mut canvas = Canvas::new();
+        canvas.set(0, 0, Cell { ch: blocks::FULL, fg: RED, bg: blue });
+        canvas.set(2, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        let ansi = to_ansi(&canvas, ColorFormat::TrueColor);
+        // The gap drops the background instead of showing it, and the
+        // foreground stays set across it, so nothing is re-emitted after
+        assert!(ansi.contains("\x1b[49m \u{2588}"), "{:?}", ansi);
+        assert_eq!(ansi.matches("38;2;205;0;0").count(), 1);
+    }
+
     #[test]
     fn test_plain_text_empty() {
         let canvas = Canvas::new();
@@ -256,6 +1021,62 @@ mod tests {
         assert!(!text.contains(' '));
     }
 
+    #[test]
+    fn test_wide_characters_keep_columns() {
+        let mut canvas = Canvas::new();
+        // 字 at x=0 covers x=1; the block at x=2 stays in column 2
+        canvas.set(0, 0, Cell { ch: '\u{5B57}', fg: RED, bg: None });
+        canvas.set(1, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        canvas.set(2, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        canvas.set(0, 1, Cell { ch: '\u{0301}', fg: RED, bg: None });
+        canvas.set(2, 1, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        assert_eq!(to_plain_text(&canvas), "\u{5B57}\u{2588}\n\u{FFFD} \u{2588}");
+        let ansi = to_ansi(&canvas, ColorFormat::TrueColor);
+        assert_eq!(ansi.matches('\u{2588}').count(), 2);
+    }
+
+    #[test]
+    fn test_plain_text_double_width() {
+        let mut canvas = Canvas::new_with_size(8, 4);
+        canvas.set(0, 0, Cell { ch: blocks::FULL, fg: Some(Rgb::WHITE), bg: None });
+        canvas.set(1, 1, Cell { ch: blocks::UPPER_HALF, fg: Some(Rgb::WHITE), bg: None });
+        canvas.set(2, 1, Cell { ch: '\u{5B57}', fg: Some(Rgb::WHITE), bg: None });
+        assert_eq!(to_plain_text_with(&canvas, false), "\u{2588}\n \u{2580}\u{5B57}");
+        assert_eq!(to_plain_text_with(&canvas, true), "\u{2588}\u{2588}\n  \u{2580}\u{2580}\u{5B57}");
+    }
+
+    #[test]
+    fn test_markdown_fences_and_notes() {
+        let mut canvas = Canvas::new_with_size(8, 4);
+        canvas.set(1, 1, Cell { ch: '`', fg: Some(Rgb::WHITE), bg: None });
+        canvas.set(2, 1, Cell { ch: '`', fg: Some(Rgb::WHITE), bg: None });
+        canvas.set(3, 1, Cell { ch: '`', fg: Some(Rgb::WHITE), bg: None });
+        assert_eq!(to_markdown(&canvas, None), "````text\n```\n````\n");
+        let md = to_markdown(&canvas, Some("bear -- v2"));
+        assert!(md.starts_with("<!-- bear - - v2 -->\n````text\n"));
+
+        let md = to_markdown_html(&canvas, None);
+        assert!(md.starts_with("<details>\n<summary>Colored version</summary>\n\n<pre"));
+        assert!(md.ends_with("</pre>\n\n</details>\n"));
+    }
+
+    #[test]
+    fn test_html_spans_runs_and_escapes() {
+        assert!(to_html(&Canvas::new()).is_empty());
+
+        let mut canvas = Canvas::new();
+        canvas.set(1, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        canvas.set(2, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        canvas.set(1, 1, Cell { ch: '<', fg: Some(Rgb::WHITE), bg: RED });
+        let html = to_html(&canvas);
+        assert_eq!(
+            html,
+            "<pre style=\"font-family:monospace;line-height:1\">\
+             <span style=\"color:#CD0000\">\u{2588}\u{2588}</span>\n\
+             <span style=\"color:#E5E5E5;background:#CD0000\">&lt;</span> </pre>"
+        );
+    }
+
     #[test]
     fn test_ansi_256_color_codes() {
         let mut canvas = Canvas::new();
@@ -308,6 +1129,176 @@ mod tests {
         assert!(ansi.contains("\x1b[38;5;7;48;5;4m"));
     }
 
+    #[test]
+    fn test_ansi_transparency_options() {
+        let mut canvas = Canvas::new();
+        let red = Some(Rgb::new(255, 0, 0));
+        canvas.set(0, 0, Cell { ch: blocks::UPPER_HALF, fg: red, bg: None });
+        canvas.set(2, 0, Cell { ch: blocks::FULL, fg: red, bg: None });
+
+        let keep = to_ansi_with(&canvas, ColorFormat::TrueColor, &AnsiOptions::default());
+        assert_eq!(keep, to_ansi(&canvas, ColorFormat::TrueColor));
+        assert!(!keep.contains("48;2"));
+
+        let reset = to_ansi_with(&canvas, ColorFormat::TrueColor, &AnsiOptions { transparent: Transparency::Reset, ..Default::default() });
+        assert!(reset.starts_with("\x1b[49m"));
+
+        let navy = AnsiOptions { transparent: Transparency::parse("#000080").unwrap(), ..Default::default() };
+        let solid = to_ansi_with(&canvas, ColorFormat::TrueColor, &navy);
+        // The half block's empty half and the gap both get the color
+        assert!(solid.starts_with("\x1b[38;2;255;0;0;48;2;0;0;128m\u{2580} \u{2588}"));
+        assert_eq!(Transparency::parse("Reset").unwrap(), Transparency::Reset);
+        assert!(Transparency::parse("clear").is_err());
+    }
+
+    #[test]
+    fn test_ansi_cursor_forward_skips_gaps() {
+        let mut canvas = Canvas::new();
+        let red = Some(Rgb::new(255, 0, 0));
+        canvas.set(0, 0, Cell { ch: blocks::FULL, fg: red, bg: None });
+        canvas.set(9, 0, Cell { ch: blocks::FULL, fg: red, bg: None });
+        canvas.set(0, 1, Cell { ch: blocks::FULL, fg: red, bg: None });
+
+        let plain = to_ansi(&canvas, ColorFormat::TrueColor);
+        assert_eq!(plain.matches(' ').count(), 8 + 9);
+        let options = AnsiOptions { cursor_forward: true, ..Default::default() };
+        let skipped = to_ansi_with(&canvas, ColorFormat::TrueColor, &options);
+        // The color carries across the gap; the second line's tail is dropped
+        assert_eq!(
+            skipped,
+            "\x1b[38;2;255;0;0m\u{2588}\x1b[8C\u{2588}\x1b[0m\n\x1b[38;2;255;0;0m\u{2588}\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_ansi_only_changed_color_is_emitted() {
+        let mut canvas = Canvas::new();
+        let (red, blue, navy) = (Rgb::new(255, 0, 0), Rgb::new(0, 0, 255), Rgb::new(0, 0, 128));
+        canvas.set(0, 0, Cell { ch: blocks::SHADE_LIGHT, fg: Some(red), bg: Some(navy) });
+        canvas.set(1, 0, Cell { ch: blocks::SHADE_LIGHT, fg: Some(blue), bg: Some(navy) });
+        let ansi = to_ansi(&canvas, ColorFormat::TrueColor);
+        assert!(ansi.contains("\u{2591}\x1b[38;2;0;0;255m\u{2591}"));
+    }
+
+    #[test]
+    fn test_export_stats_counts_escapes() {
+        let stats = ExportStats::of("\x1b[38;5;1mab\x1b[0m\ncd\x1b[3C");
+        assert_eq!((stats.bytes, stats.lines, stats.escape_bytes), (22, 2, 17));
+        assert_eq!(stats.escape_percent(), 77);
+        assert_eq!(stats.summary(0), "22 B, 2 lines, 77% escapes");
+        assert!(stats.summary(16).ends_with("over the 16 B budget"));
+        assert!(!stats.over_budget(22));
+        assert_eq!(ExportStats::of("ab\ncd").summary(4096), "5 B, 2 lines");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 << 20), "3.0 MB");
+    }
+
+    #[test]
+    fn test_to_image_crops_and_rasterizes_blocks() {
+        let mut canvas = Canvas::new();
+        let red = Some(Rgb::new(255, 0, 0));
+        let blue = Some(Rgb::new(0, 0, 255));
+        canvas.set(4, 2, Cell { ch: blocks::UPPER_HALF, fg: red, bg: None });
+        canvas.set(5, 2, Cell { ch: blocks::SHADE_MEDIUM, fg: red, bg: blue });
+        let img = to_image(&canvas, RASTER_CELL);
+        assert_eq!(img.dimensions(), (2 * RASTER_CELL.0, RASTER_CELL.1));
+        assert_eq!(*img.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(img.get_pixel(0, RASTER_CELL.1 - 1)[3], 0, "lower half is transparent");
+        assert_eq!(*img.get_pixel(RASTER_CELL.0, 0), Rgba([128, 0, 128, 255]));
+    }
+
+    #[test]
+    fn test_glyph_image_draws_patterns_and_text() {
+        let mut canvas = Canvas::new();
+        let red = Some(Rgb::new(255, 0, 0));
+        let blue = Some(Rgb::new(0, 0, 255));
+        canvas.set(0, 0, Cell { ch: blocks::SHADE_MEDIUM, fg: red, bg: blue });
+        canvas.set(1, 0, Cell { ch: 'H', fg: red, bg: blue });
+        canvas.set(2, 0, Cell { ch: blocks::LOWER_HALF, fg: red, bg: blue });
+        let img = to_glyph_image(&canvas, RASTER_CELL);
+        let (w, h) = RASTER_CELL;
+        let (fg, bg) = (Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255]));
+
+        // A checkerboard instead of purple
+        assert_eq!((*img.get_pixel(0, 0), *img.get_pixel(1, 0)), (fg, bg));
+        // H: left stem inked, gap in the middle of its top row
+        assert_eq!(*img.get_pixel(w, 0), fg);
+        assert_eq!(*img.get_pixel(w + w / 2, 0), bg);
+        // Blocks are the same as in to_image
+        let plain = to_image(&canvas, RASTER_CELL);
+        assert_eq!(img.get_pixel(2 * w, h - 1), plain.get_pixel(2 * w, h - 1));
+    }
+
+    #[test]
+    fn test_to_gif_frames_share_crop_and_palette() {
+        let empty = Canvas::new();
+        let mut drawn = Canvas::new();
+        drawn.set(3, 2, Cell { ch: blocks::FULL, fg: Some(Rgb::new(255, 0, 0)), bg: None });
+        let gif = to_gif(&[(empty, 100), (drawn, 500)], (2, 4)).unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        // Both frames are cropped to the one drawn cell
+        assert_eq!(&gif[6..10], &[2, 0, 4, 0]);
+        // Index 0 is transparent, then the frames' colors
+        assert_eq!(&gif[13..19], &[0, 0, 0, 255, 0, 0]);
+        let delays: Vec<u16> = gif
+            .windows(6)
+            .filter(|w| w[..4] == [0x21, 0xF9, 0x04, 0x09])
+            .map(|w| u16::from_le_bytes([w[4], w[5]]))
+            .collect();
+        assert_eq!(delays, [10, 50]);
+        assert_eq!(gif.last(), Some(&0x3B));
+
+        // The last frame's pixels decode to all red
+        let descriptor = [0x2C, 0, 0, 0, 0, 2, 0, 4, 0, 0];
+        let mut at = gif.windows(10).rposition(|w| w == descriptor).unwrap() + 11;
+        let mut data = Vec::new();
+        while gif[at] != 0 {
+            let len = gif[at] as usize;
+            data.extend(&gif[at + 1..at + 1 + len]);
+            at += len + 1;
+        }
+        let pixels = weezl::decode::Decoder::new(weezl::BitOrder::Lsb, 8).decode(&data).unwrap();
+        assert_eq!(pixels, [1; 8]);
+        assert!(to_gif(&[], (2, 4)).is_err());
+    }
+
+    #[test]
+    fn test_write_png_tags_srgb_and_applies_gamma() {
+        let dir = std::env::temp_dir().join("kaku_test_write_png");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.png");
+        let img = RgbaImage::from_pixel(2, 2, Rgba([64, 128, 255, 200]));
+
+        write_png(&img, &path, 1.0).unwrap();
+        let decoder = png::Decoder::new(io::BufReader::new(std::fs::File::open(&path).unwrap()));
+        let reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().srgb, Some(png::SrgbRenderingIntent::Perceptual));
+        assert_eq!(image::open(&path).unwrap().to_rgba8(), img);
+
+        write_png(&img, &path, 2.0).unwrap();
+        let px = *image::open(&path).unwrap().to_rgba8().get_pixel(0, 0);
+        assert_eq!(px, Rgba([128, 181, 255, 200]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cell_aspect() {
+        assert_eq!(CellAspect::default().raster_cell(), RASTER_CELL);
+        assert_eq!(CellAspect::parse("1:1").unwrap().raster_cell(), (8, 8));
+        assert_eq!(CellAspect::parse("9:20").unwrap().raster_cell(), (8, 18));
+        assert_eq!(CellAspect::parse(" AUTO ").unwrap(), CellAspect::Auto);
+        assert!(CellAspect::parse("0:2").is_err());
+        assert!(CellAspect::parse("1x2").is_err());
+        assert_eq!(String::from(CellAspect::Ratio(2, 5)), "2:5");
+    }
+
     // --- Bounding box tests ---
 
     #[test]
@@ -379,6 +1370,82 @@ mod tests {
         assert_eq!(nearest_16(&black), 0);
     }
 
+    #[test]
+    fn test_dither_mixes_two_nearest_colors() {
+        let fill = |color: Rgb| {
+            let mut canvas = Canvas::new_with_size(8, 8);
+            for y in 0..8 {
+                for x in 0..8 {
+                    canvas.set(x, y, Cell { ch: blocks::FULL, fg: Some(color), bg: None });
+                }
+            }
+            canvas
+        };
+        let fgs = |canvas: &Canvas| {
+            let mut colors: Vec<Rgb> = (0..64).filter_map(|i| canvas.get(i % 8, i / 8).unwrap().fg).collect();
+            colors.sort_by_key(|c| c.r);
+            colors.dedup();
+            colors
+        };
+
+        let xterm = TerminalPalette::Xterm;
+        // Halfway between black and red: a checkered mix of the two
+        let dithered = reduce_colors(&fill(Rgb::new(102, 0, 0)), ColorFormat::Color16, true, &xterm);
+        assert_eq!(fgs(&dithered), [Rgb::new(0, 0, 0), Rgb::new(205, 0, 0)]);
+
+        let red = fill(Rgb::new(205, 0, 0));
+        assert_eq!(reduce_colors(&red, ColorFormat::Color16, true, &xterm), red);
+        let odd = fill(Rgb::new(102, 0, 0));
+        assert_eq!(reduce_colors(&odd, ColorFormat::TrueColor, true, &xterm), odd);
+    }
+
+    #[test]
+    fn test_terminal_palette_picks_index_by_real_colors() {
+        let mut canvas = Canvas::new_with_size(8, 8);
+        // Dracula's "blue" (index 4) is a light purple
+        canvas.set(0, 0, Cell { ch: blocks::FULL, fg: Some(Rgb::new(0xBD, 0x93, 0xF9)), bg: None });
+        let mapped = reduce_colors(&canvas, ColorFormat::Color16, false, &TerminalPalette::Dracula);
+        assert_eq!(mapped.get(0, 0).unwrap().fg, Some(color256_to_rgb(4)));
+        assert!(to_ansi(&mapped, ColorFormat::Color16).contains("\x1b[38;5;4m"));
+
+        let plain = reduce_colors(&canvas, ColorFormat::Color16, false, &TerminalPalette::Xterm);
+        assert_ne!(plain.get(0, 0).unwrap().fg, Some(color256_to_rgb(4)));
+    }
+
+    #[test]
+    fn test_depth_report_ranks_off_palette_colors() {
+        let mut canvas = Canvas::new_with_size(8, 8);
+        let orange = Rgb::new(255, 136, 0);
+        let teal = Rgb::new(0, 128, 128);
+        let red = color256_to_rgb(1);
+        for x in 0..3 {
+            canvas.set(x, 0, Cell { ch: blocks::FULL, fg: Some(orange), bg: None });
+        }
+        canvas.set(0, 1, Cell { ch: blocks::FULL, fg: Some(teal), bg: Some(red) });
+
+        let report = depth_report(&canvas, ColorFormat::Color16, &TerminalPalette::Xterm);
+        assert_eq!((report.distinct, report.limit, report.inexact), (3, 16, 2));
+        assert_eq!(report.merges.len(), 2);
+        assert_eq!((report.merges[0].color, report.merges[0].cells), (orange, 3));
+        assert_eq!(report.merges[1].color, teal);
+        assert_eq!(report.merges[1].into, color256_to_rgb(6));
+
+        let report = depth_report(&canvas, ColorFormat::TrueColor, &TerminalPalette::Xterm);
+        assert_eq!(report.inexact, 0);
+        assert!(report.merges.is_empty());
+    }
+
+    #[test]
+    fn test_terminal_palette_parse() {
+        assert_eq!(TerminalPalette::parse(" Solarized ").unwrap(), TerminalPalette::Solarized);
+        let custom = ["#000000"; 16].join(",");
+        let parsed = TerminalPalette::parse(&custom).unwrap();
+        assert_eq!(parsed.colors(), [Rgb::new(0, 0, 0); 16]);
+        assert_eq!(String::from(parsed), custom);
+        assert!(TerminalPalette::parse("#000000,#FFFFFF").is_err());
+        assert!(TerminalPalette::parse("gruvbox").is_err());
+    }
+
     #[test]
     fn test_truecolor_fg_bg() {
         let mut canvas = Canvas::new();
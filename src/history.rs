@@ -1,9 +1,17 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
 use crate::canvas::Canvas;
 use crate::cell::Cell;
 
 const MAX_HISTORY: usize = 256;
 
-#[derive(Clone, Debug)]
+/// Most undo steps written to a history sidecar.
+pub const SAVED_STEPS: usize = 64;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CellMutation {
     pub x: usize,
     pub y: usize,
@@ -11,15 +19,36 @@ pub struct CellMutation {
     pub new: Cell,
 }
 
-#[derive(Clone)]
+/// One undo step: a stroke, a shape, or a bulk edit such as recoloring a
+/// selection.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Action {
+    /// What the step did, for "Undo: ..." status messages
+    pub label: String,
     pub mutations: Vec<CellMutation>,
+    /// For a step that changes the canvas size (a resize or crop), the whole
+    /// canvas on the other side of it: undo and redo swap it with the live
+    /// one. Such steps have no cell mutations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canvas: Option<Box<Canvas>>,
+}
+
+impl Action {
+    /// A step that replaced the whole canvas, `before` being what it was.
+    pub fn reshape(label: &str, before: Canvas) -> Self {
+        Action { label: label.to_string(), mutations: Vec::new(), canvas: Some(Box::new(before)) }
+    }
+
+    /// Whether undoing or redoing the step changes the canvas size.
+    pub fn reshapes(&self) -> bool {
+        self.canvas.is_some()
+    }
 }
 
 pub struct History {
     undo_stack: Vec<Action>,
     redo_stack: Vec<Action>,
-    pending: Option<Vec<CellMutation>>,
+    pending: Option<Action>,
 }
 
 impl History {
@@ -32,34 +61,53 @@ impl History {
     }
 
     /// Start accumulating mutations for a drag stroke.
-    pub fn begin_stroke(&mut self) {
-        self.pending = Some(Vec::new());
+    pub fn begin_stroke(&mut self, label: &str) {
+        self.pending = Some(Action { label: label.to_string(), mutations: Vec::new(), canvas: None });
     }
 
     /// Add a mutation to the current pending stroke.
     /// If no stroke is active, commits immediately as a single action.
     pub fn push_mutation(&mut self, mutation: CellMutation) {
         if let Some(ref mut pending) = self.pending {
-            pending.push(mutation);
+            pending.mutations.push(mutation);
         } else {
             self.commit(Action {
+                label: "Edit".to_string(),
                 mutations: vec![mutation],
+                canvas: None,
             });
         }
     }
 
+    /// What cell (x, y) held before the stroke in progress first changed it.
+    pub fn pending_original(&self, x: usize, y: usize) -> Option<Cell> {
+        let pending = self.pending.as_ref()?;
+        pending.mutations.iter().find(|m| m.x == x && m.y == y).map(|m| m.old)
+    }
+
     /// Finish the current drag stroke and commit it as one action.
     pub fn end_stroke(&mut self) {
-        if let Some(mutations) = self.pending.take() {
-            if !mutations.is_empty() {
-                self.commit(Action { mutations });
-            }
+        if let Some(action) = self.pending.take() {
+            self.commit(action);
+        }
+    }
+
+    /// Record everything `f` pushes as one labeled action. Inside a stroke
+    /// or another transaction the mutations join that instead, so a bulk
+    /// edit never splits into several undo steps or one per cell.
+    pub fn transaction<R>(&mut self, label: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        if self.pending.is_some() {
+            return f(self);
         }
+        self.begin_stroke(label);
+        let result = f(self);
+        self.end_stroke();
+        result
     }
 
     /// Commit an action to the undo stack.
     pub fn commit(&mut self, action: Action) {
-        if action.mutations.is_empty() {
+        if action.mutations.is_empty() && !action.reshapes() {
             return;
         }
         self.redo_stack.clear();
@@ -71,7 +119,10 @@ impl History {
 
     /// Undo the last action, applying old cell values.
     pub fn undo(&mut self, canvas: &mut Canvas) -> bool {
-        if let Some(action) = self.undo_stack.pop() {
+        if let Some(mut action) = self.undo_stack.pop() {
+            if let Some(ref mut other) = action.canvas {
+                std::mem::swap(canvas, other);
+            }
             for m in action.mutations.iter().rev() {
                 canvas.set(m.x, m.y, m.old);
             }
@@ -84,7 +135,10 @@ impl History {
 
     /// Redo the last undone action, applying new cell values.
     pub fn redo(&mut self, canvas: &mut Canvas) -> bool {
-        if let Some(action) = self.redo_stack.pop() {
+        if let Some(mut action) = self.redo_stack.pop() {
+            if let Some(ref mut other) = action.canvas {
+                std::mem::swap(canvas, other);
+            }
             for m in &action.mutations {
                 canvas.set(m.x, m.y, m.new);
             }
@@ -95,6 +149,70 @@ impl History {
         }
     }
 
+    /// The action the next undo would revert.
+    pub fn peek_undo(&self) -> Option<&Action> {
+        self.undo_stack.last()
+    }
+
+    /// The action the next redo would reapply.
+    pub fn peek_redo(&self) -> Option<&Action> {
+        self.redo_stack.last()
+    }
+
+    /// Number of undoable and redoable actions.
+    pub fn len(&self) -> usize {
+        self.undo_stack.len() + self.redo_stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many actions are applied: 0 is the oldest state, `len()` the
+    /// newest.
+    pub fn position(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Label of the action that leads to state `step`, counting from 1.
+    pub fn label_at(&self, step: usize) -> Option<&str> {
+        let index = step.checked_sub(1)?;
+        let action = match self.undo_stack.get(index) {
+            Some(action) => action,
+            None => self.redo_stack.get(self.len().checked_sub(index + 1)?)?,
+        };
+        Some(&action.label)
+    }
+
+    /// Every state up to the current one, oldest first, rebuilt from the
+    /// current `canvas` by undoing each step on a copy.
+    pub fn states(&self, canvas: &Canvas) -> Vec<Canvas> {
+        let mut state = canvas.clone();
+        let mut states = vec![state.clone()];
+        for action in self.undo_stack.iter().rev() {
+            if let Some(ref before) = action.canvas {
+                state = (**before).clone();
+            }
+            for m in action.mutations.iter().rev() {
+                state.set(m.x, m.y, m.old);
+            }
+            states.push(state.clone());
+        }
+        states.reverse();
+        states
+    }
+
+    /// Rough heap bytes held by undo, redo and any stroke in progress.
+    pub fn memory_estimate(&self) -> usize {
+        let action = |a: &Action| {
+            let canvas = a.canvas.as_ref().map_or(0, |c| c.width * c.height * std::mem::size_of::<Cell>());
+            std::mem::size_of::<Action>() + a.mutations.capacity() * std::mem::size_of::<CellMutation>() + canvas
+        };
+        let stacks: usize = self.undo_stack.iter().chain(&self.redo_stack).map(action).sum();
+        let pending = self.pending.as_ref().map_or(0, |p| p.mutations.capacity() * std::mem::size_of::<CellMutation>());
+        stacks + pending
+    }
+
     pub fn can_undo(&self) -> bool {
         !self.undo_stack.is_empty()
     }
@@ -108,6 +226,53 @@ impl History {
     }
 }
 
+/// `art.kaku` -> `art.kaku.history`
+pub fn sidecar_path(kaku_path: &Path) -> PathBuf {
+    let mut p = kaku_path.as_os_str().to_os_string();
+    p.push(".history");
+    PathBuf::from(p)
+}
+
+/// The recent undo stack, tied to the canvas it was saved with.
+#[derive(Serialize, Deserialize)]
+struct Sidecar {
+    canvas: u64,
+    undo: Vec<Action>,
+}
+
+/// FNV-1a over the canvas's JSON, to notice edits made outside the editor.
+fn fingerprint(canvas: &Canvas) -> u64 {
+    let bytes = serde_json::to_vec(canvas).unwrap_or_default();
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+impl History {
+    /// Write the last `SAVED_STEPS` undo steps next to a project saved with
+    /// `canvas`. Redo steps are dropped.
+    pub fn save_sidecar(&self, path: &Path, canvas: &Canvas) -> io::Result<()> {
+        let start = self.undo_stack.len().saturating_sub(SAVED_STEPS);
+        let sidecar = Sidecar { canvas: fingerprint(canvas), undo: self.undo_stack[start..].to_vec() };
+        let json = serde_json::to_string(&sidecar).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Undo history from a sidecar, or None if there isn't one, the
+    /// project has changed since it was written, or a stored canvas is
+    /// malformed.
+    pub fn load_sidecar(path: &Path, canvas: &Canvas) -> Option<History> {
+        let data = std::fs::read_to_string(path).ok()?;
+        let sidecar: Sidecar = serde_json::from_str(&data).ok()?;
+        if sidecar.canvas != fingerprint(canvas) {
+            return None;
+        }
+        // Reshape steps swap their canvas in whole, so check them like a project's
+        if sidecar.undo.iter().filter_map(|a| a.canvas.as_ref()).any(|c| c.validate().is_err()) {
+            return None;
+        }
+        Some(History { undo_stack: sidecar.undo, ..History::new() })
+    }
+}
+
 impl Default for History {
     fn default() -> Self {
         Self::new()
@@ -154,7 +319,7 @@ mod tests {
         let mut canvas = Canvas::new();
         let mut history = History::new();
 
-        history.begin_stroke();
+        history.begin_stroke("Pencil");
         for x in 0..5 {
             let old = canvas.get(x, 0).unwrap();
             let new = red_cell();
@@ -175,6 +340,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_transaction_groups_and_nests() {
+        let mut history = History::new();
+        let m = |x| CellMutation { x, y: 0, old: Cell::default(), new: red_cell() };
+
+        history.transaction("Recolor", |h| {
+            h.push_mutation(m(0));
+            h.transaction("Inner", |h| h.push_mutation(m(1)));
+        });
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.peek_undo().unwrap().label, "Recolor");
+        assert_eq!(history.peek_undo().unwrap().mutations.len(), 2);
+
+        history.transaction("Empty", |_| {});
+        assert_eq!(history.len(), 1, "empty transactions leave no step");
+    }
+
+    #[test]
+    fn test_sidecar_roundtrip_and_staleness() {
+        let dir = std::env::temp_dir().join("kaku_test_history_sidecar");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = sidecar_path(&dir.join("art.kaku"));
+
+        let mut canvas = Canvas::new();
+        let mut history = History::new();
+        for i in 0..SAVED_STEPS + 2 {
+            let (x, y) = (i % 16, i / 16);
+            history.transaction("Pencil", |h| {
+                h.push_mutation(CellMutation { x, y, old: canvas.get(x, y).unwrap(), new: red_cell() });
+            });
+            canvas.set(x, y, red_cell());
+        }
+        history.save_sidecar(&path, &canvas).unwrap();
+
+        let mut loaded = History::load_sidecar(&path, &canvas).unwrap();
+        assert_eq!(loaded.len(), SAVED_STEPS);
+        assert!(loaded.undo(&mut canvas));
+
+        assert!(History::load_sidecar(&path, &canvas).is_none(), "canvas changed since saving");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sidecar_with_malformed_reshape_is_dropped() {
+        let dir = std::env::temp_dir().join("kaku_test_history_sidecar_reshape");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = sidecar_path(&dir.join("art.kaku"));
+
+        let canvas = Canvas::new_with_size(8, 8);
+        let mut history = History::new();
+        history.commit(Action::reshape("Resize", Canvas::new_with_size(16, 16)));
+        history.save_sidecar(&path, &canvas).unwrap();
+        assert!(History::load_sidecar(&path, &canvas).is_some());
+
+        let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json["undo"][0]["canvas"]["cells"] = serde_json::json!([]);
+        std::fs::write(&path, json.to_string()).unwrap();
+        assert!(History::load_sidecar(&path, &canvas).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_new_action_clears_redo() {
         let mut canvas = Canvas::new();
@@ -266,4 +494,49 @@ mod tests {
         assert!(history.redo(&mut canvas));
         assert_eq!(canvas.get(4, 6).unwrap().ch, blocks::SHADE_DARK);
     }
+
+    #[test]
+    fn test_position_and_labels_span_undo_and_redo() {
+        let mut canvas = Canvas::new();
+        let mut history = History::new();
+        for (i, label) in ["One", "Two", "Three"].iter().enumerate() {
+            history.commit(Action { label: label.to_string(), mutations: vec![CellMutation { x: i, y: 0, old: Cell::default(), new: red_cell() }], canvas: None });
+        }
+        history.undo(&mut canvas);
+        history.undo(&mut canvas);
+        assert_eq!(history.position(), 1);
+        let labels: Vec<_> = (0..=4).map(|step| history.label_at(step)).collect();
+        assert_eq!(labels, [None, Some("One"), Some("Two"), Some("Three"), None]);
+    }
+
+    #[test]
+    fn test_reshape_swaps_whole_canvas() {
+        let mut canvas = Canvas::new_with_size(16, 16);
+        canvas.set(15, 15, red_cell());
+        let mut history = History::new();
+        let before = std::mem::replace(&mut canvas, Canvas::new_with_size(8, 8));
+        history.commit(Action::reshape("Crop", before));
+        assert_eq!(history.states(&canvas)[0].width, 16);
+
+        assert!(history.undo(&mut canvas));
+        assert_eq!(canvas.width, 16);
+        assert_eq!(canvas.get(15, 15), Some(red_cell()));
+        assert!(history.redo(&mut canvas));
+        assert_eq!(canvas.width, 8);
+    }
+
+    #[test]
+    fn test_states_rebuild_each_step() {
+        let mut canvas = Canvas::new();
+        let mut history = History::new();
+        for x in 0..2 {
+            canvas.set(x, 0, red_cell());
+            history.commit(Action { label: "Dot".to_string(), mutations: vec![CellMutation { x, y: 0, old: Cell::default(), new: red_cell() }], canvas: None });
+        }
+        let states = history.states(&canvas);
+        assert_eq!(states.len(), 3);
+        let drawn = |c: &Canvas| (0..2).filter(|&x| !c.get(x, 0).unwrap().is_empty()).count();
+        assert_eq!(states.iter().map(drawn).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(states[2], canvas);
+    }
 }
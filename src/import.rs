@@ -0,0 +1,765 @@
+use std::path::{Path, PathBuf};
+
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+
+use crate::aseprite;
+use crate::canvas::{Canvas, MAX_DIMENSION, MIN_DIMENSION};
+use crate::cell::{self, blocks, color256_to_rgb, Cell, Rgb};
+use crate::palette;
+
+/// Pixels with alpha below this are treated as transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImportMode {
+    /// Two pixels per cell using half blocks.
+    #[default]
+    Blocks,
+    /// Line-art sketch from edge detection, drawn with box characters.
+    Edges,
+    /// Classic ASCII art: brightness picks a character from a density ramp.
+    Ascii,
+    /// Like `Ascii`, but with the shade blocks as the density ramp.
+    Shades,
+    /// Four pixels per cell: picks the half, quadrant or full block and
+    /// fg/bg pair that best matches each 2x2 pixel group.
+    Best,
+}
+
+/// Density ramps, from empty to densest. Brighter pixels get denser characters.
+const ASCII_RAMP: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+const SHADE_RAMP: [char; 5] = [' ', blocks::SHADE_LIGHT, blocks::SHADE_MEDIUM, blocks::SHADE_DARK, blocks::FULL];
+
+#[derive(Default)]
+pub struct ImportOptions {
+    pub mode: ImportMode,
+    /// Maximum canvas width in cells.
+    pub width: usize,
+    /// Maximum canvas height in cells (each cell covers two pixel rows).
+    pub height: usize,
+    /// Map brightness onto these colors instead of nearest-color matching.
+    /// `Edges` ignores it; its lines are always white.
+    pub ramp: Option<Vec<Rgb>>,
+    /// Match colors against these instead of the xterm-256 palette.
+    pub palette: Option<Vec<Rgb>>,
+    /// Spread each pixel's color error onto its neighbours (Floyd-Steinberg)
+    /// in `Blocks` mode, so gradients survive a small palette.
+    pub dither: bool,
+}
+
+/// Load a PNG or JPEG file as RGBA pixels, or the first frame of an
+/// Aseprite file with its layers flattened.
+pub fn load_image(path: &Path) -> Result<RgbaImage, String> {
+    if aseprite::is_aseprite(path) {
+        return aseprite::load(path).map(|mut frames| frames.swap_remove(0));
+    }
+    image::open(path)
+        .map(|img| img.to_rgba8())
+        .map_err(|e| format!("Image error: {}", e))
+}
+
+/// PNG, JPEG and Aseprite files in a directory, sorted by name.
+pub fn list_image_files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                .filter(|name| {
+                    Path::new(name).extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                        ["png", "jpg", "jpeg", "ase", "aseprite"].iter().any(|ext| e.eq_ignore_ascii_case(ext))
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// List PNG frames in a directory, ordered by the number in their file name
+/// (`frame2.png` before `frame10.png`).
+pub fn list_frame_files(dir: &Path) -> Vec<PathBuf> {
+    let mut frames: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.extension()
+                        .and_then(|e| e.to_str())
+                        .is_some_and(|e| e.eq_ignore_ascii_case("png"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    frames.sort_by_key(|p| {
+        let stem = p.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_string();
+        let digits: String = stem.chars().rev().take_while(|c| c.is_ascii_digit()).collect();
+        let number = digits.chars().rev().collect::<String>().parse::<u64>().unwrap_or(0);
+        (number, stem)
+    });
+    frames
+}
+
+/// Convert an image into a canvas, scaling it to fit the requested size while
+/// keeping its aspect ratio. A cell is treated as two pixels tall.
+pub fn import_image(img: &RgbaImage, opts: &ImportOptions) -> Canvas {
+    let (px_w, px_h) = fit_size(img.width(), img.height(), opts.width, opts.height * 2);
+    let cells_w = (px_w as usize).clamp(MIN_DIMENSION, MAX_DIMENSION);
+    let cells_h = (px_h as usize).div_ceil(2).clamp(MIN_DIMENSION, MAX_DIMENSION);
+    let mut canvas = Canvas::new_with_size(cells_w, cells_h);
+
+    match opts.mode {
+        ImportMode::Blocks => draw_half_blocks(&mut canvas, img, px_w, px_h, opts),
+        ImportMode::Edges => draw_edges(&mut canvas, img, px_w, px_h.div_ceil(2)),
+        ImportMode::Ascii => draw_density(&mut canvas, img, px_w, px_h.div_ceil(2), &ASCII_RAMP, opts),
+        ImportMode::Shades => draw_density(&mut canvas, img, px_w, px_h.div_ceil(2), &SHADE_RAMP, opts),
+        ImportMode::Best => draw_best_blocks(&mut canvas, img, px_w, px_h.div_ceil(2), opts),
+    }
+    canvas
+}
+
+fn draw_half_blocks(canvas: &mut Canvas, img: &RgbaImage, px_w: u32, px_h: u32, opts: &ImportOptions) {
+    let scaled = image::imageops::resize(img, px_w, px_h, FilterType::Triangle);
+    let (cells_w, cells_h) = (canvas.width, canvas.height);
+
+    let colors = ColorMap::new(opts);
+    let pixels = if opts.dither {
+        dither(&scaled, &colors)
+    } else {
+        scaled.pixels().map(|p| colors.map(p)).collect()
+    };
+    let pixel = |x: usize, y: usize| pixels[y * px_w as usize + x];
+
+    for y in 0..(px_h as usize).div_ceil(2).min(cells_h) {
+        for x in 0..(px_w as usize).min(cells_w) {
+            let top = pixel(x, y * 2);
+            let bottom = if (y * 2 + 1) < px_h as usize {
+                pixel(x, y * 2 + 1)
+            } else {
+                None
+            };
+            canvas.set(x, y, half_block_cell(top, bottom));
+        }
+    }
+}
+
+/// Block for each 2x2 coverage mask. Bits: 1 = top-left, 2 = top-right,
+/// 4 = bottom-left, 8 = bottom-right; set bits are drawn in the fg color.
+const QUADRANT_BLOCKS: [char; 16] = [
+    ' ', '\u{2598}', '\u{259D}', blocks::UPPER_HALF,
+    '\u{2596}', blocks::LEFT_HALF, '\u{259E}', '\u{259B}',
+    '\u{2597}', '\u{259A}', blocks::RIGHT_HALF, '\u{259C}',
+    blocks::LOWER_HALF, '\u{2599}', '\u{259F}', blocks::FULL,
+];
+
+/// Sample 2x2 pixels per cell and pick the block/color split with the
+/// lowest squared error, similar to chafa's symbol selection.
+fn draw_best_blocks(canvas: &mut Canvas, img: &RgbaImage, w: u32, h: u32, opts: &ImportOptions) {
+    let scaled = image::imageops::resize(img, w * 2, h * 2, FilterType::Triangle);
+    let colors = ColorMap::new(opts);
+
+    for y in 0..(h as usize).min(canvas.height) {
+        for x in 0..(w as usize).min(canvas.width) {
+            let (px, py) = (x as u32 * 2, y as u32 * 2);
+            let group = [
+                *scaled.get_pixel(px, py),
+                *scaled.get_pixel(px + 1, py),
+                *scaled.get_pixel(px, py + 1),
+                *scaled.get_pixel(px + 1, py + 1),
+            ];
+            let (mask, fg, bg) = best_split(&group);
+            if mask == 0 {
+                continue;
+            }
+            let fg = colors.map(&fg);
+            let bg = if mask == 15 { None } else { colors.map(&bg) };
+            canvas.set(x, y, Cell { ch: QUADRANT_BLOCKS[mask], fg, bg });
+        }
+    }
+}
+
+/// Split a 2x2 group into fg/bg pixels. Returns the fg mask and the mean
+/// fg and bg colors. Transparent pixels always go to the background.
+fn best_split(group: &[Rgba<u8>; 4]) -> (usize, Rgba<u8>, Rgba<u8>) {
+    let opaque: usize = (0..4)
+        .filter(|&i| group[i][3] >= ALPHA_THRESHOLD)
+        .map(|i| 1 << i)
+        .sum();
+    if opaque != 15 {
+        let fg = mean_color(group, opaque);
+        return (opaque, fg, Rgba([0, 0, 0, 0]));
+    }
+
+    let mut best = (15, mean_color(group, 15), Rgba([0, 0, 0, 0]));
+    let mut best_err = split_error(group, 15, &best.1, &best.2);
+    // Masks 1..=7 cover every two-color split; 8..=14 are their inverses.
+    for mask in 1..8 {
+        let fg = mean_color(group, mask);
+        let bg = mean_color(group, 15 ^ mask);
+        let err = split_error(group, mask, &fg, &bg);
+        if err < best_err {
+            best_err = err;
+            best = (mask, fg, bg);
+        }
+    }
+    best
+}
+
+fn mean_color(group: &[Rgba<u8>; 4], mask: usize) -> Rgba<u8> {
+    let (mut sum, mut n) = ([0u32; 3], 0u32);
+    for (i, p) in group.iter().enumerate() {
+        if mask & (1 << i) != 0 {
+            for c in 0..3 {
+                sum[c] += p[c] as u32;
+            }
+            n += 1;
+        }
+    }
+    if n == 0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    Rgba([(sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8, 255])
+}
+
+fn split_error(group: &[Rgba<u8>; 4], mask: usize, fg: &Rgba<u8>, bg: &Rgba<u8>) -> u32 {
+    group
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let target = if mask & (1 << i) != 0 { fg } else { bg };
+            (0..3)
+                .map(|c| (p[c] as i32 - target[c] as i32).pow(2) as u32)
+                .sum::<u32>()
+        })
+        .sum()
+}
+
+/// Maps pixels to canvas colors: the ramp entry for their brightness, the
+/// nearest palette color, or else the nearest xterm-256 color.
+struct ColorMap {
+    ramp: Option<Vec<Rgb>>,
+    palette: Option<Vec<Rgb>>,
+}
+
+impl ColorMap {
+    fn new(opts: &ImportOptions) -> Self {
+        ColorMap {
+            ramp: opts.ramp.as_ref().filter(|r| !r.is_empty()).map(|r| sort_by_luminance(r)),
+            palette: opts.palette.clone().filter(|p| !p.is_empty()),
+        }
+    }
+
+    /// Transparent pixels map to `None`.
+    fn map(&self, p: &Rgba<u8>) -> Option<Rgb> {
+        if p[3] < ALPHA_THRESHOLD {
+            return None;
+        }
+        Some(match (&self.ramp, &self.palette) {
+            (Some(ramp), _) => ramp_color(ramp, luminance(p)),
+            (None, Some(colors)) => nearest_of(colors, p),
+            (None, None) => palette::nearest_color(p[0], p[1], p[2]),
+        })
+    }
+}
+
+fn nearest_of(colors: &[Rgb], p: &Rgba<u8>) -> Rgb {
+    let target = Rgb::new(p[0], p[1], p[2]);
+    *colors
+        .iter()
+        .min_by(|a, b| cell::distance(a, &target).total_cmp(&cell::distance(b, &target)))
+        .expect("palette is not empty")
+}
+
+/// Quantize every pixel, row by row, pushing each one's error onto the
+/// unvisited pixels around it with the Floyd-Steinberg weights.
+fn dither(img: &RgbaImage, colors: &ColorMap) -> Vec<Option<Rgb>> {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    let mut error = vec![[0f32; 3]; w * h];
+    let mut out = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let p = img.get_pixel(x as u32, y as u32);
+            let e = error[y * w + x];
+            let channel = |c: usize| (p[c] as f32 + e[c]).round().clamp(0.0, 255.0) as u8;
+            let adjusted = Rgba([channel(0), channel(1), channel(2), p[3]]);
+            let color = colors.map(&adjusted);
+            if let Some(c) = color {
+                let diff = [
+                    adjusted[0] as f32 - c.r as f32,
+                    adjusted[1] as f32 - c.g as f32,
+                    adjusted[2] as f32 - c.b as f32,
+                ];
+                for (dx, dy, weight) in [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)] {
+                    let (nx, ny) = (x as isize + dx, y + dy);
+                    if nx < 0 || nx as usize >= w || ny >= h {
+                        continue;
+                    }
+                    let target = &mut error[ny * w + nx as usize];
+                    for c in 0..3 {
+                        target[c] += diff[c] * weight / 16.0;
+                    }
+                }
+            }
+            out.push(color);
+        }
+    }
+    out
+}
+
+/// Sobel gradient magnitude (on 0.0..=1.0 luminance) above which a cell is an edge.
+const EDGE_THRESHOLD: f64 = 1.0;
+
+/// Sample the image once per cell and draw box characters along the edges
+/// found by a Sobel filter, oriented to follow each edge.
+fn draw_edges(canvas: &mut Canvas, img: &RgbaImage, w: u32, h: u32) {
+    let scaled = image::imageops::resize(img, w, h, FilterType::Triangle);
+    let lum = |x: i64, y: i64| -> f64 {
+        let x = x.clamp(0, w as i64 - 1) as u32;
+        let y = y.clamp(0, h as i64 - 1) as u32;
+        let p = scaled.get_pixel(x, y);
+        if p[3] < ALPHA_THRESHOLD { 0.0 } else { luminance(p) }
+    };
+
+    for y in 0..(h as usize).min(canvas.height) {
+        for x in 0..(w as usize).min(canvas.width) {
+            let (xi, yi) = (x as i64, y as i64);
+            let gx = (lum(xi + 1, yi - 1) + 2.0 * lum(xi + 1, yi) + lum(xi + 1, yi + 1))
+                - (lum(xi - 1, yi - 1) + 2.0 * lum(xi - 1, yi) + lum(xi - 1, yi + 1));
+            let gy = (lum(xi - 1, yi + 1) + 2.0 * lum(xi, yi + 1) + lum(xi + 1, yi + 1))
+                - (lum(xi - 1, yi - 1) + 2.0 * lum(xi, yi - 1) + lum(xi + 1, yi - 1));
+            if (gx * gx + gy * gy).sqrt() >= EDGE_THRESHOLD {
+                canvas.set(x, y, Cell { ch: edge_char(gx, gy), fg: Some(Rgb::WHITE), bg: None });
+            }
+        }
+    }
+}
+
+/// Sample the image once per cell and map brightness onto a character ramp,
+/// and onto a color ramp too if there is one.
+fn draw_density(canvas: &mut Canvas, img: &RgbaImage, w: u32, h: u32, ramp: &[char], opts: &ImportOptions) {
+    let colors = ColorMap::new(opts).ramp;
+    let scaled = image::imageops::resize(img, w, h, FilterType::Triangle);
+    for y in 0..(h as usize).min(canvas.height) {
+        for x in 0..(w as usize).min(canvas.width) {
+            let p = scaled.get_pixel(x as u32, y as u32);
+            if p[3] < ALPHA_THRESHOLD {
+                continue;
+            }
+            let lum = luminance(p);
+            let ch = density_char(ramp, lum);
+            if ch != ' ' {
+                let fg = colors.as_deref().map_or(Rgb::WHITE, |colors| ramp_color(colors, lum));
+                canvas.set(x, y, Cell { ch, fg: Some(fg), bg: None });
+            }
+        }
+    }
+}
+
+fn density_char(ramp: &[char], lum: f64) -> char {
+    let idx = (lum * ramp.len() as f64).floor() as usize;
+    ramp[idx.min(ramp.len() - 1)]
+}
+
+/// Box character running along the edge perpendicular to a gradient.
+/// `gy` grows downward, as in image coordinates.
+fn edge_char(gx: f64, gy: f64) -> char {
+    // The edge runs along (-gy, gx); flip y so angles are measured upward.
+    let angle = (-gx).atan2(-gy).to_degrees().rem_euclid(180.0);
+    match angle {
+        a if !(22.5..157.5).contains(&a) => '\u{2500}', // ─
+        a if a < 67.5 => '\u{2571}',                    // ╱
+        a if a < 112.5 => '\u{2502}',                   // │
+        _ => '\u{2572}',                                // ╲
+    }
+}
+
+/// Largest size within `max_w` x `max_h` that keeps the source aspect ratio.
+fn fit_size(src_w: u32, src_h: u32, max_w: usize, max_h: usize) -> (u32, u32) {
+    let scale = (max_w as f64 / src_w.max(1) as f64).min(max_h as f64 / src_h.max(1) as f64);
+    let w = ((src_w as f64 * scale).round() as u32).max(1);
+    let h = ((src_h as f64 * scale).round() as u32).max(1);
+    (w, h)
+}
+
+/// Build a cell from the colors of its upper and lower pixel.
+fn half_block_cell(top: Option<Rgb>, bottom: Option<Rgb>) -> Cell {
+    match (top, bottom) {
+        (None, None) => Cell::default(),
+        (Some(t), Some(b)) if t == b => Cell { ch: blocks::FULL, fg: Some(t), bg: None },
+        (Some(t), b) => Cell { ch: blocks::UPPER_HALF, fg: Some(t), bg: b },
+        (None, Some(b)) => Cell { ch: blocks::LOWER_HALF, fg: Some(b), bg: None },
+    }
+}
+
+/// Perceived brightness in 0.0..=1.0 (Rec. 601 weights).
+fn luminance(p: &Rgba<u8>) -> f64 {
+    (0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64) / 255.0
+}
+
+fn rgb_luminance(c: &Rgb) -> f64 {
+    luminance(&Rgba([c.r, c.g, c.b, 255]))
+}
+
+fn sort_by_luminance(colors: &[Rgb]) -> Vec<Rgb> {
+    let mut sorted = colors.to_vec();
+    sorted.sort_by(|a, b| rgb_luminance(a).total_cmp(&rgb_luminance(b)));
+    sorted
+}
+
+/// Posterize a brightness value onto a dark-to-light color ramp.
+fn ramp_color(ramp: &[Rgb], lum: f64) -> Rgb {
+    let idx = (lum * ramp.len() as f64).floor() as usize;
+    ramp[idx.min(ramp.len() - 1)]
+}
+
+/// Parse ANSI art, as written by `export::to_ansi`, back into a canvas.
+/// Understands 24-bit, 256-color and basic 16-color SGR codes; other escape
+/// sequences are skipped. Spaces, and cells skipped by cursor-forward
+/// sequences, become empty cells.
+pub fn from_ansi(text: &str) -> Canvas {
+    let rows: Vec<Vec<Cell>> = text.lines().map(parse_ansi_line).collect();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut canvas = Canvas::new_with_size(width, rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            canvas.set(x, y, *cell);
+        }
+    }
+    canvas
+}
+
+fn parse_ansi_line(line: &str) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let mut fg = None;
+    let mut bg = None;
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => {
+                if chars.next() != Some('[') {
+                    continue;
+                }
+                let mut params = String::new();
+                for c in chars.by_ref() {
+                    if c.is_ascii_digit() || c == ';' {
+                        params.push(c);
+                    } else {
+                        match c {
+                            'm' => apply_sgr(&params, &mut fg, &mut bg),
+                            // Cursor forward: skipped cells are empty
+                            'C' => {
+                                let n = params.parse().unwrap_or(1).max(1);
+                                cells.resize(cells.len() + n, Cell::default());
+                            }
+                            _ => {}
+                        }
+                        break;
+                    }
+                }
+            }
+            '\r' => {}
+            ' ' => cells.push(Cell::default()),
+            _ => cells.push(Cell { ch, fg, bg }),
+        }
+    }
+    cells
+}
+
+fn apply_sgr(params: &str, fg: &mut Option<Rgb>, bg: &mut Option<Rgb>) {
+    let codes: Vec<u32> = params
+        .split(';')
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => {
+                *fg = None;
+                *bg = None;
+            }
+            c @ 30..=37 => *fg = Some(color256_to_rgb((c - 30) as u8)),
+            c @ 90..=97 => *fg = Some(color256_to_rgb((c - 90 + 8) as u8)),
+            c @ 40..=47 => *bg = Some(color256_to_rgb((c - 40) as u8)),
+            c @ 100..=107 => *bg = Some(color256_to_rgb((c - 100 + 8) as u8)),
+            39 => *fg = None,
+            49 => *bg = None,
+            c @ (38 | 48) => {
+                let target = if c == 38 { &mut *fg } else { &mut *bg };
+                let channel = |n: usize| codes.get(i + n).map(|&v| v.min(255) as u8);
+                match codes.get(i + 1) {
+                    Some(5) => {
+                        if let Some(n) = channel(2) {
+                            *target = Some(color256_to_rgb(n));
+                        }
+                        i += 2;
+                    }
+                    Some(2) => {
+                        if let (Some(r), Some(g), Some(b)) = (channel(2), channel(3), channel(4)) {
+                            *target = Some(Rgb::new(r, g, b));
+                        }
+                        i += 4;
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(w: u32, h: u32, px: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(w, h, Rgba(px))
+    }
+
+    #[test]
+    fn test_fit_size_keeps_aspect() {
+        assert_eq!(fit_size(100, 50, 48, 64), (48, 24));
+        assert_eq!(fit_size(50, 100, 48, 64), (32, 64));
+    }
+
+    #[test]
+    fn test_import_solid_image_uses_full_blocks() {
+        let img = solid(16, 32, [255, 0, 0, 255]);
+        let opts = ImportOptions { mode: ImportMode::Blocks, width: 16, height: 16, ..Default::default() };
+        let canvas = import_image(&img, &opts);
+        assert_eq!((canvas.width, canvas.height), (16, 16));
+        let cell = canvas.get(3, 3).unwrap();
+        assert_eq!(cell.ch, blocks::FULL);
+        assert_eq!(cell.fg, Some(palette::nearest_color(255, 0, 0)));
+    }
+
+    #[test]
+    fn test_import_two_tone_rows_use_half_blocks() {
+        let mut img = solid(8, 16, [0, 0, 0, 255]);
+        for x in 0..8 {
+            for y in (0..16).step_by(2) {
+                img.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+        let opts = ImportOptions { mode: ImportMode::Blocks, width: 8, height: 8, ..Default::default() };
+        let canvas = import_image(&img, &opts);
+        let cell = canvas.get(0, 0).unwrap();
+        assert_eq!(cell.ch, blocks::UPPER_HALF);
+        assert_eq!(cell.fg, Some(palette::nearest_color(255, 255, 255)));
+        assert_eq!(cell.bg, Some(palette::nearest_color(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_import_transparent_pixels_stay_empty() {
+        let img = solid(8, 16, [10, 10, 10, 0]);
+        let canvas = import_image(&img, &ImportOptions { mode: ImportMode::Blocks, width: 8, height: 8, ..Default::default() });
+        assert_eq!(canvas.get(0, 0), Some(Cell::default()));
+    }
+
+    #[test]
+    fn test_import_with_ramp_posterizes() {
+        let dark = Rgb::new(20, 0, 40);
+        let light = Rgb::new(250, 220, 120);
+        let ramp = Some(vec![light, dark]);
+        let canvas = import_image(
+            &solid(8, 16, [30, 30, 30, 255]),
+            &ImportOptions { mode: ImportMode::Blocks, width: 8, height: 8, ramp: ramp.clone(), ..Default::default() },
+        );
+        assert_eq!(canvas.get(0, 0).unwrap().fg, Some(dark));
+        let canvas = import_image(
+            &solid(8, 16, [230, 230, 230, 255]),
+            &ImportOptions { mode: ImportMode::Blocks, width: 8, height: 8, ramp, ..Default::default() },
+        );
+        assert_eq!(canvas.get(0, 0).unwrap().fg, Some(light));
+
+        // The density modes color their characters from the ramp too
+        let canvas = import_image(
+            &solid(8, 16, [200, 200, 200, 255]),
+            &ImportOptions { mode: ImportMode::Shades, width: 8, height: 8, ramp: Some(vec![light, dark]), ..Default::default() },
+        );
+        assert_eq!(canvas.get(0, 0).unwrap(), Cell { ch: blocks::SHADE_DARK, fg: Some(light), bg: None });
+    }
+
+    #[test]
+    fn test_import_dither_mixes_palette_colors() {
+        let (black, white) = (Rgb::new(0, 0, 0), Rgb::new(255, 255, 255));
+        let opts = |dither| ImportOptions {
+            width: 8,
+            height: 8,
+            palette: Some(vec![black, white]),
+            dither,
+            ..Default::default()
+        };
+        let gray = solid(8, 16, [100, 100, 100, 255]);
+        let colors = |canvas: &Canvas| {
+            let mut seen: Vec<Rgb> = canvas.used_colors();
+            seen.sort_by_key(|c| c.r);
+            seen
+        };
+
+        let flat = import_image(&gray, &opts(false));
+        assert_eq!(colors(&flat), [black]);
+        let dithered = import_image(&gray, &opts(true));
+        assert_eq!(colors(&dithered), [black, white]);
+    }
+
+    #[test]
+    fn test_list_image_files() {
+        let dir = std::env::temp_dir().join("kaku_test_image_files");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["b.JPG", "a.png", "c.jpeg", "d.ase", "notes.txt", "art.kaku"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        assert_eq!(list_image_files(&dir), ["a.png", "b.JPG", "c.jpeg", "d.ase"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_edge_char_orientation() {
+        // Brightness changing top-to-bottom: horizontal edge
+        assert_eq!(edge_char(0.0, 4.0), '\u{2500}');
+        // Brightness changing left-to-right: vertical edge
+        assert_eq!(edge_char(4.0, 0.0), '\u{2502}');
+        // Bright bottom-right corner: edge runs bottom-left to top-right
+        assert_eq!(edge_char(3.0, 3.0), '\u{2571}');
+        assert_eq!(edge_char(-3.0, 3.0), '\u{2572}');
+    }
+
+    #[test]
+    fn test_import_edges_outlines_shape() {
+        // Left half black, right half white: a single vertical edge
+        let mut img = solid(16, 32, [0, 0, 0, 255]);
+        for y in 0..32 {
+            for x in 8..16 {
+                img.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+        let opts = ImportOptions { mode: ImportMode::Edges, width: 16, height: 16, ..Default::default() };
+        let canvas = import_image(&img, &opts);
+        assert_eq!(canvas.get(8, 8).unwrap().ch, '\u{2502}');
+        assert_eq!(canvas.get(2, 8), Some(Cell::default()));
+        assert_eq!(canvas.get(13, 8), Some(Cell::default()));
+    }
+
+    #[test]
+    fn test_density_char_ramp() {
+        assert_eq!(density_char(&ASCII_RAMP, 0.0), ' ');
+        assert_eq!(density_char(&ASCII_RAMP, 0.45), '=');
+        assert_eq!(density_char(&ASCII_RAMP, 1.0), '@');
+        assert_eq!(density_char(&SHADE_RAMP, 0.5), blocks::SHADE_MEDIUM);
+    }
+
+    #[test]
+    fn test_import_ascii_gradient() {
+        let mut img = solid(10, 2, [0, 0, 0, 255]);
+        for x in 0..10 {
+            let v = (x * 255 / 9) as u8;
+            img.put_pixel(x, 0, Rgba([v, v, v, 255]));
+            img.put_pixel(x, 1, Rgba([v, v, v, 255]));
+        }
+        let opts = ImportOptions { mode: ImportMode::Ascii, width: 10, height: 1, ..Default::default() };
+        let canvas = import_image(&img, &opts);
+        assert_eq!(canvas.get(0, 0), Some(Cell::default()));
+        assert_eq!(canvas.get(9, 0).unwrap().ch, '@');
+        assert_eq!(canvas.get(9, 0).unwrap().fg, Some(Rgb::WHITE));
+    }
+
+    #[test]
+    fn test_best_split_picks_quadrant() {
+        let w = Rgba([255, 255, 255, 255]);
+        let k = Rgba([0, 0, 0, 255]);
+        // Only the top-left pixel differs
+        let (mask, fg, bg) = best_split(&[w, k, k, k]);
+        assert!(mask == 1 || mask == 14);
+        let (lone, rest) = if mask == 1 { (fg, bg) } else { (bg, fg) };
+        assert_eq!(lone, w);
+        assert_eq!(rest, k);
+        // Left column vs right column
+        let (mask, _, _) = best_split(&[w, k, w, k]);
+        assert!(mask == 5 || mask == 10);
+        // Uniform group is a full block
+        assert_eq!(best_split(&[w, w, w, w]).0, 15);
+    }
+
+    #[test]
+    fn test_best_split_transparent_pixels() {
+        let w = Rgba([255, 255, 255, 255]);
+        let t = Rgba([0, 0, 0, 0]);
+        assert_eq!(best_split(&[t, t, t, t]).0, 0);
+        let (mask, fg, _) = best_split(&[t, w, t, w]);
+        assert_eq!(mask, 10);
+        assert_eq!(fg, w);
+    }
+
+    #[test]
+    fn test_import_best_uses_vertical_halves() {
+        // Alternating black/white columns: each cell splits left/right
+        let mut img = solid(16, 16, [0, 0, 0, 255]);
+        for y in 0..16 {
+            for x in (0..16).step_by(2) {
+                img.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+        let opts = ImportOptions { mode: ImportMode::Best, width: 8, height: 8, ..Default::default() };
+        let canvas = import_image(&img, &opts);
+        let cell = canvas.get(2, 2).unwrap();
+        assert!(cell.ch == blocks::LEFT_HALF || cell.ch == blocks::RIGHT_HALF);
+        assert_ne!(cell.fg, cell.bg);
+    }
+
+    #[test]
+    fn test_list_frame_files_numeric_order() {
+        let dir = std::env::temp_dir().join("kaku_test_frames");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["frame10.png", "frame2.png", "frame1.png", "notes.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let names: Vec<_> = list_frame_files(&dir)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["frame1.png", "frame2.png", "frame10.png"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ramp_color_bounds() {
+        let ramp = [Rgb::BLACK, Rgb::new(128, 128, 128), Rgb::WHITE];
+        assert_eq!(ramp_color(&ramp, 0.0), Rgb::BLACK);
+        assert_eq!(ramp_color(&ramp, 0.5), Rgb::new(128, 128, 128));
+        assert_eq!(ramp_color(&ramp, 1.0), Rgb::WHITE);
+    }
+
+    #[test]
+    fn test_from_ansi_truecolor() {
+        let canvas = from_ansi("\x1b[38;2;255;0;0mA\x1b[38;2;0;0;255;48;2;0;255;0mB\x1b[0m");
+        assert_eq!(canvas.get(0, 0).unwrap().fg, Some(Rgb::new(255, 0, 0)));
+        assert_eq!(canvas.get(0, 0).unwrap().bg, None);
+        let b = canvas.get(1, 0).unwrap();
+        assert_eq!((b.ch, b.fg, b.bg), ('B', Some(Rgb::new(0, 0, 255)), Some(Rgb::new(0, 255, 0))));
+    }
+
+    #[test]
+    fn test_from_ansi_indexed_and_resets() {
+        let canvas = from_ansi("\x1b[31;44mx\x1b[39my\x1b[0m z\n\x1b[38;5;196mq");
+        let x = canvas.get(0, 0).unwrap();
+        assert_eq!((x.fg, x.bg), (Some(color256_to_rgb(1)), Some(color256_to_rgb(4))));
+        let y = canvas.get(1, 0).unwrap();
+        assert_eq!((y.fg, y.bg), (None, Some(color256_to_rgb(4))));
+        assert!(canvas.get(2, 0).unwrap().is_empty());
+        assert_eq!(canvas.get(3, 0).unwrap().fg, None);
+        assert_eq!(canvas.get(0, 1).unwrap().fg, Some(Rgb::new(255, 0, 0)));
+    }
+
+    #[test]
+    fn test_from_ansi_ignores_malformed_escapes() {
+        let canvas = from_ansi("\x1b[38;2;999mA\x1b[2Kb\x1bc");
+        assert_eq!(canvas.get(0, 0).unwrap().ch, 'A');
+        assert_eq!(canvas.get(1, 0).unwrap().ch, 'b');
+    }
+}
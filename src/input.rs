@@ -1,10 +1,10 @@
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
 
-use crate::app::{App, AppMode};
-use crate::canvas::Canvas;
-use crate::history::History;
-use crate::palette::{PaletteItem, PaletteSection};
-use crate::tools::{ToolKind, ToolState};
+use crate::app::{App, AppMode, FileOp};
+use crate::command::Command;
+use crate::listing::ListView;
+use crate::symmetry::SymmetryMode;
 
 /// Canvas area position in terminal coordinates.
 /// Set by the UI renderer each frame.
@@ -16,6 +16,11 @@ pub struct CanvasArea {
     /// Viewport dimensions in canvas cells (set by renderer)
     pub viewport_w: usize,
     pub viewport_h: usize,
+    /// With the tile preview on, the screen area its copies fill
+    pub tiles: Option<Rect>,
+    /// The minimap's screen area and the canvas cells each of its cells
+    /// stands for, each way
+    pub minimap: Option<(Rect, usize)>,
 }
 
 impl CanvasArea {
@@ -37,23 +42,73 @@ impl CanvasArea {
         };
         Some((canvas_x, canvas_y))
     }
+
+    /// The cell a copy in the tile preview shows at a screen position, as
+    /// if the canvas at the center repeated. Only set up when the whole
+    /// canvas is in view.
+    pub fn screen_to_tile(&self, screen_x: u16, screen_y: u16, zoom: u8, width: usize, height: usize) -> Option<(usize, usize)> {
+        let tiles = self.tiles?;
+        if !tiles.contains(Position::new(screen_x, screen_y)) {
+            return None;
+        }
+        let rows = if zoom == 4 { 2 } else { 1 };
+        let x = (screen_x as isize - self.left as isize).div_euclid(zoom as isize);
+        let y = (screen_y as isize - self.top as isize).div_euclid(rows);
+        Some((x.rem_euclid(width as isize) as usize, y.rem_euclid(height as isize) as usize))
+    }
+
+    /// The canvas cell at the middle of the part of the canvas a minimap
+    /// cell stands for, or None off the minimap.
+    pub fn minimap_to_canvas(&self, screen_x: u16, screen_y: u16) -> Option<(usize, usize)> {
+        let (rect, scale) = self.minimap?;
+        if !rect.contains(Position::new(screen_x, screen_y)) {
+            return None;
+        }
+        let x = (screen_x - rect.x) as usize * scale + scale / 2;
+        let y = (screen_y - rect.y) as usize * scale + scale / 2;
+        Some((x, y))
+    }
+}
+
+/// Drop events that are superseded by the next one: consecutive mouse moves
+/// and terminal resizes only matter for their final position/size. Drags
+/// are kept so strokes stay continuous.
+pub fn coalesce_events(events: Vec<Event>) -> Vec<Event> {
+    let mut out: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events {
+        let superseded = match (out.last(), &event) {
+            (Some(Event::Mouse(prev)), Event::Mouse(next)) => {
+                prev.kind == MouseEventKind::Moved && next.kind == MouseEventKind::Moved
+            }
+            (Some(Event::Resize(..)), Event::Resize(..)) => true,
+            _ => false,
+        };
+        if superseded {
+            out.pop();
+        }
+        out.push(event);
+    }
+    out
 }
 
 pub fn handle_event(app: &mut App, event: Event, canvas_area: &CanvasArea) {
+    // Windows reports key releases too; only presses and repeats are input
+    if let Event::Key(key) = event {
+        if key.kind == KeyEventKind::Release {
+            return;
+        }
+    }
     match app.mode {
         AppMode::Help => {
-            // Any key dismisses help
-            if matches!(event, Event::Key(_)) {
-                app.mode = AppMode::Normal;
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_help(app, code);
             }
             return;
         }
         AppMode::Quitting => {
             if let Event::Key(KeyEvent { code, .. }) = event {
                 match code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        app.running = false;
-                    }
+                    KeyCode::Char('y') | KeyCode::Char('Y') => app.close_document(),
                     _ => {
                         app.mode = AppMode::Normal;
                     }
@@ -61,16 +116,22 @@ pub fn handle_event(app: &mut App, event: Event, canvas_area: &CanvasArea) {
             }
             return;
         }
+        AppMode::RevertConfirm => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                match code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => app.revert_to_saved(),
+                    _ => app.mode = AppMode::Normal,
+                }
+            }
+            return;
+        }
         AppMode::Recovery => {
             if let Event::Key(KeyEvent { code, .. }) = event {
                 match code {
                     KeyCode::Char('y') | KeyCode::Char('Y') => {
                         app.recover_autosave();
                     }
-                    _ => {
-                        app.recovery_path = None;
-                        app.mode = AppMode::Normal;
-                    }
+                    _ => app.decline_recovery(),
                 }
             }
             return;
@@ -81,6 +142,25 @@ pub fn handle_event(app: &mut App, event: Event, canvas_area: &CanvasArea) {
             }
             return;
         }
+        AppMode::FileRename => {
+            if let Event::Key(key) = event {
+                handle_text_input(app, key, TextInputPurpose::FileRename);
+            }
+            return;
+        }
+        AppMode::FileDeleteConfirm => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                match code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        app.delete_selected_project();
+                    }
+                    _ => {
+                        app.mode = AppMode::FileDialog;
+                    }
+                }
+            }
+            return;
+        }
         AppMode::ExportDialog => {
             if let Event::Key(KeyEvent { code, .. }) = event {
                 handle_export_dialog(app, code);
@@ -111,12 +191,60 @@ pub fn handle_event(app: &mut App, event: Event, canvas_area: &CanvasArea) {
             }
             return;
         }
+        AppMode::ImportDialog => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_import_dialog(app, code);
+            }
+            return;
+        }
+        AppMode::HistoryScrubber => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_history_scrubber(app, code);
+            }
+            return;
+        }
+        AppMode::SymmetryPicker => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_symmetry_picker(app, code);
+            }
+            return;
+        }
+        AppMode::Versions => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_versions(app, code);
+            }
+            return;
+        }
+        AppMode::ResizeCanvas => {
+            if let Event::Key(key) = event {
+                handle_resize_canvas(app, key);
+            }
+            return;
+        }
+        AppMode::Inspector => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_inspector(app, code);
+            }
+            return;
+        }
         AppMode::PaletteNameInput => {
             if let Event::Key(key) = event {
                 handle_text_input(app, key, TextInputPurpose::PaletteName);
             }
             return;
         }
+        AppMode::ReleaseNotes => {
+            if let Event::Key(KeyEvent { code, .. }) = event {
+                handle_release_notes(app, code);
+            }
+            return;
+        }
+        AppMode::LospecSlugInput => {
+            if let Event::Key(key) = event {
+                handle_text_input(app, key, TextInputPurpose::LospecSlug);
+            }
+            return;
+        }
         AppMode::PaletteRename => {
             if let Event::Key(key) = event {
                 handle_text_input(app, key, TextInputPurpose::PaletteRename);
@@ -129,6 +257,18 @@ pub fn handle_event(app: &mut App, event: Event, canvas_area: &CanvasArea) {
             }
             return;
         }
+        AppMode::RunInput => {
+            if let Event::Key(key) = event {
+                handle_text_input(app, key, TextInputPurpose::Run);
+            }
+            return;
+        }
+        AppMode::RectSizeInput => {
+            if let Event::Key(key) = event {
+                handle_text_input(app, key, TextInputPurpose::RectSize);
+            }
+            return;
+        }
         AppMode::NewCanvas => {
             if let Event::Key(KeyEvent { code, .. }) = event {
                 handle_new_canvas(app, code);
@@ -159,319 +299,187 @@ pub fn handle_event(app: &mut App, event: Event, canvas_area: &CanvasArea) {
 }
 
 fn handle_key(app: &mut App, key: KeyEvent) {
-    // Ctrl combinations
-    if key.modifiers.contains(KeyModifiers::CONTROL) {
-        match key.code {
-            KeyCode::Char('z') => {
-                app.undo();
-                return;
-            }
-            KeyCode::Char('y') => {
-                app.redo();
-                return;
-            }
-            KeyCode::Char('s') => {
-                // Save
-                if !app.save_project() {
-                    // No path set — prompt for name
-                    app.text_input = app
-                        .project_name
-                        .clone()
-                        .unwrap_or_else(|| "untitled".to_string());
-                    app.mode = AppMode::SaveAs;
+    if let Some(cmd) = key_command(app, key) {
+        app.execute(cmd);
+    }
+}
+
+/// Map a normal-mode key press to a command through the app's keymap. Some
+/// keys depend on state: A/S move the canvas cursor only while it is active.
+pub fn key_command(app: &App, key: KeyEvent) -> Option<Command> {
+    app.keymap.lookup(key.code, key.modifiers, app.canvas_cursor_active)
+}
+
+/// Help overlay: arrows and paging keys scroll, `/` searches, any other
+/// key closes it.
+fn handle_help(app: &mut App, code: KeyCode) {
+    let page = app.help_page.get().max(1) as isize;
+    match code {
+        KeyCode::Up => app.scroll_help(-1),
+        KeyCode::Down => app.scroll_help(1),
+        KeyCode::PageUp => app.scroll_help(-page),
+        KeyCode::PageDown => app.scroll_help(page),
+        KeyCode::Home => app.help_scroll = 0,
+        KeyCode::End => app.scroll_help(isize::MAX),
+        _ if app.help_searching => {
+            match code {
+                KeyCode::Char(c) => app.help_query.push(c),
+                KeyCode::Backspace => {
+                    app.help_query.pop();
                 }
-                return;
-            }
-            KeyCode::Char('o') => {
-                // Open file dialog
-                app.open_file_dialog();
-                return;
-            }
-            KeyCode::Char('n') => {
-                // New canvas dialog
-                app.new_canvas_width = app.canvas.width;
-                app.new_canvas_height = app.canvas.height;
-                app.new_canvas_cursor = 0;
-                app.mode = AppMode::NewCanvas;
-                return;
-            }
-            KeyCode::Char('t') => {
-                app.cycle_theme();
-                return;
-            }
-            KeyCode::Char('e') => {
-                // Export dialog
-                app.export_format = 0;
-                app.export_dest = 0;
-                app.export_cursor = 0;
-                app.export_color_format = 0;
-                app.mode = AppMode::ExportDialog;
-                return;
-            }
-            KeyCode::Char('c') => {
-                if app.dirty {
-                    app.mode = AppMode::Quitting;
-                    app.set_status("Unsaved changes. Quit? (y/n)");
-                } else {
-                    app.running = false;
+                KeyCode::Esc => {
+                    app.help_query.clear();
+                    app.help_searching = false;
                 }
-                return;
+                KeyCode::Enter => app.help_searching = false,
+                _ => {}
             }
-            _ => return,
+            app.help_scroll = 0;
         }
+        KeyCode::Char('/') => app.help_searching = true,
+        _ => app.mode = AppMode::Normal,
     }
+}
 
-    match key.code {
-        // Tool selection
-        KeyCode::Char('p') | KeyCode::Char('P') => {
-            app.active_tool = ToolKind::Pencil;
-            app.cancel_tool();
-        }
-        KeyCode::Char('e') | KeyCode::Char('E') => {
-            app.active_tool = ToolKind::Eraser;
-            app.cancel_tool();
-        }
-        KeyCode::Char('l') | KeyCode::Char('L') => {
-            app.active_tool = ToolKind::Line;
-            app.cancel_tool();
-        }
-        KeyCode::Char('r') | KeyCode::Char('R') => {
-            app.active_tool = ToolKind::Rectangle;
-            app.cancel_tool();
-        }
-        KeyCode::Char('f') | KeyCode::Char('F') => {
-            app.active_tool = ToolKind::Fill;
-            app.cancel_tool();
-        }
-        KeyCode::Char('i') | KeyCode::Char('I') => {
-            app.active_tool = ToolKind::Eyedropper;
-            app.cancel_tool();
-        }
-
-        // Symmetry
-        KeyCode::Char('h') | KeyCode::Char('H') => {
-            app.symmetry = app.symmetry.toggle_horizontal();
-            app.set_status(&format!("Symmetry: {}", app.symmetry.label()));
-        }
-        KeyCode::Char('v') | KeyCode::Char('V') => {
-            app.symmetry = app.symmetry.toggle_vertical();
-            app.set_status(&format!("Symmetry: {}", app.symmetry.label()));
-        }
-
-        // Zoom cycle
-        KeyCode::Char('z') | KeyCode::Char('Z') => {
-            app.cycle_zoom();
-        }
-
-        // Quick color pick: 1-9 → curated palette slots 0-8, 0 → slot 9
-        KeyCode::Char(c @ '1'..='9') => {
-            let n = (c as u8 - b'1') as usize;
-            app.quick_pick_color(n);
-        }
-        KeyCode::Char('0') => {
-            app.quick_pick_color(9);
-        }
+/// Filter typing and sort cycling shared by the file-listing dialogs.
+/// Enter, Down or Esc stop typing but keep the filter, so the dialog's
+/// shortcuts work on the filtered list; Esc again clears it.
+/// Returns true if the key was used and the list needs refreshing.
+fn handle_list_view(view: &mut ListView, code: KeyCode) -> bool {
+    if view.filtering {
+        match code {
+            KeyCode::Char(c) => view.filter.push(c),
+            KeyCode::Backspace => {
+                view.filter.pop();
+            }
+            KeyCode::Enter | KeyCode::Down | KeyCode::Esc => view.filtering = false,
+            _ => return false,
+        }
+        return true;
+    }
+    match code {
+        KeyCode::Char('/') => view.filtering = true,
+        KeyCode::Char('s') | KeyCode::Char('S') => view.sort = view.sort.next(),
+        KeyCode::Esc if !view.filter.is_empty() => view.clear_filter(),
+        _ => return false,
+    }
+    true
+}
 
-        // Palette navigation (uses palette_layout)
-        KeyCode::Up => {
-            if app.palette_cursor > 0 {
-                app.palette_cursor -= 1;
-                if let Some(PaletteItem::Color(color)) = app.palette_layout.get(app.palette_cursor) {
-                    app.color = *color;
-                }
-                app.ensure_palette_cursor_visible(15);
-            }
+fn handle_file_dialog(app: &mut App, code: KeyCode) {
+    if handle_list_view(&mut app.file_dialog_view, code) {
+        app.file_dialog_selected = 0;
+        app.refresh_file_dialog();
+        return;
+    }
+    match code {
+        KeyCode::Up if app.file_dialog_selected > 0 => {
+            app.file_dialog_selected -= 1;
         }
-        KeyCode::Down => {
-            if app.palette_cursor + 1 < app.palette_layout.len() {
-                app.palette_cursor += 1;
-                if let Some(PaletteItem::Color(color)) = app.palette_layout.get(app.palette_cursor) {
-                    app.color = *color;
-                }
-                app.ensure_palette_cursor_visible(15);
-            }
+        KeyCode::Down if app.file_dialog_selected + 1 < app.file_dialog_files.len() => {
+            app.file_dialog_selected += 1;
         }
-        KeyCode::Left => {
-            if app.palette_cursor >= 6 {
-                app.palette_cursor -= 6;
-                if let Some(PaletteItem::Color(color)) = app.palette_layout.get(app.palette_cursor) {
-                    app.color = *color;
-                }
-                app.ensure_palette_cursor_visible(15);
+        KeyCode::Enter => {
+            if let Some(filename) = app.file_dialog_files.get(app.file_dialog_selected).cloned() {
+                app.mode = AppMode::Normal;
+                app.execute(Command::OpenFile(filename));
             }
         }
-        KeyCode::Right => {
-            if app.palette_cursor + 6 < app.palette_layout.len() {
-                app.palette_cursor += 6;
-                if let Some(PaletteItem::Color(color)) = app.palette_layout.get(app.palette_cursor) {
-                    app.color = *color;
-                }
-                app.ensure_palette_cursor_visible(15);
+        KeyCode::Char('t') | KeyCode::Char('T') => {
+            if let Some(filename) = app.file_dialog_files.get(app.file_dialog_selected).cloned() {
+                app.mode = AppMode::Normal;
+                app.execute(Command::OpenFileInTab(filename));
             }
         }
-        // Enter on palette: toggle section header or select color
-        KeyCode::Enter => {
-            if let Some(item) = app.palette_layout.get(app.palette_cursor).copied() {
-                match item {
-                    PaletteItem::SectionHeader(section) => {
-                        match section {
-                            PaletteSection::Standard => {
-                                app.palette_sections.standard_expanded = !app.palette_sections.standard_expanded;
-                            }
-                            PaletteSection::HueGroups => {
-                                app.palette_sections.hue_expanded = !app.palette_sections.hue_expanded;
-                            }
-                            PaletteSection::Grayscale => {
-                                app.palette_sections.grayscale_expanded = !app.palette_sections.grayscale_expanded;
-                            }
-                        }
-                        app.rebuild_palette_layout();
-                        // Clamp cursor if layout shrank
-                        if app.palette_cursor >= app.palette_layout.len() {
-                            app.palette_cursor = app.palette_layout.len().saturating_sub(1);
-                        }
-                    }
-                    PaletteItem::Color(color) => {
-                        app.color = color;
-                    }
-                }
+        KeyCode::Char('r') | KeyCode::Char('R') if !app.file_dialog_files.is_empty() => {
+            if let Some(filename) = app.file_dialog_files.get(app.file_dialog_selected) {
+                app.text_input = filename[..filename.len() - ".kaku".len()].to_string();
             }
+            app.mode = AppMode::FileRename;
         }
-
-        // WASD canvas navigation
-        KeyCode::Char('w') | KeyCode::Char('W') => {
-            app.canvas_cursor.1 = app.canvas_cursor.1.saturating_sub(1);
-            app.canvas_cursor_active = true;
-            let (cx, cy) = app.canvas_cursor;
-            app.ensure_cursor_in_viewport(cx, cy, app.viewport_w, app.viewport_h);
+        KeyCode::Char('u') | KeyCode::Char('U') => {
+            app.duplicate_selected_project();
         }
-        KeyCode::Char('d') | KeyCode::Char('D') => {
-            app.canvas_cursor.0 = (app.canvas_cursor.0 + 1).min(app.canvas.width.saturating_sub(1));
-            app.canvas_cursor_active = true;
-            let (cx, cy) = app.canvas_cursor;
-            app.ensure_cursor_in_viewport(cx, cy, app.viewport_w, app.viewport_h);
-        }
-        KeyCode::Char(' ') => {
-            if app.canvas_cursor_active {
-                let (x, y) = app.canvas_cursor;
-                if matches!(app.active_tool, ToolKind::Pencil | ToolKind::Eraser) {
-                    app.begin_stroke();
-                }
-                app.apply_tool(x, y);
-                if matches!(app.active_tool, ToolKind::Pencil | ToolKind::Eraser) {
-                    app.end_stroke();
-                }
-            }
+        KeyCode::Char('i') | KeyCode::Char('I') => {
+            app.mode = AppMode::Normal;
+            app.execute(Command::OpenImportDialog);
         }
-
-        // S key: canvas down if active, otherwise HSL sliders
-        KeyCode::Char('s') | KeyCode::Char('S') => {
-            if app.canvas_cursor_active {
-                app.canvas_cursor.1 = (app.canvas_cursor.1 + 1).min(app.canvas.height.saturating_sub(1));
-                let (cx, cy) = app.canvas_cursor;
-                app.ensure_cursor_in_viewport(cx, cy, app.viewport_w, app.viewport_h);
-            } else {
-                let (h, s, l) = crate::palette::rgb_to_hsl(app.color.r, app.color.g, app.color.b);
-                app.slider_h = h;
-                app.slider_s = s;
-                app.slider_l = l;
-                app.slider_active = 0;
-                app.mode = AppMode::ColorSliders;
-            }
+        KeyCode::Char('d') | KeyCode::Char('D') if !app.file_dialog_files.is_empty() => {
+            app.mode = AppMode::FileDeleteConfirm;
         }
-
-        // A key: canvas left if active, otherwise add to palette
-        KeyCode::Char('a') | KeyCode::Char('A') => {
-            if app.canvas_cursor_active {
-                app.canvas_cursor.0 = app.canvas_cursor.0.saturating_sub(1);
-                let (cx, cy) = app.canvas_cursor;
-                app.ensure_cursor_in_viewport(cx, cy, app.viewport_w, app.viewport_h);
+        // Only a deleted project; a new canvas or palette change undoes from
+        // the editor, where its effect shows
+        KeyCode::Char('z') | KeyCode::Char('Z') => {
+            if matches!(app.last_file_op, Some(FileOp::ProjectDeleted { .. })) {
+                app.undo_file_op();
             } else {
-                app.add_color_to_custom_palette();
+                app.set_status("Nothing to undo");
             }
         }
-
-        // Custom palette dialog
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            app.open_palette_dialog();
+        KeyCode::Esc => {
+            app.mode = AppMode::Normal;
         }
+        _ => {}
+    }
+}
 
-        // Cycle block character type
-        KeyCode::Char('b') => {
-            app.cycle_block();
+fn handle_import_dialog(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up if app.import_dialog_selected > 0 => {
+            app.import_dialog_selected -= 1;
         }
-        KeyCode::Char('B') => {
-            app.open_block_picker();
+        KeyCode::Down if app.import_dialog_selected + 1 < app.import_dialog_files.len() => {
+            app.import_dialog_selected += 1;
         }
-
-        // Shade cycle (G key)
-        KeyCode::Char('g') | KeyCode::Char('G') => {
-            app.cycle_shade();
-        }
-
-        // Toggle filled/outline rectangle
-        KeyCode::Char('t') | KeyCode::Char('T') => {
-            app.filled_rect = !app.filled_rect;
-            app.set_status(if app.filled_rect { "Rect: Filled" } else { "Rect: Outline" });
+        KeyCode::Char('d') | KeyCode::Char('D') => {
+            app.import_dither = !app.import_dither;
         }
+        KeyCode::Enter => app.import_selected_image(),
+        KeyCode::Esc => app.mode = AppMode::Normal,
+        _ => {}
+    }
+}
 
-        // Hex color input dialog
-        KeyCode::Char('x') | KeyCode::Char('X') => {
-            app.text_input = String::new();
-            app.mode = AppMode::HexColorInput;
-        }
+fn handle_history_scrubber(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Left => app.scrub_history(-1),
+        KeyCode::Right => app.scrub_history(1),
+        KeyCode::Home => app.scrub_history(-(app.history.position() as isize)),
+        KeyCode::End => app.scrub_history(app.history.len() as isize),
+        KeyCode::Enter => app.finish_history_scrub(true),
+        KeyCode::Esc => app.finish_history_scrub(false),
+        _ => {}
+    }
+}
 
-        // Cancel multi-click tool / deactivate canvas cursor
-        KeyCode::Esc => {
-            if app.canvas_cursor_active {
-                app.canvas_cursor_active = false;
-                app.set_status("Canvas cursor off");
-            } else {
-                app.cancel_tool();
-                app.set_status("Cancelled");
-            }
+fn handle_symmetry_picker(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up if app.symmetry_picker_selected > 0 => {
+            app.symmetry_picker_selected -= 1;
         }
-
-        // Help
-        KeyCode::Char('?') => {
-            app.mode = AppMode::Help;
+        KeyCode::Down if app.symmetry_picker_selected + 1 < SymmetryMode::ALL.len() => {
+            app.symmetry_picker_selected += 1;
         }
-
-        // Quit
-        KeyCode::Char('q') | KeyCode::Char('Q') => {
-            if app.dirty {
-                app.mode = AppMode::Quitting;
-                app.set_status("Unsaved changes. Quit? (y/n)");
-            } else {
-                app.running = false;
-            }
+        KeyCode::Enter => {
+            app.symmetry = SymmetryMode::ALL[app.symmetry_picker_selected];
+            app.mode = AppMode::Normal;
+            app.set_status(&format!("Symmetry: {}", app.symmetry.label()));
         }
-
+        KeyCode::Esc => app.mode = AppMode::Normal,
         _ => {}
     }
 }
 
-fn handle_file_dialog(app: &mut App, code: KeyCode) {
+fn handle_versions(app: &mut App, code: KeyCode) {
     match code {
-        KeyCode::Up => {
-            if app.file_dialog_selected > 0 {
-                app.file_dialog_selected -= 1;
-            }
-        }
-        KeyCode::Down => {
-            if app.file_dialog_selected + 1 < app.file_dialog_files.len() {
-                app.file_dialog_selected += 1;
-            }
-        }
-        KeyCode::Enter => {
-            if let Some(filename) = app.file_dialog_files.get(app.file_dialog_selected).cloned() {
-                app.mode = AppMode::Normal;
-                app.load_project(&filename);
-            }
+        KeyCode::Up if app.versions_selected > 0 => {
+            app.versions_selected -= 1;
         }
-        KeyCode::Esc => {
-            app.mode = AppMode::Normal;
+        KeyCode::Down if app.versions_selected + 1 < app.versions.len() => {
+            app.versions_selected += 1;
         }
+        KeyCode::Enter => app.restore_version(),
+        KeyCode::Esc => app.mode = AppMode::Normal,
         _ => {}
     }
 }
@@ -481,22 +489,22 @@ fn handle_export_dialog(app: &mut App, code: KeyCode) {
     let max_row = if app.export_format == 1 { 2 } else { 1 };
 
     match code {
-        KeyCode::Up => {
-            if app.export_cursor > 0 {
-                app.export_cursor -= 1;
-            }
+        KeyCode::Up if app.export_cursor > 0 => {
+            app.export_cursor -= 1;
         }
-        KeyCode::Down => {
-            if app.export_cursor < max_row {
-                app.export_cursor += 1;
-            }
+        KeyCode::Down if app.export_cursor < max_row => {
+            app.export_cursor += 1;
         }
         KeyCode::Left | KeyCode::Right => {
             if app.export_cursor == 0 {
-                // Toggle format: PlainText <-> ANSI
-                app.export_format = 1 - app.export_format;
-                // Clamp cursor when switching from ANSI to plain text
-                if app.export_format == 0 && app.export_cursor > 1 {
+                // Cycle format: PlainText, ANSI, HTML, Markdown, Markdown with HTML
+                if code == KeyCode::Right {
+                    app.export_format = (app.export_format + 1) % 5;
+                } else {
+                    app.export_format = (app.export_format + 4) % 5;
+                }
+                // Clamp cursor when switching away from ANSI
+                if app.export_format != 1 && app.export_cursor > 1 {
                     app.export_cursor = 1;
                 }
             } else if app.export_format == 1 && app.export_cursor == 1 {
@@ -506,11 +514,30 @@ fn handle_export_dialog(app: &mut App, code: KeyCode) {
                 } else {
                     app.export_color_format = (app.export_color_format + 2) % 3;
                 }
+                if app.export_analysis.is_some() {
+                    app.analyze_export_depth();
+                }
             } else {
                 // Dest row
                 app.export_dest = 1 - app.export_dest;
             }
         }
+        KeyCode::Char('w') | KeyCode::Char('W') if app.export_format == 0 => {
+            app.export_double_width = !app.export_double_width;
+        }
+        KeyCode::Char('d') | KeyCode::Char('D') if app.export_format == 1 => {
+            app.export_dither = !app.export_dither;
+        }
+        KeyCode::Char('b') | KeyCode::Char('B') if app.export_format == 1 => {
+            app.cycle_export_transparency();
+        }
+        KeyCode::Char('c') | KeyCode::Char('C') if app.export_format == 1 => {
+            app.export_ansi.cursor_forward = !app.export_ansi.cursor_forward;
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') if app.export_format == 1 => {
+            app.analyze_export_depth();
+        }
+        KeyCode::Char('g') | KeyCode::Char('G') => app.start_gif_export(),
         KeyCode::Enter => {
             app.do_export();
         }
@@ -527,6 +554,10 @@ enum TextInputPurpose {
     PaletteName,
     PaletteRename,
     PaletteExport,
+    LospecSlug,
+    FileRename,
+    Run,
+    RectSize,
 }
 
 fn handle_text_input(app: &mut App, key: KeyEvent, purpose: TextInputPurpose) {
@@ -534,7 +565,11 @@ fn handle_text_input(app: &mut App, key: KeyEvent, purpose: TextInputPurpose) {
         KeyCode::Enter => {
             let input = app.text_input.clone();
             if input.trim().is_empty() {
-                app.set_status("Name cannot be empty");
+                app.set_status(match purpose {
+                    TextInputPurpose::Run => "Type a count and direction, e.g. 8d",
+                    TextInputPurpose::RectSize => "Type a size, e.g. 12x5",
+                    _ => "Name cannot be empty",
+                });
                 return;
             }
             match purpose {
@@ -554,6 +589,25 @@ fn handle_text_input(app: &mut App, key: KeyEvent, purpose: TextInputPurpose) {
                 TextInputPurpose::PaletteExport => {
                     app.export_selected_palette(input.trim());
                 }
+                TextInputPurpose::LospecSlug => {
+                    app.download_lospec_palette(input.trim());
+                }
+                TextInputPurpose::FileRename => {
+                    app.rename_selected_project(input.trim());
+                }
+                TextInputPurpose::Run | TextInputPurpose::RectSize => {
+                    let parsed = match purpose {
+                        TextInputPurpose::Run => crate::command::parse_run(&input),
+                        _ => crate::command::parse_rect_size(&input),
+                    };
+                    match parsed {
+                        Ok(cmd) => {
+                            app.mode = AppMode::Normal;
+                            app.execute(cmd);
+                        }
+                        Err(e) => app.set_status(&e),
+                    }
+                }
             }
         }
         KeyCode::Esc => {
@@ -562,10 +616,8 @@ fn handle_text_input(app: &mut App, key: KeyEvent, purpose: TextInputPurpose) {
         KeyCode::Backspace => {
             app.text_input.pop();
         }
-        KeyCode::Char(c) => {
-            if app.text_input.len() < 64 {
-                app.text_input.push(c);
-            }
+        KeyCode::Char(c) if app.text_input.len() < 64 => {
+            app.text_input.push(c);
         }
         _ => {}
     }
@@ -573,15 +625,11 @@ fn handle_text_input(app: &mut App, key: KeyEvent, purpose: TextInputPurpose) {
 
 fn handle_color_sliders(app: &mut App, code: KeyCode) {
     match code {
-        KeyCode::Up => {
-            if app.slider_active > 0 {
-                app.slider_active -= 1;
-            }
+        KeyCode::Up if app.slider_active > 0 => {
+            app.slider_active -= 1;
         }
-        KeyCode::Down => {
-            if app.slider_active < 2 {
-                app.slider_active += 1;
-            }
+        KeyCode::Down if app.slider_active < 2 => {
+            app.slider_active += 1;
         }
         KeyCode::Left => {
             match app.slider_active {
@@ -600,7 +648,7 @@ fn handle_color_sliders(app: &mut App, code: KeyCode) {
         KeyCode::Enter => {
             let (r, g, b) = crate::palette::hsl_to_rgb(app.slider_h, app.slider_s, app.slider_l);
             let color = crate::palette::nearest_color(r, g, b);
-            app.color = color;
+            app.execute(Command::SetColor(color));
             app.mode = AppMode::Normal;
             app.set_status(&format!("Color: {}", color.name()));
         }
@@ -612,16 +660,21 @@ fn handle_color_sliders(app: &mut App, code: KeyCode) {
 }
 
 fn handle_palette_dialog(app: &mut App, code: KeyCode) {
+    if handle_list_view(&mut app.palette_dialog_view, code) {
+        app.palette_dialog_selected = 0;
+        app.refresh_palette_dialog();
+        return;
+    }
+    if app.palette_dialog_trash {
+        handle_palette_trash(app, code);
+        return;
+    }
     match code {
-        KeyCode::Up => {
-            if app.palette_dialog_selected > 0 {
-                app.palette_dialog_selected -= 1;
-            }
+        KeyCode::Up if app.palette_dialog_selected > 0 => {
+            app.palette_dialog_selected -= 1;
         }
-        KeyCode::Down => {
-            if app.palette_dialog_selected + 1 < app.palette_dialog_files.len() {
-                app.palette_dialog_selected += 1;
-            }
+        KeyCode::Down if app.palette_dialog_selected + 1 < app.palette_dialog_files.len() => {
+            app.palette_dialog_selected += 1;
         }
         KeyCode::Enter => {
             app.load_selected_palette();
@@ -633,25 +686,40 @@ fn handle_palette_dialog(app: &mut App, code: KeyCode) {
         KeyCode::Char('d') | KeyCode::Char('D') => {
             app.delete_selected_palette();
         }
-        KeyCode::Char('r') | KeyCode::Char('R') => {
-            if !app.palette_dialog_files.is_empty() {
-                // Pre-fill with current name (without .palette extension)
-                if let Some(filename) = app.palette_dialog_files.get(app.palette_dialog_selected) {
-                    app.text_input = filename.trim_end_matches(".palette").to_string();
-                }
-                app.mode = AppMode::PaletteRename;
+        KeyCode::Char('r') | KeyCode::Char('R') if !app.palette_dialog_files.is_empty() => {
+            // Pre-fill with current name (without .palette extension)
+            if let Some(filename) = app.palette_dialog_files.get(app.palette_dialog_selected) {
+                app.text_input = filename.trim_end_matches(".palette").to_string();
             }
+            app.mode = AppMode::PaletteRename;
         }
         KeyCode::Char('u') | KeyCode::Char('U') => {
             app.duplicate_selected_palette();
         }
-        KeyCode::Char('x') | KeyCode::Char('X') => {
-            if !app.palette_dialog_files.is_empty() {
-                if let Some(filename) = app.palette_dialog_files.get(app.palette_dialog_selected) {
-                    app.text_input = filename.clone();
-                }
-                app.mode = AppMode::PaletteExport;
+        KeyCode::Char('z') | KeyCode::Char('Z') => {
+            let undone = app.undo_file_op();
+            if !undone {
+                app.set_status("Nothing to undo");
+            }
+        }
+        KeyCode::Char('x') | KeyCode::Char('X') if !app.palette_dialog_files.is_empty() => {
+            if let Some(filename) = app.palette_dialog_files.get(app.palette_dialog_selected) {
+                app.text_input = filename.clone();
             }
+            app.mode = AppMode::PaletteExport;
+        }
+        KeyCode::Char('t') | KeyCode::Char('T') => {
+            app.toggle_palette_trash();
+        }
+        KeyCode::Char('c') | KeyCode::Char('C') => {
+            app.share_selected_palette();
+        }
+        KeyCode::Char('v') | KeyCode::Char('V') => {
+            app.paste_shared_palette();
+        }
+        KeyCode::Char('l') | KeyCode::Char('L') => {
+            app.text_input = String::new();
+            app.mode = AppMode::LospecSlugInput;
         }
         KeyCode::Esc => {
             app.mode = AppMode::Normal;
@@ -660,6 +728,24 @@ fn handle_palette_dialog(app: &mut App, code: KeyCode) {
     }
 }
 
+fn handle_palette_trash(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up if app.palette_dialog_selected > 0 => {
+            app.palette_dialog_selected -= 1;
+        }
+        KeyCode::Down if app.palette_dialog_selected + 1 < app.palette_dialog_files.len() => {
+            app.palette_dialog_selected += 1;
+        }
+        KeyCode::Enter => {
+            app.restore_selected_palette();
+        }
+        KeyCode::Char('t') | KeyCode::Char('T') | KeyCode::Esc => {
+            app.toggle_palette_trash();
+        }
+        _ => {}
+    }
+}
+
 fn handle_new_canvas(app: &mut App, code: KeyCode) {
     use crate::canvas::{MIN_DIMENSION, MAX_DIMENSION};
 
@@ -682,21 +768,11 @@ fn handle_new_canvas(app: &mut App, code: KeyCode) {
             }
         }
         KeyCode::Enter => {
-            let w = app.new_canvas_width;
-            let h = app.new_canvas_height;
-            app.canvas = Canvas::new_with_size(w, h);
-            app.history = History::new();
-            app.dirty = false;
-            app.project_name = None;
-            app.project_path = None;
-            app.cursor = None;
-            app.canvas_cursor = (0, 0);
-            app.canvas_cursor_active = false;
-            app.viewport_x = 0;
-            app.viewport_y = 0;
-            app.tool_state = ToolState::Idle;
             app.mode = AppMode::Normal;
-            app.set_status(&format!("New canvas {}x{}", w, h));
+            app.execute(Command::NewCanvas {
+                width: app.new_canvas_width,
+                height: app.new_canvas_height,
+            });
         }
         KeyCode::Esc => {
             app.mode = AppMode::Normal;
@@ -705,13 +781,70 @@ fn handle_new_canvas(app: &mut App, code: KeyCode) {
     }
 }
 
+fn handle_resize_canvas(app: &mut App, key: KeyEvent) {
+    use crate::canvas::{Anchor, MAX_DIMENSION, MIN_DIMENSION};
+
+    // Shift steps sizes by 8
+    let step = if key.modifiers.contains(KeyModifiers::SHIFT) { 8 } else { 1 };
+    let delta = match key.code {
+        KeyCode::Left => -step,
+        KeyCode::Right => step,
+        _ => 0,
+    };
+    match key.code {
+        KeyCode::Up => app.resize_cursor = (app.resize_cursor + 2) % 3,
+        KeyCode::Down => app.resize_cursor = (app.resize_cursor + 1) % 3,
+        KeyCode::Left | KeyCode::Right => {
+            let adjust = |size: usize| (size as isize + delta).clamp(MIN_DIMENSION as isize, MAX_DIMENSION as isize) as usize;
+            match app.resize_cursor {
+                0 => app.resize_width = adjust(app.resize_width),
+                1 => app.resize_height = adjust(app.resize_height),
+                // Left and right walk the anchors in reading order
+                _ => {
+                    let i = (app.resize_anchor.y * 3 + app.resize_anchor.x) as isize;
+                    let i = (i + delta.signum()).clamp(0, 8) as u8;
+                    app.resize_anchor = Anchor { x: i % 3, y: i / 3 };
+                }
+            }
+        }
+        KeyCode::Char(c) if c.is_ascii_digit() => {
+            if let Some(anchor) = Anchor::from_keypad(c as u8 - b'0') {
+                app.resize_anchor = anchor;
+            }
+        }
+        KeyCode::Enter => {
+            app.mode = AppMode::Normal;
+            app.execute(Command::ResizeCanvas {
+                width: app.resize_width,
+                height: app.resize_height,
+                anchor: app.resize_anchor,
+            });
+        }
+        KeyCode::Esc => {
+            app.mode = AppMode::Normal;
+        }
+        _ => {}
+    }
+}
+
+fn handle_release_notes(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up => app.release_notes_scroll = app.release_notes_scroll.saturating_sub(1),
+        KeyCode::Down => app.release_notes_scroll = app.release_notes_scroll.saturating_add(1),
+        KeyCode::PageUp => app.release_notes_scroll = app.release_notes_scroll.saturating_sub(10),
+        KeyCode::PageDown => app.release_notes_scroll = app.release_notes_scroll.saturating_add(10),
+        KeyCode::Esc | KeyCode::Enter => app.mode = AppMode::Normal,
+        _ => {}
+    }
+}
+
 fn handle_hex_input(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Enter => {
             match crate::cell::parse_hex_color(&app.text_input) {
                 Some(rgb) => {
                     let matched = crate::palette::nearest_color(rgb.r, rgb.g, rgb.b);
-                    app.color = matched;
+                    app.execute(Command::SetColor(matched));
                     app.mode = AppMode::Normal;
                     app.set_status(&format!("Color: {} → {}", rgb.name(), matched.name()));
                 }
@@ -726,10 +859,38 @@ fn handle_hex_input(app: &mut App, key: KeyEvent) {
         KeyCode::Backspace => {
             app.text_input.pop();
         }
-        KeyCode::Char(c) => {
-            if app.text_input.len() < 7 {
-                app.text_input.push(c);
+        KeyCode::Char(c) if app.text_input.len() < 7 => {
+            app.text_input.push(c);
+        }
+        _ => {}
+    }
+}
+
+fn handle_inspector(app: &mut App, code: KeyCode) {
+    let field = &mut app.inspector_fields[app.inspector_field];
+    match code {
+        KeyCode::Tab | KeyCode::Down => {
+            app.inspector_field = (app.inspector_field + 1) % 3;
+        }
+        KeyCode::BackTab | KeyCode::Up => {
+            app.inspector_field = (app.inspector_field + 2) % 3;
+        }
+        KeyCode::Enter => match app.inspector_cell() {
+            Ok(cell) => {
+                let (x, y) = app.inspector_pos;
+                app.mode = AppMode::Normal;
+                app.execute(Command::EditCell { x, y, cell });
             }
+            Err(e) => app.set_status(&e),
+        },
+        KeyCode::Esc => {
+            app.mode = AppMode::Normal;
+        }
+        KeyCode::Backspace => {
+            field.pop();
+        }
+        KeyCode::Char(c) if field.chars().count() < 10 => {
+            field.push(c);
         }
         _ => {}
     }
@@ -741,10 +902,8 @@ fn handle_block_picker(app: &mut App, key: KeyEvent) {
     let num_rows = sizes.len();
 
     match key.code {
-        KeyCode::Left => {
-            if app.block_picker_col > 0 {
-                app.block_picker_col -= 1;
-            }
+        KeyCode::Left if app.block_picker_col > 0 => {
+            app.block_picker_col -= 1;
         }
         KeyCode::Right => {
             let max_col = sizes[app.block_picker_row].saturating_sub(1);
@@ -752,24 +911,20 @@ fn handle_block_picker(app: &mut App, key: KeyEvent) {
                 app.block_picker_col += 1;
             }
         }
-        KeyCode::Up => {
-            if app.block_picker_row > 0 {
-                app.block_picker_row -= 1;
-                // Clamp column to new row's width
-                let max_col = sizes[app.block_picker_row].saturating_sub(1);
-                if app.block_picker_col > max_col {
-                    app.block_picker_col = max_col;
-                }
+        KeyCode::Up if app.block_picker_row > 0 => {
+            app.block_picker_row -= 1;
+            // Clamp column to new row's width
+            let max_col = sizes[app.block_picker_row].saturating_sub(1);
+            if app.block_picker_col > max_col {
+                app.block_picker_col = max_col;
             }
         }
-        KeyCode::Down => {
-            if app.block_picker_row < num_rows - 1 {
-                app.block_picker_row += 1;
-                // Clamp column to new row's width
-                let max_col = sizes[app.block_picker_row].saturating_sub(1);
-                if app.block_picker_col > max_col {
-                    app.block_picker_col = max_col;
-                }
+        KeyCode::Down if app.block_picker_row < num_rows - 1 => {
+            app.block_picker_row += 1;
+            // Clamp column to new row's width
+            let max_col = sizes[app.block_picker_row].saturating_sub(1);
+            if app.block_picker_col > max_col {
+                app.block_picker_col = max_col;
             }
         }
         KeyCode::Enter => {
@@ -777,8 +932,7 @@ fn handle_block_picker(app: &mut App, key: KeyEvent) {
             let offset: usize = sizes[..app.block_picker_row].iter().sum();
             let idx = offset + app.block_picker_col;
             if idx < blocks::ALL.len() {
-                app.active_block = blocks::ALL[idx];
-                app.set_status(&format!("Block: {}", app.active_block));
+                app.execute(Command::SetBlock(blocks::ALL[idx]));
             }
             app.mode = AppMode::Normal;
         }
@@ -790,58 +944,56 @@ fn handle_block_picker(app: &mut App, key: KeyEvent) {
 }
 
 fn handle_mouse(app: &mut App, mouse: MouseEvent, canvas_area: &CanvasArea) {
-    let zoom = app.zoom;
-    let vp_x = app.viewport_x;
-    let vp_y = app.viewport_y;
+    if let Some(cmd) = mouse_command(app, mouse, canvas_area) {
+        app.execute(cmd);
+    }
+}
+
+/// Map a mouse event to a command, translating screen to canvas coordinates.
+pub fn mouse_command(app: &App, mouse: MouseEvent, canvas_area: &CanvasArea) -> Option<Command> {
+    // The minimap covers canvas cells; clicking it moves the view instead
+    if let Some((x, y)) = canvas_area.minimap_to_canvas(mouse.column, mouse.row) {
+        return match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                Some(Command::CenterView { x, y })
+            }
+            MouseEventKind::Up(MouseButton::Left) => Some(Command::Release),
+            MouseEventKind::Moved => Some(Command::Hover(None)),
+            _ => None,
+        };
+    }
+    let cell = canvas_area.screen_to_canvas(mouse.column, mouse.row, app.zoom, app.viewport_x, app.viewport_y);
+    // Drawing on a copy in the tile preview draws on the canvas
+    let tile_cell = cell.or_else(|| {
+        canvas_area.screen_to_tile(mouse.column, mouse.row, app.zoom, app.canvas.width, app.canvas.height)
+    });
     match mouse.kind {
         MouseEventKind::Down(MouseButton::Left) => {
-            if let Some((x, y)) = canvas_area.screen_to_canvas(mouse.column, mouse.row, zoom, vp_x, vp_y) {
-                app.cursor = Some((x, y));
-                app.canvas_cursor = (x, y);
-                app.canvas_cursor_active = false;
-                // Start stroke for continuous tools
-                if matches!(app.active_tool, ToolKind::Pencil | ToolKind::Eraser) {
-                    app.begin_stroke();
-                }
-                app.apply_tool(x, y);
-            }
+            let unmirrored = mouse.modifiers.contains(KeyModifiers::ALT);
+            tile_cell.map(|(x, y)| Command::Press { x, y, unmirrored })
         }
         MouseEventKind::Drag(MouseButton::Left) => {
-            if let Some((x, y)) = canvas_area.screen_to_canvas(mouse.column, mouse.row, zoom, vp_x, vp_y) {
-                app.cursor = Some((x, y));
-                if matches!(app.active_tool, ToolKind::Pencil | ToolKind::Eraser) {
-                    app.apply_tool(x, y);
-                }
-            }
-        }
-        MouseEventKind::Up(MouseButton::Left) => {
-            if app.history.is_stroke_active() {
-                app.end_stroke();
-            }
-        }
-        MouseEventKind::Down(MouseButton::Right) => {
-            // Quick eyedropper
-            if let Some((x, y)) = canvas_area.screen_to_canvas(mouse.column, mouse.row, zoom, vp_x, vp_y) {
-                if let Some((picked_fg, _bg, ch)) = crate::tools::eyedropper(&app.canvas, x, y) {
-                    if let Some(picked) = picked_fg {
-                        app.color = picked;
-                        app.set_status(&format!("Picked: {} {}", picked.name(), ch));
-                    }
-                    if ch != ' ' {
-                        app.active_block = ch;
-                    }
-                }
-            }
-        }
-        MouseEventKind::Moved => {
-            if let Some((x, y)) = canvas_area.screen_to_canvas(mouse.column, mouse.row, zoom, vp_x, vp_y) {
-                app.cursor = Some((x, y));
-                app.canvas_cursor_active = false;
-            } else {
-                app.cursor = None;
-            }
-        }
-        _ => {}
+            let locked = mouse.modifiers.contains(KeyModifiers::SHIFT);
+            tile_cell.map(|(x, y)| Command::Drag { x, y, locked })
+        }
+        MouseEventKind::Up(MouseButton::Left) => Some(Command::Release),
+        // Quick eyedropper
+        MouseEventKind::Down(MouseButton::Right) => tile_cell.map(|(x, y)| Command::PickAt { x, y }),
+        // Middle-drag pans the view
+        MouseEventKind::Down(MouseButton::Middle) if cell.is_some() => {
+            Some(Command::GrabView { column: mouse.column, row: mouse.row })
+        }
+        // The wheel zooms around the pointer
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => cell.map(|(x, y)| Command::ZoomAt {
+            x,
+            y,
+            column: mouse.column - canvas_area.left,
+            row: mouse.row - canvas_area.top,
+            step: if mouse.kind == MouseEventKind::ScrollUp { 1 } else { -1 },
+        }),
+        MouseEventKind::Drag(MouseButton::Middle) => Some(Command::DragView { column: mouse.column, row: mouse.row }),
+        MouseEventKind::Moved => Some(Command::Hover(tile_cell)),
+        _ => None,
     }
 }
 
@@ -850,7 +1002,7 @@ mod tests {
     use super::*;
 
     fn area() -> CanvasArea {
-        CanvasArea { left: 10, top: 5, width: 64, height: 32, viewport_w: 64, viewport_h: 32 }
+        CanvasArea { left: 10, top: 5, width: 64, height: 32, viewport_w: 64, viewport_h: 32, tiles: None, minimap: None }
     }
 
     #[test]
@@ -889,4 +1041,118 @@ mod tests {
         assert_eq!(a.screen_to_canvas(10, 5, 1, 10, 5), Some((10, 5)));
         assert_eq!(a.screen_to_canvas(14, 8, 1, 10, 5), Some((14, 8)));
     }
+
+    #[test]
+    fn test_list_view_keys() {
+        let mut view = ListView::default();
+        // Letters are dialog shortcuts until filtering starts
+        assert!(!handle_list_view(&mut view, KeyCode::Char('d')));
+        assert!(handle_list_view(&mut view, KeyCode::Char('s')));
+        assert_eq!(view.sort, crate::listing::SortOrder::Modified);
+
+        assert!(handle_list_view(&mut view, KeyCode::Char('/')));
+        for c in "dsx".chars() {
+            assert!(handle_list_view(&mut view, KeyCode::Char(c)));
+        }
+        assert!(handle_list_view(&mut view, KeyCode::Backspace));
+        assert_eq!(view.filter, "ds");
+        // Enter keeps the filter and frees the letters for shortcuts
+        assert!(handle_list_view(&mut view, KeyCode::Enter));
+        assert_eq!((view.filter.as_str(), view.filtering), ("ds", false));
+        assert!(!handle_list_view(&mut view, KeyCode::Char('d')));
+
+        assert!(handle_list_view(&mut view, KeyCode::Char('/')));
+        assert!(handle_list_view(&mut view, KeyCode::Down));
+        assert_eq!((view.filter.as_str(), view.filtering), ("ds", false));
+
+        // The first Esc stops typing, the second clears, the third is the dialog's
+        assert!(handle_list_view(&mut view, KeyCode::Char('/')));
+        assert!(handle_list_view(&mut view, KeyCode::Esc));
+        assert_eq!((view.filter.as_str(), view.filtering), ("ds", false));
+        assert!(handle_list_view(&mut view, KeyCode::Esc));
+        assert!(view.filter.is_empty() && !view.filtering);
+        assert!(!handle_list_view(&mut view, KeyCode::Esc));
+    }
+
+    #[test]
+    fn test_file_dialog_delete_asks_first() {
+        let mut app = App::new();
+        app.file_dialog_files = vec!["kaku_test_never_created.kaku".to_string()];
+        app.mode = AppMode::FileDialog;
+        let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        handle_event(&mut app, key('d'), &area());
+        assert_eq!(app.mode, AppMode::FileDeleteConfirm);
+        handle_event(&mut app, key('n'), &area());
+        assert_eq!(app.mode, AppMode::FileDialog);
+        assert_eq!(app.file_dialog_files.len(), 1);
+
+        handle_event(&mut app, key('r'), &area());
+        assert_eq!(app.mode, AppMode::FileRename);
+        assert_eq!(app.text_input, "kaku_test_never_created");
+    }
+
+    #[test]
+    fn test_file_dialog_z_only_restores_deleted_projects() {
+        let mut app = App::new();
+        app.new_canvas(16, 16);
+        app.mode = AppMode::FileDialog;
+        handle_event(&mut app, Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE)), &area());
+        assert_eq!(app.canvas.width, 16);
+        assert!(matches!(app.last_file_op, Some(FileOp::NewCanvas(_))));
+        assert_eq!(app.status_message.as_ref().unwrap().text, "Nothing to undo");
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16) -> Event {
+        Event::Mouse(MouseEvent { kind, column, row: 0, modifiers: KeyModifiers::NONE })
+    }
+
+    #[test]
+    fn test_key_release_ignored() {
+        let mut app = App::new();
+        let mut release = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        handle_event(&mut app, Event::Key(release), &area());
+        assert_eq!(app.mode, AppMode::Normal);
+
+        handle_event(&mut app, Event::Key(KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE)), &area());
+        assert_eq!(app.mode, AppMode::Help);
+    }
+
+    #[test]
+    fn test_altgr_is_not_ctrl() {
+        let app = App::new();
+        let ctrl_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(key_command(&app, ctrl_z), Some(Command::Undo));
+        let altgr_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert_eq!(key_command(&app, altgr_z), Some(Command::CycleZoom));
+    }
+
+    #[test]
+    fn test_coalesce_keeps_last_move() {
+        let events = vec![
+            mouse(MouseEventKind::Moved, 1),
+            mouse(MouseEventKind::Moved, 2),
+            mouse(MouseEventKind::Moved, 3),
+        ];
+        assert_eq!(coalesce_events(events), vec![mouse(MouseEventKind::Moved, 3)]);
+    }
+
+    #[test]
+    fn test_coalesce_keeps_drags_and_keys() {
+        let drag = |c| mouse(MouseEventKind::Drag(MouseButton::Left), c);
+        let key = Event::Key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE));
+        let events = vec![
+            drag(1),
+            drag(2),
+            mouse(MouseEventKind::Moved, 3),
+            key.clone(),
+            mouse(MouseEventKind::Moved, 4),
+            Event::Resize(80, 24),
+            Event::Resize(100, 40),
+        ];
+        assert_eq!(
+            coalesce_events(events),
+            vec![drag(1), drag(2), mouse(MouseEventKind::Moved, 3), key, mouse(MouseEventKind::Moved, 4), Event::Resize(100, 40)]
+        );
+    }
 }
@@ -0,0 +1,139 @@
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::canvas::Canvas;
+use crate::cell::Cell;
+
+/// One line of the crash-recovery journal. A journal opens with a snapshot
+/// of the canvas and then records every cell the editor changes, so it can
+/// be replayed on its own after a crash.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum Entry {
+    Snapshot(Canvas),
+    Cells(Vec<(usize, usize, Cell)>),
+}
+
+/// `art.kaku` -> `art.kaku.journal`
+pub fn journal_path(kaku_path: &Path) -> PathBuf {
+    let mut p = kaku_path.as_os_str().to_os_string();
+    p.push(".journal");
+    PathBuf::from(p)
+}
+
+/// The project path a journal belongs to, or None if `journal` isn't one.
+pub fn journal_target(journal: &Path) -> Option<PathBuf> {
+    let name = journal.file_name()?.to_str()?;
+    if name.len() <= ".journal".len() || !name.to_ascii_lowercase().ends_with(".journal") {
+        return None;
+    }
+    Some(journal.with_file_name(&name[..name.len() - ".journal".len()]))
+}
+
+/// Find a leftover journal in a directory.
+pub fn find_journal(dir: &Path) -> Option<String> {
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        if let Some(name) = entry.file_name().to_str() {
+            if name.to_ascii_lowercase().ends_with(".kaku.journal") {
+                return Some(name.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// An open journal. Entries are buffered and reach the disk on `sync`, so
+/// the editor can batch a whole frame of changes into one write and fsync.
+/// Dropping it writes out anything still buffered.
+pub struct Journal {
+    file: io::BufWriter<std::fs::File>,
+    unsynced: bool,
+}
+
+impl Journal {
+    /// Start a journal at `path`, replacing any old one, with a snapshot of
+    /// `canvas`. The snapshot is on disk before this returns.
+    pub fn create(path: &Path, canvas: &Canvas) -> io::Result<Journal> {
+        let mut journal = Journal {
+            file: io::BufWriter::new(std::fs::File::create(path)?),
+            unsynced: false,
+        };
+        journal.append(&Entry::Snapshot(canvas.clone()))?;
+        journal.sync()?;
+        Ok(journal)
+    }
+
+    /// Buffer an entry; it is written at the next `sync`.
+    pub fn append(&mut self, entry: &Entry) -> io::Result<()> {
+        let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+        writeln!(self.file, "{}", line)?;
+        self.unsynced = true;
+        Ok(())
+    }
+
+    /// Write out buffered entries and flush them to disk. Does nothing if
+    /// there are none.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if !self.unsynced {
+            return Ok(());
+        }
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.unsynced = false;
+        Ok(())
+    }
+}
+
+/// Rebuild the canvas a journal describes. A torn final line, left by a
+/// crash mid-write, is ignored.
+pub fn replay(path: &Path) -> io::Result<Canvas> {
+    let reader = io::BufReader::new(std::fs::File::open(path)?);
+    let mut canvas: Option<Canvas> = None;
+    for line in reader.lines() {
+        let line = line?;
+        let Ok(entry) = serde_json::from_str::<Entry>(&line) else {
+            break;
+        };
+        match (entry, canvas.as_mut()) {
+            (Entry::Snapshot(snapshot), _) => canvas = Some(snapshot),
+            (Entry::Cells(cells), Some(canvas)) => {
+                for (x, y, cell) in cells {
+                    canvas.set(x, y, cell);
+                }
+            }
+            (Entry::Cells(_), None) => break,
+        }
+    }
+    canvas.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Journal has no snapshot"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::blocks;
+
+    #[test]
+    fn test_replay_ignores_torn_tail() {
+        let dir = std::env::temp_dir().join("kaku_test_journal");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = journal_path(&dir.join("art.kaku"));
+        assert_eq!(journal_target(&path), Some(dir.join("art.kaku")));
+
+        let mut canvas = Canvas::new_with_size(8, 8);
+        let mut journal = Journal::create(&path, &canvas).unwrap();
+        let cell = Cell { ch: blocks::FULL, fg: None, bg: None };
+        journal.append(&Entry::Cells(vec![(1, 2, cell), (3, 4, cell)])).unwrap();
+        assert_eq!(replay(&path).unwrap(), canvas, "buffered until synced");
+        journal.sync().unwrap();
+        canvas.set(1, 2, cell);
+        canvas.set(3, 4, cell);
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap()
+            .write_all(b"{\"Cells\":[[5,5,").unwrap();
+
+        assert_eq!(replay(&path).unwrap(), canvas);
+        assert_eq!(find_journal(&dir).as_deref(), Some("art.kaku.journal"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
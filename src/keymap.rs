@@ -0,0 +1,682 @@
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::tools::ToolKind;
+
+/// A key as written in key maps and shown in help: "P", "Shift+B",
+/// "Ctrl+S", "↑" (or "Up"), "Space".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("↑", KeyCode::Up),
+    ("↓", KeyCode::Down),
+    ("←", KeyCode::Left),
+    ("→", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+impl KeyChord {
+    pub const fn key(c: char) -> Self {
+        KeyChord { code: KeyCode::Char(c), ctrl: false }
+    }
+
+    pub const fn ctrl(c: char) -> Self {
+        KeyChord { code: KeyCode::Char(c), ctrl: true }
+    }
+
+    pub const fn named(code: KeyCode) -> Self {
+        KeyChord { code, ctrl: false }
+    }
+
+    pub const fn ctrl_named(code: KeyCode) -> Self {
+        KeyChord { code, ctrl: true }
+    }
+
+    /// The chord a key event matches. Windows reports AltGr as Ctrl+Alt, so
+    /// a character typed with AltGr (e.g. `@` on German layouts) is not a
+    /// Ctrl shortcut. Ctrl+Shift+letter is an upper case letter, though
+    /// only terminals that report Shift with Ctrl tell it from Ctrl+letter.
+    pub fn from_event(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL) && !modifiers.contains(KeyModifiers::ALT);
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) if ctrl && shift => KeyCode::Char(c.to_ascii_uppercase()),
+            KeyCode::Char(c) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            other => other,
+        };
+        KeyChord { code, ctrl }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut rest = s;
+        let mut ctrl = false;
+        let mut shift = false;
+        loop {
+            if let Some(r) = strip_prefix_ignore_case(rest, "Ctrl+") {
+                ctrl = true;
+                rest = r;
+            } else if let Some(r) = strip_prefix_ignore_case(rest, "Shift+") {
+                shift = true;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+        if rest.eq_ignore_ascii_case("Space") {
+            return Ok(KeyChord { code: KeyCode::Char(' '), ctrl });
+        }
+        if let Some((_, code)) = NAMED_KEYS.iter().find(|(name, _)| name.eq_ignore_ascii_case(rest)) {
+            return Ok(KeyChord { code: *code, ctrl });
+        }
+        if let Some(n) = rest.strip_prefix(['F', 'f']).and_then(|n| n.parse::<u8>().ok()) {
+            if (1..=12).contains(&n) {
+                return Ok(KeyChord { code: KeyCode::F(n), ctrl });
+            }
+        }
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                // Letters are lower case unless written with Shift+
+                let c = if shift { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() };
+                Ok(KeyChord { code: KeyCode::Char(c), ctrl })
+            }
+            _ => Err(format!("Unknown key '{}'", s)),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) if c.is_ascii_uppercase() => format!("Shift+{}", c),
+            KeyCode::Char(c) => c.to_ascii_uppercase().to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => NAMED_KEYS
+                .iter()
+                .find(|(_, named)| *named == code)
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_else(|| format!("{:?}", code)),
+        };
+        if self.ctrl {
+            format!("Ctrl+{}", key)
+        } else {
+            key
+        }
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    // Keep "Shift+" alone as a key name rather than a prefix of nothing
+    if head.eq_ignore_ascii_case(prefix) && s.len() > prefix.len() {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> {
+        KeyChord::parse(&s)
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(k: KeyChord) -> String {
+        k.label()
+    }
+}
+
+/// When a binding applies. A and S share their keys between canvas cursor
+/// movement and color commands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum When {
+    #[default]
+    Always,
+    CursorActive,
+    CursorInactive,
+}
+
+impl When {
+    fn applies(self, cursor_active: bool) -> bool {
+        match self {
+            When::Always => true,
+            When::CursorActive => cursor_active,
+            When::CursorInactive => !cursor_active,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub key: KeyChord,
+    pub command: Command,
+    #[serde(default)]
+    pub when: When,
+}
+
+fn bind(key: KeyChord, command: Command) -> Binding {
+    Binding { key, command, when: When::Always }
+}
+
+fn bind_when(key: KeyChord, command: Command, when: When) -> Binding {
+    Binding { key, command, when }
+}
+
+/// Normal-mode key bindings. Letters match either case unless the upper
+/// case letter has a binding of its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Command::*;
+        let mut bindings = vec![
+            bind(KeyChord::key('p'), SelectTool(ToolKind::Pencil)),
+            bind(KeyChord::key('e'), SelectTool(ToolKind::Eraser)),
+            bind(KeyChord::key('l'), SelectTool(ToolKind::Line)),
+            bind(KeyChord::key('r'), SelectTool(ToolKind::Rectangle)),
+            bind(KeyChord::key('f'), SelectTool(ToolKind::Fill)),
+            bind(KeyChord::key('i'), SelectTool(ToolKind::Eyedropper)),
+            bind(KeyChord::key('y'), SelectTool(ToolKind::Spray)),
+            bind(KeyChord::key('t'), ToggleFilledRect),
+            bind(KeyChord::key('T'), CycleLineStyle),
+            bind(KeyChord::key('G'), CycleBrushPattern),
+            bind(KeyChord::key('L'), ToggleAxisLock),
+            bind(KeyChord::key('J'), TogglePixelPerfect),
+            bind(KeyChord::key('F'), CycleFillMode),
+            bind(KeyChord::key('b'), CycleBlock),
+            bind(KeyChord::key('B'), OpenBlockPicker),
+            bind(KeyChord::key('g'), CycleShade),
+            bind(KeyChord::key('w'), MoveCanvasCursor { dx: 0, dy: -1 }),
+            bind_when(KeyChord::key('a'), MoveCanvasCursor { dx: -1, dy: 0 }, When::CursorActive),
+            bind_when(KeyChord::key('s'), MoveCanvasCursor { dx: 0, dy: 1 }, When::CursorActive),
+            bind(KeyChord::key('d'), MoveCanvasCursor { dx: 1, dy: 0 }),
+            bind(KeyChord::key('W'), Pan { dx: 0, dy: -1 }),
+            bind(KeyChord::key('A'), Pan { dx: -1, dy: 0 }),
+            bind(KeyChord::key('S'), Pan { dx: 0, dy: 1 }),
+            bind(KeyChord::key('D'), Pan { dx: 1, dy: 0 }),
+            bind(KeyChord::key('o'), ToggleMinimap),
+            bind(KeyChord::key('E'), ToggleTilePreview),
+            bind_when(KeyChord::key(' '), ApplyAtCursor, When::CursorActive),
+            bind(KeyChord::key('n'), OpenRunPrompt),
+            bind(KeyChord::key('N'), OpenRectSizePrompt),
+            bind(KeyChord::key('m'), SelectActiveBlock),
+            bind(KeyChord::key(']'), ShadeSelection(1)),
+            bind(KeyChord::key('['), ShadeSelection(-1)),
+            bind(KeyChord::key('M'), RecolorSelection),
+            bind(KeyChord::ctrl('f'), FindColor),
+            bind(KeyChord::key('>'), JumpToSelected(1)),
+            bind(KeyChord::key('<'), JumpToSelected(-1)),
+            bind(KeyChord::key('I'), SwapCellColors),
+            bind(KeyChord::key('C'), OpenInspector),
+            bind(KeyChord::named(KeyCode::Delete), EraseSelection),
+            bind(KeyChord::key('Y'), Copy),
+            bind(KeyChord::ctrl('v'), Paste),
+            bind(KeyChord::named(KeyCode::Esc), Cancel),
+            bind(KeyChord::key('z'), CycleZoom),
+        ];
+        // 1-9 pick curated slots 0-8, 0 picks slot 9
+        for (i, c) in "1234567890".chars().enumerate() {
+            bindings.push(bind(KeyChord::key(c), QuickPickColor(i)));
+        }
+        bindings.extend([
+            bind_when(KeyChord::key('s'), OpenColorSliders, When::CursorInactive),
+            bind(KeyChord::key('x'), OpenHexInput),
+            bind_when(KeyChord::key('a'), AddColorToPalette, When::CursorInactive),
+            bind(KeyChord::key('c'), OpenPaletteDialog),
+            bind(KeyChord::key('k'), TogglePaletteMark),
+            bind(KeyChord::key('X'), DeletePaletteColors),
+            bind(KeyChord::key(','), MovePaletteColors(-1)),
+            bind(KeyChord::key('.'), MovePaletteColors(1)),
+            bind(KeyChord::named(KeyCode::Up), MovePaletteCursor(-1)),
+            bind(KeyChord::named(KeyCode::Down), MovePaletteCursor(1)),
+            bind(KeyChord::named(KeyCode::Left), MovePaletteCursor(-6)),
+            bind(KeyChord::named(KeyCode::Right), MovePaletteCursor(6)),
+            bind(KeyChord::named(KeyCode::PageUp), FlipColorsPage(-1)),
+            bind(KeyChord::named(KeyCode::PageDown), FlipColorsPage(1)),
+            bind(KeyChord::key('O'), TogglePaletteOrder),
+            bind(KeyChord::named(KeyCode::Enter), ActivatePaletteItem),
+            bind(KeyChord::key('h'), ToggleSymmetryHorizontal),
+            bind(KeyChord::key('v'), ToggleSymmetryVertical),
+            bind(KeyChord::key('H'), OpenSymmetryPicker),
+            bind(KeyChord::ctrl('s'), Save),
+            bind(KeyChord::ctrl('o'), OpenFileDialog),
+            bind(KeyChord::ctrl('r'), RevertToSaved),
+            bind(KeyChord::ctrl('n'), OpenNewCanvasDialog),
+            bind(KeyChord::key('R'), OpenResizeDialog),
+            bind(KeyChord::key('K'), CropToContent),
+            bind(KeyChord::ctrl_named(KeyCode::Up), ShiftCanvas { dx: 0, dy: -1, wrap: true }),
+            bind(KeyChord::ctrl_named(KeyCode::Down), ShiftCanvas { dx: 0, dy: 1, wrap: true }),
+            bind(KeyChord::ctrl_named(KeyCode::Left), ShiftCanvas { dx: -1, dy: 0, wrap: true }),
+            bind(KeyChord::ctrl_named(KeyCode::Right), ShiftCanvas { dx: 1, dy: 0, wrap: true }),
+            bind(KeyChord::ctrl('e'), OpenExportDialog),
+            // Without the kitty keyboard protocol this arrives as plain Tab,
+            // which stays free; `I` in the open dialog imports too
+            bind(KeyChord::ctrl('i'), OpenImportDialog),
+            bind(KeyChord::ctrl_named(KeyCode::Tab), NextTab),
+            // Ctrl+Tab needs the kitty keyboard protocol; Ctrl+PageDown
+            // reaches us from nearly any terminal
+            bind(KeyChord::ctrl_named(KeyCode::PageDown), NextTab),
+            bind(KeyChord::ctrl('z'), Undo),
+            bind(KeyChord::ctrl('y'), Redo),
+            bind(KeyChord::key('u'), OpenHistoryScrubber),
+            bind(KeyChord::key('U'), OpenVersions),
+            bind(KeyChord::ctrl('t'), CycleTheme),
+            bind(KeyChord::named(KeyCode::F(12)), ToggleDebugOverlay),
+            bind(KeyChord::named(KeyCode::F(2)), ToggleMouseCapture),
+            bind(KeyChord::key('q'), Quit),
+            bind(KeyChord::ctrl('C'), CopySelectionAnsi),
+            bind(KeyChord::ctrl('c'), Quit),
+            bind(KeyChord::key('?'), OpenHelp),
+            bind(KeyChord::key('V'), OpenReleaseNotes),
+        ]);
+        Keymap { bindings }
+    }
+}
+
+/// Mouse controls, listed alongside the key bindings in help.
+pub const MOUSE_HELP: &[(&str, &str)] = &[
+    ("Click", "Draw / place point"),
+    ("Drag", "Draw stroke"),
+    ("Shift+Drag", "Straight stroke"),
+    ("Alt+Click", "Ignore symmetry"),
+    ("Right", "Pick color"),
+    ("Middle+Drag", "Pan view"),
+    ("Wheel", "Zoom at pointer"),
+];
+
+const SECTIONS: &[&str] = &["Tools", "Canvas", "Colors", "Symmetry", "File", "Other"];
+/// Section for bindings left out of help and cheat sheets.
+const HIDDEN: &str = "Hidden";
+
+/// Help section and description for a bound command.
+pub fn describe(cmd: &Command) -> (&'static str, String) {
+    use Command::*;
+    let (section, text) = match cmd {
+        SelectTool(tool) => ("Tools", tool.name()),
+        ToggleFilledRect => ("Tools", "Rect fill/outline"),
+        CycleLineStyle => ("Tools", "Line style (solid/dashed/dotted)"),
+        CycleBrushPattern => ("Tools", "Pencil pattern (solid/2x2 checker/25/50/75%)"),
+        ToggleAxisLock => ("Tools", "Axis-locked strokes"),
+        TogglePixelPerfect => ("Tools", "Pixel-perfect pencil"),
+        CycleFillMode => ("Tools", "Fill match (cell/char/color)"),
+        CycleBlock => ("Tools", "Cycle block"),
+        OpenBlockPicker => ("Tools", "Block picker"),
+        CycleShade => ("Tools", "Cycle shade (\u{2591}\u{2592}\u{2593})"),
+        SetBlock(ch) => return ("Tools", format!("Block {}", ch)),
+        MoveCanvasCursor { .. } => ("Canvas", "Move cursor"),
+        Pan { .. } => ("Canvas", "Pan view"),
+        ToggleMinimap => ("Canvas", "Minimap of large canvas"),
+        ToggleTilePreview => ("Canvas", "Tile preview (3x3 copies)"),
+        ApplyAtCursor => ("Canvas", "Draw at cursor"),
+        OpenRunPrompt => ("Canvas", "Paint a run (count + direction)"),
+        OpenRectSizePrompt => ("Canvas", "Rectangle by size (WxH)"),
+        SelectActiveBlock => ("Canvas", "Select all of active block"),
+        ShadeSelection(d) if *d > 0 => ("Canvas", "Selection shade darker"),
+        ShadeSelection(_) => ("Canvas", "Selection shade lighter"),
+        RecolorSelection => ("Canvas", "Recolor selection"),
+        FindColor => ("Canvas", "Find cells of active color"),
+        JumpToSelected(d) if *d < 0 => ("Canvas", "Previous selected cell"),
+        JumpToSelected(_) => ("Canvas", "Next selected cell"),
+        SwapCellColors => ("Canvas", "Swap colors of cell under cursor"),
+        OpenInspector => ("Canvas", "Inspect/edit cell under cursor"),
+        EraseSelection => ("Canvas", "Erase selection"),
+        Copy => ("Canvas", "Copy selected cells"),
+        Paste => ("Canvas", "Paste copied cells at the cursor"),
+        Cancel => ("Canvas", "Cancel / cursor off"),
+        CycleZoom => ("Canvas", "Cycle zoom (1x/2x/4x)"),
+        QuickPickColor(_) => ("Colors", "Quick pick"),
+        OpenColorSliders => ("Colors", "HSL sliders"),
+        OpenHexInput => ("Colors", "Hex color input"),
+        AddColorToPalette => ("Colors", "Add color"),
+        TogglePaletteMark => ("Colors", "Mark palette color"),
+        DeletePaletteColors => ("Colors", "Remove marked colors"),
+        MovePaletteColors(d) if *d < 0 => ("Colors", "Move marked colors earlier"),
+        MovePaletteColors(_) => ("Colors", "Move marked colors later"),
+        OpenPaletteDialog => ("Colors", "Palettes"),
+        MovePaletteCursor(_) => ("Colors", "Browse palette"),
+        FlipColorsPage(d) if *d < 0 => ("Colors", "Previous page of colors"),
+        FlipColorsPage(_) => ("Colors", "Next page of colors"),
+        TogglePaletteOrder => ("Colors", "Palette order (index/perceptual)"),
+        ActivatePaletteItem => ("Colors", "Select/toggle"),
+        SetColor(c) => return ("Colors", format!("Color {}", c.name())),
+        ToggleSymmetryHorizontal => ("Symmetry", "Horizontal mirror"),
+        ToggleSymmetryVertical => ("Symmetry", "Vertical mirror"),
+        OpenSymmetryPicker => ("Symmetry", "Pick mode, including radial"),
+        Save => ("File", "Save"),
+        OpenFileDialog => ("File", "Open"),
+        RevertToSaved => ("File", "Revert to saved"),
+        OpenNewCanvasDialog => ("File", "New canvas"),
+        OpenResizeDialog => ("File", "Resize canvas"),
+        CropToContent => ("File", "Crop canvas to art"),
+        ShiftCanvas { wrap: true, .. } => ("Canvas", "Shift canvas, wrapping around"),
+        ShiftCanvas { wrap: false, .. } => ("Canvas", "Shift canvas"),
+        OpenExportDialog => ("File", "Export"),
+        CopySelectionAnsi => ("File", "Copy selection as ANSI"),
+        OpenImportDialog => ("File", "Import image"),
+        Undo => ("File", "Undo"),
+        Redo => ("File", "Redo"),
+        OpenHistoryScrubber => ("File", "History timeline"),
+        OpenVersions => ("File", "Autosaved versions"),
+        CycleTheme => ("File", "Cycle theme"),
+        ToggleDebugOverlay => (HIDDEN, "Debug overlay"),
+        ToggleMouseCapture => ("File", "Mouse capture (off for text selection)"),
+        Suspend => ("File", "Suspend to shell"),
+        NextTab => ("File", "Next tab"),
+        Quit => ("File", "Quit"),
+        OpenHelp => ("File", "Help"),
+        OpenReleaseNotes => ("File", "Release notes of a newer version"),
+        NewCanvas { width, height } => return ("File", format!("New {}x{} canvas", width, height)),
+        ResizeCanvas { width, height, anchor } => {
+            return ("File", format!("Resize to {}x{}, {}", width, height, anchor.name()))
+        }
+        OpenFile(path) => return ("File", format!("Open {}", path)),
+        OpenFileInTab(path) => return ("File", format!("Open {} in a new tab", path)),
+        other => return ("Other", format!("{:?}", other)),
+    };
+    (section, text.to_string())
+}
+
+/// A titled group of help rows: (keys, description).
+pub struct HelpSection {
+    pub title: &'static str,
+    pub rows: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SheetFormat {
+    Markdown,
+    Text,
+}
+
+impl Keymap {
+    /// The command bound to a key event, if any. An upper case letter falls
+    /// back to the lower case binding; typed without Shift (Caps Lock) it
+    /// tries the lower case binding first.
+    pub fn lookup(&self, code: KeyCode, modifiers: KeyModifiers, cursor_active: bool) -> Option<Command> {
+        let chord = KeyChord::from_event(code, modifiers);
+        let folded = match chord.code {
+            KeyCode::Char(c) if c.is_ascii_uppercase() => {
+                Some(KeyChord { code: KeyCode::Char(c.to_ascii_lowercase()), ..chord })
+            }
+            _ => None,
+        };
+        let caps_lock = !chord.ctrl && !modifiers.contains(KeyModifiers::SHIFT);
+        let order = if caps_lock { [folded, Some(chord)] } else { [Some(chord), folded] };
+        order.into_iter().flatten().find_map(|chord| self.find(chord, cursor_active))
+    }
+
+    fn find(&self, chord: KeyChord, cursor_active: bool) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|b| b.key == chord && b.when.applies(cursor_active))
+            .map(|b| b.command.clone())
+    }
+
+    /// Label of the first key bound to `cmd`.
+    pub fn key_label(&self, cmd: &Command) -> Option<String> {
+        self.bindings.iter().find(|b| &b.command == cmd).map(|b| b.key.label())
+    }
+
+    /// The default bindings, with `overrides` taking every key they name.
+    pub fn with_overrides(overrides: Vec<Binding>) -> Self {
+        let mut bindings: Vec<Binding> = Keymap::default()
+            .bindings
+            .into_iter()
+            .filter(|b| !overrides.iter().any(|o| o.key == b.key))
+            .collect();
+        bindings.splice(0..0, overrides);
+        Keymap { bindings }
+    }
+
+    /// Where user remaps live (`~/.config/kakukuma/keys.json` on Linux).
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("kakukuma").join("keys.json"))
+    }
+
+    /// Load user remaps from a JSON list of `{"key", "command", "when"}`
+    /// objects. A missing file means no remaps.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Keymap::default());
+        }
+        let data = std::fs::read_to_string(path).map_err(|e| format!("Read error: {}", e))?;
+        let overrides: Vec<Binding> =
+            serde_json::from_str(&data).map_err(|e| format!("Parse error: {}", e))?;
+        Ok(Keymap::with_overrides(overrides))
+    }
+
+    /// The effective keymap: defaults plus the user's remaps, if any.
+    pub fn load_user() -> Result<Self, String> {
+        match Keymap::config_path() {
+            Some(path) => Keymap::load(&path),
+            None => Ok(Keymap::default()),
+        }
+    }
+
+    /// Bindings grouped for display. Keys sharing a description share a row.
+    pub fn sections(&self) -> Vec<HelpSection> {
+        let mut sections: Vec<HelpSection> = SECTIONS
+            .iter()
+            .map(|title| HelpSection { title, rows: Vec::new() })
+            .collect();
+        let mut keys: Vec<Vec<String>> = Vec::new();
+        let mut index: Vec<(usize, String)> = Vec::new();
+        for b in &self.bindings {
+            let (section, text) = describe(&b.command);
+            if section == HIDDEN {
+                continue;
+            }
+            let s = SECTIONS.iter().position(|t| *t == section).unwrap_or(SECTIONS.len() - 1);
+            let label = b.key.label();
+            match index.iter().position(|(si, t)| *si == s && *t == text) {
+                Some(row) => {
+                    if !keys[row].contains(&label) {
+                        keys[row].push(label);
+                    }
+                }
+                None => {
+                    index.push((s, text));
+                    keys.push(vec![label]);
+                }
+            }
+        }
+        for ((s, text), row_keys) in index.into_iter().zip(keys) {
+            sections[s].rows.push((join_keys(&row_keys), text));
+        }
+        let canvas = SECTIONS.iter().position(|t| *t == "Canvas").unwrap_or(0);
+        for (key, text) in MOUSE_HELP {
+            sections[canvas].rows.push((key.to_string(), text.to_string()));
+        }
+        sections.retain(|s| !s.rows.is_empty());
+        sections
+    }
+
+    /// Sections with only the rows whose keys or description contain
+    /// `query`, ignoring case. A matching section title keeps every row.
+    pub fn sections_matching(&self, query: &str) -> Vec<HelpSection> {
+        let query = query.to_lowercase();
+        let mut sections = self.sections();
+        for section in &mut sections {
+            if !section.title.to_lowercase().contains(&query) {
+                section.rows.retain(|(keys, text)| {
+                    keys.to_lowercase().contains(&query) || text.to_lowercase().contains(&query)
+                });
+            }
+        }
+        sections.retain(|s| !s.rows.is_empty());
+        sections
+    }
+
+    /// The keymap as a printable cheat sheet.
+    pub fn cheat_sheet(&self, format: SheetFormat) -> String {
+        let sections = self.sections();
+        let mut out = String::new();
+        match format {
+            SheetFormat::Markdown => {
+                out.push_str("# kakukuma keys\n");
+                for section in &sections {
+                    out.push_str(&format!("\n## {}\n\n| Key | Action |\n|-----|--------|\n", section.title));
+                    for (keys, text) in &section.rows {
+                        out.push_str(&format!("| `{}` | {} |\n", keys, text));
+                    }
+                }
+            }
+            SheetFormat::Text => {
+                let width = sections
+                    .iter()
+                    .flat_map(|s| s.rows.iter().map(|(k, _)| k.chars().count()))
+                    .max()
+                    .unwrap_or(0);
+                for (i, section) in sections.iter().enumerate() {
+                    if i > 0 {
+                        out.push('\n');
+                    }
+                    out.push_str(&format!("{}\n", section.title));
+                    for (keys, text) in &section.rows {
+                        out.push_str(&format!("  {:<width$}  {}\n", keys, text, width = width));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// "1-0" for a run of digit keys, otherwise keys joined with "/".
+fn join_keys(keys: &[String]) -> String {
+    let digits = keys.len() > 2 && keys.iter().all(|k| k.len() == 1 && k.as_bytes()[0].is_ascii_digit());
+    if digits {
+        format!("{}-{}", keys[0], keys[keys.len() - 1])
+    } else {
+        keys.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_label_roundtrip() {
+        for s in ["P", "Shift+B", "Ctrl+S", "Ctrl+Shift+C", "↑", "Space", "Esc", "?", "F5"] {
+            assert_eq!(KeyChord::parse(s).unwrap().label(), s);
+        }
+        assert_eq!(KeyChord::parse("ctrl+S").unwrap(), KeyChord::ctrl('s'));
+        assert_eq!(KeyChord::parse("Up").unwrap(), KeyChord::named(KeyCode::Up));
+        assert!(KeyChord::parse("Ctrl+").is_err());
+        assert!(KeyChord::parse("Hyper").is_err());
+    }
+
+    #[test]
+    fn test_lookup_folds_case_and_context() {
+        let km = Keymap::default();
+        let none = KeyModifiers::NONE;
+        assert_eq!(km.lookup(KeyCode::Char('P'), KeyModifiers::SHIFT, false), Some(Command::SelectTool(ToolKind::Pencil)));
+        assert_eq!(km.lookup(KeyCode::Char('B'), KeyModifiers::SHIFT, false), Some(Command::OpenBlockPicker));
+        assert_eq!(km.lookup(KeyCode::Char('s'), none, false), Some(Command::OpenColorSliders));
+        assert_eq!(km.lookup(KeyCode::Char('s'), none, true), Some(Command::MoveCanvasCursor { dx: 0, dy: 1 }));
+        assert_eq!(km.lookup(KeyCode::Char('S'), KeyModifiers::SHIFT, true), Some(Command::Pan { dx: 0, dy: 1 }));
+        // Caps Lock: an upper case letter without Shift
+        assert_eq!(km.lookup(KeyCode::Char('S'), none, true), Some(Command::MoveCanvasCursor { dx: 0, dy: 1 }));
+        assert_eq!(km.lookup(KeyCode::Char(' '), none, false), None);
+        assert_eq!(km.lookup(KeyCode::Char('Z'), KeyModifiers::CONTROL, false), Some(Command::Undo));
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        assert_eq!(km.lookup(KeyCode::Char('c'), ctrl_shift, false), Some(Command::CopySelectionAnsi));
+        assert_eq!(km.lookup(KeyCode::Char('Z'), ctrl_shift, false), Some(Command::Undo));
+        // Ctrl+Alt is AltGr: a plain character, not a Ctrl shortcut
+        assert_eq!(km.lookup(KeyCode::Char('q'), KeyModifiers::CONTROL | KeyModifiers::ALT, false), Some(Command::Quit));
+        assert_eq!(km.lookup(KeyCode::Char('k'), KeyModifiers::CONTROL, false), None);
+    }
+
+    #[test]
+    fn test_next_tab_without_keyboard_protocol() {
+        let km = Keymap::default();
+        // Without the kitty protocol Ctrl+Tab (and Ctrl+I) arrive as plain Tab
+        assert_eq!(km.lookup(KeyCode::Tab, KeyModifiers::NONE, false), None);
+        assert_eq!(km.lookup(KeyCode::Tab, KeyModifiers::CONTROL, false), Some(Command::NextTab));
+        assert_eq!(km.lookup(KeyCode::PageDown, KeyModifiers::CONTROL, false), Some(Command::NextTab));
+        assert_eq!(km.lookup(KeyCode::PageDown, KeyModifiers::NONE, false), Some(Command::FlipColorsPage(1)));
+    }
+
+    #[test]
+    fn test_user_remap_replaces_key() {
+        let path = std::env::temp_dir().join("kaku_test_keys.json");
+        std::fs::write(&path, r#"[{"key": "p", "command": {"SelectTool": "Line"}},
+                                  {"key": "Ctrl+K", "command": "Undo"}]"#).unwrap();
+        let km = Keymap::load(&path).unwrap();
+        let none = KeyModifiers::NONE;
+        assert_eq!(km.lookup(KeyCode::Char('p'), none, false), Some(Command::SelectTool(ToolKind::Line)));
+        assert_eq!(km.lookup(KeyCode::Char('k'), KeyModifiers::CONTROL, false), Some(Command::Undo));
+        assert_eq!(km.key_label(&Command::SelectTool(ToolKind::Pencil)), None);
+        assert!(km.cheat_sheet(SheetFormat::Text).contains("Ctrl+K/Ctrl+Z"));
+
+        std::fs::write(&path, r#"[{"key": "Hyper+P", "command": "Undo"}]"#).unwrap();
+        assert!(Keymap::load(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_ctrl_z_can_suspend() {
+        let path = std::env::temp_dir().join("kaku_test_keys_suspend.json");
+        std::fs::write(&path, r#"[{"key": "Ctrl+Z", "command": "Suspend"},
+                                  {"key": "Ctrl+U", "command": "Undo"}]"#).unwrap();
+        let km = Keymap::load(&path).unwrap();
+        assert_eq!(km.lookup(KeyCode::Char('z'), KeyModifiers::CONTROL, false), Some(Command::Suspend));
+        assert_eq!(km.key_label(&Command::Undo).as_deref(), Some("Ctrl+U"));
+        assert_eq!(Keymap::default().key_label(&Command::Suspend), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_sections_merge_keys() {
+        let sections = Keymap::default().sections();
+        let row = |title: &str, text: &str| {
+            sections.iter().find(|s| s.title == title).unwrap()
+                .rows.iter().find(|(_, t)| t == text).unwrap().0.clone()
+        };
+        assert_eq!(row("Colors", "Quick pick"), "1-0");
+        assert_eq!(row("Canvas", "Move cursor"), "W/A/S/D");
+        assert_eq!(row("File", "Quit"), "Q/Ctrl+C");
+        assert!(sections.iter().all(|s| s.title != "Other"));
+    }
+
+    #[test]
+    fn test_sections_matching() {
+        let km = Keymap::default();
+        let found = km.sections_matching("ZOOM");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].rows, vec![
+            ("Z".to_string(), "Cycle zoom (1x/2x/4x)".to_string()),
+            ("Wheel".to_string(), "Zoom at pointer".to_string()),
+        ]);
+        let symmetry = km.sections_matching("symmetry");
+        assert_eq!(symmetry.iter().find(|s| s.title == "Symmetry").unwrap().rows.len(), 3);
+        assert!(symmetry.iter().any(|s| s.rows.iter().any(|(k, _)| k == "Alt+Click")));
+        assert!(km.sections_matching("no such thing").is_empty());
+    }
+}
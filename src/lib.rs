@@ -0,0 +1,34 @@
+//! Library side of kakukuma: the canvas model, tools, import/export and
+//! TUI rendering, usable without a terminal (benchmarks, tests, fuzzing).
+
+pub mod app;
+pub mod aseprite;
+pub mod canvas;
+pub mod cell;
+pub mod cli;
+pub mod command;
+pub mod crash;
+pub mod export;
+pub mod font;
+pub mod history;
+pub mod import;
+pub mod input;
+pub mod journal;
+pub mod keymap;
+pub mod lab;
+pub mod listing;
+pub mod net;
+pub mod oplog;
+pub mod palette;
+pub mod project;
+pub mod selection;
+pub mod session;
+pub mod settings;
+pub mod symmetry;
+pub mod terminal;
+pub mod theme;
+pub mod tools;
+pub mod trash;
+pub mod ui;
+pub mod update;
+pub mod versions;
@@ -0,0 +1,97 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Order of the entries in a file dialog.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Name,
+    /// Most recently modified first
+    Modified,
+}
+
+impl SortOrder {
+    pub fn next(self) -> Self {
+        match self {
+            SortOrder::Name => SortOrder::Modified,
+            SortOrder::Modified => SortOrder::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Modified => "modified",
+        }
+    }
+}
+
+/// Filter and sort state for a dialog that lists files in a directory.
+#[derive(Default)]
+pub struct ListView {
+    /// Case-insensitive substring the file name must contain
+    pub filter: String,
+    /// Keystrokes go to the filter instead of dialog shortcuts
+    pub filtering: bool,
+    pub sort: SortOrder,
+}
+
+impl ListView {
+    /// Stop filtering and show every file again.
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filtering = false;
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        name.to_lowercase().contains(&self.filter.to_lowercase())
+    }
+
+    /// Filter and order a listing of `dir`.
+    pub fn apply(&self, dir: &Path, names: Vec<String>) -> Vec<String> {
+        let mut names: Vec<String> = names.into_iter().filter(|n| self.matches(n)).collect();
+        match self.sort {
+            SortOrder::Name => names.sort(),
+            SortOrder::Modified => {
+                let modified = |name: &String| {
+                    std::fs::metadata(dir.join(name))
+                        .and_then(|m| m.modified())
+                        .unwrap_or(SystemTime::UNIX_EPOCH)
+                };
+                names.sort_by(|a, b| modified(b).cmp(&modified(a)).then_with(|| a.cmp(b)));
+            }
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_filter_is_case_insensitive() {
+        let view = ListView { filter: "CAT".to_string(), ..Default::default() };
+        let names = vec!["bobcat.kaku".to_string(), "dog.kaku".to_string(), "Cats.kaku".to_string()];
+        assert_eq!(view.apply(Path::new("."), names), vec!["Cats.kaku", "bobcat.kaku"]);
+    }
+
+    #[test]
+    fn test_sort_by_modified_newest_first() {
+        let dir = std::env::temp_dir().join("kaku_test_listing_sort");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = SystemTime::now() - Duration::from_secs(1000);
+        for (i, name) in ["a.kaku", "b.kaku", "c.kaku"].iter().enumerate() {
+            let file = std::fs::File::create(dir.join(name)).unwrap();
+            // b newest, then a, then c
+            let age = [1, 0, 2][i];
+            file.set_modified(base - Duration::from_secs(age * 100)).unwrap();
+        }
+        let names = vec!["a.kaku".to_string(), "b.kaku".to_string(), "c.kaku".to_string()];
+        let view = ListView { sort: SortOrder::Modified, ..Default::default() };
+        assert_eq!(view.apply(&dir, names), vec!["b.kaku", "a.kaku", "c.kaku"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,32 +1,29 @@
-mod app;
-mod canvas;
-mod cell;
-mod cli;
-mod export;
-mod history;
-mod input;
-mod oplog;
-mod palette;
-mod project;
-mod symmetry;
-mod theme;
-mod tools;
-mod ui;
-
 use std::io;
-use std::time::Duration;
+use std::path::Path;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    supports_keyboard_enhancement,
 };
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 
-use app::App;
 use clap::Parser;
-use input::CanvasArea;
+use kakukuma::app::App;
+use kakukuma::export::{self, ColorFormat};
+use kakukuma::input::CanvasArea;
+use kakukuma::session::{self, SessionLog};
+use kakukuma::{cli, crash, input, keymap, settings, terminal, ui};
 
 fn main() -> io::Result<()> {
     let args = cli::Cli::parse();
@@ -34,47 +31,189 @@ fn main() -> io::Result<()> {
     match args.command {
         Some(cmd) => {
             // CLI path — no terminal initialization
-            cli::run(cmd)
+            cli::run(cmd, cli::OutputOpts { quiet: args.quiet, json: args.json })
         }
         None => {
+            if let Some(ref log) = args.replay {
+                return replay(log);
+            }
             // TUI path — existing behavior
-            run_tui(args.file)
+            let colors = args
+                .colors
+                .as_ref()
+                .map(cli::to_color_format)
+                .unwrap_or_else(terminal::detect_color_support);
+            let options = TuiOptions {
+                tick_rate: Duration::from_millis(args.tick_rate),
+                record: args.record_session,
+                colors,
+                screen_reader: args.screen_reader,
+                monochrome: args.monochrome || terminal::no_color_requested(),
+                safe: args.safe,
+            };
+            run_tui(args.file, options)
         }
     }
 }
 
-fn run_tui(file: Option<String>) -> io::Result<()> {
+/// Replay a recorded session without a terminal and print the final canvas.
+fn replay(log_path: &str) -> io::Result<()> {
+    let log = SessionLog::load(Path::new(log_path)).map_err(io::Error::other)?;
+    let app = session::replay(&log);
+    println!("{}", export::to_ansi(&app.canvas, ColorFormat::TrueColor));
+    Ok(())
+}
+
+/// Longest the event loop sleeps when no timer is pending. Input still
+/// wakes it immediately.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Minimum time between redraws (~60 FPS).
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+/// Upper bound on events handled between two redraws.
+const MAX_EVENTS_PER_FRAME: usize = 256;
+
+/// Editor settings taken from the command line.
+struct TuiOptions {
+    tick_rate: Duration,
+    record: Option<String>,
+    colors: ColorFormat,
+    screen_reader: bool,
+    monochrome: bool,
+    safe: bool,
+}
+
+fn run_tui(file: Option<String>, options: TuiOptions) -> io::Result<()> {
     // Setup terminal
-    enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Where the terminal can, have it report Shift with Ctrl, so shortcuts
+    // like Ctrl+Shift+C differ from Ctrl+C
+    let enhanced = !options.safe && supports_keyboard_enhancement().unwrap_or(false);
+    enter_terminal(&mut stdout, !options.safe, enhanced)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Setup panic handler to restore terminal
+    // Setup panic handler to restore terminal and leave a crash report
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
-        let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+        let _ = leave_terminal(&mut io::stdout(), enhanced);
         original_hook(panic_info);
+        match crash::write_bundle(panic_info) {
+            Ok(path) => eprintln!(
+                "Crash report saved to {}\nPlease attach it to a bug report at {}/issues",
+                path.display(),
+                env!("CARGO_PKG_REPOSITORY")
+            ),
+            Err(e) => eprintln!("Could not save a crash report: {}", e),
+        }
     }));
 
-    let result = run(&mut terminal, file);
+    let result = run(&mut terminal, file, options, enhanced);
 
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    leave_terminal(terminal.backend_mut(), enhanced)?;
     terminal.show_cursor()?;
 
     result
 }
 
-fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, file: Option<String>) -> io::Result<()> {
+/// Raw mode on the alternate screen, with mouse capture and keyboard
+/// enhancement as asked.
+fn enter_terminal(out: &mut impl io::Write, mouse: bool, enhanced: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen)?;
+    if mouse {
+        execute!(out, EnableMouseCapture)?;
+    }
+    if enhanced {
+        execute!(out, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+    }
+    Ok(())
+}
+
+/// Undo `enter_terminal`, giving the shell back its screen.
+fn leave_terminal(out: &mut impl io::Write, enhanced: bool) -> io::Result<()> {
+    if enhanced {
+        execute!(out, PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    execute!(out, LeaveAlternateScreen, DisableMouseCapture)
+}
+
+/// Job control signals, caught so the terminal can be handed back before
+/// the process stops (SIGTSTP) and taken again when it continues (SIGCONT).
+#[cfg(unix)]
+struct JobSignals {
+    stop: Arc<AtomicBool>,
+    resumed: Arc<AtomicBool>,
+}
+
+#[cfg(unix)]
+impl JobSignals {
+    fn listen() -> io::Result<JobSignals> {
+        use signal_hook::consts::{SIGCONT, SIGTSTP, SIGWINCH};
+
+        let signals = JobSignals {
+            stop: Arc::new(AtomicBool::new(false)),
+            resumed: Arc::new(AtomicBool::new(false)),
+        };
+        let (stop, resumed) = (signals.stop.clone(), signals.resumed.clone());
+        let mut iter = signal_hook::iterator::Signals::new([SIGTSTP, SIGCONT])?;
+        std::thread::spawn(move || {
+            for signal in iter.forever() {
+                let flag = if signal == SIGTSTP { &stop } else { &resumed };
+                flag.store(true, Ordering::SeqCst);
+                // Wake the event loop: crossterm reports SIGWINCH as a resize
+                let _ = signal_hook::low_level::raise(SIGWINCH);
+            }
+        });
+        Ok(signals)
+    }
+}
+
+/// Give the shell its screen back and stop, like Ctrl+Z in a shell; once
+/// continued with `fg`, take the terminal again and repaint all of it.
+#[cfg(unix)]
+fn suspend(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mouse: bool,
+    enhanced: bool,
+) -> io::Result<()> {
+    leave_terminal(terminal.backend_mut(), enhanced)?;
+    terminal.show_cursor()?;
+    signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)?;
+    enter_terminal(terminal.backend_mut(), mouse, enhanced)?;
+    terminal.clear()
+}
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    file: Option<String>,
+    options: TuiOptions,
+    enhanced: bool,
+) -> io::Result<()> {
+    let TuiOptions { tick_rate, record, colors, screen_reader, monochrome, safe } = options;
     let mut app = App::new();
+    app.color_support = colors;
+    app.screen_reader = screen_reader;
+    app.monochrome = monochrome;
+    app.safe_mode = safe;
+    app.mouse_capture = !safe;
+    let mut mouse_captured = app.mouse_capture;
+    if !safe {
+        match keymap::Keymap::load_user() {
+            Ok(keymap) => app.keymap = keymap,
+            Err(e) => app.set_status(&format!("Key map not loaded: {}", e)),
+        }
+        match settings::Settings::load_user() {
+            Ok(settings) => app.settings = settings,
+            Err(e) => app.set_status(&format!("Settings not loaded: {}", e)),
+        }
+    }
+    kakukuma::cell::set_color_distance(app.settings.color_distance);
+    app.start_update_check();
+    if let Some(ref path) = record {
+        app.session_log = Some(SessionLog::create(Path::new(path), file.clone()).map_err(io::Error::other)?);
+    }
     let mut canvas_area = CanvasArea {
         left: 0,
         top: 0,
@@ -82,6 +221,8 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, file: Option<Strin
         height: 0,
         viewport_w: 0,
         viewport_h: 0,
+        tiles: None,
+        minimap: None,
     };
 
     // Load file from command-line argument if provided
@@ -89,33 +230,154 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, file: Option<Strin
         app.load_project(path);
     }
 
-    // Check for autosave recovery on startup (only if no file was loaded)
-    if app.project_path.is_none() {
-        app.check_recovery();
-    }
+    // Offer to recover work left by a crash
+    app.check_recovery();
+    app.journaling = true;
+    crash::set_state(app.crash_state());
+
+    #[cfg(unix)]
+    let signals = JobSignals::listen()?;
+
+    let mut needs_redraw = true;
+    let mut last_draw: Option<Instant> = None;
+    let mut shown_indicator = app.save_indicator(SystemTime::now());
 
     while app.running {
-        // Render
-        terminal.draw(|f| {
-            canvas_area = ui::render(f, &app);
-        })?;
+        // Render at most once per frame budget
+        let frame_due = last_draw.is_none_or(|t| t.elapsed() >= FRAME_BUDGET);
+        if needs_redraw && frame_due {
+            let started = Instant::now();
+            terminal.draw(|f| {
+                canvas_area = ui::render(f, &app);
+            })?;
+            app.perf.render = started.elapsed();
+            last_draw = Some(Instant::now());
+            needs_redraw = false;
 
-        // Store viewport dimensions for input handling
-        app.viewport_w = canvas_area.viewport_w;
-        app.viewport_h = canvas_area.viewport_h;
+            // Store viewport dimensions for input handling
+            app.viewport_w = canvas_area.viewport_w;
+            app.viewport_h = canvas_area.viewport_h;
+        }
 
-        // Poll for events with timeout for status message ticking
-        if event::poll(Duration::from_millis(100))? {
-            let event = event::read()?;
-            input::handle_event(&mut app, event, &canvas_area);
+        // Sleep until input arrives or the next timer is due. With nothing
+        // pending (clean canvas, no status message) sleep much longer.
+        let busy = app.history.is_stroke_active()
+            || app.status_message.is_some()
+            || app.palette_download.is_some()
+            || app.update_check.is_some();
+        let max_wait = if busy { tick_rate } else { IDLE_TIMEOUT };
+        let mut timeout = app
+            .next_deadline()
+            .map(|d| d.saturating_duration_since(Instant::now()))
+            .unwrap_or(max_wait)
+            .min(max_wait);
+        if needs_redraw {
+            let since_draw = last_draw.map_or(FRAME_BUDGET, |t| t.elapsed());
+            timeout = timeout.min(FRAME_BUDGET.saturating_sub(since_draw));
+        }
+        if event::poll(timeout)? {
+            // Drain everything already queued so a burst of drag events
+            // costs a single redraw
+            let started = Instant::now();
+            let mut events = vec![event::read()?];
+            while events.len() < MAX_EVENTS_PER_FRAME && event::poll(Duration::ZERO)? {
+                events.push(event::read()?);
+            }
+            for event in input::coalesce_events(events) {
+                input::handle_event(&mut app, event, &canvas_area);
+            }
+            crash::set_state(app.crash_state());
+            if let Some(ref mut log) = app.session_log {
+                log.flush();
+            }
+            app.perf.events = started.elapsed();
+            needs_redraw = true;
+        }
+        if app.mouse_capture != mouse_captured {
+            if app.mouse_capture {
+                execute!(terminal.backend_mut(), EnableMouseCapture)?;
+            } else {
+                execute!(terminal.backend_mut(), DisableMouseCapture)?;
+            }
+            mouse_captured = app.mouse_capture;
+        }
+        #[cfg(unix)]
+        {
+            let stop = signals.stop.swap(false, Ordering::SeqCst);
+            if std::mem::take(&mut app.suspend_requested) || stop {
+                suspend(terminal, mouse_captured, enhanced)?;
+                needs_redraw = true;
+            }
+            // Continued after any stop, including one that couldn't be
+            // caught (SIGSTOP): the shell may have drawn over the screen
+            if signals.resumed.swap(false, Ordering::SeqCst) {
+                enable_raw_mode()?;
+                terminal.clear()?;
+                needs_redraw = true;
+            }
+        }
+        #[cfg(not(unix))]
+        if std::mem::take(&mut app.suspend_requested) {
+            app.set_status("Suspend needs a Unix job control shell");
+            needs_redraw = true;
         }
 
-        // Tick status message timer
+        // Expire timers by wall-clock time, independent of the poll rate
+        let status_before = app.status_message.as_ref().map(|m| m.expires_at);
+        if app.tick_spray() {
+            needs_redraw = true;
+        }
         app.tick_status();
-
-        // Tick auto-save timer
         app.tick_auto_save();
+        if app.tick_downloads() {
+            needs_redraw = true;
+        }
+        // One write and fsync for everything journaled this iteration
+        app.sync_journal();
+        if app.status_message.as_ref().map(|m| m.expires_at) != status_before {
+            needs_redraw = true;
+        }
+        // The header's "saved 2m ago" moves on with no input
+        let indicator = app.save_indicator(SystemTime::now());
+        if indicator != shown_indicator {
+            shown_indicator = indicator;
+            needs_redraw = true;
+        }
     }
 
+    // A clean exit means the changes were saved or deliberately discarded
+    app.end_journal();
+
     Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// Wait up to a second for a signal thread to set `flag`.
+    fn wait_for(flag: &AtomicBool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while Instant::now() < deadline {
+            if flag.load(Ordering::SeqCst) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn test_job_signals_set_their_flags() {
+        use signal_hook::consts::{SIGCONT, SIGTSTP};
+
+        let signals = JobSignals::listen().unwrap();
+        signal_hook::low_level::raise(SIGCONT).unwrap();
+        assert!(wait_for(&signals.resumed));
+        assert!(!signals.stop.load(Ordering::SeqCst));
+
+        // Caught, so the test process isn't stopped
+        signal_hook::low_level::raise(SIGTSTP).unwrap();
+        assert!(wait_for(&signals.stop));
+    }
+}
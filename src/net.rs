@@ -0,0 +1,46 @@
+//! Downloads, for the few features that reach the internet. They run `curl`
+//! so the build needs no TLS stack, and only once the user has turned on
+//! `allow_network` in settings.
+
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+
+/// Longest a download may take, in seconds.
+const TIMEOUT_SECS: &str = "15";
+
+/// Fetch `url` as text.
+pub fn get(url: &str) -> Result<String, String> {
+    let out = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--max-time", TIMEOUT_SECS])
+        .args(["--user-agent", concat!("kakukuma/", env!("CARGO_PKG_VERSION"))])
+        .arg(url)
+        .output()
+        .map_err(|_| "Downloads need curl on PATH".to_string())?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(format!("Download failed: {}", err.trim().trim_start_matches("curl: ")));
+    }
+    String::from_utf8(out.stdout).map_err(|_| "Download is not text".to_string())
+}
+
+/// Work running on another thread, so the editor keeps drawing while it
+/// waits. Poll it from the event loop.
+pub struct Pending<T> {
+    rx: Receiver<T>,
+}
+
+impl<T: Send + 'static> Pending<T> {
+    pub fn spawn(work: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(work());
+        });
+        Pending { rx }
+    }
+
+    /// The result, once it's ready.
+    pub fn poll(&self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
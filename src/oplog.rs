@@ -82,7 +82,7 @@ pub fn log_path(kaku_path: &Path) -> PathBuf {
 pub fn init_log(path: &Path) -> io::Result<()> {
     let header = LogHeader { pointer: 0, total: 0 };
     let line = serde_json::to_string(&header)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
     std::fs::write(path, format!("{}\n", line))
 }
 
@@ -124,11 +124,11 @@ fn read_raw(path: &Path) -> io::Result<(LogHeader, Vec<LogEntry>)> {
 fn write_raw(path: &Path, header: &LogHeader, entries: &[LogEntry]) -> io::Result<()> {
     let mut file = std::fs::File::create(path)?;
     let header_json = serde_json::to_string(header)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
     writeln!(file, "{}", header_json)?;
     for entry in entries {
         let entry_json = serde_json::to_string(entry)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
         writeln!(file, "{}", entry_json)?;
     }
     Ok(())
@@ -176,7 +176,7 @@ pub fn pop_for_undo(path: &Path, count: usize) -> io::Result<Vec<LogEntry>> {
     let (header, entries) = read_raw(path)?;
 
     if header.pointer == 0 {
-        return Err(io::Error::new(io::ErrorKind::Other, "Nothing to undo"));
+        return Err(io::Error::other("Nothing to undo"));
     }
 
     let actual_count = count.min(header.pointer);
@@ -200,7 +200,7 @@ pub fn push_for_redo(path: &Path, count: usize) -> io::Result<Vec<LogEntry>> {
 
     let undone_count = entries.len() - header.pointer;
     if undone_count == 0 {
-        return Err(io::Error::new(io::ErrorKind::Other, "Nothing to redo"));
+        return Err(io::Error::other("Nothing to redo"));
     }
 
     let actual_count = count.min(undone_count);
@@ -1,8 +1,14 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 
-use crate::cell::{color256_to_rgb, Rgb};
+use crate::cell::{color256_to_rgb, parse_hex_color, Rgb};
+
+/// Colors shown at once in the Colors box: four rows of six. Larger custom
+/// palettes are split into pages.
+pub const COLORS_PER_PAGE: usize = 24;
 
 /// Curated 24-color default palette covering neutrals, warm, cool, and accent hues.
 /// Computed from original xterm-256 indices.
@@ -80,7 +86,40 @@ pub fn list_palette_files(dir: &Path) -> Vec<String> {
 /// Load a custom palette from a `.palette` JSON file.
 pub fn load_palette(path: &Path) -> Result<CustomPalette, String> {
     let data = std::fs::read_to_string(path).map_err(|e| format!("Read error: {}", e))?;
-    serde_json::from_str(&data).map_err(|e| format!("Parse error: {}", e))
+    load_palette_from_str(&data)
+}
+
+/// Parse a palette from `.palette` JSON.
+pub fn load_palette_from_str(data: &str) -> Result<CustomPalette, String> {
+    serde_json::from_str(data).map_err(|e| format!("Parse error: {}", e))
+}
+
+/// Number of colors shown per palette in the dialog's swatch strip.
+pub const SWATCH_COUNT: usize = 8;
+
+/// Swatch strips for the palette dialog, loaded the first time a file is
+/// shown and reloaded when its modification time changes.
+#[derive(Default)]
+pub struct SwatchCache {
+    entries: HashMap<PathBuf, (Option<SystemTime>, Vec<Rgb>)>,
+}
+
+impl SwatchCache {
+    /// First `SWATCH_COUNT` colors of the palette at `path`; empty if it
+    /// can't be read.
+    pub fn get(&mut self, path: &Path) -> Vec<Rgb> {
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if let Some((cached_mtime, colors)) = self.entries.get(path) {
+            if *cached_mtime == mtime {
+                return colors.clone();
+            }
+        }
+        let colors: Vec<Rgb> = load_palette(path)
+            .map(|p| p.colors.into_iter().take(SWATCH_COUNT).collect())
+            .unwrap_or_default();
+        self.entries.insert(path.to_path_buf(), (mtime, colors.clone()));
+        colors
+    }
 }
 
 /// Save a custom palette to a `.palette` JSON file.
@@ -89,6 +128,121 @@ pub fn save_palette(palette: &CustomPalette, path: &Path) -> Result<(), String>
     std::fs::write(path, json).map_err(|e| format!("Write error: {}", e))
 }
 
+/// Start of a palette share string.
+pub const SHARE_PREFIX: &str = "kakupal:";
+
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode a palette as one line of text to paste in chat: `kakupal:` then
+/// URL-safe base64 of the name's length, the name, and the RGB bytes.
+/// Names over 255 bytes are shortened.
+pub fn to_share_string(palette: &CustomPalette) -> String {
+    let mut end = palette.name.len().min(255);
+    while !palette.name.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut bytes = vec![end as u8];
+    bytes.extend_from_slice(&palette.name.as_bytes()[..end]);
+    for c in &palette.colors {
+        bytes.extend_from_slice(&[c.r, c.g, c.b]);
+    }
+
+    let mut out = SHARE_PREFIX.to_string();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        // Three bytes make four digits; a short final chunk makes fewer
+        for i in 0..=chunk.len() {
+            out.push(BASE64_URL[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Parse a palette shared as text: a `kakupal:` string, or hex colors
+/// separated by spaces, commas or new lines, as Lospec and most palette
+/// sites list them. A hex list is named `name`.
+pub fn from_share_string(text: &str, name: &str) -> Result<CustomPalette, String> {
+    let text = text.trim();
+    let Some(encoded) = text.strip_prefix(SHARE_PREFIX) else {
+        let colors = text
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                let hex = s.strip_prefix('#').unwrap_or(s);
+                hex.bytes()
+                    .all(|b| b.is_ascii_hexdigit())
+                    .then(|| parse_hex_color(hex))
+                    .flatten()
+                    .ok_or_else(|| format!("Not a hex color: {}", s))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if colors.is_empty() {
+            return Err("No colors to import".to_string());
+        }
+        return Ok(CustomPalette { name: name.to_string(), colors });
+    };
+
+    let mut bytes = Vec::new();
+    let (mut bits, mut n) = (0u32, 0u32);
+    for ch in encoded.bytes() {
+        let digit = BASE64_URL.iter().position(|&b| b == ch).ok_or("Palette string is damaged")?;
+        n = n << 6 | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+    let (&len, rest) = bytes.split_first().ok_or("Palette string is empty")?;
+    let (name, rgb) = rest.split_at_checked(len as usize).ok_or("Palette string is cut short")?;
+    if rgb.len() % 3 != 0 {
+        return Err("Palette string is cut short".to_string());
+    }
+    Ok(CustomPalette {
+        name: String::from_utf8(name.to_vec()).map_err(|_| "Palette string is damaged")?,
+        colors: rgb.chunks(3).map(|c| Rgb::new(c[0], c[1], c[2])).collect(),
+    })
+}
+
+/// Where Lospec serves a palette as JSON.
+pub fn lospec_url(slug: &str) -> String {
+    format!("https://lospec.com/palette-list/{}.json", slug)
+}
+
+/// Lospec slugs are lowercase words joined by hyphens, like `sweetie-16`.
+pub fn is_lospec_slug(slug: &str) -> bool {
+    !slug.is_empty() && slug.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// Parse Lospec's palette JSON (`{"name": ..., "colors": ["1a1c2c", ...]}`)
+/// into a palette named `name`.
+pub fn from_lospec_json(data: &str, name: &str) -> Result<CustomPalette, String> {
+    let json: serde_json::Value = serde_json::from_str(data).map_err(|_| "Lospec sent something that isn't a palette")?;
+    let colors = json["colors"].as_array().ok_or("Lospec has no such palette")?;
+    let colors = colors
+        .iter()
+        .map(|c| {
+            c.as_str()
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(parse_hex_color)
+                .ok_or_else(|| format!("Lospec sent a bad color: {}", c))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if colors.is_empty() {
+        return Err("Lospec has no such palette".to_string());
+    }
+    Ok(CustomPalette { name: name.to_string(), colors })
+}
+
+/// Download the Lospec palette with `slug`. Blocks until it arrives.
+pub fn fetch_lospec(slug: &str) -> Result<CustomPalette, String> {
+    if !is_lospec_slug(slug) {
+        return Err(format!("Not a Lospec palette name: {}", slug));
+    }
+    from_lospec_json(&crate::net::get(&lospec_url(slug))?, slug)
+}
+
 pub struct HueGroup {
     #[allow(dead_code)] // Used in tests; may be displayed in expanded sections later
     pub name: &'static str,
@@ -169,6 +323,15 @@ pub fn build_hue_groups() -> Vec<HueGroup> {
     ]
 }
 
+/// Reorder colors into ramps that read naturally: grouped by saturation,
+/// each group running dark to light.
+pub fn sort_perceptual(colors: &mut [Rgb]) {
+    colors.sort_by_key(|c| {
+        let (h, s, l) = rgb_to_hsl(c.r, c.g, c.b);
+        (s, l, h)
+    });
+}
+
 /// Convert RGB (0–255 each) to HSL. H in 0–359, S and L in 0–100.
 pub fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (u16, u8, u8) {
     let rf = r as f32 / 255.0;
@@ -240,7 +403,8 @@ pub fn hsl_to_rgb(h: u16, s: u8, l: u8) -> (u8, u8, u8) {
     )
 }
 
-/// Find the nearest xterm-256 color to an (R, G, B) value using Euclidean distance.
+/// Find the nearest xterm-256 color to an (R, G, B) value under the
+/// configured color distance (see `cell::set_color_distance`).
 /// Returns the Rgb value of the nearest match.
 pub fn nearest_color(r: u8, g: u8, b: u8) -> Rgb {
     let target = Rgb::new(r, g, b);
@@ -253,6 +417,48 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn test_share_string_round_trip() {
+        for len in 0..4 {
+            let palette = CustomPalette {
+                name: "Warm \u{2600}".to_string(),
+                colors: DEFAULT_PALETTE[6..6 + len].to_vec(),
+            };
+            let shared = to_share_string(&palette);
+            assert!(shared.starts_with(SHARE_PREFIX));
+            let parsed = from_share_string(&shared, "ignored").unwrap();
+            assert_eq!((parsed.name, parsed.colors), (palette.name, palette.colors));
+        }
+
+        let shared = to_share_string(&CustomPalette { name: "x".into(), colors: DEFAULT_PALETTE.to_vec() });
+        assert!(from_share_string(&shared[..shared.len() - 2], "x").is_err());
+        assert!(from_share_string("kakupal:!!", "x").is_err());
+    }
+
+    #[test]
+    fn test_share_string_hex_list() {
+        let parsed = from_share_string("#cd0000, ff8700\n0000ff", "lospec").unwrap();
+        assert_eq!(parsed.name, "lospec");
+        assert_eq!(parsed.colors, vec![DEFAULT_PALETTE[6], DEFAULT_PALETTE[8], DEFAULT_PALETTE[16]]);
+        assert_eq!(from_share_string("ff0000 nope", "x").unwrap_err(), "Not a hex color: nope");
+        assert!(from_share_string("a\u{e9}abc", "x").is_err());
+        assert!(from_share_string("  ", "x").is_err());
+    }
+
+    #[test]
+    fn test_from_lospec_json() {
+        let data = r#"{"name": "Two Tone", "author": "", "colors": ["cd0000", "0000FF"]}"#;
+        let parsed = from_lospec_json(data, "two-tone").unwrap();
+        assert_eq!(parsed.name, "two-tone");
+        assert_eq!(parsed.colors, vec![DEFAULT_PALETTE[6], DEFAULT_PALETTE[16]]);
+
+        assert!(from_lospec_json(r##"{"colors": ["#cd0000"]}"##, "x").is_err());
+        assert!(from_lospec_json(r#"{"error": "not found"}"#, "x").is_err());
+        assert!(from_lospec_json("<html>", "x").is_err());
+        assert!(is_lospec_slug("sweetie-16"));
+        assert!(!is_lospec_slug("../etc") && !is_lospec_slug("Sweetie") && !is_lospec_slug(""));
+    }
+
     #[test]
     fn test_default_palette_unique_and_valid() {
         let mut seen: HashSet<(u8, u8, u8)> = HashSet::new();
@@ -448,6 +654,38 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_load_palette_from_str() {
+        let pal = load_palette_from_str(r#"{"name":"duo","colors":[[1,2,3],200]}"#).unwrap();
+        assert_eq!(pal.colors, vec![Rgb::new(1, 2, 3), color256_to_rgb(200)]);
+        assert!(load_palette_from_str(r#"{"name":"bad","colors":[[1,2]]}"#).is_err());
+        assert!(load_palette_from_str(r#"{"name":"bad","colors":[999]}"#).is_err());
+    }
+
+    #[test]
+    fn test_swatch_cache_reloads_changed_file() {
+        let dir = std::env::temp_dir().join("kaku_test_swatches");
+        let _ = std::fs::create_dir_all(&dir);
+        let path = dir.join("ten.palette");
+        let colors: Vec<Rgb> = (0..10).map(color256_to_rgb).collect();
+        save_palette(&CustomPalette { name: "ten".into(), colors }, &path).unwrap();
+        // Back-date it, so the rewrite below has a different mtime even on
+        // coarse-grained file systems
+        let minute_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(minute_ago).unwrap();
+
+        let mut cache = SwatchCache::default();
+        let strip = cache.get(&path);
+        assert_eq!(strip.len(), SWATCH_COUNT);
+        assert_eq!(strip[0], color256_to_rgb(0));
+
+        save_palette(&CustomPalette { name: "ten".into(), colors: vec![Rgb::new(1, 2, 3)] }, &path).unwrap();
+        assert_eq!(cache.get(&path), vec![Rgb::new(1, 2, 3)]);
+        assert!(cache.get(&dir.join("missing.palette")).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_rename_palette() {
         let dir = std::env::temp_dir().join("kaku_test_rename_rgb");
@@ -573,4 +811,14 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn test_sort_perceptual_ramps_dark_to_light() {
+        let (dark, mid, light) = (Rgb::new(95, 0, 0), Rgb::new(175, 0, 0), Rgb::new(255, 135, 135));
+        let gray = Rgb::new(128, 128, 128);
+        let mut colors = vec![light, dark, gray, mid];
+        sort_perceptual(&mut colors);
+        assert_eq!(colors[0], gray, "unsaturated colors come first");
+        assert_eq!(&colors[1..], &[dark, mid, light]);
+    }
 }
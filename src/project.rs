@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
 use serde::{Deserialize, Serialize};
 
 use crate::canvas::Canvas;
@@ -13,6 +17,9 @@ pub struct Project {
     pub color: Rgb,
     pub symmetry: SymmetryMode,
     pub canvas: Canvas,
+    /// Recently used colors, most recent first. Absent in older files.
+    #[serde(default)]
+    pub recent_colors: Vec<Rgb>,
 }
 
 impl Project {
@@ -26,6 +33,7 @@ impl Project {
             color,
             symmetry: sym,
             canvas,
+            recent_colors: Vec::new(),
         }
     }
 
@@ -40,8 +48,15 @@ impl Project {
     pub fn load_from_file(path: &std::path::Path) -> Result<Self, String> {
         let data = std::fs::read_to_string(path)
             .map_err(|e| format!("Read error: {}", e))?;
-        let project: Project = serde_json::from_str(&data)
+        Self::load_from_str(&data)
+    }
+
+    /// Parse and validate a project from `.kaku` JSON. Never panics, whatever
+    /// the input.
+    pub fn load_from_str(data: &str) -> Result<Self, String> {
+        let project: Project = serde_json::from_str(data)
             .map_err(|e| format!("Parse error: {}", e))?;
+        project.canvas.validate()?;
         // Accept v1 (legacy 16-color), v2 (256-color), v3 (dynamic canvas), v4 (generic char), v5 (RGB)
         if project.version > 5 {
             return Err(format!(
@@ -53,13 +68,77 @@ impl Project {
     }
 }
 
+/// Header fields of a project file, shown in the open dialog before loading.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub version: u32,
+    pub width: usize,
+    pub height: usize,
+    pub modified_at: String,
+}
+
+#[derive(Deserialize)]
+struct InfoHeader {
+    version: u32,
+    name: String,
+    #[serde(default)]
+    modified_at: String,
+    canvas: InfoCanvas,
+}
+
+#[derive(Deserialize)]
+struct InfoCanvas {
+    #[serde(default = "default_width")]
+    width: usize,
+    #[serde(default = "default_height")]
+    height: usize,
+}
+
+fn default_width() -> usize { crate::canvas::DEFAULT_WIDTH }
+fn default_height() -> usize { crate::canvas::DEFAULT_HEIGHT }
+
+/// Read a project's header without building its canvas.
+pub fn read_project_info(path: &Path) -> Result<ProjectInfo, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| format!("Read error: {}", e))?;
+    let header: InfoHeader = serde_json::from_str(&data).map_err(|e| format!("Parse error: {}", e))?;
+    Ok(ProjectInfo {
+        name: header.name,
+        version: header.version,
+        width: header.canvas.width,
+        height: header.canvas.height,
+        modified_at: header.modified_at,
+    })
+}
+
+/// Project headers for the open dialog, read the first time a file is
+/// highlighted and again when its modification time changes.
+#[derive(Default)]
+pub struct InfoCache {
+    entries: HashMap<PathBuf, (Option<SystemTime>, Result<ProjectInfo, String>)>,
+}
+
+impl InfoCache {
+    pub fn get(&mut self, path: &Path) -> Result<ProjectInfo, String> {
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if let Some((cached_mtime, info)) = self.entries.get(path) {
+            if *cached_mtime == mtime {
+                return info.clone();
+            }
+        }
+        let info = read_project_info(path);
+        self.entries.insert(path.to_path_buf(), (mtime, info.clone()));
+        info
+    }
+}
+
 /// List .kaku files in the given directory, sorted by name.
 pub fn list_kaku_files(dir: &std::path::Path) -> Vec<String> {
     let mut files = Vec::new();
     if let Ok(entries) = std::fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) == Some("kaku") {
+            if path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("kaku")) {
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                     files.push(name.to_string());
                 }
@@ -70,13 +149,47 @@ pub fn list_kaku_files(dir: &std::path::Path) -> Vec<String> {
     files
 }
 
+/// Rename a project file in `dir`, updating the name stored inside it. The
+/// CLI operation log moves along. Returns the new file name.
+pub fn rename_project_file(dir: &Path, filename: &str, new_name: &str) -> Result<String, String> {
+    let new_filename = format!("{}.kaku", new_name);
+    let from = dir.join(filename);
+    let to = dir.join(&new_filename);
+    if to.exists() {
+        return Err(format!("{} already exists", new_filename));
+    }
+    let mut project = Project::load_from_file(&from)?;
+    project.name = new_name.to_string();
+    project.save_to_file(&to)?;
+    std::fs::remove_file(&from).map_err(|e| format!("Remove error: {}", e))?;
+    let log = crate::oplog::log_path(&from);
+    if log.exists() {
+        let _ = std::fs::rename(&log, crate::oplog::log_path(&to));
+    }
+    Ok(new_filename)
+}
+
+/// Copy a project file in `dir` to "NAME (Copy).kaku". Returns the new file name.
+pub fn duplicate_project_file(dir: &Path, filename: &str) -> Result<String, String> {
+    let stem = Path::new(filename).file_stem().and_then(|s| s.to_str()).unwrap_or(filename);
+    let new_filename = format!("{} (Copy).kaku", stem);
+    let to = dir.join(&new_filename);
+    if to.exists() {
+        return Err(format!("{} already exists", new_filename));
+    }
+    let mut project = Project::load_from_file(&dir.join(filename))?;
+    project.name = format!("{} (Copy)", project.name);
+    project.save_to_file(&to)?;
+    Ok(new_filename)
+}
+
 /// Find autosave files in the given directory.
 pub fn find_autosave(dir: &std::path::Path) -> Option<String> {
     if let Ok(entries) = std::fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.ends_with(".kaku.autosave") {
+                if name.to_ascii_lowercase().ends_with(".kaku.autosave") {
                     return Some(name.to_string());
                 }
             }
@@ -85,6 +198,51 @@ pub fn find_autosave(dir: &std::path::Path) -> Option<String> {
     None
 }
 
+/// Delete autosaves in `dir` that are superseded — their project was saved
+/// after them — and were last written more than `max_age` ago. An autosave
+/// whose project doesn't exist is kept: it may be the only copy of work that
+/// was never saved. Returns how many were removed.
+pub fn remove_stale_autosaves(dir: &Path, max_age: Duration) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let now = SystemTime::now();
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    let mut removed = 0;
+    for path in entries.flatten().map(|e| e.path()) {
+        let is_autosave = path.file_name().and_then(|n| n.to_str())
+            .is_some_and(|n| n.to_ascii_lowercase().ends_with(".kaku.autosave"));
+        let (Some(target), Some(written)) = (autosave_target(&path), modified(&path)) else {
+            continue;
+        };
+        if !is_autosave || now.duration_since(written).unwrap_or_default() < max_age {
+            continue;
+        }
+        let superseded = modified(&target).is_some_and(|saved| saved >= written);
+        if superseded && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// `art.kaku` -> `art.kaku.autosave`. Works on the raw OS string so
+/// non-UTF-8 and Windows paths keep their exact form.
+pub fn autosave_path(kaku_path: &Path) -> PathBuf {
+    let mut p = kaku_path.as_os_str().to_os_string();
+    p.push(".autosave");
+    PathBuf::from(p)
+}
+
+/// The project path an autosave belongs to, or None if `autosave` isn't one.
+pub fn autosave_target(autosave: &Path) -> Option<PathBuf> {
+    let name = autosave.file_name()?.to_str()?;
+    if name.len() <= ".autosave".len() || !name.to_ascii_lowercase().ends_with(".autosave") {
+        return None;
+    }
+    Some(autosave.with_file_name(&name[..name.len() - ".autosave".len()]))
+}
+
 pub(crate) fn now_iso8601() -> String {
     // Simple UTC timestamp without external crate
     use std::time::SystemTime;
@@ -201,6 +359,45 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_save_load_recent_colors() {
+        let mut project = Project::new(
+            "recent-test",
+            Canvas::new(),
+            Rgb::new(10, 20, 30),
+            SymmetryMode::Off,
+        );
+        project.recent_colors = vec![Rgb::new(10, 20, 30), Rgb::new(255, 0, 0)];
+
+        let dir = std::env::temp_dir();
+        let path = dir.join("kaku_test_recent_colors.kaku");
+        project.save_to_file(&path).unwrap();
+
+        let loaded = Project::load_from_file(&path).unwrap();
+        assert_eq!(
+            loaded.recent_colors,
+            vec![Rgb::new(10, 20, 30), Rgb::new(255, 0, 0)]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_load_without_recent_colors() {
+        let project = Project::new("old", Canvas::new(), Rgb::WHITE, SymmetryMode::Off);
+        let mut value = serde_json::to_value(&project).unwrap();
+        value.as_object_mut().unwrap().remove("recent_colors");
+
+        let dir = std::env::temp_dir();
+        let path = dir.join("kaku_test_no_recent_colors.kaku");
+        std::fs::write(&path, value.to_string()).unwrap();
+
+        let loaded = Project::load_from_file(&path).unwrap();
+        assert!(loaded.recent_colors.is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_load_legacy_v1_file() {
         // Build a valid v1-style project with string color name,
@@ -229,6 +426,15 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_load_from_str_rejects_mismatched_canvas() {
+        let json = r#"{"version":5,"name":"x","created_at":"","modified_at":"",
+            "color":[255,255,255],"symmetry":"Off",
+            "canvas":{"width":100,"height":100,"cells":[[{"ch":"a","fg":null,"bg":null}]]}}"#;
+        let err = Project::load_from_str(json).err().unwrap();
+        assert!(err.contains("Canvas"), "{}", err);
+    }
+
     #[test]
     fn test_load_invalid_file() {
         let dir = std::env::temp_dir();
@@ -257,6 +463,46 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_read_project_info() {
+        let path = std::env::temp_dir().join("kaku_test_project_info.kaku");
+        let mut project = Project::new("header", Canvas::new_with_size(20, 10), Rgb::WHITE, SymmetryMode::Off);
+        project.save_to_file(&path).unwrap();
+
+        let mut cache = InfoCache::default();
+        let info = cache.get(&path).unwrap();
+        assert_eq!(info.name, "header");
+        assert_eq!((info.width, info.height), (20, 10));
+        assert_eq!(info.version, 5);
+        assert_eq!(info.modified_at, project.modified_at);
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(read_project_info(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_rename_and_duplicate_project_file() {
+        let dir = std::env::temp_dir().join("kaku_test_file_ops");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut project = Project::new("old", Canvas::new(), Rgb::WHITE, SymmetryMode::Off);
+        project.save_to_file(&dir.join("old.kaku")).unwrap();
+        std::fs::write(crate::oplog::log_path(&dir.join("old.kaku")), "{}").unwrap();
+
+        assert_eq!(rename_project_file(&dir, "old.kaku", "new").unwrap(), "new.kaku");
+        assert!(!dir.join("old.kaku").exists());
+        assert!(dir.join("new.kaku.log").exists());
+        assert_eq!(Project::load_from_file(&dir.join("new.kaku")).unwrap().name, "new");
+
+        assert_eq!(duplicate_project_file(&dir, "new.kaku").unwrap(), "new (Copy).kaku");
+        assert_eq!(Project::load_from_file(&dir.join("new (Copy).kaku")).unwrap().name, "new (Copy)");
+        assert!(duplicate_project_file(&dir, "new.kaku").is_err());
+        assert!(rename_project_file(&dir, "new.kaku", "new (Copy)").is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_find_autosave() {
         let dir = std::env::temp_dir().join("kaku_test_autosave");
@@ -270,6 +516,62 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_remove_stale_autosaves() {
+        let dir = std::env::temp_dir().join("kaku_test_stale_autosave");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("gone.kaku.autosave"), "{}").unwrap();
+        std::fs::write(dir.join("live.kaku"), "{}").unwrap();
+        std::fs::write(dir.join("live.kaku.autosave"), "{}").unwrap();
+        // Saved after its autosave, so the autosave is superseded
+        std::fs::write(dir.join("old.kaku.autosave"), "{}").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::write(dir.join("old.kaku"), "{}").unwrap();
+        let age = |name: &str| {
+            let path = dir.join(name);
+            let f = std::fs::File::options().write(true).open(&path).unwrap();
+            f.set_modified(SystemTime::now() - Duration::from_secs(10 * 86400)).unwrap();
+        };
+        age("live.kaku");
+
+        assert_eq!(remove_stale_autosaves(&dir, Duration::from_secs(86400)), 0, "all recent");
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(remove_stale_autosaves(&dir, Duration::ZERO), 1);
+        assert!(dir.join("live.kaku.autosave").exists(), "newer than its project");
+        assert!(dir.join("gone.kaku.autosave").exists(), "may be the only copy");
+        assert!(!dir.join("old.kaku.autosave").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_autosave_path_roundtrip() {
+        let kaku = Path::new("art").join("my art.kaku");
+        let autosave = autosave_path(&kaku);
+        assert_eq!(autosave, Path::new("art").join("my art.kaku.autosave"));
+        assert_eq!(autosave_target(&autosave), Some(kaku));
+        assert_eq!(autosave_target(Path::new("art.kaku")), None);
+        assert_eq!(autosave_target(Path::new(".autosave")), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_autosave_path_windows() {
+        let kaku = Path::new(r"C:\Users\me\Art\Bear.KAKU");
+        let autosave = autosave_path(kaku);
+        assert_eq!(autosave, Path::new(r"C:\Users\me\Art\Bear.KAKU.autosave"));
+        assert_eq!(autosave_target(&autosave).as_deref(), Some(kaku));
+    }
+
+    #[test]
+    fn test_list_kaku_files_ignores_extension_case() {
+        let dir = std::env::temp_dir().join("kaku_test_list_case");
+        let _ = std::fs::create_dir_all(&dir);
+        std::fs::write(dir.join("Upper.KAKU"), "{}").unwrap();
+        assert_eq!(list_kaku_files(&dir), vec!["Upper.KAKU".to_string()]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     // --- Cycle 15 QA: File I/O roundtrip tests ---
 
     #[test]
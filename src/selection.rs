@@ -0,0 +1,125 @@
+use crate::canvas::Canvas;
+use crate::cell::{Cell, Rgb};
+
+/// A set of canvas cells that bulk edits apply to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    width: usize,
+    cells: Vec<bool>,
+}
+
+impl Selection {
+    /// Every cell whose character is `ch`.
+    pub fn of_block(canvas: &Canvas, ch: char) -> Self {
+        Self::matching(canvas, |c| c.ch == ch)
+    }
+
+    /// Every cell showing `color`: as its background, or as the foreground
+    /// of a character that isn't a space.
+    pub fn of_color(canvas: &Canvas, color: Rgb) -> Self {
+        Self::matching(canvas, |c| c.bg == Some(color) || (!c.is_empty() && c.fg == Some(color)))
+    }
+
+    fn matching(canvas: &Canvas, pred: impl Fn(&Cell) -> bool) -> Self {
+        let mut cells = vec![false; canvas.width * canvas.height];
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                if canvas.get(x, y).is_some_and(|c| pred(&c)) {
+                    cells[y * canvas.width + x] = true;
+                }
+            }
+        }
+        Selection { width: canvas.width, cells }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && self.cells.get(y * self.width + x).copied().unwrap_or(false)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.iter().filter(|&&c| c).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.cells.contains(&true)
+    }
+
+    /// Selected cells in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c)
+            .map(move |(i, _)| (i % width, i / width))
+    }
+
+    /// The selected cell after (positive `step`) or before `from` in
+    /// row-major order, wrapping around, and its position among them.
+    /// Without `from`, the first (or last) selected cell.
+    pub fn step_from(&self, from: Option<(usize, usize)>, step: isize) -> Option<(usize, (usize, usize))> {
+        let cells: Vec<(usize, usize)> = self.iter().collect();
+        let here = from.map(|(x, y)| y * self.width + x);
+        let index = |&(x, y): &(usize, usize)| y * self.width + x;
+        let found = if step > 0 {
+            cells.iter().position(|c| here.is_none_or(|h| index(c) > h)).unwrap_or(0)
+        } else {
+            cells.iter().rposition(|c| here.is_none_or(|h| index(c) < h)).unwrap_or(cells.len().checked_sub(1)?)
+        };
+        cells.get(found).map(|&c| (found, c))
+    }
+
+    /// A copy of `canvas` with only the selected cells; the rest are blank.
+    pub fn extract(&self, canvas: &Canvas) -> Canvas {
+        let mut region = Canvas::new_with_size(canvas.width, canvas.height);
+        for (x, y) in self.iter() {
+            if let Some(cell) = canvas.get(x, y) {
+                region.set(x, y, cell);
+            }
+        }
+        region
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{blocks, Cell};
+
+    #[test]
+    fn test_of_color_matches_visible_colors() {
+        let red = Rgb { r: 205, g: 0, b: 0 };
+        let mut canvas = Canvas::new_with_size(4, 4);
+        canvas.set(0, 0, Cell { ch: blocks::FULL, fg: Some(red), bg: None });
+        canvas.set(1, 0, Cell { ch: ' ', fg: None, bg: Some(red) });
+        // A space's foreground doesn't show
+        canvas.set(2, 0, Cell { ch: ' ', fg: Some(red), bg: None });
+        let sel = Selection::of_color(&canvas, red);
+        assert_eq!(sel.iter().collect::<Vec<_>>(), vec![(0, 0), (1, 0)]);
+        assert_eq!(Selection::of_color(&canvas, Rgb::BLACK).step_from(None, 1), None);
+    }
+
+    #[test]
+    fn test_of_block_selects_matching_chars() {
+        let mut canvas = Canvas::new_with_size(8, 8);
+        let shade = Cell { ch: blocks::SHADE_MEDIUM, fg: None, bg: None };
+        canvas.set(1, 0, shade);
+        canvas.set(3, 2, shade);
+        canvas.set(2, 2, Cell { ch: blocks::FULL, fg: None, bg: None });
+        let sel = Selection::of_block(&canvas, blocks::SHADE_MEDIUM);
+        assert_eq!(sel.len(), 2);
+        assert!(sel.contains(3, 2));
+        assert!(!sel.contains(2, 2));
+        assert!(!sel.contains(8, 0), "out of bounds");
+        assert_eq!(sel.iter().collect::<Vec<_>>(), vec![(1, 0), (3, 2)]);
+
+        assert_eq!(sel.step_from(Some((1, 0)), 1), Some((1, (3, 2))));
+        assert_eq!(sel.step_from(Some((3, 2)), 1), Some((0, (1, 0))), "wraps");
+        assert_eq!(sel.step_from(Some((0, 0)), -1), Some((1, (3, 2))));
+        assert_eq!(sel.step_from(None, -1), Some((1, (3, 2))));
+
+        let region = sel.extract(&canvas);
+        assert_eq!(region.get(3, 2), Some(shade));
+        assert!(region.get(2, 2).unwrap().is_empty());
+    }
+}
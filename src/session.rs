@@ -0,0 +1,220 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::App;
+use crate::command::Command;
+
+/// Opt-in record of every command executed in a TUI session, for
+/// reproducing editing bugs with `--replay`.
+///
+/// On disk a log is JSON lines: a header with the version and file, then
+/// one line per command. Lines are written as commands run, so a session
+/// that crashes still leaves everything up to its last flush.
+#[derive(Serialize, Deserialize)]
+pub struct SessionLog {
+    pub version: u32,
+    /// File opened at startup, if any
+    pub file: Option<String>,
+    #[serde(skip)]
+    pub commands: Vec<LoggedCommand>,
+    #[serde(skip, default = "Instant::now")]
+    started: Instant,
+    #[serde(skip)]
+    out: Option<BufWriter<File>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoggedCommand {
+    /// Milliseconds since the session started
+    pub ms: u64,
+    pub cmd: Command,
+}
+
+impl SessionLog {
+    pub fn new(file: Option<String>) -> Self {
+        SessionLog {
+            version: 2,
+            file,
+            commands: Vec::new(),
+            started: Instant::now(),
+            out: None,
+        }
+    }
+
+    /// Start a log that streams to `path`, writing its header right away.
+    pub fn create(path: &Path, file: Option<String>) -> Result<Self, String> {
+        let mut log = SessionLog::new(file);
+        let mut out = BufWriter::new(File::create(path).map_err(|e| format!("Write error: {}", e))?);
+        write_line(&mut out, &log)?;
+        out.flush().map_err(|e| format!("Write error: {}", e))?;
+        log.out = Some(out);
+        Ok(log)
+    }
+
+    pub fn record(&mut self, cmd: &Command) {
+        let entry = LoggedCommand {
+            ms: self.started.elapsed().as_millis() as u64,
+            cmd: cmd.clone(),
+        };
+        if let Some(ref mut out) = self.out {
+            if write_line(out, &entry).is_err() {
+                self.out = None;
+            }
+        }
+        self.commands.push(entry);
+    }
+
+    /// Push buffered commands to disk. Called once per batch of input, so
+    /// recording costs one write per frame rather than one per command.
+    pub fn flush(&mut self) {
+        if let Some(ref mut out) = self.out {
+            if out.flush().is_err() {
+                self.out = None;
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut out = BufWriter::new(File::create(path).map_err(|e| format!("Write error: {}", e))?);
+        write_line(&mut out, self)?;
+        for entry in &self.commands {
+            write_line(&mut out, entry)?;
+        }
+        out.flush().map_err(|e| format!("Write error: {}", e))
+    }
+
+    /// Read a log. A log cut short by a crash loads up to its last
+    /// complete line; version 1 logs, one JSON object, load too.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("Read error: {}", e))?;
+        if let Ok(v1) = serde_json::from_str::<LegacyLog>(&data) {
+            let mut log = SessionLog::new(v1.file);
+            log.commands = v1.commands;
+            return Ok(log);
+        }
+        let mut lines = data.lines();
+        let header = lines.next().ok_or("Parse error: empty log")?;
+        let mut log: SessionLog = serde_json::from_str(header).map_err(|e| format!("Parse error: {}", e))?;
+        log.commands = lines.map_while(|line| serde_json::from_str(line).ok()).collect();
+        Ok(log)
+    }
+}
+
+/// The single JSON object `--record-session` wrote before logs streamed.
+#[derive(Deserialize)]
+struct LegacyLog {
+    file: Option<String>,
+    commands: Vec<LoggedCommand>,
+}
+
+fn write_line<T: Serialize>(out: &mut impl Write, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|e| format!("Serialize error: {}", e))?;
+    writeln!(out, "{}", json).map_err(|e| format!("Write error: {}", e))
+}
+
+/// Replay a session against a fresh app, without a terminal. Commands that
+/// reach outside the editor (saves, palette file edits, clipboard copies)
+/// are skipped so replaying never writes to disk or the clipboard.
+pub fn replay(log: &SessionLog) -> App {
+    let mut app = App::new();
+    if let Some(ref file) = log.file {
+        app.load_project(file);
+    }
+    replay_onto(&mut app, log);
+    app
+}
+
+/// Run the logged commands against `app`, skipping side effects as
+/// [`replay`] does.
+pub fn replay_onto(app: &mut App, log: &SessionLog) {
+    for entry in &log.commands {
+        if !entry.cmd.has_side_effects() {
+            app.execute(entry.cmd.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolKind;
+
+    #[test]
+    fn test_recorded_session_replays_to_same_canvas() {
+        let mut app = App::new();
+        app.session_log = Some(SessionLog::new(None));
+        app.execute(Command::SelectTool(ToolKind::Rectangle));
+        app.execute(Command::ToggleFilledRect);
+        app.execute(Command::Press { x: 2, y: 2, unmirrored: false });
+        app.execute(Command::Press { x: 5, y: 4, unmirrored: false });
+        app.execute(Command::Release);
+
+        let log = app.session_log.take().unwrap();
+        assert_eq!(log.commands.len(), 5);
+        let replayed = replay(&log);
+        assert_eq!(replayed.canvas, app.canvas);
+        assert!(!replayed.canvas.get(4, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_save_load_log() {
+        let path = std::env::temp_dir().join("kaku_test_session.json");
+        let mut log = SessionLog::new(Some("art.kaku".to_string()));
+        log.record(&Command::CycleZoom);
+        log.save(&path).unwrap();
+
+        let loaded = SessionLog::load(&path).unwrap();
+        assert_eq!(loaded.file.as_deref(), Some("art.kaku"));
+        assert_eq!(loaded.commands[0].cmd, Command::CycleZoom);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_streamed_log_survives_without_a_clean_exit() {
+        let path = std::env::temp_dir().join("kaku_test_session_stream.jsonl");
+        let mut log = SessionLog::create(&path, None).unwrap();
+        log.record(&Command::CycleZoom);
+        log.record(&Command::ToggleMinimap);
+        log.flush();
+        // Leaked, as if the process died before the log was dropped
+        std::mem::forget(log);
+        let mut data = std::fs::read_to_string(&path).unwrap();
+        data.push_str("{\"ms\":12,\"cmd\"");
+        std::fs::write(&path, data).unwrap();
+
+        let loaded = SessionLog::load(&path).unwrap();
+        assert_eq!(loaded.commands.len(), 2, "a torn last line is dropped");
+        assert_eq!(loaded.commands[1].cmd, Command::ToggleMinimap);
+
+        // Logs from before streaming still load
+        std::fs::write(&path, r#"{"version":1,"file":null,"commands":[{"ms":0,"cmd":"Undo"}]}"#).unwrap();
+        assert_eq!(SessionLog::load(&path).unwrap().commands[0].cmd, Command::Undo);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_replay_skips_side_effects() {
+        let name = std::env::temp_dir().join(format!("kaku_test_replay_{}", std::process::id()));
+        let file = name.with_extension("palette");
+        let mut log = SessionLog::new(None);
+        for cmd in [Command::AddColorToPalette, Command::MovePaletteColors(1), Command::CopySelectionAnsi, Command::Save] {
+            assert!(cmd.has_side_effects());
+            log.record(&cmd);
+        }
+        log.record(&Command::CycleZoom);
+        let mut app = App::new();
+        app.custom_palette = Some(crate::palette::CustomPalette {
+            name: name.to_string_lossy().into_owned(),
+            colors: Vec::new(),
+        });
+        replay_onto(&mut app, &log);
+        assert!(!file.exists());
+        assert!(app.custom_palette.unwrap().colors.is_empty());
+        assert_eq!(app.zoom, 2);
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::canvas::MAX_DIMENSION;
+use crate::cell::ColorDistance;
+use crate::export::{CellAspect, TerminalPalette};
+use crate::tools::{DashPattern, LineStyle};
+
+/// Most cells the spray tool paints per tick.
+const MAX_SPRAY_DENSITY: usize = 256;
+
+/// Longest frame delay a GIF can hold, in milliseconds.
+const MAX_GIF_FRAME_MS: u32 = u16::MAX as u32 * 10;
+
+/// How the canvas cursor is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorStyle {
+    /// Swap the cell's foreground and background colors
+    #[default]
+    Swap,
+    /// The cell's glyph in its background color on the theme's highlight
+    Inverse,
+    /// Reverse video that blinks
+    Blink,
+    /// `[` and `]` around the cell's column on the canvas border, or in the
+    /// cell's padding at 4x, leaving the art visible
+    Brackets,
+    /// Tint the cursor's row and column, with tick marks on the canvas border
+    Crosshair,
+}
+
+/// User preferences from `settings.json`. Missing fields keep their defaults.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub cursor_style: CursorStyle,
+    /// Show a dimmed preview of what the pencil would paint under the cursor
+    pub ghost_preview: bool,
+    /// Patterns for the dashed and dotted line styles
+    pub dash: DashPattern,
+    pub dot: DashPattern,
+    /// How far the spray tool scatters from the cursor, in cells
+    pub spray_radius: usize,
+    /// Cells the spray tool paints per tick while the mouse is down, up
+    /// to 256
+    pub spray_density: usize,
+    /// Terminal cell width:height for PNG exports, or "auto" to ask the
+    /// terminal
+    pub cell_aspect: CellAspect,
+    /// Gamma adjustment for PNG exports, from 0.1 to 10; 1.0 leaves colors
+    /// as they are
+    pub export_gamma: f32,
+    /// Keep recent undo steps in a `.kaku.history` file beside the project
+    pub persist_history: bool,
+    /// Days before orphaned autosaves are deleted at startup; 0 keeps them
+    pub autosave_retention_days: u64,
+    /// How nearest colors are found when quantizing to 256 or 16 colors or
+    /// to a palette: "rgb" or "cielab"
+    pub color_distance: ColorDistance,
+    /// The destination terminal's 16 ANSI colors, for 16-color exports
+    pub terminal_palette: TerminalPalette,
+    /// Warn when a text export is larger than this many bytes; 0 for no limit
+    pub export_budget: u64,
+    /// How long each undo step shows in a history GIF, in milliseconds,
+    /// from 10 to 655350
+    pub gif_frame_ms: u32,
+    /// Let kakukuma reach the internet, to download Lospec palettes
+    pub allow_network: bool,
+    /// Look for a newer release at startup; needs `allow_network` too
+    pub check_updates: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            cursor_style: CursorStyle::default(),
+            ghost_preview: true,
+            dash: DashPattern { on: 3, off: 2 },
+            dot: DashPattern { on: 1, off: 1 },
+            spray_radius: 3,
+            spray_density: 4,
+            cell_aspect: CellAspect::default(),
+            export_gamma: 1.0,
+            persist_history: false,
+            autosave_retention_days: 7,
+            color_distance: ColorDistance::default(),
+            terminal_palette: TerminalPalette::default(),
+            export_budget: 0,
+            gif_frame_ms: 150,
+            allow_network: false,
+            check_updates: false,
+        }
+    }
+}
+
+impl Settings {
+    /// The dash pattern for a line style; `None` draws solid.
+    pub fn pattern(&self, style: LineStyle) -> Option<DashPattern> {
+        match style {
+            LineStyle::Solid => None,
+            LineStyle::Dashed => Some(self.dash),
+            LineStyle::Dotted => Some(self.dot),
+        }
+    }
+
+    /// Where settings live (`~/.config/kakukuma/settings.json` on Linux).
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("kakukuma").join("settings.json"))
+    }
+
+    /// Load settings from a JSON object. A missing file means defaults.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Settings::default());
+        }
+        let data = std::fs::read_to_string(path).map_err(|e| format!("Read error: {}", e))?;
+        let mut settings: Settings =
+            serde_json::from_str(&data).map_err(|e| format!("Parse error: {}", e))?;
+        settings.clamp();
+        Ok(settings)
+    }
+
+    /// Pull hand-edited values back into the range the tools can use.
+    fn clamp(&mut self) {
+        // A pattern that never draws would make dashed lines invisible
+        self.dash.on = self.dash.on.max(1);
+        self.dot.on = self.dot.on.max(1);
+        // A radius past the largest canvas only wastes throws off the edge
+        self.spray_radius = self.spray_radius.min(MAX_DIMENSION);
+        self.spray_density = self.spray_density.clamp(1, MAX_SPRAY_DENSITY);
+        // The same range `--gamma` accepts; 0 would turn every pixel to NaN
+        self.export_gamma = self.export_gamma.clamp(0.1, 10.0);
+        // GIF delays count hundredths of a second in 16 bits
+        self.gif_frame_ms = self.gif_frame_ms.clamp(10, MAX_GIF_FRAME_MS);
+    }
+
+    /// The user's settings, or defaults if there is no settings file.
+    pub fn load_user() -> Result<Self, String> {
+        match Settings::config_path() {
+            Some(path) => Settings::load(&path),
+            None => Ok(Settings::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_fields_and_file() {
+        let dir = std::env::temp_dir().join("kaku_test_settings");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+        std::fs::write(&path, r#"{"cursor_style": "crosshair"}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().cursor_style, CursorStyle::Crosshair);
+        std::fs::write(&path, r#"{"cursor_style": "sparkles"}"#).unwrap();
+        assert!(Settings::load(&path).unwrap_err().starts_with("Parse error"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_clamps_out_of_range_values() {
+        let dir = std::env::temp_dir().join("kaku_test_settings_clamp");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        std::fs::write(&path, r#"{"dash": {"on": 0, "off": 2}, "dot": {"on": 0, "off": 0}}"#).unwrap();
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!(settings.dash, DashPattern { on: 1, off: 2 });
+        assert_eq!(settings.dot, DashPattern { on: 1, off: 0 });
+
+        std::fs::write(&path, r#"{"spray_radius": 99999999999, "spray_density": 99999999999}"#).unwrap();
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!((settings.spray_radius, settings.spray_density), (MAX_DIMENSION, MAX_SPRAY_DENSITY));
+        std::fs::write(&path, r#"{"spray_density": 0}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().spray_density, 1);
+
+        std::fs::write(&path, r#"{"export_gamma": 0}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().export_gamma, 0.1);
+        std::fs::write(&path, r#"{"export_gamma": -3.5}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().export_gamma, 0.1);
+        std::fs::write(&path, r#"{"export_gamma": 1e30}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().export_gamma, 10.0);
+
+        std::fs::write(&path, r#"{"gif_frame_ms": 4294967295}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().gif_frame_ms, MAX_GIF_FRAME_MS);
+        std::fs::write(&path, r#"{"gif_frame_ms": 0}"#).unwrap();
+        assert_eq!(Settings::load(&path).unwrap().gif_frame_ms, 10);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
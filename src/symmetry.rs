@@ -8,12 +8,26 @@ pub enum SymmetryMode {
     Horizontal,
     Vertical,
     Quad,
+    /// Rotated copies at 90, 180 and 270 degrees around the canvas center
+    Radial4,
+    /// Radial4 plus the mirror image of each rotation
+    Radial8,
 }
 
 impl SymmetryMode {
+    /// Every mode, in the order the symmetry picker lists them.
+    pub const ALL: [SymmetryMode; 6] = [
+        SymmetryMode::Off,
+        SymmetryMode::Horizontal,
+        SymmetryMode::Vertical,
+        SymmetryMode::Quad,
+        SymmetryMode::Radial4,
+        SymmetryMode::Radial8,
+    ];
+
     pub fn toggle_horizontal(self) -> SymmetryMode {
         match self {
-            SymmetryMode::Off => SymmetryMode::Horizontal,
+            SymmetryMode::Off | SymmetryMode::Radial4 | SymmetryMode::Radial8 => SymmetryMode::Horizontal,
             SymmetryMode::Horizontal => SymmetryMode::Off,
             SymmetryMode::Vertical => SymmetryMode::Quad,
             SymmetryMode::Quad => SymmetryMode::Vertical,
@@ -22,7 +36,7 @@ impl SymmetryMode {
 
     pub fn toggle_vertical(self) -> SymmetryMode {
         match self {
-            SymmetryMode::Off => SymmetryMode::Vertical,
+            SymmetryMode::Off | SymmetryMode::Radial4 | SymmetryMode::Radial8 => SymmetryMode::Vertical,
             SymmetryMode::Vertical => SymmetryMode::Off,
             SymmetryMode::Horizontal => SymmetryMode::Quad,
             SymmetryMode::Quad => SymmetryMode::Horizontal,
@@ -37,12 +51,30 @@ impl SymmetryMode {
         matches!(self, SymmetryMode::Vertical | SymmetryMode::Quad)
     }
 
+    pub fn is_radial(self) -> bool {
+        matches!(self, SymmetryMode::Radial4 | SymmetryMode::Radial8)
+    }
+
     pub fn label(self) -> &'static str {
         match self {
             SymmetryMode::Off => "Off",
             SymmetryMode::Horizontal => "Horiz",
             SymmetryMode::Vertical => "Vert",
             SymmetryMode::Quad => "Quad",
+            SymmetryMode::Radial4 => "Radial 4",
+            SymmetryMode::Radial8 => "Radial 8",
+        }
+    }
+
+    /// One-line explanation for the symmetry picker.
+    pub fn description(self) -> &'static str {
+        match self {
+            SymmetryMode::Off => "No mirroring",
+            SymmetryMode::Horizontal => "Mirror left and right",
+            SymmetryMode::Vertical => "Mirror top and bottom",
+            SymmetryMode::Quad => "Mirror both ways",
+            SymmetryMode::Radial4 => "Rotate 4 ways around center",
+            SymmetryMode::Radial8 => "Rotate and mirror, 8 ways",
         }
     }
 }
@@ -58,37 +90,74 @@ pub fn apply_symmetry(mutations: Vec<CellMutation>, mode: SymmetryMode, width: u
 
     for m in &mutations {
         result.push(m.clone());
-
-        if mode.has_horizontal() {
-            let mx = width - 1 - m.x;
-            if mx != m.x {
-                let mut mirrored = m.clone();
-                mirrored.x = mx;
-                result.push(mirrored);
-            }
+        for (x, y) in mirrors(m.x, m.y, mode, width, height) {
+            result.push(CellMutation { x, y, ..m.clone() });
         }
+    }
 
-        if mode.has_vertical() {
-            let my = height - 1 - m.y;
-            if my != m.y {
-                let mut mirrored = m.clone();
-                mirrored.y = my;
-                result.push(mirrored);
-            }
-        }
+    result
+}
 
-        if mode == SymmetryMode::Quad {
-            let mx = width - 1 - m.x;
-            let my = height - 1 - m.y;
-            if mx != m.x && my != m.y {
-                let mut mirrored = m.clone();
-                mirrored.x = mx;
-                mirrored.y = my;
-                result.push(mirrored);
-            }
+/// Points plus their mirrored copies, for previewing what a shape will
+/// paint once symmetry is applied.
+pub fn mirror_points(points: Vec<(usize, usize)>, mode: SymmetryMode, width: usize, height: usize) -> Vec<(usize, usize)> {
+    if mode == SymmetryMode::Off {
+        return points;
+    }
+    let mut result = Vec::with_capacity(points.len() * 4);
+    for &(x, y) in &points {
+        result.push((x, y));
+        if x < width && y < height {
+            result.extend(mirrors(x, y, mode, width, height));
         }
     }
+    result
+}
+
+/// The distinct mirror images of (x, y), excluding the point itself.
+fn mirrors(x: usize, y: usize, mode: SymmetryMode, width: usize, height: usize) -> Vec<(usize, usize)> {
+    if mode.is_radial() {
+        return rotations(x, y, mode == SymmetryMode::Radial8, width, height);
+    }
+    let mx = width - 1 - x;
+    let my = height - 1 - y;
+    let mut result = Vec::with_capacity(3);
+    if mode.has_horizontal() && mx != x {
+        result.push((mx, y));
+    }
+    if mode.has_vertical() && my != y {
+        result.push((x, my));
+    }
+    if mode == SymmetryMode::Quad && mx != x && my != y {
+        result.push((mx, my));
+    }
+    result
+}
+
+/// Copies of (x, y) rotated a quarter turn at a time around the canvas
+/// center, in cell space, and with `mirrored` each rotation's left-right
+/// mirror image too. On a canvas that isn't square, copies that would land
+/// outside it are dropped.
+fn rotations(x: usize, y: usize, mirrored: bool, width: usize, height: usize) -> Vec<(usize, usize)> {
+    // Offsets from the center in half cells, so even sizes rotate exactly
+    let (w, h) = (width as i64 - 1, height as i64 - 1);
+    let (dx, dy) = (2 * x as i64 - w, 2 * y as i64 - h);
+    let mut offsets = vec![(-dy, dx), (-dx, -dy), (dy, -dx)];
+    if mirrored {
+        offsets.extend([(-dx, dy), (dy, dx), (dx, -dy), (-dy, -dx)]);
+    }
 
+    let mut result = Vec::with_capacity(offsets.len());
+    for (ox, oy) in offsets {
+        let (rx, ry) = ((ox + w).div_euclid(2), (oy + h).div_euclid(2));
+        if rx < 0 || ry < 0 || rx > w || ry > h {
+            continue;
+        }
+        let point = (rx as usize, ry as usize);
+        if point != (x, y) && !result.contains(&point) {
+            result.push(point);
+        }
+    }
     result
 }
 
@@ -146,6 +215,40 @@ mod tests {
         assert_eq!((result[3].x, result[3].y), (26, 21));
     }
 
+    #[test]
+    fn test_mirror_points_matches_mutations() {
+        let points = vec![(1, 2), (4, 2)];
+        assert_eq!(mirror_points(points.clone(), SymmetryMode::Off, 10, 10), points);
+        assert_eq!(
+            mirror_points(points, SymmetryMode::Quad, 10, 10),
+            vec![(1, 2), (8, 2), (1, 7), (8, 7), (4, 2), (5, 2), (4, 7), (5, 7)]
+        );
+        let muts = apply_symmetry(vec![make_mutation(1, 2)], SymmetryMode::Quad, 10, 10);
+        let from_muts: Vec<_> = muts.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(mirror_points(vec![(1, 2)], SymmetryMode::Quad, 10, 10), from_muts);
+    }
+
+    #[test]
+    fn test_radial_rotates_around_center() {
+        let result = apply_symmetry(vec![make_mutation(2, 1)], SymmetryMode::Radial4, 10, 10);
+        let points: Vec<_> = result.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(points, vec![(2, 1), (8, 2), (7, 8), (1, 7)]);
+
+        let result = apply_symmetry(vec![make_mutation(2, 1)], SymmetryMode::Radial8, 10, 10);
+        let points: Vec<_> = result.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(points, vec![(2, 1), (8, 2), (7, 8), (1, 7), (7, 1), (1, 2), (2, 8), (8, 7)]);
+    }
+
+    #[test]
+    fn test_radial_skips_center_and_out_of_bounds() {
+        // The center of an odd canvas maps to itself
+        assert_eq!(apply_symmetry(vec![make_mutation(2, 2)], SymmetryMode::Radial8, 5, 5).len(), 1);
+        // On a wide canvas, quarter turns of a far-left point fall outside
+        let result = apply_symmetry(vec![make_mutation(0, 2)], SymmetryMode::Radial4, 20, 6);
+        let points: Vec<_> = result.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(points, vec![(0, 2), (19, 3)]);
+    }
+
     #[test]
     fn test_center_axis_no_duplicate() {
         // Point on the horizontal center axis (x=15, x mirrored = 16, not same)
@@ -0,0 +1,92 @@
+use crate::export::ColorFormat;
+
+/// Detect how many colors the attached terminal can show, from `COLORTERM`,
+/// `TERM`, and as a last resort terminfo via `tput colors`.
+pub fn detect_color_support() -> ColorFormat {
+    let colorterm = std::env::var("COLORTERM").ok();
+    let term = std::env::var("TERM").ok();
+    // Windows Terminal supports 24-bit color but sets neither variable
+    if colorterm.is_none() && term.is_none() && std::env::var_os("WT_SESSION").is_some() {
+        return ColorFormat::TrueColor;
+    }
+    color_support_from_env(colorterm.as_deref(), term.as_deref())
+        .or_else(terminfo_colors)
+        .unwrap_or(ColorFormat::Color256)
+}
+
+/// Color support implied by the environment, if it says anything definite.
+pub fn color_support_from_env(colorterm: Option<&str>, term: Option<&str>) -> Option<ColorFormat> {
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return Some(ColorFormat::TrueColor);
+    }
+    let term = term?;
+    if term.ends_with("-direct") {
+        Some(ColorFormat::TrueColor)
+    } else if term.contains("256color") {
+        Some(ColorFormat::Color256)
+    } else if matches!(term, "linux" | "vt100" | "vt220" | "ansi" | "dumb")
+        || term.ends_with("-16color")
+    {
+        Some(ColorFormat::Color16)
+    } else {
+        None
+    }
+}
+
+fn terminfo_colors() -> Option<ColorFormat> {
+    let out = std::process::Command::new("tput").arg("colors").output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let colors: u32 = String::from_utf8_lossy(&out.stdout).trim().parse().ok()?;
+    Some(match colors {
+        0x100_0000.. => ColorFormat::TrueColor,
+        256.. => ColorFormat::Color256,
+        _ => ColorFormat::Color16,
+    })
+}
+
+/// A terminal cell's size in pixels, if the terminal reports its pixel size.
+pub fn detect_cell_size() -> Option<(u32, u32)> {
+    let size = crossterm::terminal::window_size().ok()?;
+    if size.width == 0 || size.height == 0 || size.columns == 0 || size.rows == 0 {
+        return None;
+    }
+    Some(((size.width / size.columns) as u32, (size.height / size.rows) as u32))
+        .filter(|&(w, h)| w > 0 && h > 0)
+}
+
+/// Whether the user asked for no color via the `NO_COLOR` convention
+/// (set to any non-empty value).
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// Short label for the status bar.
+pub fn color_support_label(format: ColorFormat) -> &'static str {
+    match format {
+        ColorFormat::TrueColor => "24-bit",
+        ColorFormat::Color256 => "256-color",
+        ColorFormat::Color16 => "16-color",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colorterm_truecolor() {
+        assert_eq!(color_support_from_env(Some("truecolor"), Some("xterm")), Some(ColorFormat::TrueColor));
+        assert_eq!(color_support_from_env(Some("24bit"), None), Some(ColorFormat::TrueColor));
+    }
+
+    #[test]
+    fn test_term_names() {
+        assert_eq!(color_support_from_env(None, Some("xterm-256color")), Some(ColorFormat::Color256));
+        assert_eq!(color_support_from_env(None, Some("xterm-direct")), Some(ColorFormat::TrueColor));
+        assert_eq!(color_support_from_env(None, Some("linux")), Some(ColorFormat::Color16));
+        assert_eq!(color_support_from_env(None, Some("xterm")), None);
+        assert_eq!(color_support_from_env(None, None), None);
+    }
+}
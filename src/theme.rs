@@ -13,7 +13,7 @@ pub struct Theme {
     pub grid_odd: Color,
 }
 
-pub const THEMES: [Theme; 3] = [WARM, NEON, DARK];
+pub const THEMES: [Theme; 4] = [WARM, NEON, DARK, CONTRAST];
 
 pub const WARM: Theme = Theme {
     name: "Warm",
@@ -54,13 +54,27 @@ pub const DARK: Theme = Theme {
     grid_odd: Color::Indexed(235),
 };
 
+/// Black panels, white text and bright yellow highlights for low vision.
+pub const CONTRAST: Theme = Theme {
+    name: "Contrast",
+    border_accent: Color::Indexed(231),
+    header_bg: Color::Indexed(16),
+    highlight: Color::Indexed(226),
+    accent: Color::Indexed(51),
+    dim: Color::Indexed(250),
+    separator: Color::Indexed(231),
+    panel_bg: Color::Indexed(16),
+    grid_even: Color::Indexed(16),
+    grid_odd: Color::Indexed(238),
+};
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_themes_count() {
-        assert_eq!(THEMES.len(), 3);
+        assert_eq!(THEMES.len(), 4);
     }
 
     #[test]
@@ -68,6 +82,7 @@ mod tests {
         assert_eq!(THEMES[0].name, "Warm");
         assert_eq!(THEMES[1].name, "Neon");
         assert_eq!(THEMES[2].name, "Dark");
+        assert_eq!(THEMES[3].name, "Contrast");
     }
 
     #[test]
@@ -1,8 +1,10 @@
+use serde::{Deserialize, Serialize};
+
 use crate::canvas::Canvas;
 use crate::cell::{Cell, Rgb};
 use crate::history::CellMutation;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum ToolKind {
     Pencil,
     Eraser,
@@ -10,6 +12,7 @@ pub enum ToolKind {
     Rectangle,
     Fill,
     Eyedropper,
+    Spray,
 }
 
 impl ToolKind {
@@ -21,6 +24,7 @@ impl ToolKind {
             ToolKind::Rectangle => "Rect",
             ToolKind::Fill => "Fill",
             ToolKind::Eyedropper => "Pick",
+            ToolKind::Spray => "Spray",
         }
     }
 
@@ -32,30 +36,61 @@ impl ToolKind {
             ToolKind::Rectangle => "\u{25AD}", // ▭
             ToolKind::Fill => "\u{25C9}",      // ◉
             ToolKind::Eyedropper => "\u{25C8}", // ◈
+            ToolKind::Spray => "\u{2234}",    // ∴
         }
     }
 
-    pub fn key(self) -> &'static str {
-        match self {
-            ToolKind::Pencil => "P",
-            ToolKind::Eraser => "E",
-            ToolKind::Line => "L",
-            ToolKind::Rectangle => "R",
-            ToolKind::Fill => "F",
-            ToolKind::Eyedropper => "I",
-        }
-    }
-
-    pub const ALL: [ToolKind; 6] = [
+    pub const ALL: [ToolKind; 7] = [
         ToolKind::Pencil,
         ToolKind::Eraser,
         ToolKind::Line,
         ToolKind::Rectangle,
         ToolKind::Fill,
         ToolKind::Eyedropper,
+        ToolKind::Spray,
     ];
 }
 
+/// Which cells a flood fill spreads through.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum FillMode {
+    /// Cells identical to the start cell; each becomes the new cell
+    #[default]
+    Cell,
+    /// Cells with the start cell's character, whatever their color; each is
+    /// recolored and keeps its character
+    Char,
+    /// Cells with the start cell's colors, whatever their character; each
+    /// becomes the new cell
+    Color,
+}
+
+impl FillMode {
+    pub fn next(self) -> Self {
+        match self {
+            FillMode::Cell => FillMode::Char,
+            FillMode::Char => FillMode::Color,
+            FillMode::Color => FillMode::Cell,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FillMode::Cell => "cell",
+            FillMode::Char => "char",
+            FillMode::Color => "color",
+        }
+    }
+
+    fn matches(self, cell: Cell, target: Cell) -> bool {
+        match self {
+            FillMode::Cell => cell == target,
+            FillMode::Char => cell.ch == target.ch,
+            FillMode::Color => cell.fg == target.fg && cell.bg == target.bg,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum ToolState {
     Idle,
@@ -129,6 +164,26 @@ pub fn bresenham_line(x0: usize, y0: usize, x1: usize, y1: usize) -> Vec<(usize,
     points
 }
 
+/// The points of a freehand path worth keeping for a one-cell-wide line:
+/// a point whose neighbors on the path touch diagonally is an L-shaped
+/// corner, doubling the line there, and is dropped.
+pub fn pixel_perfect(path: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut kept: Vec<(usize, usize)> = Vec::new();
+    for &p in path {
+        if kept.last() == Some(&p) {
+            continue;
+        }
+        if let [.., a, b] = kept[..] {
+            let diagonal = a.0.abs_diff(p.0) == 1 && a.1.abs_diff(p.1) == 1;
+            if diagonal && (b == (a.0, p.1) || b == (p.0, a.1)) {
+                kept.pop();
+            }
+        }
+        kept.push(p);
+    }
+    kept
+}
+
 /// Draw a line from (x0,y0) to (x1,y1).
 #[allow(clippy::too_many_arguments)]
 pub fn line(
@@ -141,10 +196,14 @@ pub fn line(
     fg: Option<Rgb>,
     bg: Option<Rgb>,
 ) -> Vec<CellMutation> {
-    let points = bresenham_line(x0, y0, x1, y1);
+    plot(canvas, &bresenham_line(x0, y0, x1, y1), ch, fg, bg)
+}
+
+/// Place the same cell at each point.
+pub fn plot(canvas: &Canvas, points: &[(usize, usize)], ch: char, fg: Option<Rgb>, bg: Option<Rgb>) -> Vec<CellMutation> {
     let new = Cell { ch, fg, bg };
     let mut mutations = Vec::new();
-    for (x, y) in points {
+    for &(x, y) in points {
         if let Some(old) = canvas.get(x, y) {
             if old != new {
                 mutations.push(CellMutation { x, y, old, new });
@@ -154,6 +213,173 @@ pub fn line(
     mutations
 }
 
+/// Outline of the rectangle with corners (x0,y0) and (x1,y1), walked
+/// clockwise from the top-left so a dash pattern runs around it.
+pub fn rect_outline_points(x0: usize, y0: usize, x1: usize, y1: usize) -> Vec<(usize, usize)> {
+    let (min_x, max_x) = (x0.min(x1), x0.max(x1));
+    let (min_y, max_y) = (y0.min(y1), y0.max(y1));
+    let mut points: Vec<(usize, usize)> = (min_x..=max_x).map(|x| (x, min_y)).collect();
+    points.extend((min_y + 1..=max_y).map(|y| (max_x, y)));
+    if max_y > min_y {
+        points.extend((min_x..max_x).rev().map(|x| (x, max_y)));
+    }
+    if max_x > min_x {
+        points.extend((min_y + 1..max_y).rev().map(|y| (min_x, y)));
+    }
+    points
+}
+
+/// Cells drawn then skipped along a dashed or dotted outline.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct DashPattern {
+    pub on: usize,
+    pub off: usize,
+}
+
+impl DashPattern {
+    /// Keep the points that fall on the pattern's drawn part.
+    pub fn mask(self, points: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        let period = (self.on + self.off).max(1);
+        points
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % period < self.on)
+            .map(|(_, p)| p)
+            .collect()
+    }
+}
+
+/// Stroke style for the Line tool and rectangle outlines.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    pub fn next(self) -> Self {
+        match self {
+            LineStyle::Solid => LineStyle::Dashed,
+            LineStyle::Dashed => LineStyle::Dotted,
+            LineStyle::Dotted => LineStyle::Solid,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineStyle::Solid => "solid",
+            LineStyle::Dashed => "dashed",
+            LineStyle::Dotted => "dotted",
+        }
+    }
+}
+
+/// Which cells the pencil paints, so large areas can be shaded with a
+/// texture in one stroke. Patterns are anchored to the canvas, so
+/// overlapping strokes line up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum BrushPattern {
+    #[default]
+    Solid,
+    /// Alternating 2x2 squares
+    Checker,
+    /// One cell in four
+    Quarter,
+    /// Every other cell
+    Half,
+    /// Three cells in four
+    ThreeQuarters,
+}
+
+impl BrushPattern {
+    pub fn next(self) -> Self {
+        match self {
+            BrushPattern::Solid => BrushPattern::Checker,
+            BrushPattern::Checker => BrushPattern::Quarter,
+            BrushPattern::Quarter => BrushPattern::Half,
+            BrushPattern::Half => BrushPattern::ThreeQuarters,
+            BrushPattern::ThreeQuarters => BrushPattern::Solid,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BrushPattern::Solid => "solid",
+            BrushPattern::Checker => "2x2",
+            BrushPattern::Quarter => "25%",
+            BrushPattern::Half => "50%",
+            BrushPattern::ThreeQuarters => "75%",
+        }
+    }
+
+    /// Whether the pattern paints the cell at (x, y).
+    pub fn covers(self, x: usize, y: usize) -> bool {
+        match self {
+            BrushPattern::Solid => true,
+            BrushPattern::Checker => (x / 2 + y / 2).is_multiple_of(2),
+            BrushPattern::Quarter => (x + 2 * y).is_multiple_of(4),
+            BrushPattern::Half => (x + y).is_multiple_of(2),
+            BrushPattern::ThreeQuarters => (x + 2 * y) % 4 != 2,
+        }
+    }
+}
+
+/// Small xorshift generator for the spray tool; scatter only needs to
+/// look random, not be good randomness.
+#[derive(Clone, Debug)]
+pub struct Scatter(u64);
+
+impl Scatter {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero
+        Scatter(seed | 1)
+    }
+
+    /// Seeded from the clock.
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Scatter::new(nanos)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// `count` random points within `radius` of (cx, cy). Points left of or
+/// above the canvas are dropped; `plot` skips the ones past its far edges.
+pub fn spray_points(cx: usize, cy: usize, radius: usize, count: usize, rng: &mut Scatter) -> Vec<(usize, usize)> {
+    let r = radius as isize;
+    let side = 2 * radius + 1;
+    let mut points = Vec::with_capacity(count);
+    let mut placed = 0;
+    while placed < count {
+        let dx = rng.below(side) as isize - r;
+        let dy = rng.below(side) as isize - r;
+        if dx * dx + dy * dy > r * r {
+            continue;
+        }
+        placed += 1;
+        let (x, y) = (cx as isize + dx, cy as isize + dy);
+        if x >= 0 && y >= 0 {
+            points.push((x as usize, y as usize));
+        }
+    }
+    points
+}
+
 /// Draw a rectangle outline from (x0,y0) to (x1,y1).
 #[allow(clippy::too_many_arguments)]
 pub fn rectangle(
@@ -189,7 +415,8 @@ pub fn rectangle(
     mutations
 }
 
-/// Iterative flood fill from (start_x, start_y).
+/// Iterative flood fill from (start_x, start_y), spreading through the
+/// cells `mode` considers alike.
 pub fn flood_fill(
     canvas: &Canvas,
     start_x: usize,
@@ -197,14 +424,20 @@ pub fn flood_fill(
     ch: char,
     fg: Option<Rgb>,
     bg: Option<Rgb>,
+    mode: FillMode,
 ) -> Vec<CellMutation> {
     let target = match canvas.get(start_x, start_y) {
         Some(cell) => cell,
         None => return vec![],
     };
 
-    let new = Cell { ch, fg, bg };
-    if target == new {
+    let fill = Cell { ch, fg, bg };
+    // Recoloring keeps each cell's character, unless there is none to keep
+    let new_for = |cell: Cell| match mode {
+        FillMode::Char if !cell.is_empty() => Cell { ch: cell.ch, fg, bg },
+        _ => fill,
+    };
+    if mode == FillMode::Cell && target == fill {
         return vec![]; // No-op: already the target color
     }
 
@@ -218,21 +451,16 @@ pub fn flood_fill(
         if x >= w || y >= h || visited[y * w + x] {
             continue;
         }
-        if let Some(cell) = canvas.get(x, y) {
-            if cell != target {
-                continue;
-            }
-        } else {
-            continue;
-        }
+        let cell = match canvas.get(x, y) {
+            Some(cell) if mode.matches(cell, target) => cell,
+            _ => continue,
+        };
 
         visited[y * w + x] = true;
-        mutations.push(CellMutation {
-            x,
-            y,
-            old: target,
-            new,
-        });
+        let new = new_for(cell);
+        if new != cell {
+            mutations.push(CellMutation { x, y, old: cell, new });
+        }
 
         if x > 0 {
             stack.push((x - 1, y));
@@ -275,6 +503,45 @@ mod tests {
         Cell::default()
     }
 
+    #[test]
+    fn test_pixel_perfect_drops_l_corners() {
+        // A stair drawn with orthogonal steps keeps only the diagonal
+        let path = [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)];
+        assert_eq!(pixel_perfect(&path), vec![(0, 0), (1, 1), (2, 2)]);
+        // Straight runs and repeated points stay; a right-angle turn is cut
+        let path = [(0, 0), (1, 0), (1, 0), (2, 0), (2, 1), (2, 2)];
+        assert_eq!(pixel_perfect(&path), vec![(0, 0), (1, 0), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_brush_pattern_densities() {
+        let count = |p: BrushPattern| (0..8).flat_map(|y| (0..8).map(move |x| (x, y))).filter(|&(x, y)| p.covers(x, y)).count();
+        assert_eq!(count(BrushPattern::Solid), 64);
+        assert_eq!(count(BrushPattern::Checker), 32);
+        assert_eq!(count(BrushPattern::Quarter), 16);
+        assert_eq!(count(BrushPattern::Half), 32);
+        assert_eq!(count(BrushPattern::ThreeQuarters), 48);
+        // 75% leaves gaps exactly where a shifted 25% paints
+        assert!((0..8).all(|x| BrushPattern::ThreeQuarters.covers(x, 3) != BrushPattern::Quarter.covers(x + 2, 3)));
+    }
+
+    #[test]
+    fn test_spray_points_stay_in_radius() {
+        let mut rng = Scatter::new(42);
+        let points = spray_points(10, 10, 3, 50, &mut rng);
+        assert_eq!(points.len(), 50);
+        assert!(points.iter().all(|&(x, y)| {
+            let (dx, dy) = (x as isize - 10, y as isize - 10);
+            dx * dx + dy * dy <= 9
+        }));
+        assert!(points.iter().any(|&p| p != points[0]), "scattered, not stacked");
+
+        // Near the corner some samples land off the canvas and are dropped
+        let corner = spray_points(0, 0, 3, 50, &mut rng);
+        assert!(corner.len() < 50);
+        assert!(spray_points(5, 5, 0, 4, &mut rng).iter().all(|&p| p == (5, 5)));
+    }
+
     #[test]
     fn test_bresenham_horizontal() {
         let points = bresenham_line(0, 0, 5, 0);
@@ -390,12 +657,61 @@ mod tests {
         }
         canvas.set(0, 1, wall);
         canvas.set(2, 1, wall);
-        let mutations = flood_fill(&canvas, 1, 1, blocks::FULL, BLUE, None);
+        let mutations = flood_fill(&canvas, 1, 1, blocks::FULL, BLUE, None, FillMode::Cell);
         assert_eq!(mutations.len(), 1);
         assert_eq!(mutations[0].x, 1);
         assert_eq!(mutations[0].y, 1);
     }
 
+    #[test]
+    fn test_flood_fill_char_mode_recolors_matching_chars() {
+        let mut canvas = Canvas::new();
+        let shade = |fg| Cell { ch: blocks::SHADE_LIGHT, fg, bg: None };
+        canvas.set(0, 0, shade(RED));
+        canvas.set(1, 0, shade(GREEN));
+        canvas.set(2, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        canvas.set(3, 0, shade(RED));
+        let mutations = flood_fill(&canvas, 0, 0, blocks::FULL, BLUE, None, FillMode::Char);
+        assert_eq!(mutations.len(), 2, "the full block cuts the region");
+        for m in &mutations {
+            assert_eq!(m.new, shade(BLUE));
+        }
+    }
+
+    #[test]
+    fn test_flood_fill_color_mode_ignores_chars() {
+        let mut canvas = Canvas::new();
+        canvas.set(0, 0, Cell { ch: blocks::SHADE_LIGHT, fg: RED, bg: None });
+        canvas.set(1, 0, Cell { ch: blocks::FULL, fg: RED, bg: None });
+        canvas.set(2, 0, Cell { ch: blocks::FULL, fg: GREEN, bg: None });
+        let mutations = flood_fill(&canvas, 0, 0, blocks::FULL, BLUE, None, FillMode::Color);
+        let cells: Vec<(usize, usize)> = mutations.iter().map(|m| (m.x, m.y)).collect();
+        assert_eq!(cells.len(), 2);
+        assert!(cells.contains(&(0, 0)) && cells.contains(&(1, 0)));
+    }
+
+    #[test]
+    fn test_rect_outline_points_walk_once_around() {
+        let points = rect_outline_points(4, 3, 1, 1);
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[0], (1, 1));
+        assert_eq!(points[3], (4, 1));
+        assert_eq!(points[5], (4, 3));
+        assert_eq!(*points.last().unwrap(), (1, 2));
+        assert_eq!(rect_outline_points(2, 2, 2, 2), vec![(2, 2)]);
+        assert_eq!(rect_outline_points(0, 0, 0, 2).len(), 3);
+    }
+
+    #[test]
+    fn test_dash_pattern_mask() {
+        let points = bresenham_line(0, 0, 9, 0);
+        let dashed = DashPattern { on: 3, off: 2 }.mask(points.clone());
+        let xs: Vec<usize> = dashed.iter().map(|p| p.0).collect();
+        assert_eq!(xs, vec![0, 1, 2, 5, 6, 7]);
+        let dotted = DashPattern { on: 1, off: 1 }.mask(points);
+        assert_eq!(dotted.len(), 5);
+    }
+
     #[test]
     fn test_flood_fill_noop() {
         let canvas = Canvas::new();
@@ -406,6 +722,7 @@ mod tests {
             ' ',
             Some(Rgb::WHITE),
             None,
+            FillMode::Cell,
         );
         assert_eq!(mutations.len(), 0);
     }
@@ -420,6 +737,7 @@ mod tests {
             blocks::FULL,
             RED,
             None,
+            FillMode::Cell,
         );
         assert_eq!(mutations.len(), canvas.width * canvas.height);
     }
@@ -596,7 +914,7 @@ mod tests {
     fn test_fill_shade_char() {
         let canvas = Canvas::new();
         // Fill entire empty region with shade char
-        let mutations = flood_fill(&canvas, 0, 0, blocks::SHADE_MEDIUM, RED, None);
+        let mutations = flood_fill(&canvas, 0, 0, blocks::SHADE_MEDIUM, RED, None, FillMode::Cell);
         assert!(!mutations.is_empty(), "Fill should produce mutations");
         // All mutations should use shade char
         for m in &mutations {
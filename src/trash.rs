@@ -0,0 +1,169 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Default trash location (`~/.local/share/kakukuma/trash` on Linux).
+pub fn trash_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("kakukuma").join("trash"))
+}
+
+/// Move a file into the trash directory, returning its new location. A file
+/// already trashed under the same name is kept: the new one gets a numbered
+/// name like `warm (2).palette`. Where it came from is recorded next to it,
+/// for [`original_path`].
+pub fn move_to_trash(path: &Path, trash: &Path) -> io::Result<PathBuf> {
+    let (Some(stem), Some(name)) = (path.file_stem(), path.file_name()) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file path"));
+    };
+    std::fs::create_dir_all(trash)?;
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut dest = trash.join(name);
+    let mut n = 1;
+    while dest.exists() || origin_file(&dest).exists() {
+        n += 1;
+        dest = trash.join(format!("{} ({}){}", stem.to_string_lossy(), n, ext));
+    }
+    let original = std::path::absolute(path)?;
+    std::fs::write(origin_file(&dest), original.as_os_str().as_encoded_bytes())?;
+    if let Err(e) = move_file(path, &dest) {
+        let _ = std::fs::remove_file(origin_file(&dest));
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+/// Where a trashed file was before it was trashed, if that was recorded.
+pub fn original_path(trashed: &Path) -> Option<PathBuf> {
+    let bytes = std::fs::read(origin_file(trashed)).ok()?;
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+/// Move a trashed file back to `dest`. Refuses to overwrite an existing file.
+pub fn restore(trashed: &Path, dest: &Path) -> io::Result<()> {
+    if dest.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dest.display()),
+        ));
+    }
+    move_file(trashed, dest)?;
+    let _ = std::fs::remove_file(origin_file(trashed));
+    Ok(())
+}
+
+/// `warm.palette` -> `warm.palette.origin`, holding the original path.
+fn origin_file(trashed: &Path) -> PathBuf {
+    let mut p = trashed.as_os_str().to_os_string();
+    p.push(".origin");
+    PathBuf::from(p)
+}
+
+/// List trashed files with the given extension, sorted by name.
+pub fn list_trash(trash: &Path, ext: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(trash) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(ext) {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    files.push(name.to_string());
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Rename, falling back to copy + remove when crossing filesystems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore() {
+        let dir = std::env::temp_dir().join("kaku_test_trash_restore");
+        let trash = dir.join("trash");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("warm.palette");
+        std::fs::write(&file, "{}").unwrap();
+
+        let trashed = move_to_trash(&file, &trash).unwrap();
+        assert!(!file.exists());
+        assert_eq!(trashed, trash.join("warm.palette"));
+        assert!(trashed.exists());
+        assert_eq!(original_path(&trashed), Some(file.clone()));
+
+        restore(&trashed, &file).unwrap();
+        assert!(file.exists());
+        assert!(!trashed.exists());
+        assert_eq!(original_path(&trashed), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_same_names_dont_clobber_each_other() {
+        let dir = std::env::temp_dir().join("kaku_test_trash_same_name");
+        let trash = dir.join("trash");
+        let _ = std::fs::remove_dir_all(&dir);
+        for sub in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+            std::fs::write(dir.join(sub).join("warm.palette"), sub).unwrap();
+        }
+
+        let first = move_to_trash(&dir.join("a").join("warm.palette"), &trash).unwrap();
+        let second = move_to_trash(&dir.join("b").join("warm.palette"), &trash).unwrap();
+        assert_eq!(second, trash.join("warm (2).palette"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "a");
+        assert_eq!(list_trash(&trash, "palette"), vec!["warm (2).palette", "warm.palette"]);
+
+        // Each goes back where it came from
+        let original = original_path(&second).unwrap();
+        assert_eq!(original, dir.join("b").join("warm.palette"));
+        restore(&second, &original).unwrap();
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "b");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_list_trash() {
+        let dir = std::env::temp_dir().join("kaku_test_trash_list");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.palette"), "{}").unwrap();
+        std::fs::write(dir.join("a.palette"), "{}").unwrap();
+        std::fs::write(dir.join("art.kaku"), "{}").unwrap();
+
+        assert_eq!(list_trash(&dir, "palette"), vec!["a.palette", "b.palette"]);
+        assert!(list_trash(&dir.join("missing"), "palette").is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restore_refuses_overwrite() {
+        let dir = std::env::temp_dir().join("kaku_test_trash_overwrite");
+        let trash = dir.join("trash");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("cool.palette");
+        std::fs::write(&file, "old").unwrap();
+
+        let trashed = move_to_trash(&file, &trash).unwrap();
+        std::fs::write(&file, "new").unwrap();
+        assert!(restore(&trashed, &file).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+
+use crate::app::App;
+use crate::export::format_bytes;
+
+/// Diagnostics for slowness reports, in the canvas's top-right corner.
+pub fn render(f: &mut Frame, app: &App, canvas: Rect) {
+    let lines: Vec<Line> = stat_lines(app).into_iter().map(Line::from).collect();
+    let width = 30.min(canvas.width);
+    let height = (lines.len() as u16 + 2).min(canvas.height);
+    let area = Rect::new(canvas.right().saturating_sub(width), canvas.y, width, height);
+
+    let theme = app.theme();
+    let style = Style::default().fg(Color::White).bg(theme.panel_bg);
+    let panel = Paragraph::new(lines).style(style).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(" Debug ")
+            .style(Style::default().fg(theme.dim).bg(theme.panel_bg)),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(panel, area);
+}
+
+/// One line per statistic.
+pub fn stat_lines(app: &App) -> Vec<String> {
+    let canvas = &app.canvas;
+    let memory = resident_bytes().map_or_else(|| "n/a".to_string(), format_bytes);
+    vec![
+        format!(" Frame   {}", format_ms(app.perf.render)),
+        format!(" Events  {}", format_ms(app.perf.events)),
+        format!(" History {} steps, {}", app.history.len(), format_bytes(app.history.memory_estimate())),
+        format!(" Canvas  {}x{}, {}", canvas.width, canvas.height, format_bytes(canvas.allocated_bytes())),
+        format!(" Memory  {}", memory),
+    ]
+}
+
+fn format_ms(d: Duration) -> String {
+    format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+}
+
+/// The process's resident set size, where the OS makes it cheap to read.
+fn resident_bytes() -> Option<usize> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    // Second field of statm is resident pages; pages are 4 KiB on the
+    // platforms kakukuma ships for
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
@@ -1,14 +1,44 @@
+use std::collections::HashSet;
+
 use ratatui::Frame;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::widgets::{Block, Borders, BorderType, Widget};
 
 use crate::app::App;
-use crate::cell::{blocks, is_half_block, Cell, resolve_half_block};
+use crate::cell::{blocks, char_width, display_char, is_half_block, Cell, Rgb, resolve_half_block};
+use crate::export::ColorFormat;
 use crate::input::CanvasArea;
+use crate::settings::CursorStyle;
 use crate::theme::Theme;
-use crate::tools::{self, ToolState};
+use crate::tools;
+
+/// Glyphs that mark editor state without relying on color, used in
+/// monochrome and screen reader modes.
+const CURSOR_MARKER: char = '+';
+const PREVIEW_MARKER: char = '*';
+
+/// Marker for an empty cell on a symmetry axis: `|` on the vertical mirror
+/// line, `-` on the horizontal one, `#` where they cross or at the center
+/// of radial symmetry.
+fn axis_marker(on_h_axis: bool, on_v_axis: bool) -> char {
+    match (on_h_axis, on_v_axis) {
+        (true, true) => '#',
+        (true, false) => '|',
+        _ => '-',
+    }
+}
+
+/// The pencil's ghost preview: the cell it would paint, at reduced intensity.
+fn ghost_cell(cell: Cell) -> Cell {
+    let dim = |c: Rgb| Rgb::new(
+        (c.r as u16 * 3 / 5) as u8,
+        (c.g as u16 * 3 / 5) as u8,
+        (c.b as u16 * 3 / 5) as u8,
+    );
+    Cell { ch: cell.ch, fg: cell.fg.map(dim), bg: cell.bg.map(dim) }
+}
 
 /// Return the visual background color for an empty/transparent cell position.
 fn grid_bg(x: usize, y: usize, show_grid: bool, theme: &Theme) -> Color {
@@ -25,18 +55,35 @@ fn grid_bg(x: usize, y: usize, show_grid: bool, theme: &Theme) -> Color {
 
 /// Thin wrapper around `cell::resolve_half_block` that maps transparent halves
 /// to grid background colors for terminal display.
-fn resolve_half_block_for_display(cell: Cell, x: usize, y: usize, show_grid: bool, theme: &Theme) -> (char, Color, Color) {
+fn resolve_half_block_for_display(cell: Cell, x: usize, y: usize, show_grid: bool, theme: &Theme, format: ColorFormat) -> (char, Color, Color) {
     let resolved = resolve_half_block(&cell).unwrap();
 
     if resolved.ch == ' ' {
         return (' ', Color::Reset, grid_bg(x, y, show_grid, theme));
     }
 
-    let fg = resolved.fg.map_or(Color::Reset, |rgb| rgb.to_ratatui());
-    let bg = resolved.bg.map_or(grid_bg(x, y, show_grid, theme), |rgb| rgb.to_ratatui());
+    let fg = resolved.fg.map_or(Color::Reset, |rgb| rgb.to_ratatui_as(format));
+    let bg = resolved.bg.map_or(grid_bg(x, y, show_grid, theme), |rgb| rgb.to_ratatui_as(format));
     (resolved.ch, fg, bg)
 }
 
+/// The glyph and colors a cell is drawn with, before any overlay.
+fn resolve_cell(app: &App, cell: Cell, x: usize, y: usize, show_grid: bool) -> (char, Color, Color) {
+    let theme = app.theme();
+    if cell.ch == blocks::FULL {
+        let c = cell.fg.map_or(Color::Reset, |rgb| app.display_color(rgb));
+        ('\u{2588}', c, c)
+    } else if cell.is_empty() {
+        (' ', Color::Reset, grid_bg(x, y, show_grid, theme))
+    } else if is_half_block(cell.ch) {
+        resolve_half_block_for_display(cell, x, y, show_grid, theme, app.color_support)
+    } else {
+        // Fractional fills, shades, and other single-color blocks
+        let fg_color = cell.fg.map_or(Color::Reset, |rgb| app.display_color(rgb));
+        (cell.ch, fg_color, grid_bg(x, y, show_grid, theme))
+    }
+}
+
 /// Render the canvas editor and return the screen area for mouse mapping.
 pub fn render(f: &mut Frame, app: &App, area: Rect) -> CanvasArea {
     let theme = app.theme();
@@ -76,45 +123,34 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) -> CanvasArea {
         bordered_h.min(area.height),
     );
 
-    // Render the border
+    // Render the border, except around the tile preview, where it would
+    // hide the seams
+    let tiled = app.tile_preview && vis_w == app.canvas.width && vis_h == app.canvas.height;
     let border = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(theme.separator));
     let inner_rect = border.inner(bordered_rect);
-    f.render_widget(border, bordered_rect);
+    if !tiled {
+        f.render_widget(border, bordered_rect);
+    }
 
-    // Scroll indicators on border edges
+    // Scrollbar thumbs on the bottom and right borders when the canvas is
+    // larger than the view
     let buf = f.buffer_mut();
-    let ind_style = Style::default().fg(theme.dim);
-    if app.viewport_x > 0 {
-        // Left arrow on left border
-        let mid_y = bordered_rect.y + bordered_rect.height / 2;
-        if mid_y < bordered_rect.y + bordered_rect.height {
-            buf.set_string(bordered_rect.x, mid_y, "\u{25C0}", ind_style);
+    let thumb_style = Style::default().fg(theme.accent);
+    let right_x = bordered_rect.x + bordered_rect.width.saturating_sub(1);
+    let bot_y = bordered_rect.y + bordered_rect.height.saturating_sub(1);
+    if vis_w < app.canvas.width {
+        let (start, len) = scroll_thumb(app.viewport_x, vis_w, app.canvas.width, inner_rect.width);
+        for i in start..start + len {
+            buf.set_string(inner_rect.x + i, bot_y, "\u{2501}", thumb_style); // ━
         }
     }
-    if app.viewport_x + vis_w < app.canvas.width {
-        // Right arrow on right border
-        let mid_y = bordered_rect.y + bordered_rect.height / 2;
-        let right_x = bordered_rect.x + bordered_rect.width.saturating_sub(1);
-        if mid_y < bordered_rect.y + bordered_rect.height {
-            buf.set_string(right_x, mid_y, "\u{25B6}", ind_style);
-        }
-    }
-    if app.viewport_y > 0 {
-        // Up arrow on top border
-        let mid_x = bordered_rect.x + bordered_rect.width / 2;
-        if mid_x < bordered_rect.x + bordered_rect.width {
-            buf.set_string(mid_x, bordered_rect.y, "\u{25B2}", ind_style);
-        }
-    }
-    if app.viewport_y + vis_h < app.canvas.height {
-        // Down arrow on bottom border
-        let mid_x = bordered_rect.x + bordered_rect.width / 2;
-        let bot_y = bordered_rect.y + bordered_rect.height.saturating_sub(1);
-        if mid_x < bordered_rect.x + bordered_rect.width {
-            buf.set_string(mid_x, bot_y, "\u{25BC}", ind_style);
+    if vis_h < app.canvas.height {
+        let (start, len) = scroll_thumb(app.viewport_y, vis_h, app.canvas.height, inner_rect.height);
+        for i in start..start + len {
+            buf.set_string(right_x, inner_rect.y + i, "\u{2503}", thumb_style); // ┃
         }
     }
 
@@ -122,6 +158,56 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) -> CanvasArea {
     let widget = CanvasWidget { app };
     f.render_widget(widget, inner_rect);
 
+    let minimap = if app.show_minimap && (vis_w < app.canvas.width || vis_h < app.canvas.height) && !app.screen_reader {
+        render_minimap(f.buffer_mut(), app, inner_rect, vis_w, vis_h)
+    } else {
+        None
+    };
+    let tiles = tiled.then(|| render_tiles(f.buffer_mut(), app, area, inner_rect));
+
+    // Below 4x, brackets on the border around the cursor's column, and
+    // pointers at its row, leave the art next to it alone
+    if app.settings.cursor_style == CursorStyle::Brackets && zoom < 4 && !tiled && !app.char_markers() {
+        if let Some((cx, cy)) = app.effective_cursor() {
+            let in_view = cx >= app.viewport_x && cx < app.viewport_x + vis_w && cy >= app.viewport_y && cy < app.viewport_y + vis_h;
+            if in_view {
+                let buf = f.buffer_mut();
+                let style = Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD);
+                let right_x = bordered_rect.x + bordered_rect.width.saturating_sub(1);
+                let bot_y = bordered_rect.y + bordered_rect.height.saturating_sub(1);
+                let left = inner_rect.x + (cx - app.viewport_x) as u16 * zoom;
+                let y = inner_rect.y + (cy - app.viewport_y) as u16;
+                for row in [bordered_rect.y, bot_y] {
+                    buf.set_string(left - 1, row, "[", style);
+                    buf.set_string(left + zoom, row, "]", style);
+                }
+                buf.set_string(bordered_rect.x, y, "\u{25B6}", style); // ▶
+                buf.set_string(right_x, y, "\u{25C0}", style); // ◀
+            }
+        }
+    }
+
+    // Crosshair ticks on the border, like ruler marks
+    if app.settings.cursor_style == CursorStyle::Crosshair && !tiled {
+        if let Some((cx, cy)) = app.effective_cursor() {
+            let buf = f.buffer_mut();
+            let tick_style = Style::default().fg(theme.highlight);
+            let right_x = bordered_rect.x + bordered_rect.width.saturating_sub(1);
+            let bot_y = bordered_rect.y + bordered_rect.height.saturating_sub(1);
+            if cx >= app.viewport_x && cx < app.viewport_x + vis_w {
+                let x = inner_rect.x + (cx - app.viewport_x) as u16 * zoom + zoom / 2;
+                buf.set_string(x, bordered_rect.y, "\u{252C}", tick_style); // ┬
+                buf.set_string(x, bot_y, "\u{2534}", tick_style); // ┴
+            }
+            if cy >= app.viewport_y && cy < app.viewport_y + vis_h {
+                let rows = if zoom == 4 { 2 } else { 1 };
+                let y = inner_rect.y + (cy - app.viewport_y) as u16 * rows;
+                buf.set_string(bordered_rect.x, y, "\u{251C}", tick_style); // ├
+                buf.set_string(right_x, y, "\u{2524}", tick_style); // ┤
+            }
+        }
+    }
+
     CanvasArea {
         left: inner_rect.x,
         top: inner_rect.y,
@@ -129,41 +215,116 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) -> CanvasArea {
         height: inner_rect.height,
         viewport_w: vp_w,
         viewport_h: vp_h,
+        tiles,
+        minimap,
     }
 }
 
-struct CanvasWidget<'a> {
-    app: &'a App,
-}
-
-impl<'a> CanvasWidget<'a> {
-    fn is_in_tool_preview(&self, x: usize, y: usize) -> bool {
-        let cursor = match self.app.effective_cursor() {
-            Some(c) => c,
-            None => return false,
-        };
-        match &self.app.tool_state {
-            ToolState::LineStart { x: x0, y: y0 } => {
-                let points = tools::bresenham_line(*x0, *y0, cursor.0, cursor.1);
-                points.contains(&(x, y))
+/// Draw copies of the canvas around it, up to a whole canvas away on each
+/// side, so a pattern can be checked for seams. Returns the area they fill.
+fn render_tiles(buf: &mut Buffer, app: &App, area: Rect, canvas: Rect) -> Rect {
+    let left = canvas.x.saturating_sub(canvas.width).max(area.x);
+    let top = canvas.y.saturating_sub(canvas.height).max(area.y);
+    let right = (canvas.x + canvas.width * 2).min(area.right());
+    let bottom = (canvas.y + canvas.height * 2).min(area.bottom());
+    let tiles = Rect::new(left, top, right - left, bottom - top);
+
+    let (w, h) = (app.canvas.width as isize, app.canvas.height as isize);
+    let zoom = app.zoom as isize;
+    let rows = if zoom == 4 { 2 } else { 1 };
+    for ty in -h..h * 2 {
+        for tx in -w..w * 2 {
+            if (0..w).contains(&tx) && (0..h).contains(&ty) {
+                continue;
+            }
+            let screen_x = canvas.x as isize + tx * zoom;
+            let screen_y = canvas.y as isize + ty * rows;
+            if screen_x < left as isize || screen_x >= right as isize || screen_y < top as isize || screen_y >= bottom as isize {
+                continue;
             }
-            ToolState::RectStart { x: x0, y: y0 } => {
-                let min_x = (*x0).min(cursor.0);
-                let max_x = (*x0).max(cursor.0);
-                let min_y = (*y0).min(cursor.1);
-                let max_y = (*y0).max(cursor.1);
-                let is_border = x == min_x || x == max_x || y == min_y || y == max_y;
-                x >= min_x && x <= max_x && y >= min_y && y <= max_y && is_border
+            let (x, y) = (tx.rem_euclid(w) as usize, ty.rem_euclid(h) as usize);
+            let Some(cell) = app.canvas.get(x, y) else {
+                continue;
+            };
+            let (ch, fg, bg) = resolve_cell(app, cell, x, y, false);
+            let ch = display_char(ch);
+            let s: String = std::iter::repeat_n(ch, (zoom as usize / char_width(ch)).max(1)).collect();
+            let (screen_x, screen_y) = (screen_x as u16, screen_y as u16);
+            for row in screen_y..(screen_y + rows as u16).min(bottom) {
+                buf.set_stringn(screen_x, row, &s, (right - screen_x) as usize, Style::default().fg(fg).bg(bg));
             }
-            ToolState::Idle => false,
         }
     }
+    tiles
+}
+
+/// Largest minimap, in terminal cells.
+const MINIMAP_SIZE: (usize, usize) = (24, 12);
+
+/// Canvas cells per minimap cell, each way: the smallest whole number that
+/// fits the canvas in `MINIMAP_SIZE`.
+fn minimap_scale(width: usize, height: usize) -> usize {
+    width.div_ceil(MINIMAP_SIZE.0).max(height.div_ceil(MINIMAP_SIZE.1)).max(1)
+}
+
+/// The whole canvas, downsampled, in the bottom-right corner of the canvas
+/// area, with the part in view outlined. Skipped when it wouldn't fit.
+/// Returns where it was drawn and its scale.
+fn render_minimap(buf: &mut Buffer, app: &App, area: Rect, vis_w: usize, vis_h: usize) -> Option<(Rect, usize)> {
+    let theme = app.theme();
+    let scale = minimap_scale(app.canvas.width, app.canvas.height);
+    // Pixels are one canvas cell wide and half a cell tall, as the editor
+    // draws them, so `scale` pixels down is `scale` half rows
+    let pixels = crate::versions::downsample(&app.canvas, scale as f64);
+    let cols = pixels.first().map_or(0, Vec::len) as u16;
+    let rows = pixels.len().div_ceil(2) as u16;
+    if cols + 2 > area.width || rows + 2 > area.height {
+        return None;
+    }
+    let left = area.right() - cols - 1;
+    let top = area.bottom() - rows - 1;
+
+    let (x0, x1) = (app.viewport_x / scale, (app.viewport_x + vis_w.max(1) - 1) / scale);
+    let (y0, y1) = (app.viewport_y * 2 / scale, ((app.viewport_y + vis_h.max(1)) * 2 - 1) / scale);
+    let color = |px: usize, py: usize| {
+        let inside = (x0..=x1).contains(&px) && (y0..=y1).contains(&py);
+        if inside && (px == x0 || px == x1 || py == y0 || py == y1) {
+            return theme.highlight;
+        }
+        pixels.get(py).and_then(|r| r.get(px)).copied().flatten().map_or(theme.panel_bg, |c| app.display_color(c))
+    };
+    for row in 0..rows {
+        for col in 0..cols {
+            let (px, py) = (col as usize, row as usize * 2);
+            let style = Style::default().fg(color(px, py)).bg(color(px, py + 1));
+            buf.set_string(left + col, top + row, "\u{2580}", style);
+        }
+    }
+    Some((Rect::new(left, top, cols, rows), scale))
+}
+
+/// Start and length of a scrollbar thumb on a `track`-long border, for a
+/// view of `visible` cells at `offset` into `total`. The thumb is at least
+/// one cell long.
+fn scroll_thumb(offset: usize, visible: usize, total: usize, track: u16) -> (u16, u16) {
+    let track = track as usize;
+    if total == 0 || track == 0 {
+        return (0, 0);
+    }
+    let len = (visible * track / total).clamp(1, track);
+    let start = (offset * track / total).min(track - len);
+    (start as u16, len as u16)
+}
+
+struct CanvasWidget<'a> {
+    app: &'a App,
 }
 
 impl<'a> Widget for CanvasWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let zoom = self.app.zoom;
         let show_grid = zoom > 1;
+        let markers = self.app.char_markers();
         let theme = self.app.theme();
         let vp_x = self.app.viewport_x;
         let vp_y = self.app.viewport_y;
@@ -177,6 +338,9 @@ impl<'a> Widget for CanvasWidget<'a> {
 
         let vis_w = vp_w.min(self.app.canvas.width.saturating_sub(vp_x));
         let vis_h = vp_h.min(self.app.canvas.height.saturating_sub(vp_y));
+        let cursor = self.app.effective_cursor();
+        let ghosts = self.app.pencil_ghost();
+        let preview: HashSet<(usize, usize)> = self.app.tool_preview_points().into_iter().collect();
 
         for vy in 0..vis_h {
             for vx in 0..vis_w {
@@ -198,65 +362,118 @@ impl<'a> Widget for CanvasWidget<'a> {
                     None => continue,
                 };
 
-                let is_cursor = self.app.effective_cursor() == Some((x, y));
+                let is_cursor = cursor == Some((x, y));
 
                 // Tool preview overlay (line/rect in progress)
-                let render_cell = if self.is_in_tool_preview(x, y) && !is_cursor {
+                let in_preview = preview.contains(&(x, y)) && !is_cursor;
+                let ghost = ghosts.iter().find(|m| m.x == x && m.y == y);
+                let render_cell = if in_preview {
                     tools::compose_cell(
                         cell,
                         self.app.active_block,
                         Some(self.app.color),
                         None,
                     )
+                } else if let Some(m) = ghost {
+                    ghost_cell(m.new)
                 } else {
                     cell
                 };
 
-                // Resolve to (char, fg, bg) using unified path
-                let (ch_out, mut fg, mut bg) = if render_cell.ch == blocks::FULL {
-                    let c = render_cell.fg.map_or(Color::Reset, |rgb| rgb.to_ratatui());
-                    ('\u{2588}', c, c)
-                } else if render_cell.is_empty() {
-                    (' ', Color::Reset, grid_bg(x, y, show_grid, theme))
-                } else if is_half_block(render_cell.ch) {
-                    resolve_half_block_for_display(render_cell, x, y, show_grid, theme)
-                } else {
-                    // Fractional fills, shades, and other single-color blocks
-                    let fg_color = render_cell.fg.map_or(Color::Reset, |rgb| rgb.to_ratatui());
-                    (render_cell.ch, fg_color, grid_bg(x, y, show_grid, theme))
-                };
+                let (mut ch_out, mut fg, mut bg) = resolve_cell(self.app, render_cell, x, y, show_grid);
+
+                // Selection: a shade overlay on full blocks (keeping their
+                // color visible), a highlight background elsewhere
+                let selected = self.app.selection.as_ref().is_some_and(|sel| sel.contains(x, y));
+                if selected && !is_cursor {
+                    if ch_out == '\u{2588}' {
+                        ch_out = blocks::SHADE_MEDIUM;
+                        bg = fg;
+                        fg = theme.highlight;
+                    } else {
+                        bg = theme.highlight;
+                    }
+                }
 
                 // Symmetry axis highlight
                 let canvas_w = self.app.canvas.width;
                 let canvas_h = self.app.canvas.height;
-                let on_h_axis = self.app.symmetry.has_horizontal()
+                let on_center = self.app.symmetry.is_radial()
+                    && (x == (canvas_w - 1) / 2 || x == canvas_w / 2)
+                    && (y == (canvas_h - 1) / 2 || y == canvas_h / 2);
+                let on_h_axis = on_center || self.app.symmetry.has_horizontal()
                     && (x == canvas_w / 2 - 1 || x == canvas_w / 2);
-                let on_v_axis = self.app.symmetry.has_vertical()
+                let on_v_axis = on_center || self.app.symmetry.has_vertical()
                     && (y == canvas_h / 2 - 1 || y == canvas_h / 2);
                 if (on_h_axis || on_v_axis) && !is_cursor
                     && render_cell.is_empty()
                 {
                     bg = Color::Indexed(238);
+                    if markers {
+                        ch_out = axis_marker(on_h_axis, on_v_axis);
+                    }
+                }
+                if in_preview && markers {
+                    ch_out = PREVIEW_MARKER;
                 }
 
-                // Cursor inversion
-                if is_cursor {
-                    std::mem::swap(&mut fg, &mut bg);
+                // Crosshair: tint empty cells in the cursor's row and column
+                let cursor_style = self.app.settings.cursor_style;
+                if cursor_style == CursorStyle::Crosshair && !is_cursor && render_cell.is_empty() {
+                    if let Some((cx, cy)) = cursor {
+                        if x == cx || y == cy {
+                            bg = theme.separator;
+                        }
+                    }
                 }
 
-                let style = Style::default().fg(fg).bg(bg);
+                // Cursor; with character markers, use the terminal's own
+                // reverse video and a marker glyph instead of color
+                let style = if is_cursor && markers {
+                    ch_out = CURSOR_MARKER;
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else if is_cursor {
+                    // Swapping does nothing to a full block, whose colors
+                    // match, so those show the highlight color instead
+                    let solid = fg == bg;
+                    let highlight = Style::default().fg(theme.highlight).bg(theme.highlight);
+                    match cursor_style {
+                        // The ghost already shows where the pencil is
+                        CursorStyle::Swap | CursorStyle::Crosshair if ghost.is_some() => {
+                            Style::default().fg(fg).bg(bg)
+                        }
+                        CursorStyle::Swap | CursorStyle::Crosshair if solid => highlight,
+                        CursorStyle::Swap | CursorStyle::Crosshair => Style::default().fg(bg).bg(fg),
+                        CursorStyle::Inverse if solid => highlight,
+                        CursorStyle::Inverse => Style::default().fg(bg).bg(theme.highlight),
+                        CursorStyle::Blink if solid => highlight.add_modifier(Modifier::SLOW_BLINK),
+                        CursorStyle::Blink => Style::default()
+                            .fg(fg)
+                            .bg(bg)
+                            .add_modifier(Modifier::REVERSED | Modifier::SLOW_BLINK),
+                        CursorStyle::Brackets => Style::default().fg(fg).bg(bg),
+                    }
+                } else {
+                    Style::default().fg(fg).bg(bg)
+                };
+
+                let style = if selected && markers { style.add_modifier(Modifier::UNDERLINED) } else { style };
 
-                // Paint across zoom width
+                // Paint across zoom width. A wide character fills two
+                // columns, so it repeats half as often; at zoom 1 it covers
+                // the next cell, as it will in an export.
+                let ch_out = display_char(ch_out);
+                let repeats = (zoom as usize / char_width(ch_out)).max(1);
                 match zoom {
                     1 => {
                         buf.set_string(screen_x, screen_y, ch_out.to_string(), style);
                     }
                     2 => {
-                        let s: String = std::iter::repeat_n(ch_out, 2).collect();
+                        let s: String = std::iter::repeat_n(ch_out, repeats).collect();
                         buf.set_string(screen_x, screen_y, &s, style);
                     }
                     4 => {
-                        let s: String = std::iter::repeat_n(ch_out, 4).collect();
+                        let s: String = std::iter::repeat_n(ch_out, repeats).collect();
                         buf.set_string(screen_x, screen_y, &s, style);
                         // Second row: same content
                         if screen_y + 1 < area.y + area.height {
@@ -267,6 +484,22 @@ impl<'a> Widget for CanvasWidget<'a> {
                 }
             }
         }
+
+        // At 4x the brackets fit in the cursor cell's padding, after every
+        // cell is drawn; narrower cells get them on the border instead
+        if let (CursorStyle::Brackets, false, Some((cx, cy)), 4) = (self.app.settings.cursor_style, markers, cursor, zoom) {
+            if cx >= vp_x && cy >= vp_y && cx < vp_x + vis_w && cy < vp_y + vis_h {
+                let left = area.x + (cx - vp_x) as u16 * 4;
+                let top = area.y + ((cy - vp_y) as u16) * 2;
+                let style = Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD);
+                for row in top..(top + 2).min(area.y + area.height) {
+                    buf.set_string(left, row, "[", style);
+                    if left + 3 < area.x + area.width {
+                        buf.set_string(left + 3, row, "]", style);
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -276,6 +509,22 @@ mod tests {
     use crate::cell::Rgb;
     use crate::theme::WARM;
 
+    #[test]
+    fn minimap_scale_fits_canvas() {
+        assert_eq!(minimap_scale(24, 12), 1);
+        assert_eq!(minimap_scale(128, 128), 11);
+        assert_eq!(minimap_scale(128, 16), 6);
+    }
+
+    #[test]
+    fn scroll_thumb_tracks_view() {
+        // Half the canvas visible: half the track
+        assert_eq!(scroll_thumb(0, 32, 64, 32), (0, 16));
+        assert_eq!(scroll_thumb(32, 32, 64, 32), (16, 16));
+        // Tiny views still get one cell, and the thumb stays on the track
+        assert_eq!(scroll_thumb(999, 1, 1000, 10), (9, 1));
+    }
+
     // --- grid_bg tests ---
 
     #[test]
@@ -307,7 +556,7 @@ mod tests {
 
     #[test]
     fn upper_half_one_transparent_bottom() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, Some(RED), None), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, Some(RED), None), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▀');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, WARM.grid_even);
@@ -315,7 +564,7 @@ mod tests {
 
     #[test]
     fn upper_half_both_opaque() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, Some(RED), Some(BLUE)), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, Some(RED), Some(BLUE)), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▀');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, Color::Indexed(4));
@@ -323,7 +572,7 @@ mod tests {
 
     #[test]
     fn upper_half_one_transparent_top_flips() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, None, Some(BLUE)), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, None, Some(BLUE)), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▄');
         assert_eq!(fg, Color::Indexed(4));
         assert_eq!(bg, WARM.grid_even);
@@ -331,14 +580,14 @@ mod tests {
 
     #[test]
     fn upper_half_both_transparent() {
-        let (ch, _fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, None, None), 0, 0, true, &WARM);
+        let (ch, _fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, None, None), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, ' ');
         assert_eq!(bg, WARM.grid_even);
     }
 
     #[test]
     fn left_half_one_transparent_right() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LEFT_HALF, Some(RED), None), 1, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LEFT_HALF, Some(RED), None), 1, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▌');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, WARM.grid_odd);
@@ -346,7 +595,7 @@ mod tests {
 
     #[test]
     fn left_half_flips_when_left_transparent() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LEFT_HALF, None, Some(RED)), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LEFT_HALF, None, Some(RED)), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▐');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, WARM.grid_even);
@@ -354,7 +603,7 @@ mod tests {
 
     #[test]
     fn lower_half_defensive() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LOWER_HALF, Some(BLUE), None), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LOWER_HALF, Some(BLUE), None), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▄');
         assert_eq!(fg, Color::Indexed(4));
         assert_eq!(bg, WARM.grid_even);
@@ -362,7 +611,7 @@ mod tests {
 
     #[test]
     fn right_half_defensive() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::RIGHT_HALF, Some(RED), None), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::RIGHT_HALF, Some(RED), None), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▐');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, WARM.grid_even);
@@ -370,7 +619,7 @@ mod tests {
 
     #[test]
     fn resolve_grid_off_uses_reset() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, Some(RED), None), 0, 0, false, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::UPPER_HALF, Some(RED), None), 0, 0, false, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▀');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, Color::Reset);
@@ -378,7 +627,7 @@ mod tests {
 
     #[test]
     fn left_half_both_opaque() {
-        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LEFT_HALF, Some(RED), Some(BLUE)), 0, 0, true, &WARM);
+        let (ch, fg, bg) = resolve_half_block_for_display(make_cell(blocks::LEFT_HALF, Some(RED), Some(BLUE)), 0, 0, true, &WARM, ColorFormat::Color256);
         assert_eq!(ch, '▌');
         assert_eq!(fg, Color::Indexed(1));
         assert_eq!(bg, Color::Indexed(4));
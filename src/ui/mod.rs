@@ -1,3 +1,4 @@
+pub mod debug;
 pub mod editor;
 pub mod toolbar;
 pub mod palette;
@@ -6,23 +7,30 @@ pub mod statusbar;
 use ratatui::Frame;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
 
 use crate::app::{App, AppMode};
 use crate::input::CanvasArea;
+use crate::listing::ListView;
+use crate::symmetry::SymmetryMode;
 use crate::theme::Theme;
 
+/// Smallest terminal for the full layout with toolbar and palette.
+pub const FULL_LAYOUT_SIZE: (u16, u16) = (100, 36);
+/// Smallest terminal for the compact layout: just the canvas and status bar.
+pub const COMPACT_LAYOUT_SIZE: (u16, u16) = (60, 20);
+
 /// Render the full UI and return the canvas area for mouse mapping.
 pub fn render(f: &mut Frame, app: &App) -> CanvasArea {
     let size = f.area();
     let theme = app.theme();
 
     // Check minimum size
-    if size.width < 100 || size.height < 36 {
+    if size.width < COMPACT_LAYOUT_SIZE.0 || size.height < COMPACT_LAYOUT_SIZE.1 {
         let lines = vec![
             ratatui::text::Line::from(""),
             ratatui::text::Line::from(ratatui::text::Span::styled(
-                "\u{0295}\u{2022}\u{1d25}\u{2022}\u{0294}",
+                if app.screen_reader { "" } else { "\u{0295}\u{2022}\u{1d25}\u{2022}\u{0294}" },
                 Style::default().fg(theme.accent),
             )),
             ratatui::text::Line::from(""),
@@ -36,7 +44,7 @@ pub fn render(f: &mut Frame, app: &App) -> CanvasArea {
                 Style::default().fg(theme.dim),
             )),
             ratatui::text::Line::from(ratatui::text::Span::styled(
-                "need:    100x36",
+                format!("need:    {}x{}", COMPACT_LAYOUT_SIZE.0, COMPACT_LAYOUT_SIZE.1),
                 Style::default().fg(theme.dim),
             )),
             ratatui::text::Line::from(""),
@@ -54,8 +62,13 @@ pub fn render(f: &mut Frame, app: &App) -> CanvasArea {
             height: 0,
             viewport_w: 0,
             viewport_h: 0,
+            tiles: None,
+            minimap: None,
         };
     }
+    if size.width < FULL_LAYOUT_SIZE.0 || size.height < FULL_LAYOUT_SIZE.1 {
+        return render_compact(f, app, size);
+    }
 
     // Top-level: main bordered frame + status bar outside
     let outer = Layout::default()
@@ -113,10 +126,10 @@ pub fn render(f: &mut Frame, app: &App) -> CanvasArea {
     let blk_lines = toolbar::block_lines(app);
     let clr_lines = toolbar::color_swatch_lines(app);
     render_box_column(f, toolbar_area, &[
-        BoxContent { title: " \u{2022} Tools \u{2022} ", lines: &tool_lines },
-        BoxContent { title: " \u{2022} Symmetry \u{2022} ", lines: &sym_lines },
-        BoxContent { title: " \u{2022} Block \u{2022} ", lines: &blk_lines },
-        BoxContent { title: " \u{2022} Active \u{2022} ", lines: &clr_lines },
+        BoxContent { title: &panel_title(app, "Tools"), lines: &tool_lines },
+        BoxContent { title: &panel_title(app, "Symmetry"), lines: &sym_lines },
+        BoxContent { title: &panel_title(app, "Block"), lines: &blk_lines },
+        BoxContent { title: &panel_title(app, "Active"), lines: &clr_lines },
     ], theme);
 
     // Canvas — unified zoom-aware renderer
@@ -126,41 +139,104 @@ pub fn render(f: &mut Frame, app: &App) -> CanvasArea {
     let colors_lines = palette::color_lines(app);
     let section_lines = palette::section_lines(app);
     let info_lines = palette::info_lines(app);
-    let section_title = if let Some(ref cp) = app.custom_palette {
-        format!(" \u{2022} {} \u{2022} ", cp.name)
-    } else {
-        " \u{2022} Sections \u{2022} ".to_string()
+    let section_title = match app.custom_palette {
+        Some(ref cp) => panel_title(app, &cp.name),
+        None => panel_title(app, "Sections"),
     };
     render_palette_column(
         f, palette_area,
         &colors_lines, &section_lines, &info_lines,
-        &section_title, app.palette_scroll, theme,
+        [&panel_title(app, "Colors"), &section_title, &panel_title(app, "Color")],
+        app.palette_scroll, theme,
     );
 
     // Status bar (outside the border)
     statusbar::render(f, app, status_area);
+    if app.screen_reader && app.mode == AppMode::Normal {
+        // Screen readers follow the terminal cursor; park it on the status line
+        f.set_cursor_position((status_area.x, status_area.y));
+    }
+
+    if app.debug_overlay {
+        debug::render(f, app, canvas_area);
+    }
+    render_overlays(f, app, size);
 
-    // Overlays
+    canvas_screen_area
+}
+
+/// Canvas and status bar only, for terminals too small for the panels.
+/// Tools and colors still work from the keyboard; the status bar shows the
+/// active ones.
+fn render_compact(f: &mut Frame, app: &App, size: Rect) -> CanvasArea {
+    let outer = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(size);
+    let canvas_screen_area = editor::render(f, app, outer[0]);
+    statusbar::render(f, app, outer[1]);
+    if app.screen_reader && app.mode == AppMode::Normal {
+        f.set_cursor_position((outer[1].x, outer[1].y));
+    }
+    if app.debug_overlay {
+        debug::render(f, app, outer[0]);
+    }
+    render_overlays(f, app, size);
+
+    canvas_screen_area
+}
+
+/// The dialog or prompt for the current mode, over whichever layout.
+fn render_overlays(f: &mut Frame, app: &App, size: Rect) {
     match app.mode {
         AppMode::Help => render_help(f, app, size),
-        AppMode::Quitting => render_quit_prompt(f, size),
+        AppMode::Quitting if app.documents.is_empty() => {
+            render_confirm_prompt(f, size, "Quit", "Unsaved changes. Quit? (y/n)")
+        }
+        AppMode::Quitting => render_confirm_prompt(f, size, "Close", "Unsaved changes. Close this tab? (y/n)"),
+        AppMode::RevertConfirm => {
+            render_confirm_prompt(f, size, "Revert", "Discard unsaved changes and reload? (y/n)")
+        }
         AppMode::FileDialog => render_file_dialog(f, app, size),
+        AppMode::FileRename => render_text_input(f, app, size, "Rename Project", "Enter new name:"),
+        AppMode::FileDeleteConfirm => {
+            render_file_dialog(f, app, size);
+            let filename = app.file_dialog_files.get(app.file_dialog_selected).map_or("", |s| s.as_str());
+            render_confirm_prompt(f, size, "Delete", &format!("Move {} to trash? (y/n)", filename));
+        }
         AppMode::ExportDialog => render_export_dialog(f, app, size),
+        AppMode::ImportDialog => render_import_dialog(f, app, size),
+        AppMode::SymmetryPicker => render_symmetry_picker(f, app, size),
+        AppMode::HistoryScrubber => render_history_scrubber(f, app, size),
+        AppMode::Versions => render_versions(f, app, size),
         AppMode::SaveAs => render_text_input(f, app, size, "Save As", "Enter project name:"),
         AppMode::ExportFile => render_text_input(f, app, size, "Export", "Enter filename:"),
         AppMode::Recovery => render_recovery_prompt(f, app, size),
         AppMode::ColorSliders => render_color_sliders(f, app, size),
         AppMode::PaletteDialog => render_palette_dialog(f, app, size),
         AppMode::PaletteNameInput => render_text_input(f, app, size, "New Palette", "Enter palette name:"),
+        AppMode::LospecSlugInput => render_text_input(f, app, size, "Lospec Palette", "Lospec name, e.g. sweetie-16:"),
         AppMode::PaletteRename => render_text_input(f, app, size, "Rename Palette", "Enter new name:"),
         AppMode::PaletteExport => render_text_input(f, app, size, "Export Palette", "Enter destination path:"),
         AppMode::NewCanvas => render_new_canvas(f, app, size),
+        AppMode::ResizeCanvas => render_resize_canvas(f, app, size),
+        AppMode::ReleaseNotes => render_release_notes(f, app, size),
         AppMode::HexColorInput => render_hex_input(f, app, size),
         AppMode::BlockPicker => render_block_picker(f, app, size),
+        AppMode::Inspector => render_inspector(f, app, size),
+        AppMode::RunInput => render_text_input(f, app, size, "Run", "Count and direction (e.g. 8d):"),
+        AppMode::RectSizeInput => render_text_input(f, app, size, "Rectangle", "Width x height (e.g. 12x5):"),
         _ => {}
     }
+}
 
-    canvas_screen_area
+/// Panel title, with bullet decorations unless in screen reader mode.
+fn panel_title(app: &App, name: &str) -> String {
+    if app.screen_reader {
+        format!(" {} ", name)
+    } else {
+        format!(" \u{2022} {} \u{2022} ", name)
+    }
 }
 
 struct BoxContent<'a> {
@@ -201,7 +277,7 @@ fn render_palette_column(
     colors_lines: &[ratatui::text::Line<'static>],
     section_lines: &[ratatui::text::Line<'static>],
     info_lines: &[ratatui::text::Line<'static>],
-    section_title: &str,
+    titles: [&str; 3],
     scroll: usize,
     theme: &Theme,
 ) {
@@ -225,17 +301,17 @@ fn render_palette_column(
 
     // Colors box
     let colors_area = Rect::new(column.x, y, column.width, colors_height);
-    render_bordered_panel(f, colors_area, colors_lines, " \u{2022} Colors \u{2022} ", theme);
+    render_bordered_panel(f, colors_area, colors_lines, titles[0], theme);
     y += colors_height + gap;
 
     // Sections box (scrollable)
     let section_area = Rect::new(column.x, y, column.width, section_box_height);
-    render_bordered_panel_scrollable(f, section_area, section_lines, section_title, scroll, theme);
+    render_bordered_panel_scrollable(f, section_area, section_lines, titles[1], scroll, theme);
     y += section_box_height + gap;
 
     // Color info box
     let info_area = Rect::new(column.x, y, column.width, info_height);
-    render_bordered_panel(f, info_area, info_lines, " \u{2022} Color \u{2022} ", theme);
+    render_bordered_panel(f, info_area, info_lines, titles[2], theme);
 }
 
 /// Render content lines inside a vertically-centered bordered panel.
@@ -311,29 +387,55 @@ fn render_bordered_panel_scrollable(
 }
 
 fn render_header(f: &mut Frame, app: &App, area: Rect, theme: &Theme) {
-    let name = app
-        .project_name
-        .as_deref()
-        .unwrap_or("untitled");
-    let dirty_marker = if app.dirty { "*" } else { "" };
+    use ratatui::text::{Line, Span};
+
+    // With several projects open, the name becomes a strip of tabs
+    let tabs = app.tab_labels();
+    let mut title: Vec<Span> = Vec::new();
+    for (i, (label, active)) in tabs.iter().enumerate() {
+        if tabs.len() == 1 {
+            title.push(Span::raw(label.clone()));
+            break;
+        }
+        if i > 0 {
+            title.push(Span::styled(" \u{2502} ", Style::default().fg(theme.dim)));
+        }
+        title.push(match (active, app.char_markers()) {
+            (true, true) => Span::raw(format!("[{}]", label)),
+            (true, false) => Span::styled(label.clone(), Style::default().add_modifier(Modifier::REVERSED)),
+            (false, _) => Span::styled(label.clone(), Style::default().fg(theme.dim)),
+        });
+    }
+    title.push(Span::raw(format!(
+        "{}{}",
+        if app.safe_mode { " [safe mode]" } else { "" },
+        if app.mouse_capture { "" } else { " [mouse off]" }
+    )));
+    let title_len: usize = title.iter().map(|s| s.content.chars().count()).sum();
     let tool_name = app.active_tool.name();
     let sym = app.symmetry.label();
 
-    let header_text = format!(
-        " \u{0295}\u{2022}\u{1d25}\u{2022}\u{0294} kakukuma \u{2014} {}{} {:>width$}",
-        name,
-        dirty_marker,
-        format!("Tool: {}  Sym: {}", tool_name, sym),
-        width = (area.width as usize).saturating_sub(name.len() + dirty_marker.len() + 22)
-    );
+    let saved = app
+        .save_indicator(std::time::SystemTime::now())
+        .map(|s| format!("{}  ", s))
+        .unwrap_or_default();
+
+    let logo = if app.screen_reader { "" } else { "\u{0295}\u{2022}\u{1d25}\u{2022}\u{0294} " };
+    let mut spans = vec![Span::raw(format!(" {}kakukuma \u{2014} ", logo))];
+    spans.extend(title);
+    spans.push(Span::raw(format!(
+        " {:>width$}",
+        format!("{}Tool: {}  Sym: {}", saved, tool_name, sym),
+        width = (area.width as usize).saturating_sub(title_len + logo.chars().count() + 16)
+    )));
 
-    let header = Paragraph::new(header_text)
+    let header = Paragraph::new(Line::from(spans))
         .style(Style::default().fg(Color::White).bg(theme.header_bg));
     f.render_widget(header, area);
 }
 
 fn render_help(f: &mut Frame, app: &App, area: Rect) {
-    use ratatui::text::Span;
+    use ratatui::text::{Line, Span};
     let theme = app.theme();
 
     let sep = Style::default().fg(theme.separator).bg(theme.panel_bg);
@@ -341,106 +443,65 @@ fn render_help(f: &mut Frame, app: &App, area: Rect) {
     let txt = Style::default().fg(Color::White).bg(theme.panel_bg);
     let dim = Style::default().fg(theme.dim).bg(theme.panel_bg);
 
-    let lines: Vec<ratatui::text::Line> = vec![
-        ratatui::text::Line::from(""),
-        ratatui::text::Line::from(vec![
-            Span::styled("  Tools", hdr),
-            Span::styled("                Canvas", hdr),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}", sep),
-            Span::styled("                \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}", sep),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  P  Pencil", txt),
-            Span::styled("         WASD  Move cursor", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  E  Eraser", txt),
-            Span::styled("         Space Draw at cursor", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  L  Line", txt),
-            Span::styled("           Mouse Click/drag", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  R  Rectangle", txt),
-            Span::styled("      Z    Cycle zoom (1x/2x/4x)", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  F  Fill", txt),
-            Span::styled("           B    Cycle block", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  I  Eyedropper", txt),
-            Span::styled("     \u{21E7}B   Block picker", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("                    ", txt),
-            Span::styled("G    Cycle shade (\u{2591}\u{2592}\u{2593})", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("                    ", txt),
-            Span::styled("T    Rect fill/outline", txt),
-        ]),
-        ratatui::text::Line::from(""),
-        ratatui::text::Line::from(vec![
-            Span::styled("  Colors", hdr),
-            Span::styled("            Symmetry", hdr),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}", sep),
-            Span::styled("            \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}", sep),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  1-0  Quick pick", txt),
-            Span::styled("   H  Horizontal mirror", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  S    HSL sliders", txt),
-            Span::styled("  V  Vertical mirror", txt),
-        ]),
-        ratatui::text::Line::from(Span::styled("  X    Hex color input", txt)),
-        ratatui::text::Line::from(vec![
-            Span::styled("  A    Add color", txt),
-            Span::styled("    File", hdr),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  C    Palettes", txt),
-            Span::styled("     \u{2500}\u{2500}\u{2500}\u{2500}", sep),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("                    ", txt),
-            Span::styled("^S Save  ^O Open", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  Palette", hdr),
-            Span::styled("           ^N New   ^E Export", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}", sep),
-            Span::styled("           ^Z Undo  ^Y Redo", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  \u{2191}\u{2193}\u{2190}\u{2192} Browse", txt),
-            Span::styled("        ^T Theme", txt),
-        ]),
-        ratatui::text::Line::from(vec![
-            Span::styled("  Enter  Select/Toggle", txt),
-            Span::styled("  Q Quit  ? Help", txt),
-        ]),
-        ratatui::text::Line::from(""),
-        ratatui::text::Line::from(Span::styled(
-            "         Press any key to close",
-            dim,
-        )),
-    ];
+    // Generated from the keymap, so remapped keys show up here too
+    let sections = app.help_sections();
+    let key_width = sections
+        .iter()
+        .flat_map(|s| s.rows.iter().map(|(k, _)| k.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .min(12);
+    let mut body: Vec<Line> = Vec::new();
+    for section in &sections {
+        body.push(Line::from(Span::styled(format!("  {}", section.title), hdr)));
+        body.push(Line::from(Span::styled(
+            format!("  {}", "\u{2500}".repeat(section.title.chars().count())),
+            sep,
+        )));
+        for (keys, text) in &section.rows {
+            body.push(Line::from(Span::styled(format!("  {:<w$}  {}", keys, text, w = key_width), txt)));
+        }
+        body.push(Line::from(""));
+    }
+    if body.is_empty() {
+        body.push(Line::from(Span::styled("  No matching keys", dim)));
+    }
 
+    // Search line, body, and two hint lines inside the border
     let width = 48;
-    let height = lines.len() as u16 + 2;
+    let height = (body.len() as u16 + 5).min(area.height.saturating_sub(2));
+    let page = (height as usize).saturating_sub(5);
+    app.help_page.set(page);
+    let scroll = app.help_scroll.min(body.len().saturating_sub(page));
+    let total = body.len();
+
+    let search = if app.help_searching || !app.help_query.is_empty() {
+        let cursor = if app.help_searching { "_" } else { "" };
+        Line::from(Span::styled(format!(" /{}{}", app.help_query, cursor), hdr))
+    } else {
+        Line::from("")
+    };
+    let mut lines = vec![search];
+    lines.extend(body.into_iter().skip(scroll).take(page));
+    while lines.len() < page + 1 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(""));
+    let hint = if app.help_searching {
+        " Type to search  Enter Done  Esc Clear"
+    } else {
+        " \u{2191}\u{2193} PgUp PgDn Scroll  / Search  Esc Close"
+    };
+    lines.push(Line::from(Span::styled(hint, dim)));
+
+    let title = if total > page {
+        format!(" Help {}-{}/{} ", scroll + 1, (scroll + page).min(total), total)
+    } else {
+        " Help ".to_string()
+    };
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let help_area = Rect::new(x, y, width, height);
+    let help_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
     let help = Paragraph::new(lines)
         .style(Style::default().fg(Color::White).bg(theme.panel_bg))
@@ -448,27 +509,28 @@ fn render_help(f: &mut Frame, app: &App, area: Rect) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .title(" Help ")
+                .title(title)
                 .style(Style::default().fg(Color::White).bg(theme.panel_bg)),
         );
     f.render_widget(Clear, help_area);
     f.render_widget(help, help_area);
 }
 
-fn render_quit_prompt(f: &mut Frame, area: Rect) {
-    let width = 40;
+/// A red yes/no prompt, sized to fit its message.
+fn render_confirm_prompt(f: &mut Frame, area: Rect, title: &str, message: &str) {
+    let width = (message.chars().count() as u16 + 4).clamp(40, area.width);
     let height = 5;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let prompt_area = Rect::new(x, y, width, height);
+    let prompt_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
-    let prompt = Paragraph::new(" Unsaved changes. Quit? (y/n)")
+    let prompt = Paragraph::new(format!(" {}", message))
         .style(Style::default().fg(Color::White).bg(Color::Red))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .title(" Quit ")
+                .title(format!(" {} ", title))
                 .style(Style::default().fg(Color::White).bg(Color::Red)),
         );
     f.render_widget(Clear, prompt_area);
@@ -477,24 +539,31 @@ fn render_quit_prompt(f: &mut Frame, area: Rect) {
 
 fn render_file_dialog(f: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
-    let file_count = app.file_dialog_files.len();
-    let height = (file_count as u16 + 4).min(20);
-    let width = 44;
+    let view = &app.file_dialog_view;
+    let filter_line = list_filter_line(view, theme);
+    let chrome = 6 + filter_line.is_some() as u16;
+    let file_count = app.file_dialog_files.len().max(1);
+    // Tall enough for the info pane even with one file
+    let height = (file_count as u16 + chrome).clamp(10, chrome + 14).min(area.height);
+    let list_width = 42;
+    let width = (list_width + 2 + 26).min(area.width);
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
     let mut lines: Vec<ratatui::text::Line> = Vec::new();
-    let visible_start = if app.file_dialog_selected > (height as usize).saturating_sub(5) {
-        app.file_dialog_selected - (height as usize).saturating_sub(5)
-    } else {
-        0
-    };
+    lines.extend(filter_line);
+    let rows = height.saturating_sub(chrome) as usize;
+    let visible_start = app.file_dialog_selected.saturating_sub(rows.saturating_sub(1));
 
-    for (i, filename) in app.file_dialog_files.iter().enumerate().skip(visible_start) {
-        if lines.len() >= (height as usize).saturating_sub(4) {
-            break;
-        }
+    if app.file_dialog_files.is_empty() {
+        let empty = if view.filter.is_empty() { " No .kaku files" } else { " No matches" };
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+            empty,
+            Style::default().fg(theme.dim).bg(theme.panel_bg),
+        )));
+    }
+    for (i, filename) in app.file_dialog_files.iter().enumerate().skip(visible_start).take(rows) {
         let is_selected = i == app.file_dialog_selected;
         let prefix = if is_selected { "> " } else { "  " };
         let style = if is_selected {
@@ -510,39 +579,326 @@ fn render_file_dialog(f: &mut Frame, app: &App, area: Rect) {
 
     lines.push(ratatui::text::Line::from(""));
     lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
-        " \u{2191}\u{2193} Navigate  Enter Open  Esc Cancel",
+        " \u{2191}\u{2193} Move  Enter Open  T New tab  Esc Close",
+        Style::default().fg(theme.dim).bg(theme.panel_bg),
+    )));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        " I Import  R Rename  U Dup  D Del  Z Undo",
+        Style::default().fg(theme.dim).bg(theme.panel_bg),
+    )));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        list_view_hint(view),
         Style::default().fg(theme.dim).bg(theme.panel_bg),
     )));
 
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(" Open File ")
+        .style(Style::default().fg(Color::White).bg(theme.panel_bg));
+    let inner = block.inner(dialog_area);
+    let [list_area, info_area] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(list_width), Constraint::Min(0)])
+        .areas(inner);
+    f.render_widget(Clear, dialog_area);
+    f.render_widget(block, dialog_area);
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().fg(Color::White).bg(theme.panel_bg)),
+        list_area,
+    );
+    f.render_widget(
+        Paragraph::new(project_info_lines(app))
+            .style(Style::default().fg(Color::White).bg(theme.panel_bg))
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::LEFT).border_style(Style::default().fg(theme.dim))),
+        info_area,
+    );
+}
+
+fn render_import_dialog(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme();
+    let chrome = 6;
+    let height = (app.import_dialog_files.len() as u16 + chrome).clamp(8, chrome + 14);
+    let width = 44;
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
+    let dim = Style::default().fg(theme.dim).bg(theme.panel_bg);
+
+    let mut lines: Vec<ratatui::text::Line> = Vec::new();
+    let rows = height.saturating_sub(chrome) as usize;
+    let visible_start = app.import_dialog_selected.saturating_sub(rows.saturating_sub(1));
+    for (i, filename) in app.import_dialog_files.iter().enumerate().skip(visible_start).take(rows) {
+        let is_selected = i == app.import_dialog_selected;
+        let prefix = if is_selected { "> " } else { "  " };
+        let style = if is_selected {
+            Style::default().fg(Color::Black).bg(theme.highlight)
+        } else {
+            Style::default().fg(Color::White).bg(theme.panel_bg)
+        };
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!("{}{}", prefix, filename),
+            style,
+        )));
+    }
+
+    lines.push(ratatui::text::Line::from(""));
+    let dither = if app.import_dither { "on" } else { "off" };
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        format!(" Dithering: {}", dither),
+        Style::default().fg(theme.accent).bg(theme.panel_bg),
+    )));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        " \u{2191}\u{2193} Navigate  Enter Import  D Dither",
+        dim,
+    )));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(" Esc Cancel", dim)));
+
     let dialog = Paragraph::new(lines)
         .style(Style::default().fg(Color::White).bg(theme.panel_bg))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .title(" Open File ")
+                .title(" Import Image ")
                 .style(Style::default().fg(Color::White).bg(theme.panel_bg)),
         );
     f.render_widget(Clear, dialog_area);
     f.render_widget(dialog, dialog_area);
 }
 
+/// Timeline along the bottom of the screen, leaving the canvas in view.
+fn render_history_scrubber(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme();
+    let width = 56.min(area.width);
+    let height = 5;
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = area.height.saturating_sub(height + 2);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
+    let dim = Style::default().fg(theme.dim).bg(theme.panel_bg);
+
+    let position = app.history.position();
+    let total = app.history.len();
+    let label = app.history.label_at(position).unwrap_or("Start");
+    let track = width.saturating_sub(4) as usize;
+    let knob = (position * track.saturating_sub(1)).checked_div(total).unwrap_or(0);
+    let bar: String = (0..track)
+        .map(|i| match i.cmp(&knob) {
+            std::cmp::Ordering::Less => '\u{2501}',
+            std::cmp::Ordering::Equal => '\u{25cf}',
+            std::cmp::Ordering::Greater => '\u{2500}',
+        })
+        .collect();
+
+    let lines = vec![
+        ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!(" Step {}/{}: {}", position, total, label),
+            Style::default().fg(theme.accent).bg(theme.panel_bg),
+        )),
+        ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!(" {}", bar),
+            Style::default().fg(theme.highlight).bg(theme.panel_bg),
+        )),
+        ratatui::text::Line::from(ratatui::text::Span::styled(
+            " \u{2190}\u{2192} Step  Home/End Ends  Enter Jump  Esc Cancel",
+            dim,
+        )),
+    ];
+
+    let dialog = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(theme.panel_bg))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(" History ")
+                .style(Style::default().fg(Color::White).bg(theme.panel_bg)),
+        );
+    f.render_widget(Clear, dialog_area);
+    f.render_widget(dialog, dialog_area);
+}
+
+fn render_symmetry_picker(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme();
+    let width = 44;
+    let height = SymmetryMode::ALL.len() as u16 + 4;
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
+    let dim = Style::default().fg(theme.dim).bg(theme.panel_bg);
+
+    let mut lines: Vec<ratatui::text::Line> = Vec::new();
+    for (i, mode) in SymmetryMode::ALL.iter().enumerate() {
+        let is_selected = i == app.symmetry_picker_selected;
+        let prefix = if is_selected { "> " } else { "  " };
+        let (style, desc_style) = if is_selected {
+            let style = Style::default().fg(Color::Black).bg(theme.highlight);
+            (style, style)
+        } else {
+            (Style::default().fg(Color::White).bg(theme.panel_bg), dim)
+        };
+        lines.push(ratatui::text::Line::from(vec![
+            ratatui::text::Span::styled(format!("{}{:<10}", prefix, mode.label()), style),
+            ratatui::text::Span::styled(format!("{:<30}", mode.description()), desc_style),
+        ]));
+    }
+
+    lines.push(ratatui::text::Line::from(""));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        " \u{2191}\u{2193} Navigate  Enter Select  Esc Cancel",
+        dim,
+    )));
+
+    let dialog = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(theme.panel_bg))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(" Symmetry ")
+                .style(Style::default().fg(Color::White).bg(theme.panel_bg)),
+        );
+    f.render_widget(Clear, dialog_area);
+    f.render_widget(dialog, dialog_area);
+}
+
+/// Autosaved versions, newest first, beside the selected one's thumbnail.
+fn render_versions(f: &mut Frame, app: &App, area: Rect) {
+    use crate::versions::THUMB_SIZE;
+    let theme = app.theme();
+    let rows = THUMB_SIZE.1.div_ceil(2);
+    let width = 20 + THUMB_SIZE.0 as u16 + 2;
+    let height = rows as u16 + 4;
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
+    let dim = Style::default().fg(theme.dim).bg(theme.panel_bg);
+    let panel = Style::default().fg(Color::White).bg(theme.panel_bg);
+
+    let visible_start = app.versions_selected.saturating_sub(rows - 1);
+    let thumbnail = app.versions.get(app.versions_selected).map(|v| &v.thumbnail[..]).unwrap_or_default();
+    let mut lines: Vec<ratatui::text::Line> = Vec::new();
+    for row in 0..rows {
+        let mut spans = Vec::new();
+        let i = visible_start + row;
+        match app.versions.get(i) {
+            Some(version) => {
+                let style = if i == app.versions_selected {
+                    Style::default().fg(Color::Black).bg(theme.highlight)
+                } else {
+                    panel
+                };
+                let prefix = if i == app.versions_selected { "> " } else { "  " };
+                spans.push(ratatui::text::Span::styled(format!("{}{:<16}", prefix, version.label()), style));
+                spans.push(ratatui::text::Span::styled("  ", panel));
+            }
+            None => spans.push(ratatui::text::Span::styled(" ".repeat(20), panel)),
+        }
+        // Two thumbnail pixels per cell: the top as foreground, the bottom as background
+        let top = thumbnail.get(row * 2).map(|r| &r[..]).unwrap_or_default();
+        let bottom = thumbnail.get(row * 2 + 1).map(|r| &r[..]).unwrap_or_default();
+        for col in 0..top.len() {
+            let color = |px: Option<&Option<crate::cell::Rgb>>| {
+                px.copied().flatten().map_or(theme.panel_bg, |c| app.display_color(c))
+            };
+            spans.push(ratatui::text::Span::styled(
+                "\u{2580}",
+                Style::default().fg(color(top.get(col))).bg(color(bottom.get(col))),
+            ));
+        }
+        lines.push(ratatui::text::Line::from(spans));
+    }
+
+    lines.push(ratatui::text::Line::from(""));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        " \u{2191}\u{2193} Navigate  Enter Restore  Esc Cancel",
+        dim,
+    )));
+
+    let dialog = Paragraph::new(lines)
+        .style(panel)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(format!(" Versions ({}) ", app.versions.len()))
+                .style(panel),
+        );
+    f.render_widget(Clear, dialog_area);
+    f.render_widget(dialog, dialog_area);
+}
+
+/// Header of the file highlighted in the open dialog, read on first view.
+fn project_info_lines(app: &App) -> Vec<ratatui::text::Line<'static>> {
+    let Some(filename) = app.file_dialog_files.get(app.file_dialog_selected) else {
+        return Vec::new();
+    };
+    let theme = app.theme();
+    let label = |text: &str| {
+        ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!(" {}", text),
+            Style::default().fg(theme.dim),
+        ))
+    };
+    let value = |text: String| ratatui::text::Line::from(format!(" {}", text));
+    let path = std::env::current_dir().unwrap_or_default().join(filename);
+    match app.file_dialog_info.borrow_mut().get(&path) {
+        Ok(info) => vec![
+            label("Name"),
+            value(info.name),
+            label("Canvas"),
+            value(format!("{}x{}", info.width, info.height)),
+            label("Version"),
+            value(format!("v{}", info.version)),
+            label("Modified"),
+            value(info.modified_at.replace('T', " ").trim_end_matches('Z').to_string()),
+        ],
+        Err(e) => vec![label("Unreadable"), value(e)],
+    }
+}
+
+/// Filter prompt for a file-listing dialog, shown while a filter is set.
+fn list_filter_line(view: &ListView, theme: &Theme) -> Option<ratatui::text::Line<'static>> {
+    if !view.filtering && view.filter.is_empty() {
+        return None;
+    }
+    let cursor = if view.filtering { "_" } else { "" };
+    Some(ratatui::text::Line::from(ratatui::text::Span::styled(
+        format!(" /{}{}", view.filter, cursor),
+        Style::default().fg(theme.accent).bg(theme.panel_bg),
+    )))
+}
+
+fn list_view_hint(view: &ListView) -> String {
+    if view.filtering {
+        " Type to filter  Enter Done".to_string()
+    } else if !view.filter.is_empty() {
+        format!(" / Filter  Esc Clear  S Sort: {}", view.sort.label())
+    } else {
+        format!(" / Filter  S Sort: {}", view.sort.label())
+    }
+}
+
 fn render_export_dialog(f: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
     let is_colored = app.export_format == 1;
-    let width = 42;
-    let height = if is_colored { 17 } else { 12 };
+    let width = 50;
+    let merges = app.export_analysis.as_ref().map_or(0, |r| r.merges.len()) as u16;
+    let height = if is_colored { 22 + merges } else { 13 };
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
-    let format_opts = ["Plain", "Colored"];
+    let format_opts = ["Plain", "Colored", "HTML", "Markdown", "MD+HTML"];
     let color_fmt_opts = ["24-bit RGB", "256 color", "16 color"];
     let dest_opts = ["Clipboard", "File"];
 
     let dim_style = Style::default().fg(theme.dim).bg(theme.panel_bg);
 
     let mut lines: Vec<ratatui::text::Line> = Vec::new();
+    // Line of the focused option row, kept in view on short terminals
+    let mut focus_line = 0;
 
     // Format row (cursor == 0)
     lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
@@ -562,19 +918,31 @@ fn render_export_dialog(f: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(Color::White).bg(theme.panel_bg)
         };
         fmt_spans.push(ratatui::text::Span::styled(format!(" {} ", opt), style));
-        if i == 0 {
+        if i < format_opts.len() - 1 {
             fmt_spans.push(ratatui::text::Span::raw(" "));
         }
     }
+    if app.export_cursor == 0 {
+        focus_line = lines.len();
+    }
     lines.push(ratatui::text::Line::from(fmt_spans));
 
     // Format description
-    let fmt_desc = if is_colored {
-        "  Blocks with ANSI color codes"
-    } else {
-        "  Block characters only, no color"
+    let fmt_desc = match app.export_format {
+        0 => "  Block characters only, no color",
+        1 => "  Blocks with ANSI color codes",
+        2 => "  Colored <pre> for web pages",
+        3 => "  Plain text in a code block, for READMEs",
+        _ => "  Colored <pre> in a Markdown <details> block",
     };
     lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(fmt_desc, dim_style)));
+    if app.export_format == 0 {
+        let double = if app.export_double_width { "on" } else { "off" };
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!("  Double width (W): {}", double),
+            dim_style,
+        )));
+    }
     lines.push(ratatui::text::Line::from(""));
 
     // Color format row (cursor == 1, only when Colored)
@@ -600,6 +968,9 @@ fn render_export_dialog(f: &mut Frame, app: &App, area: Rect) {
                 cf_spans.push(ratatui::text::Span::raw(" "));
             }
         }
+        if app.export_cursor == 1 {
+            focus_line = lines.len();
+        }
         lines.push(ratatui::text::Line::from(cf_spans));
 
         // Color format description
@@ -609,12 +980,56 @@ fn render_export_dialog(f: &mut Frame, app: &App, area: Rect) {
             _ => "  Max compat \u{2014} all terminals",
         };
         lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(cf_desc, dim_style)));
+        let dither = match (app.export_color_format, app.export_dither) {
+            (0, _) => "  Dither (D): n/a for 24-bit",
+            (_, true) => "  Dither (D): on",
+            (_, false) => "  Dither (D): off",
+        };
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(dither, dim_style)));
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!("  Transparent (B): {}", app.export_ansi.transparent.label()),
+            dim_style,
+        )));
+        let skip = if app.export_ansi.cursor_forward { "on" } else { "off" };
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+            format!("  Skip gaps (C): {}", skip),
+            dim_style,
+        )));
+        match &app.export_analysis {
+            None => lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+                "  Analyze colors (A)",
+                dim_style,
+            ))),
+            Some(report) => {
+                let summary = if report.inexact == 0 {
+                    format!("  {} colors, all exact", report.distinct)
+                } else {
+                    format!("  {} colors, {} not exact", report.distinct, report.inexact)
+                };
+                let style = if report.inexact == 0 { dim_style } else { Style::default().fg(theme.accent).bg(theme.panel_bg) };
+                lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(summary, style)));
+                for merge in &report.merges {
+                    lines.push(ratatui::text::Line::from(vec![
+                        ratatui::text::Span::raw("   "),
+                        ratatui::text::Span::styled("\u{2588}\u{2588}", Style::default().fg(app.display_color(merge.color))),
+                        ratatui::text::Span::styled(format!(" {} {:>4}x \u{2192} ", merge.color.name(), merge.cells), dim_style),
+                        ratatui::text::Span::styled("\u{2588}\u{2588}", Style::default().fg(app.display_color(merge.into))),
+                        ratatui::text::Span::styled(format!(" {}", merge.into.name()), dim_style),
+                    ]));
+                }
+            }
+        }
         lines.push(ratatui::text::Line::from(""));
     }
 
     // Destination row (cursor == 1 for Plain, cursor == 2 for Colored)
     let dest_cursor = if is_colored { 2 } else { 1 };
-    let ext = if is_colored { ".ans" } else { ".txt" };
+    let ext = match app.export_format {
+        0 => ".txt",
+        1 => ".ans",
+        2 => ".html",
+        _ => ".md",
+    };
     lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
         format!(" Destination ({}):", ext),
         Style::default().fg(theme.accent).bg(theme.panel_bg),
@@ -636,16 +1051,26 @@ fn render_export_dialog(f: &mut Frame, app: &App, area: Rect) {
             dest_spans.push(ratatui::text::Span::raw(" "));
         }
     }
+    if app.export_cursor == dest_cursor {
+        focus_line = lines.len();
+    }
     lines.push(ratatui::text::Line::from(dest_spans));
     lines.push(ratatui::text::Line::from(""));
+    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+        " Undo history as a GIF file (G)",
+        Style::default().fg(theme.dim).bg(theme.panel_bg),
+    )));
 
     lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
         " \u{2191}\u{2193} Row  \u{2190}\u{2192} Option  Enter Go  Esc Cancel",
         Style::default().fg(theme.dim).bg(theme.panel_bg),
     )));
 
+    let visible = dialog_area.height.saturating_sub(2);
+    let scroll = (focus_line as u16 + 1).saturating_sub(visible.saturating_sub(1));
     let dialog = Paragraph::new(lines)
         .style(Style::default().fg(Color::White).bg(theme.panel_bg))
+        .scroll((scroll, 0))
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -663,7 +1088,7 @@ fn render_text_input(f: &mut Frame, app: &App, area: Rect, title: &str, prompt:
     let height = 7;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
     let mut lines: Vec<ratatui::text::Line> = Vec::new();
     lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
@@ -700,9 +1125,14 @@ fn render_recovery_prompt(f: &mut Frame, app: &App, area: Rect) {
     let height = 5;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let prompt_area = Rect::new(x, y, width, height);
+    let prompt_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
-    let prompt = Paragraph::new(" Autosave found. Recover? (y/n)")
+    let text = if app.recovering_journal() {
+        " Unsaved changes found. Recover? (y/n)"
+    } else {
+        " Autosave found. Recover? (y/n)"
+    };
+    let prompt = Paragraph::new(text)
         .style(Style::default().fg(Color::White).bg(theme.border_accent))
         .block(
             Block::default()
@@ -721,7 +1151,7 @@ fn render_color_sliders(f: &mut Frame, app: &App, area: Rect) {
     let height = 15;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
     let bar_width = 20;
     let sliders: [(&str, u16, u16); 3] = [
@@ -769,7 +1199,7 @@ fn render_color_sliders(f: &mut Frame, app: &App, area: Rect) {
     // Live preview
     let (r, g, b) = crate::palette::hsl_to_rgb(app.slider_h, app.slider_s, app.slider_l);
     let preview_color = crate::palette::nearest_color(r, g, b);
-    let preview_rcolor = preview_color.to_ratatui();
+    let preview_rcolor = app.display_color(preview_color);
     let idx_256 = crate::cell::nearest_256(&preview_color);
 
     lines.push(ratatui::text::Line::from(vec![
@@ -815,31 +1245,51 @@ fn render_color_sliders(f: &mut Frame, app: &App, area: Rect) {
 
 fn render_palette_dialog(f: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
-    let file_count = app.palette_dialog_files.len();
-    let height = (file_count as u16 + 8).min(22);
+    let view = &app.palette_dialog_view;
+    let filter_line = list_filter_line(view, theme);
+    let hints: &[&str] = if app.palette_dialog_trash {
+        &[" \u{2191}\u{2193} Nav  Enter Restore", " T/Esc Back to palettes"]
+    } else {
+        &[
+            " \u{2191}\u{2193} Nav  Enter Load  N New",
+            " R Rename  U Dup  D Del  T Trash",
+            " X Export  C Copy  V Paste",
+            " L Lospec  Z Undo  Esc Close",
+        ]
+    };
+    // Borders, filter, blank line, hints and the active palette line
+    let chrome = 3
+        + filter_line.is_some() as u16
+        + hints.len() as u16 + 1
+        + if app.custom_palette.is_some() { 2 } else { 0 };
+    let file_count = app.palette_dialog_files.len().max(1);
+    // Fewer list rows on a short terminal; the list scrolls to the selection
+    let height = (file_count as u16 + chrome).min(chrome + 14).min(area.height);
     let width = 44;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
     let mut lines: Vec<ratatui::text::Line> = Vec::new();
+    lines.extend(filter_line);
 
     if app.palette_dialog_files.is_empty() {
+        let empty = if !view.filter.is_empty() {
+            " No matches"
+        } else if app.palette_dialog_trash {
+            " Trash is empty"
+        } else {
+            " No palettes found"
+        };
         lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
-            " No palettes found",
+            empty,
             Style::default().fg(theme.dim).bg(theme.panel_bg),
         )));
     } else {
-        let visible_start = if app.palette_dialog_selected > (height as usize).saturating_sub(7) {
-            app.palette_dialog_selected - (height as usize).saturating_sub(7)
-        } else {
-            0
-        };
+        let rows = height.saturating_sub(chrome).max(1) as usize;
+        let visible_start = app.palette_dialog_selected.saturating_sub(rows.saturating_sub(1));
 
-        for (i, filename) in app.palette_dialog_files.iter().enumerate().skip(visible_start) {
-            if lines.len() >= (height as usize).saturating_sub(6) {
-                break;
-            }
+        for (i, filename) in app.palette_dialog_files.iter().enumerate().skip(visible_start).take(rows) {
             let is_selected = i == app.palette_dialog_selected;
             let prefix = if is_selected { "> " } else { "  " };
             let style = if is_selected {
@@ -847,10 +1297,22 @@ fn render_palette_dialog(f: &mut Frame, app: &App, area: Rect) {
             } else {
                 Style::default().fg(Color::White).bg(theme.panel_bg)
             };
-            lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
-                format!("{}{}", prefix, filename),
+            // Name column, then a strip of the palette's first colors
+            let name_width = (width as usize).saturating_sub(crate::palette::SWATCH_COUNT + 3);
+            let label: String = format!("{}{}", prefix, filename).chars().take(name_width).collect();
+            let mut spans = vec![ratatui::text::Span::styled(
+                format!("{:<width$}", label, width = name_width),
                 style,
-            )));
+            )];
+            spans.push(ratatui::text::Span::raw(" "));
+            let swatches = app.palette_swatches.borrow_mut().get(&app.palette_dialog_path(filename));
+            for color in swatches {
+                spans.push(ratatui::text::Span::styled(
+                    "\u{2588}",
+                    Style::default().fg(app.display_color(color)).bg(theme.panel_bg),
+                ));
+            }
+            lines.push(ratatui::text::Line::from(spans));
         }
     }
 
@@ -864,18 +1326,13 @@ fn render_palette_dialog(f: &mut Frame, app: &App, area: Rect) {
     }
 
     lines.push(ratatui::text::Line::from(""));
-    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
-        " \u{2191}\u{2193} Nav  Enter Load  N New",
-        Style::default().fg(theme.dim).bg(theme.panel_bg),
-    )));
-    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
-        " R Rename  U Dup  D Del",
-        Style::default().fg(theme.dim).bg(theme.panel_bg),
-    )));
-    lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
-        " X Export  Esc Close",
-        Style::default().fg(theme.dim).bg(theme.panel_bg),
-    )));
+    let view_hint = list_view_hint(view);
+    for hint in hints.iter().copied().chain([view_hint.as_str()]) {
+        lines.push(ratatui::text::Line::from(ratatui::text::Span::styled(
+            hint.to_string(),
+            Style::default().fg(theme.dim).bg(theme.panel_bg),
+        )));
+    }
 
     let dialog = Paragraph::new(lines)
         .style(Style::default().fg(Color::White).bg(theme.panel_bg))
@@ -883,7 +1340,7 @@ fn render_palette_dialog(f: &mut Frame, app: &App, area: Rect) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .title(" Custom Palettes ")
+                .title(if app.palette_dialog_trash { " Deleted Palettes " } else { " Custom Palettes " })
                 .style(Style::default().fg(Color::White).bg(theme.panel_bg)),
         );
     f.render_widget(Clear, dialog_area);
@@ -896,7 +1353,7 @@ fn render_hex_input(f: &mut Frame, app: &App, area: Rect) {
     let height = 9u16;
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
-    let dialog_area = Rect::new(x, y, width, height);
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
 
     let mut lines: Vec<ratatui::text::Line> = Vec::new();
 
@@ -915,7 +1372,7 @@ fn render_hex_input(f: &mut Frame, app: &App, area: Rect) {
     let parsed = crate::cell::parse_hex_color(&app.text_input);
     if let Some(rgb) = parsed {
         let preview_color = crate::palette::nearest_color(rgb.r, rgb.g, rgb.b);
-        let preview_rcolor = preview_color.to_ratatui();
+        let preview_rcolor = app.display_color(preview_color);
         lines.push(ratatui::text::Line::from(vec![
             ratatui::text::Span::styled(
                 " Preview: ",
@@ -956,6 +1413,62 @@ fn render_hex_input(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(dialog, dialog_area);
 }
 
+fn render_inspector(f: &mut Frame, app: &App, area: Rect) {
+    use ratatui::text::{Line, Span};
+
+    let theme = app.theme();
+    let width = 40u16;
+    let height = 10u16;
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    let dialog_area = Rect::new(x, y, width.min(area.width), height.min(area.height));
+    let label_style = Style::default().fg(theme.dim).bg(theme.panel_bg);
+
+    let mut lines: Vec<Line> = Vec::new();
+    lines.push(Line::from(""));
+    for (i, label) in ["Char", "Fg", "Bg"].iter().enumerate() {
+        let value = &app.inspector_fields[i];
+        let (text, style) = if i == app.inspector_field {
+            (format!(" {}\u{2588}", value), Style::default().fg(Color::White).bg(Color::Black))
+        } else if value.is_empty() {
+            (" transparent".to_string(), label_style)
+        } else {
+            (format!(" {}", value), Style::default().fg(Color::White).bg(theme.panel_bg))
+        };
+        lines.push(Line::from(vec![Span::styled(format!(" {:<6}", label), label_style), Span::styled(text, style)]));
+    }
+    lines.push(Line::from(""));
+
+    // Live preview of the edited cell, or what's wrong with the input
+    match app.inspector_cell() {
+        Ok(cell) => {
+            let fg = cell.fg.map_or(Color::Reset, |c| app.display_color(c));
+            let bg = cell.bg.map_or(theme.panel_bg, |c| app.display_color(c));
+            let ch = crate::cell::display_char(cell.ch).to_string();
+            lines.push(Line::from(vec![
+                Span::styled(" Preview: ", label_style),
+                Span::styled(ch.repeat(4 / crate::cell::char_width(cell.ch).max(1)), Style::default().fg(fg).bg(bg)),
+            ]));
+        }
+        Err(e) => lines.push(Line::from(Span::styled(format!(" {}", e), Style::default().fg(theme.accent).bg(theme.panel_bg)))),
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(" Tab Next field  Enter Apply  Esc Cancel", label_style)));
+
+    let (cx, cy) = app.inspector_pos;
+    let dialog = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(theme.panel_bg))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(format!(" Cell ({},{}) ", cx, cy))
+                .style(Style::default().fg(Color::White).bg(theme.panel_bg)),
+        );
+    f.render_widget(Clear, dialog_area);
+    f.render_widget(dialog, dialog_area);
+}
+
 fn render_block_picker(f: &mut Frame, app: &App, area: Rect) {
     use crate::cell::blocks;
     use ratatui::text::{Line, Span};
@@ -1014,6 +1527,104 @@ fn render_block_picker(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(dialog, dialog_area);
 }
 
+fn render_release_notes(f: &mut Frame, app: &App, area: Rect) {
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::Wrap;
+
+    let Some(release) = &app.available_update else {
+        return;
+    };
+    let theme = app.theme();
+    let w = 64u16.min(area.width);
+    let h = 20u16.min(area.height);
+    let dialog_area = Rect::new(area.width.saturating_sub(w) / 2, area.height.saturating_sub(h) / 2, w, h);
+    f.render_widget(Clear, dialog_area);
+
+    let dim = Style::default().fg(theme.dim);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!(" You have {}. {}", env!("CARGO_PKG_VERSION"), release.url),
+            dim,
+        )),
+        Line::from(""),
+    ];
+    let notes = if release.notes.trim().is_empty() { "No release notes." } else { release.notes.as_str() };
+    lines.extend(notes.lines().map(|l| Line::from(Span::styled(format!(" {}", l), Style::default().fg(Color::White)))));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(format!(" kakukuma {} ", release.version))
+        .title_bottom(Line::from(Span::styled(" \u{2191}\u{2193} Scroll  Esc Close ", dim)))
+        .style(Style::default().fg(theme.accent).bg(theme.panel_bg));
+    let notes = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((app.release_notes_scroll, 0));
+    f.render_widget(notes, dialog_area);
+}
+
+fn render_resize_canvas(f: &mut Frame, app: &App, area: Rect) {
+    use ratatui::text::{Line, Span};
+
+    let theme = app.theme();
+    let w = 34u16;
+    let h = 13u16;
+    let dialog_area = Rect::new(
+        area.width.saturating_sub(w) / 2,
+        area.height.saturating_sub(h) / 2,
+        w.min(area.width),
+        h.min(area.height),
+    );
+    f.render_widget(Clear, dialog_area);
+
+    let focused = Style::default().fg(Color::Black).bg(theme.highlight).add_modifier(Modifier::BOLD);
+    let row_style = |row: u8| if app.resize_cursor == row { focused } else { Style::default().fg(Color::White) };
+    let dim = Style::default().fg(theme.dim);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(" Width:  ", dim),
+            Span::styled(format!("\u{25C0} {:>3} \u{25B6}", app.resize_width), row_style(0)),
+        ]),
+        Line::from(vec![
+            Span::styled(" Height: ", dim),
+            Span::styled(format!("\u{25C0} {:>3} \u{25B6}", app.resize_height), row_style(1)),
+        ]),
+        Line::from(Span::raw("")),
+    ];
+    // 3x3 grid of anchors, the chosen one filled
+    for y in 0..3u8 {
+        let mut spans = vec![Span::styled(if y == 0 { " Anchor: " } else { "         " }, dim)];
+        for x in 0..3u8 {
+            let chosen = app.resize_anchor == crate::canvas::Anchor { x, y };
+            let mark = if chosen { "\u{25A0}" } else { "\u{25A1}" };
+            let style = if chosen { row_style(2) } else { Style::default().fg(Color::White) };
+            spans.push(Span::styled(mark, style));
+            spans.push(Span::raw(" "));
+        }
+        if y == 1 {
+            spans.push(Span::styled(app.resize_anchor.name(), dim));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.extend([
+        Line::from(Span::raw("")),
+        Line::from(Span::styled(format!(" Current: {}x{}", app.canvas.width, app.canvas.height), dim)),
+        Line::from(Span::styled(" \u{2190}\u{2192}=Adjust  1-9=Anchor", dim)),
+        Line::from(Span::styled(" Enter=Resize  Esc=Cancel", dim)),
+    ]);
+
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(" Resize Canvas ")
+            .style(Style::default().fg(theme.accent).bg(theme.panel_bg)),
+    );
+    f.render_widget(dialog, dialog_area);
+}
+
 fn render_new_canvas(f: &mut Frame, app: &App, area: Rect) {
     use ratatui::text::{Line, Span};
 
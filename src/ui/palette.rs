@@ -1,20 +1,27 @@
+use std::collections::BTreeSet;
+
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 
 use crate::app::App;
 use crate::cell::Rgb;
-use crate::palette::{PaletteItem, PaletteSection};
+use crate::export::ColorFormat;
+use crate::palette::{PaletteItem, PaletteSection, COLORS_PER_PAGE};
 use crate::theme::Theme;
 
 const COLS: usize = 6;
 const PALETTE_INNER_WIDTH: usize = 18; // box width (20) minus 2 border chars
 
-/// Render a row of color swatches (up to COLS per row).
+/// Render a row of color swatches (up to COLS per row). `marks` holds the
+/// flat positions of swatches marked for a bulk palette edit.
 fn render_color_row(
     colors: &[Rgb],
     active_color: Rgb,
     flat_offset: usize,
     palette_cursor: usize,
+    marks: &BTreeSet<usize>,
+    format: ColorFormat,
+    markers: bool,
 ) -> Vec<Line<'static>> {
     let mut lines = Vec::new();
     for chunk_start in (0..colors.len()).step_by(COLS) {
@@ -25,18 +32,25 @@ fn render_color_row(
         let pad = PALETTE_INNER_WIDTH.saturating_sub(content_width) / 2;
         spans.push(Span::raw(" ".repeat(pad.max(1))));
         for (i, &color) in colors[chunk_start..chunk_end].iter().enumerate() {
-            let rcolor = color.to_ratatui();
+            let rcolor = color.to_ratatui_as(format);
             let flat_pos = flat_offset + chunk_start + i;
             let is_cursor = flat_pos == palette_cursor;
             let is_active = color == active_color;
+            let is_marked = marks.contains(&flat_pos);
 
-            let marker = if is_cursor {
+            let marker = if is_cursor && is_marked {
+                ">*"
+            } else if is_cursor {
                 ">>"
+            } else if is_marked {
+                "**"
+            } else if is_active && markers {
+                "<>"
             } else {
                 "\u{2588}\u{2588}"
             };
 
-            let style = if is_cursor || is_active {
+            let style = if is_cursor || is_active || is_marked {
                 Style::default()
                     .fg(Color::Indexed(16))
                     .bg(rcolor)
@@ -56,8 +70,13 @@ fn render_color_row(
 }
 
 /// Render a collapsible section header line.
-fn section_header_line(section: PaletteSection, expanded: bool, is_cursor: bool, theme: &Theme) -> Line<'static> {
-    let indicator = if expanded { "\u{25BE}" } else { "\u{25B8}" }; // ▾ or ▸
+fn section_header_line(section: PaletteSection, expanded: bool, is_cursor: bool, plain: bool, theme: &Theme) -> Line<'static> {
+    let indicator = match (expanded, plain) {
+        (true, false) => "\u{25BE}", // ▾
+        (false, false) => "\u{25B8}", // ▸
+        (true, true) => "-",
+        (false, true) => "+",
+    };
     let (name, count) = match section {
         PaletteSection::Standard => ("Standard", 16),
         PaletteSection::HueGroups => ("Hue Groups", 216),
@@ -87,7 +106,8 @@ fn first_section_index(app: &App) -> usize {
         .unwrap_or(app.palette_layout.len())
 }
 
-/// Curated color swatches (items before the first SectionHeader).
+/// Curated color swatches (items before the first SectionHeader), one page
+/// at a time, with a page indicator when they don't fit on one.
 pub fn color_lines(app: &App) -> Vec<Line<'static>> {
     let split = first_section_index(app);
     let layout = &app.palette_layout;
@@ -99,7 +119,26 @@ pub fn color_lines(app: &App) -> Vec<Line<'static>> {
         }
     }
 
-    render_color_row(&colors, app.color, 0, app.palette_cursor)
+    let page = app.colors_page();
+    let start = page * COLORS_PER_PAGE;
+    let end = (start + COLORS_PER_PAGE).min(colors.len());
+    let mut lines = render_color_row(
+        &colors[start.min(end)..end],
+        app.color,
+        start,
+        app.palette_cursor,
+        &app.palette_marks,
+        app.color_support,
+        app.char_markers(),
+    );
+    let pages = app.colors_page_count();
+    if pages > 1 {
+        // Keep the box the same height on a short last page
+        lines.resize(COLORS_PER_PAGE / COLS, Line::default());
+        let dim = Style::default().fg(app.theme().dim);
+        lines.push(center_line(&format!("page {}/{}", page + 1, pages), dim));
+    }
+    lines
 }
 
 /// Section headers + expanded section colors (from first SectionHeader onward).
@@ -128,6 +167,9 @@ pub fn section_lines(app: &App) -> Vec<Line<'static>> {
                         app.color,
                         batch_start,
                         app.palette_cursor,
+                        &BTreeSet::new(),
+                        app.color_support,
+                        app.char_markers(),
                     );
                     all_lines.extend(rows);
                     color_batch.clear();
@@ -140,7 +182,7 @@ pub fn section_lines(app: &App) -> Vec<Line<'static>> {
                     PaletteSection::Grayscale => app.palette_sections.grayscale_expanded,
                 };
                 let is_cursor = i == app.palette_cursor;
-                all_lines.push(section_header_line(section, expanded, is_cursor, theme));
+                all_lines.push(section_header_line(section, expanded, is_cursor, app.screen_reader, theme));
                 i += 1;
             }
         }
@@ -163,7 +205,7 @@ pub fn info_lines(app: &App) -> Vec<Line<'static>> {
     let theme = app.theme();
     let dim = Style::default().fg(theme.dim);
     let color_style = Style::default()
-        .bg(app.color.to_ratatui());
+        .bg(app.display_color(app.color));
 
     // Line 1: color swatch + name (mixed styles, centered)
     let swatch = "    ";
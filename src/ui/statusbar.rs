@@ -5,9 +5,16 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
 
 use crate::app::App;
+use crate::export::ColorFormat;
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
+    if app.screen_reader {
+        // One plain sentence, no key hints or swatches to read past
+        let text = format!(" {}", app.screen_reader_status());
+        f.render_widget(Paragraph::new(text).style(Style::default().fg(Color::White).bg(theme.panel_bg)), area);
+        return;
+    }
     let mut spans = Vec::new();
 
     // Status message takes priority
@@ -51,7 +58,7 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         // Active color swatch
         right_spans.push(Span::styled(
             "  ",
-            Style::default().bg(app.color.to_ratatui()),
+            Style::default().bg(app.display_color(app.color)),
         ));
         right_spans.push(Span::styled(" ", Style::default().bg(theme.panel_bg)));
 
@@ -68,6 +75,14 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(theme.dim).bg(theme.panel_bg),
         ));
 
+        // Reduced color depth indicator
+        if app.color_support != ColorFormat::TrueColor {
+            right_spans.push(Span::styled(
+                format!("{} ", crate::terminal::color_support_label(app.color_support)),
+                Style::default().fg(theme.dim).bg(theme.panel_bg),
+            ));
+        }
+
         for &(key, label) in &[("?", " Help "), ("Q", " Quit ")] {
             right_spans.push(Span::styled(key, Style::default().fg(Color::White).bg(theme.panel_bg)));
             right_spans.push(Span::styled(label, Style::default().fg(Color::Gray).bg(theme.panel_bg)));
@@ -2,16 +2,21 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 
 use crate::app::App;
+use crate::command::Command;
 use crate::tools::ToolKind;
 
-/// Tool list: 6 tool entries.
+/// Tool list: one entry per tool.
 pub fn tool_lines(app: &App) -> Vec<Line<'static>> {
     let theme = app.theme();
     let mut lines: Vec<Line> = Vec::new();
 
     for tool in ToolKind::ALL {
         let is_active = app.active_tool == tool;
-        let prefix = if is_active { "\u{25B8}" } else { " " }; // ▸ or space
+        let prefix = match (is_active, app.screen_reader) {
+            (true, false) => "\u{25B8}", // ▸
+            (true, true) => ">",
+            (false, _) => " ",
+        };
         let style = if is_active {
             Style::default()
                 .fg(Color::Indexed(16))
@@ -20,16 +25,19 @@ pub fn tool_lines(app: &App) -> Vec<Line<'static>> {
         } else {
             Style::default().fg(Color::White)
         };
-        lines.push(Line::from(Span::styled(
-            format!(" {}{} {} {}", prefix, tool.key(), tool.icon(), tool.name()),
-            style,
-        )));
+        let key = app.keymap.key_label(&Command::SelectTool(tool)).unwrap_or_default();
+        let text = if app.screen_reader {
+            format!(" {}{} {}", prefix, key, tool.name())
+        } else {
+            format!(" {}{} {} {}", prefix, key, tool.icon(), tool.name())
+        };
+        lines.push(Line::from(Span::styled(text, style)));
     }
 
     lines
 }
 
-/// Symmetry toggle row: [H] [V].
+/// Symmetry toggle row: [H] [V], or the radial mode when one is on.
 pub fn symmetry_lines(app: &App) -> Vec<Line<'static>> {
     let theme = app.theme();
     let sym = app.symmetry;
@@ -44,9 +52,23 @@ pub fn symmetry_lines(app: &App) -> Vec<Line<'static>> {
         Style::default().fg(theme.dim)
     };
 
+    // Without relying on color, brackets mark only the axes that are on
+    let label = |on: bool, key: &str| {
+        if on || !app.char_markers() {
+            format!("[{}]", key)
+        } else {
+            format!(" {} ", key.to_lowercase())
+        }
+    };
+    if sym.is_radial() {
+        return vec![Line::from(Span::styled(
+            format!(" [{}]", sym.label()),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        ))];
+    }
     vec![Line::from(vec![
-        Span::styled(" [H] ", h_style),
-        Span::styled("[V]", v_style),
+        Span::styled(format!(" {} ", label(sym.has_horizontal(), "H")), h_style),
+        Span::styled(label(sym.has_vertical(), "V"), v_style),
     ])]
 }
 
@@ -68,7 +90,17 @@ pub fn block_lines(app: &App) -> Vec<Line<'static>> {
     let rect_text = if app.filled_rect { " [T] Filled" } else { " [T] Outline" };
     let rect_line = Line::from(Span::styled(rect_text, Style::default().fg(theme.dim)));
 
-    vec![block_line, rect_line]
+    let style_line = Line::from(Span::styled(
+        format!(" Line: {}", app.line_style.name()),
+        Style::default().fg(theme.dim),
+    ));
+
+    let brush_line = Line::from(Span::styled(
+        format!(" Brush: {}", app.brush_pattern.name()),
+        Style::default().fg(theme.dim),
+    ));
+
+    vec![block_line, rect_line, style_line, brush_line]
 }
 
 /// Active color swatch display.
@@ -82,7 +114,7 @@ pub fn color_swatch_lines(app: &App) -> Vec<Line<'static>> {
         Span::styled(" ", Style::default()),
         Span::styled(
             "    ",
-            Style::default().bg(app.color.to_ratatui()),
+            Style::default().bg(app.display_color(app.color)),
         ),
         Span::styled(
             format!(" {}", app.color.name()),
@@ -0,0 +1,77 @@
+//! The optional check for a newer release on GitHub. It runs at startup
+//! only when both `check_updates` and `allow_network` are on.
+
+use crate::net;
+
+/// A published release.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Release {
+    pub version: String,
+    /// The release description, usually Markdown
+    pub notes: String,
+    pub url: String,
+}
+
+/// GitHub's API address for the newest release of this repository.
+pub fn latest_release_url() -> String {
+    let repo = env!("CARGO_PKG_REPOSITORY").trim_start_matches("https://github.com/");
+    format!("https://api.github.com/repos/{}/releases/latest", repo)
+}
+
+/// Parse GitHub's release JSON.
+pub fn parse_release(data: &str) -> Result<Release, String> {
+    let json: serde_json::Value = serde_json::from_str(data).map_err(|_| "GitHub sent something that isn't a release")?;
+    let tag = json["tag_name"].as_str().ok_or("GitHub has no release")?;
+    Ok(Release {
+        version: tag.trim_start_matches('v').to_string(),
+        notes: json["body"].as_str().unwrap_or_default().replace("\r\n", "\n"),
+        url: json["html_url"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Whether `version` is a later `major.minor.patch` than `current`.
+/// Versions that don't parse never are.
+pub fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Option<Vec<u64>> {
+        let mut parts = v.split(['-', '+']).next()?.split('.').map(|p| p.parse().ok()).collect::<Option<Vec<u64>>>()?;
+        parts.resize(3, 0);
+        Some(parts)
+    };
+    match (parse(version), parse(current)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+/// The newest release, if it's newer than this build. Blocks while it
+/// downloads.
+pub fn check() -> Result<Option<Release>, String> {
+    let release = parse_release(&net::get(&latest_release_url())?)?;
+    Ok(is_newer(&release.version, env!("CARGO_PKG_VERSION")).then_some(release))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.2.0", "0.1.0"));
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta.2", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn test_parse_release() {
+        let data = r#"{"tag_name": "v0.2.0", "body": "- Faster\r\n- Smaller", "html_url": "https://example.com/r"}"#;
+        let release = parse_release(data).unwrap();
+        assert_eq!(release.version, "0.2.0");
+        assert_eq!(release.notes, "- Faster\n- Smaller");
+        assert!(parse_release(r#"{"message": "Not Found"}"#).is_err());
+        assert!(latest_release_url().ends_with("/releases/latest"));
+    }
+}
@@ -0,0 +1,213 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::canvas::Canvas;
+use crate::cell::{blocks, Cell, Rgb};
+
+/// Most versions kept per project; the oldest are dropped first.
+pub const MAX_VERSIONS: usize = 30;
+
+/// Most canvas cells kept across a project's versions, so large canvases
+/// keep fewer of them. The newest version is always kept.
+pub const MAX_VERSION_CELLS: usize = 128 * 128 * 8;
+
+/// Largest thumbnail, in pixels. The browser draws two pixels per terminal
+/// cell with half blocks, so this fills 32x12 cells.
+pub const THUMB_SIZE: (usize, usize) = (32, 24);
+
+/// A canvas snapshot taken at an autosave, with a thumbnail for browsing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    pub saved_at: String,
+    /// Rows of thumbnail pixels; `None` is uncolored
+    pub thumbnail: Vec<Vec<Option<Rgb>>>,
+    pub canvas: Canvas,
+}
+
+impl Version {
+    /// Snapshot `canvas` now.
+    pub fn new(canvas: &Canvas) -> Version {
+        Version {
+            saved_at: crate::project::now_iso8601(),
+            thumbnail: thumbnail(canvas),
+            canvas: canvas.clone(),
+        }
+    }
+
+    /// `2026-10-16T09:30:00Z` -> `2026-10-16 09:30`
+    pub fn label(&self) -> String {
+        self.saved_at.get(..16).unwrap_or(&self.saved_at).replacen('T', " ", 1)
+    }
+}
+
+/// `art.kaku` -> `art.kaku.versions`
+pub fn versions_path(kaku_path: &Path) -> PathBuf {
+    let mut p = kaku_path.as_os_str().to_os_string();
+    p.push(".versions");
+    PathBuf::from(p)
+}
+
+/// Versions stored at `path`, oldest first. A missing or unreadable file
+/// has none, and versions with a malformed canvas or thumbnail are skipped.
+pub fn load(path: &Path) -> Vec<Version> {
+    let versions: Vec<Version> = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default();
+    versions
+        .into_iter()
+        .filter(|v| {
+            v.canvas.validate().is_ok()
+                && v.thumbnail.len() <= THUMB_SIZE.1
+                && v.thumbnail.iter().all(|row| row.len() <= THUMB_SIZE.0)
+        })
+        .collect()
+}
+
+/// Add a snapshot of `canvas` to the versions at `path`, unless it matches
+/// the newest one. Returns whether a version was added.
+pub fn record(path: &Path, canvas: &Canvas) -> io::Result<bool> {
+    let mut versions = load(path);
+    if versions.last().is_some_and(|v| &v.canvas == canvas) {
+        return Ok(false);
+    }
+    versions.push(Version::new(canvas));
+    let excess = versions.len().saturating_sub(MAX_VERSIONS);
+    versions.drain(..excess);
+    let mut cells: usize = versions.iter().map(|v| v.canvas.width * v.canvas.height).sum();
+    while versions.len() > 1 && cells > MAX_VERSION_CELLS {
+        let oldest = versions.remove(0);
+        cells -= oldest.canvas.width * oldest.canvas.height;
+    }
+    let json = serde_json::to_string(&versions).map_err(io::Error::other)?;
+    std::fs::write(path, json)?;
+    Ok(true)
+}
+
+/// Downscale the canvas to fit in `THUMB_SIZE`, keeping its shape: each
+/// cell is one pixel wide and two tall, like the editor draws it. Pixels
+/// are sampled, not averaged, so thin lines may drop out.
+pub fn thumbnail(canvas: &Canvas) -> Vec<Vec<Option<Rgb>>> {
+    let (w, h) = (canvas.width as f64, canvas.height as f64 * 2.0);
+    let scale = (w / THUMB_SIZE.0 as f64).max(h / THUMB_SIZE.1 as f64).max(1.0);
+    downsample(canvas, scale)
+}
+
+/// Sample the canvas at one pixel per `scale` pixels, each cell being one
+/// pixel wide and two tall. Rows of pixels; `None` is uncolored.
+pub fn downsample(canvas: &Canvas, scale: f64) -> Vec<Vec<Option<Rgb>>> {
+    let (w, h) = (canvas.width as f64, canvas.height as f64 * 2.0);
+    let tw = (w / scale).ceil() as usize;
+    let th = (h / scale).ceil() as usize;
+    (0..th)
+        .map(|py| {
+            let half = ((py as f64 + 0.5) * scale) as usize;
+            (0..tw)
+                .map(|px| {
+                    let x = ((px as f64 + 0.5) * scale) as usize;
+                    canvas.get(x, half / 2).and_then(|cell| half_color(&cell, half % 2 == 1))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The color the top (or `lower`) half of a cell shows.
+fn half_color(cell: &Cell, lower: bool) -> Option<Rgb> {
+    match cell.ch {
+        ' ' => cell.bg,
+        blocks::UPPER_HALF if lower => cell.bg,
+        blocks::LOWER_HALF if !lower => cell.bg,
+        _ => cell.fg.or(cell.bg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = Rgb { r: 205, g: 0, b: 0 };
+
+    #[test]
+    fn test_thumbnail_keeps_shape_and_halves() {
+        let mut canvas = Canvas::new_with_size(64, 32);
+        canvas.set(0, 0, Cell { ch: blocks::LOWER_HALF, fg: Some(RED), bg: None });
+        let thumb = thumbnail(&canvas);
+        // 64x64 pixels scaled down to 24x24
+        assert_eq!((thumb[0].len(), thumb.len()), (24, 24));
+        assert_eq!(thumb[0][0], None);
+
+        // Small canvases aren't enlarged
+        let mut canvas = Canvas::new_with_size(8, 8);
+        canvas.set(0, 0, Cell { ch: blocks::LOWER_HALF, fg: Some(RED), bg: None });
+        let thumb = thumbnail(&canvas);
+        assert_eq!((thumb[0].len(), thumb.len()), (8, 16));
+        assert_eq!((thumb[0][0], thumb[1][0]), (None, Some(RED)));
+    }
+
+    #[test]
+    fn test_record_skips_unchanged_and_caps() {
+        let dir = std::env::temp_dir().join("kaku_test_versions_record");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = versions_path(&dir.join("art.kaku"));
+        assert_eq!(path, dir.join("art.kaku.versions"));
+
+        let mut canvas = Canvas::new_with_size(8, 8);
+        assert!(record(&path, &canvas).unwrap());
+        assert!(!record(&path, &canvas).unwrap());
+        for i in 0..MAX_VERSIONS {
+            canvas.set(i % 8, i / 8, Cell { ch: blocks::FULL, fg: Some(RED), bg: None });
+            record(&path, &canvas).unwrap();
+        }
+        let versions = load(&path);
+        assert_eq!(versions.len(), MAX_VERSIONS);
+        assert_eq!(versions.last().unwrap().canvas, canvas);
+        assert_eq!(versions[0].label().len(), "2026-10-16 09:30".len());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_record_caps_total_cells() {
+        let dir = std::env::temp_dir().join("kaku_test_versions_cells");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = versions_path(&dir.join("art.kaku"));
+
+        let mut canvas = Canvas::new_with_size(128, 128);
+        for i in 0..12 {
+            canvas.set(i, 0, Cell { ch: blocks::FULL, fg: Some(RED), bg: None });
+            record(&path, &canvas).unwrap();
+        }
+        let versions = load(&path);
+        assert_eq!(versions.len(), MAX_VERSION_CELLS / (128 * 128));
+        assert_eq!(versions.last().unwrap().canvas, canvas);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_skips_malformed_canvases() {
+        let dir = std::env::temp_dir().join("kaku_test_versions_malformed");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = versions_path(&dir.join("art.kaku"));
+
+        let mut canvas = Canvas::new_with_size(8, 8);
+        record(&path, &canvas).unwrap();
+        canvas.set(0, 0, Cell { ch: blocks::FULL, fg: Some(RED), bg: None });
+        record(&path, &canvas).unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json[0]["canvas"]["cells"] = serde_json::json!([]);
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let versions = load(&path);
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].canvas, canvas);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
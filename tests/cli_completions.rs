@@ -0,0 +1,24 @@
+mod helpers;
+
+use helpers::*;
+
+#[test]
+fn completions_cover_subcommands() {
+    for shell in ["bash", "zsh", "fish"] {
+        let out = run_ok(kakukuma().args(["completions", shell]));
+        let script = String::from_utf8_lossy(&out.stdout);
+        assert!(script.contains("kakukuma"), "{} script names the binary", shell);
+        assert!(script.contains("thumb"), "{} script completes subcommands", shell);
+    }
+}
+
+#[test]
+fn completions_writes_man_page() {
+    let f = temp_file("man").with_extension("1");
+    let out = run_ok(kakukuma().args(["completions", "man", "--output", f.to_str().unwrap()]));
+    assert_eq!(stdout_json(&out)["written"], f.to_str().unwrap());
+    let page = std::fs::read_to_string(&f).unwrap();
+    assert!(page.contains(".TH kakukuma 1"));
+    assert!(page.contains("\\-\\-screen\\-reader"));
+    cleanup(&f);
+}
@@ -121,6 +121,43 @@ fn draw_fill() {
     cleanup(&f);
 }
 
+#[test]
+fn draw_fill_match_char_recolors_keeping_chars() {
+    let f = create_canvas("draw_fill_char");
+    let path = f.to_str().unwrap();
+    run_ok(kakukuma().args(["draw", "line", path, "0,0", "5,0", "--ch", "\u{2591}", "--color", "#FF0000"]));
+    run_ok(kakukuma().args(["draw", "pencil", path, "2,0", "--ch", "\u{2591}", "--color", "#00FF00"]));
+    run_ok(kakukuma().args(["draw", "pencil", path, "4,0", "--color", "#FF0000"]));
+    let out = run_ok(kakukuma().args([
+        "draw", "fill", path, "0,0", "--match", "char", "--color", "#0000FF",
+    ]));
+    // 0-3 share the shade; the full block at 4 stops the fill
+    assert_eq!(stdout_json(&out)["cells_modified"], 4);
+    let cell = stdout_json(&run_ok(kakukuma().args(["inspect", path, "2,0"])));
+    assert_eq!(cell["char"], "\u{2591}");
+    assert_eq!(cell["fg"], "#0000FF");
+
+    cleanup(&f);
+}
+
+#[test]
+fn draw_line_dashed_and_rect_dotted() {
+    let f = create_canvas("draw_dashed");
+    let path = f.to_str().unwrap();
+    let out = run_ok(kakukuma().args(["draw", "line", path, "0,0", "9,0", "--style", "dashed"]));
+    assert_eq!(stdout_json(&out)["cells_modified"], 6);
+    let out = run_ok(kakukuma().args([
+        "draw", "line", path, "0,1", "9,1", "--style", "dashed", "--pattern", "1,4",
+    ]));
+    assert_eq!(stdout_json(&out)["cells_modified"], 2);
+    let out = run_ok(kakukuma().args(["draw", "rect", path, "0,3", "3,6", "--style", "dotted"]));
+    assert_eq!(stdout_json(&out)["cells_modified"], 6);
+    let gap = stdout_json(&run_ok(kakukuma().args(["inspect", path, "1,3"])));
+    assert_eq!(gap["char"], " ");
+
+    cleanup(&f);
+}
+
 #[test]
 fn draw_eyedropper() {
     let f = create_canvas("draw_eye");
@@ -0,0 +1,235 @@
+mod helpers;
+
+use helpers::*;
+
+fn write_png(prefix: &str, img: &image::RgbaImage) -> std::path::PathBuf {
+    let path = temp_file(prefix).with_extension("png");
+    img.save(&path).unwrap();
+    path
+}
+
+/// An 8x8 Aseprite file with one layer, filled with one color per frame.
+fn write_ase(prefix: &str, frames: &[[u8; 4]]) -> std::path::PathBuf {
+    let chunk = |kind: u16, data: &[u8]| [&((data.len() + 6) as u32).to_le_bytes()[..], &kind.to_le_bytes(), data].concat();
+    let mut body = Vec::new();
+    for (i, color) in frames.iter().enumerate() {
+        let mut chunks = Vec::new();
+        if i == 0 {
+            // Visible normal layer, opaque, named "a"
+            let layer = [&[1, 0, 0, 0, 0, 0][..], &[0; 6], &[255, 0, 0, 0], &[1, 0, b'a']].concat();
+            chunks.push(chunk(0x2004, &layer));
+        }
+        // Layer 0 at (0, 0), opaque, raw pixels
+        let cel = [&[0, 0, 0, 0, 0, 0, 255][..], &[0, 0, 0, 0], &[0; 5], &[8, 0, 8, 0], &color.repeat(64)].concat();
+        chunks.push(chunk(0x2005, &cel));
+        let data = chunks.concat();
+        body.extend(((data.len() + 16) as u32).to_le_bytes());
+        body.extend([0xFA, 0xF1]);
+        body.extend((chunks.len() as u16).to_le_bytes());
+        body.extend([100, 0, 0, 0]);
+        body.extend((chunks.len() as u32).to_le_bytes());
+        body.extend(data);
+    }
+    let mut file = ((128 + body.len()) as u32).to_le_bytes().to_vec();
+    file.extend([0xE0, 0xA5]);
+    file.extend((frames.len() as u16).to_le_bytes());
+    file.extend([8, 0, 8, 0, 32, 0, 1, 0, 0, 0]);
+    file.resize(128, 0);
+    file.extend(body);
+    let path = temp_file(prefix).with_extension("ase");
+    std::fs::write(&path, file).unwrap();
+    path
+}
+
+#[test]
+fn import_png_fits_canvas() {
+    let img = image::RgbaImage::from_pixel(64, 64, image::Rgba([255, 0, 0, 255]));
+    let png = write_png("import_fit", &img);
+    let f = temp_file("import_fit");
+    let out = run_ok(kakukuma().args([
+        "import", png.to_str().unwrap(), f.to_str().unwrap(),
+        "--width", "16", "--height", "16",
+    ]));
+    let json = stdout_json(&out);
+    assert_eq!(json["width"], 16);
+    assert_eq!(json["height"], 8);
+    assert_eq!(json["mode"], "blocks");
+
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "0,0"]));
+    let cell = stdout_json(&out);
+    assert_eq!(cell["char"], "█");
+    cleanup(&f);
+    let _ = std::fs::remove_file(&png);
+}
+
+#[test]
+fn import_with_ramp_uses_palette_colors() {
+    let img = image::RgbaImage::from_pixel(16, 16, image::Rgba([20, 20, 20, 255]));
+    let png = write_png("import_ramp", &img);
+    let pal = temp_file("import_ramp").with_extension("palette");
+    std::fs::write(&pal, r#"{"name":"duo","colors":[[240,200,100],[40,0,80]]}"#).unwrap();
+    let f = temp_file("import_ramp");
+    let out = run_ok(kakukuma().args([
+        "import", png.to_str().unwrap(), f.to_str().unwrap(),
+        "--ramp", pal.to_str().unwrap(),
+    ]));
+    assert_eq!(stdout_json(&out)["ramp"], pal.to_str().unwrap());
+
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "0,0"]));
+    assert_eq!(stdout_json(&out)["fg"], "#280050");
+
+    // Dark, but bright enough for an ASCII character
+    let gray = image::RgbaImage::from_pixel(16, 16, image::Rgba([100, 100, 100, 255]));
+    let gray_png = write_png("import_ramp_ascii", &gray);
+    run_ok(kakukuma().args([
+        "import", gray_png.to_str().unwrap(), f.to_str().unwrap(),
+        "--ramp", pal.to_str().unwrap(), "--mode", "ascii", "--force",
+    ]));
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "0,0"]));
+    assert_eq!(stdout_json(&out)["char"], "-");
+    assert_eq!(stdout_json(&out)["fg"], "#280050");
+    let _ = std::fs::remove_file(&gray_png);
+
+    let out = kakukuma()
+        .args(["import", png.to_str().unwrap(), f.to_str().unwrap(), "--ramp", pal.to_str().unwrap(), "--mode", "edges", "--force"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    cleanup(&f);
+    let _ = std::fs::remove_file(&png);
+    let _ = std::fs::remove_file(&pal);
+}
+
+#[test]
+fn import_refuses_to_overwrite() {
+    let img = image::RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 255, 255]));
+    let png = write_png("import_exists", &img);
+    let f = temp_file("import_exists");
+    run_ok(kakukuma().args(["new", f.to_str().unwrap()]));
+    let out = kakukuma()
+        .args(["import", png.to_str().unwrap(), f.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    cleanup(&f);
+    let _ = std::fs::remove_file(&png);
+}
+
+#[test]
+fn import_edges_mode_draws_line_art() {
+    let mut img = image::RgbaImage::from_pixel(32, 32, image::Rgba([0, 0, 0, 255]));
+    for y in 0..32 {
+        for x in 16..32 {
+            img.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+        }
+    }
+    let png = write_png("import_edges", &img);
+    let f = temp_file("import_edges");
+    let out = run_ok(kakukuma().args([
+        "import", png.to_str().unwrap(), f.to_str().unwrap(),
+        "--width", "16", "--height", "16", "--mode", "edges",
+    ]));
+    assert_eq!(stdout_json(&out)["mode"], "edges");
+
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "8,4"]));
+    assert_eq!(stdout_json(&out)["char"], "│");
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "2,4"]));
+    assert_eq!(stdout_json(&out)["char"], " ");
+    cleanup(&f);
+    let _ = std::fs::remove_file(&png);
+}
+
+#[test]
+fn import_ascii_mode_plain_output() {
+    let img = image::RgbaImage::from_pixel(16, 16, image::Rgba([255, 255, 255, 255]));
+    let png = write_png("import_ascii", &img);
+    let f = temp_file("import_ascii");
+    run_ok(kakukuma().args([
+        "import", png.to_str().unwrap(), f.to_str().unwrap(), "--mode", "ascii",
+    ]));
+    let out = run_ok(kakukuma().args(["preview", f.to_str().unwrap(), "--format", "plain"]));
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.lines().next().unwrap().starts_with("@@@@"));
+    cleanup(&f);
+    let _ = std::fs::remove_file(&png);
+}
+
+#[test]
+fn import_best_mode_splits_cells() {
+    // One-pixel vertical stripes: half blocks can't show them, left/right halves can
+    let mut img = image::RgbaImage::from_pixel(32, 32, image::Rgba([0, 0, 0, 255]));
+    for y in 0..32 {
+        for x in (0..32).step_by(2) {
+            img.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+        }
+    }
+    let png = write_png("import_best", &img);
+    let f = temp_file("import_best");
+    run_ok(kakukuma().args([
+        "import", png.to_str().unwrap(), f.to_str().unwrap(),
+        "--width", "16", "--height", "16", "--mode", "best",
+    ]));
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "3,3"]));
+    let ch = stdout_json(&out)["char"].as_str().unwrap().to_string();
+    assert!(ch == "▌" || ch == "▐", "unexpected block {}", ch);
+    cleanup(&f);
+    let _ = std::fs::remove_file(&png);
+}
+
+#[test]
+fn import_frame_directory_writes_sequence() {
+    let dir = temp_file("import_frames").with_extension("d");
+    std::fs::create_dir_all(&dir).unwrap();
+    for (i, v) in [(1, 0u8), (2, 128), (10, 255)] {
+        let img = image::RgbaImage::from_pixel(8, 8, image::Rgba([v, v, v, 255]));
+        img.save(dir.join(format!("f{}.png", i))).unwrap();
+    }
+    let f = temp_file("import_frames");
+    let out = run_ok(kakukuma().args([
+        "import", dir.to_str().unwrap(), f.to_str().unwrap(), "--max-frames", "2",
+    ]));
+    let json = stdout_json(&out);
+    assert_eq!(json["frames"], 2);
+    let files: Vec<std::path::PathBuf> = json["files"].as_array().unwrap()
+        .iter().map(|v| std::path::PathBuf::from(v.as_str().unwrap())).collect();
+    assert!(files[0].to_str().unwrap().ends_with("-001.kaku"));
+    for file in &files {
+        assert!(file.exists());
+        cleanup(file);
+    }
+
+    let out = kakukuma()
+        .args(["import", dir.to_str().unwrap(), f.to_str().unwrap(), "--max-frames", "0"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--max-frames"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn import_aseprite_flattens_frames() {
+    let red = [255, 0, 0, 255];
+    let single = write_ase("import_ase_single", &[red]);
+    let f = temp_file("import_ase_single");
+    run_ok(kakukuma().args(["import", single.to_str().unwrap(), f.to_str().unwrap(), "--width", "8", "--height", "8"]));
+    let out = run_ok(kakukuma().args(["inspect", f.to_str().unwrap(), "0,0"]));
+    assert_eq!(stdout_json(&out)["fg"], "#FF0000");
+    cleanup(&f);
+    let _ = std::fs::remove_file(&single);
+
+    let animated = write_ase("import_ase_frames", &[red, [0, 0, 255, 255], [0, 255, 0, 255]]);
+    let f = temp_file("import_ase_frames");
+    let out = run_ok(kakukuma().args([
+        "import", animated.to_str().unwrap(), f.to_str().unwrap(), "--width", "8", "--height", "8", "--max-frames", "2",
+    ]));
+    let json = stdout_json(&out);
+    assert_eq!(json["frames"], 2);
+    let files: Vec<String> = json["files"].as_array().unwrap().iter().map(|v| v.as_str().unwrap().to_string()).collect();
+    let out = run_ok(kakukuma().args(["inspect", &files[1], "0,0"]));
+    assert_eq!(stdout_json(&out)["fg"], "#0000FF");
+    for file in &files {
+        cleanup(&std::path::PathBuf::from(file));
+    }
+    let _ = std::fs::remove_file(&animated);
+}
@@ -0,0 +1,40 @@
+mod helpers;
+
+use helpers::*;
+
+#[test]
+fn keys_prints_markdown_sheet() {
+    let out = run_ok(kakukuma().arg("keys"));
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.starts_with("# kakukuma keys"));
+    assert!(text.contains("| `Ctrl+S` | Save |"));
+    assert!(text.contains("| `1-0` | Quick pick |"));
+}
+
+#[test]
+fn keys_writes_text_sheet_to_file() {
+    let f = temp_file("keys").with_extension("txt");
+    let out = run_ok(kakukuma().args(["keys", "--format", "text", "--output", f.to_str().unwrap()]));
+    let json = stdout_json(&out);
+    assert_eq!(json["written"], f.to_str().unwrap());
+    let sheet = std::fs::read_to_string(&f).unwrap();
+    assert!(sheet.contains("Tools\n"));
+    assert!(sheet.lines().any(|l| l.trim_start().starts_with("Shift+B") && l.ends_with("Block picker")));
+    cleanup(&f);
+}
+
+// dirs only reads XDG_CONFIG_HOME on Linux
+#[cfg(target_os = "linux")]
+#[test]
+fn keys_includes_user_remaps() {
+    let config = temp_file("keys_config").with_extension("d");
+    std::fs::create_dir_all(config.join("kakukuma")).unwrap();
+    std::fs::write(
+        config.join("kakukuma").join("keys.json"),
+        r#"[{"key": "Ctrl+K", "command": "Save"}]"#,
+    ).unwrap();
+    let out = run_ok(kakukuma().arg("keys").env("XDG_CONFIG_HOME", &config));
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("| `Ctrl+K/Ctrl+S` | Save |"), "{}", text);
+    let _ = std::fs::remove_dir_all(&config);
+}
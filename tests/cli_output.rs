@@ -0,0 +1,47 @@
+mod helpers;
+
+use helpers::*;
+
+#[test]
+fn quiet_suppresses_status_output() {
+    let f = temp_file("out_quiet");
+    let out = run_ok(kakukuma().args(["new", f.to_str().unwrap(), "--quiet"]));
+    assert!(out.stdout.is_empty());
+    assert!(f.exists());
+    cleanup(&f);
+}
+
+#[test]
+fn json_reports_bytes_dimensions_and_colors() {
+    let f = temp_file("out_json");
+    let path = f.to_str().unwrap();
+    run_ok(kakukuma().args(["new", path, "--size", "16x8"]));
+    let out = run_ok(kakukuma().args([
+        "--json", "draw", "rect", path, "0,0", "3,3", "--fg", "#E5E5E5", "--bg", "#0000FF",
+    ]));
+    let json = stdout_json(&out);
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["width"], 16);
+    assert_eq!(json["height"], 8);
+    assert_eq!(json["colors_used"], 2);
+    assert_eq!(json["bytes_written"], std::fs::metadata(&f).unwrap().len());
+    cleanup(&f);
+}
+
+#[test]
+fn exit_codes_distinguish_missing_and_corrupt_files() {
+    let f = temp_file("out_codes");
+    let out = kakukuma().args(["stats", f.to_str().unwrap()]).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+
+    std::fs::write(&f, "not a project").unwrap();
+    let out = kakukuma().args(["stats", f.to_str().unwrap(), "--json"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(3));
+    let err: serde_json::Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(err["code"], 3);
+    assert!(err["error"].as_str().unwrap().starts_with("Failed to load"));
+
+    let out = kakukuma().args(["stats"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    cleanup(&f);
+}
@@ -0,0 +1,93 @@
+mod helpers;
+
+use helpers::*;
+
+#[test]
+fn palette_report_and_extract() {
+    let f = temp_file("pal_analyze");
+    let path = f.to_str().unwrap();
+    run_ok(kakukuma().args(["new", path, "--size", "16x8"]));
+    run_ok(kakukuma().args(["draw", "line", path, "0,0", "3,0", "--fg", "#FF0000"]));
+    run_ok(kakukuma().args(["draw", "pencil", path, "5,5", "--fg", "#00FF00", "--bg", "#FF0000"]));
+
+    let out = run_ok(kakukuma().args(["palette", path, "--report"]));
+    let json = stdout_json(&out);
+    assert_eq!(json["drawn_cells"], 5);
+    assert_eq!(json["count"], 2);
+    assert_eq!(json["colors"][0]["hex"], "#FF0000");
+    assert_eq!(json["colors"][0]["cells"], 5);
+    assert_eq!(json["colors"][0]["bg"], 1);
+
+    let pal = f.with_extension("palette");
+    let out = run_ok(kakukuma().args(["palette", path, "--extract", pal.to_str().unwrap()]));
+    assert_eq!(stdout_json(&out)["colors_extracted"], 2);
+    let data: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&pal).unwrap()).unwrap();
+    assert_eq!(data["colors"].as_array().unwrap().len(), 2);
+
+    let _ = std::fs::remove_file(&pal);
+    cleanup(&f);
+}
+
+#[test]
+fn palette_remove_undoes_add() {
+    let dir = std::env::temp_dir().join(format!("kaku_integ_pal_remove_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    run_ok(kakukuma().current_dir(&dir).args(["palette", "add", "warm", "#FF8800"]));
+    run_ok(kakukuma().current_dir(&dir).args(["palette", "add", "warm", "#112233"]));
+
+    let out = run_ok(kakukuma().current_dir(&dir).args(["--json", "palette", "remove", "warm", "#ff8800"]));
+    let json = stdout_json(&out);
+    assert_eq!(json["removed"], "#FF8800");
+    assert_eq!(json["total_colors"], 1);
+
+    let out = kakukuma().current_dir(&dir).args(["palette", "remove", "warm", "#FF8800"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn palette_share_and_import() {
+    let dir = std::env::temp_dir().join(format!("kaku_integ_pal_share_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    run_ok(kakukuma().current_dir(&dir).args(["palette", "import", "#FF8800, #112233", "--name", "warm"]));
+    let out = run_ok(kakukuma().current_dir(&dir).args(["palette", "share", "warm"]));
+    let share = stdout_json(&out)["share"].as_str().unwrap().to_string();
+    assert!(share.starts_with("kakupal:"));
+
+    // The string carries its name, so importing it again collides
+    let out = kakukuma().current_dir(&dir).args(["palette", "import", &share]).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let out = run_ok(kakukuma().current_dir(&dir).args(["--json", "palette", "import", &share, "--name", "copy"]));
+    assert_eq!(stdout_json(&out)["count"], 2);
+    let out = run_ok(kakukuma().current_dir(&dir).args(["palette", "show", "copy"]));
+    assert_eq!(stdout_json(&out)["colors"][1]["hex"], "#112233");
+
+    let out = kakukuma().current_dir(&dir).args(["palette", "import", "#FF8800"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn palette_lospec_rejects_path_names() {
+    for name in ["../escape", "a\\b", ""] {
+        let out = kakukuma().args(["palette", "lospec", "sweetie-16", "--name", name]).output().unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&out.stderr).contains("Invalid palette name"));
+    }
+}
+
+// dirs only reads XDG_CONFIG_HOME on Linux
+#[cfg(target_os = "linux")]
+#[test]
+fn palette_lospec_needs_network_opt_in() {
+    let config = temp_file("lospec_config").with_extension("d");
+    std::fs::create_dir_all(&config).unwrap();
+    let out = kakukuma()
+        .args(["palette", "lospec", "sweetie-16"])
+        .env("XDG_CONFIG_HOME", &config)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("allow_network"));
+    let _ = std::fs::remove_dir_all(&config);
+}
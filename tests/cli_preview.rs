@@ -55,3 +55,71 @@ fn preview_plain_non_empty() {
     assert!(!stdout.contains("\x1b["));
     cleanup(&f);
 }
+
+#[test]
+fn preview_html_has_inline_styles() {
+    let f = create_canvas_with_art("preview_html");
+    let out = run_ok(kakukuma().args(["preview", f.to_str().unwrap(), "--format", "html"]));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with("<pre"));
+    assert!(stdout.contains("<span style=\"color:#"));
+    assert!(!stdout.contains("\x1b["));
+    cleanup(&f);
+}
+
+#[test]
+fn thumb_writes_scaled_png() {
+    let f = create_canvas_with_art("thumb");
+    run_ok(kakukuma().args(["draw", "pencil", f.to_str().unwrap(), "8,5", "--color", "#00FF00"]));
+    let png = f.with_extension("png");
+    let out = run_ok(kakukuma().args([
+        "thumb", f.to_str().unwrap(), "-o", png.to_str().unwrap(), "--size", "64",
+    ]));
+    // Content spans 4x1 cells of 8x16 pixels, so the width is the long side
+    let json = stdout_json(&out);
+    assert_eq!(json["width"], 64);
+    assert_eq!(json["height"], 32);
+    let img = image::open(&png).unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (64, 32));
+    assert_eq!(img.get_pixel(2, 16).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(32, 16).0[3], 0, "empty cells stay transparent");
+    let _ = std::fs::remove_file(&png);
+    cleanup(&f);
+}
+
+#[test]
+fn thumb_aspect_sets_cell_shape() {
+    let f = create_canvas_with_art("thumb_aspect");
+    run_ok(kakukuma().args(["draw", "pencil", f.to_str().unwrap(), "8,5", "--color", "#00FF00"]));
+    let png = f.with_extension("png");
+    let out = run_ok(kakukuma().args([
+        "thumb", f.to_str().unwrap(), "-o", png.to_str().unwrap(), "--size", "64", "--aspect", "1:1",
+    ]));
+    // Square cells: 4x1 cells of 8x8 pixels
+    let json = stdout_json(&out);
+    assert_eq!(json["width"], 64);
+    assert_eq!(json["height"], 16);
+    let out = kakukuma().args(["thumb", f.to_str().unwrap(), "-o", "x.png", "--aspect", "1x2"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    let _ = std::fs::remove_file(&png);
+    cleanup(&f);
+}
+
+#[test]
+fn thumb_glyphs_draws_shade_pattern() {
+    let f = temp_file("thumb_glyphs");
+    run_ok(kakukuma().args(["new", f.to_str().unwrap(), "--size", "16x16"]));
+    run_ok(kakukuma().args([
+        "draw", "pencil", f.to_str().unwrap(), "0,0", "--ch", "\u{2592}", "--color", "#FF0000",
+    ]));
+    let png = f.with_extension("png");
+    // One 8x16 cell at its natural size
+    run_ok(kakukuma().args([
+        "thumb", f.to_str().unwrap(), "-o", png.to_str().unwrap(), "--size", "16", "--glyphs",
+    ]));
+    let img = image::open(&png).unwrap().to_rgba8();
+    assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(1, 0).0[3], 0, "gaps in the pattern stay transparent");
+    let _ = std::fs::remove_file(&png);
+    cleanup(&f);
+}
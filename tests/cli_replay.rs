@@ -0,0 +1,63 @@
+mod helpers;
+
+use helpers::*;
+
+#[test]
+fn replay_prints_final_canvas() {
+    let log = temp_file("replay").with_extension("json");
+    std::fs::write(&log, r#"{
+        "version": 1,
+        "file": null,
+        "commands": [
+            {"ms": 0, "cmd": {"SetColor": [255, 0, 0]}},
+            {"ms": 40, "cmd": {"Press": {"x": 0, "y": 0}}},
+            {"ms": 80, "cmd": {"Drag": {"x": 1, "y": 0}}},
+            {"ms": 90, "cmd": "Release"},
+            {"ms": 120, "cmd": "Save"}
+        ]
+    }"#).unwrap();
+
+    let out = run_ok(kakukuma().args(["--replay", log.to_str().unwrap()]));
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.starts_with("\x1b[38;2;255;0;0m██"), "unexpected output: {:?}", text);
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn replay_rejects_invalid_log() {
+    let log = temp_file("replay_bad").with_extension("json");
+    std::fs::write(&log, "{not json").unwrap();
+    let out = kakukuma()
+        .args(["--replay", log.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    let _ = std::fs::remove_file(&log);
+}
+
+#[test]
+fn replay_starts_from_recorded_file() {
+    let f = temp_file("replay_file");
+    let out = run_ok(kakukuma().args(["new", f.to_str().unwrap(), "--size", "8x8"]));
+    assert_eq!(stdout_json(&out)["width"], 8);
+    run_ok(kakukuma().args(["draw", "pencil", f.to_str().unwrap(), "0,0", "--color", "#00FF00"]));
+
+    let log = temp_file("replay_file").with_extension("json");
+    let json = serde_json::json!({
+        "version": 1,
+        "file": f.to_str().unwrap(),
+        "commands": [
+            {"ms": 0, "cmd": {"SelectTool": "Eraser"}},
+            {"ms": 10, "cmd": {"Press": {"x": 0, "y": 0}}},
+            {"ms": 20, "cmd": "Release"},
+            {"ms": 30, "cmd": "Undo"}
+        ]
+    });
+    std::fs::write(&log, json.to_string()).unwrap();
+
+    let out = run_ok(kakukuma().args(["--replay", log.to_str().unwrap()]));
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.starts_with("\x1b[38;2;0;255;0m█"), "unexpected output: {:?}", text);
+    cleanup(&f);
+    let _ = std::fs::remove_file(&log);
+}
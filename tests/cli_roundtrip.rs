@@ -66,3 +66,93 @@ fn roundtrip_draw_export_verify() {
     let _ = std::fs::remove_file(&export_path);
     cleanup(&f);
 }
+
+#[test]
+fn export_plain_double_width() {
+    let f = temp_file("rt_export_double");
+    run_ok(kakukuma().args(["new", f.to_str().unwrap(), "--width", "16", "--height", "16"]));
+    run_ok(kakukuma().args(["draw", "rect", f.to_str().unwrap(), "0,0", "2,1", "--color", "#FF0000"]));
+
+    let export_path = f.with_extension("txt");
+    let out = run_ok(kakukuma().args([
+        "--json", "export", f.to_str().unwrap(), "--output", export_path.to_str().unwrap(),
+        "--format", "plain", "--double-width",
+    ]));
+    assert_eq!(stdout_json(&out)["double_width"], true);
+    let content = std::fs::read_to_string(&export_path).unwrap();
+    assert!(content.lines().all(|line| line.chars().count() == 6), "{:?}", content);
+
+    let _ = std::fs::remove_file(&export_path);
+    cleanup(&f);
+}
+
+#[test]
+fn export_markdown_with_note() {
+    let f = temp_file("rt_export_markdown");
+    run_ok(kakukuma().args(["new", f.to_str().unwrap(), "--width", "16", "--height", "16"]));
+    run_ok(kakukuma().args(["draw", "rect", f.to_str().unwrap(), "0,0", "3,1", "--color", "#FF0000"]));
+
+    let export_path = f.with_extension("md");
+    let out = run_ok(kakukuma().args([
+        "--json", "export", f.to_str().unwrap(), "--output", export_path.to_str().unwrap(),
+        "--format", "markdown", "--note", "A box",
+    ]));
+    assert_eq!(stdout_json(&out)["format"], "markdown");
+    let content = std::fs::read_to_string(&export_path).unwrap();
+    assert!(content.starts_with("<!-- A box -->\n```text\n"));
+    assert!(content.ends_with("\n```\n"));
+    assert!(!content.contains('\x1b'));
+
+    let _ = std::fs::remove_file(&export_path);
+    cleanup(&f);
+}
+
+// dirs only reads XDG_CONFIG_HOME on Linux
+#[cfg(target_os = "linux")]
+#[test]
+fn export_reads_terminal_palette_only_for_16_colors() {
+    let config = temp_file("rt_export_settings").with_extension("d");
+    std::fs::create_dir_all(config.join("kakukuma")).unwrap();
+    std::fs::write(config.join("kakukuma").join("settings.json"), r#"{"terminal_palette": "nope"}"#).unwrap();
+    let f = temp_file("rt_export_settings");
+    run_ok(kakukuma().args(["new", f.to_str().unwrap()]));
+    let export_path = f.with_extension("ans");
+    let export = |depth: &str| {
+        kakukuma()
+            .args(["export", f.to_str().unwrap(), "--output", export_path.to_str().unwrap()])
+            .args(["--color-format", depth, "--budget", "0"])
+            .env("XDG_CONFIG_HOME", &config)
+            .output()
+            .unwrap()
+    };
+
+    assert_eq!(export("256").status.code(), Some(0));
+    let out = export("16");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Settings"));
+
+    let _ = std::fs::remove_file(&export_path);
+    let _ = std::fs::remove_dir_all(&config);
+    cleanup(&f);
+}
+
+#[test]
+fn export_reports_size_against_budget() {
+    let f = temp_file("rt_export_budget");
+    run_ok(kakukuma().args(["new", f.to_str().unwrap(), "--width", "16", "--height", "16"]));
+    run_ok(kakukuma().args(["draw", "rect", f.to_str().unwrap(), "0,0", "7,3", "--color", "#FF0000"]));
+
+    let export_path = f.with_extension("ans");
+    let out = run_ok(kakukuma().args([
+        "--json", "export", f.to_str().unwrap(), "--output", export_path.to_str().unwrap(), "--budget", "16",
+    ]));
+    let json = stdout_json(&out);
+    assert_eq!(json["lines"], 4);
+    assert_eq!(json["bytes_written"], std::fs::metadata(&export_path).unwrap().len());
+    assert!(json["escape_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(json["over_budget"], true);
+    assert!(json["summary"].as_str().unwrap().ends_with("over the 16 B budget"));
+
+    let _ = std::fs::remove_file(&export_path);
+    cleanup(&f);
+}
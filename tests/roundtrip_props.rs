@@ -0,0 +1,134 @@
+use kakukuma::canvas::Canvas;
+use kakukuma::cell::{blocks, color256_to_rgb, nearest_256, resolve_half_block, Cell, Rgb};
+use kakukuma::export::{self, AnsiOptions, ColorFormat};
+use kakukuma::import;
+use kakukuma::project::Project;
+use kakukuma::symmetry::SymmetryMode;
+use proptest::prelude::*;
+
+const CHARS: [char; 12] = [
+    ' ', blocks::FULL, blocks::UPPER_HALF, blocks::LOWER_HALF, blocks::LEFT_HALF,
+    blocks::RIGHT_HALF, blocks::SHADE_LIGHT, blocks::SHADE_MEDIUM, blocks::SHADE_DARK,
+    'a', '#', '@',
+];
+
+fn rgb() -> impl Strategy<Value = Rgb> {
+    any::<(u8, u8, u8)>().prop_map(|(r, g, b)| Rgb::new(r, g, b))
+}
+
+fn cell() -> impl Strategy<Value = Cell> {
+    (prop::sample::select(&CHARS[..]), prop::option::of(rgb()), prop::option::of(rgb()))
+        .prop_map(|(ch, fg, bg)| Cell { ch, fg, bg })
+}
+
+fn canvas(max: usize) -> impl Strategy<Value = Canvas> {
+    (8..=max, 8..=max)
+        .prop_flat_map(|(w, h)| (Just((w, h)), prop::collection::vec(cell(), w * h)))
+        .prop_map(|((w, h), cells)| {
+            let mut canvas = Canvas::new_with_size(w, h);
+            for (i, c) in cells.into_iter().enumerate() {
+                canvas.set(i % w, i / w, c);
+            }
+            canvas
+        })
+}
+
+fn symmetry() -> impl Strategy<Value = SymmetryMode> {
+    prop::sample::select(vec![
+        SymmetryMode::Off,
+        SymmetryMode::Horizontal,
+        SymmetryMode::Vertical,
+        SymmetryMode::Quad,
+    ])
+}
+
+/// What a cell should look like after export and re-import: half blocks in
+/// canonical form, blanks emptied, colors passed through `quantize`.
+fn expected(cell: Cell, quantize: fn(Rgb) -> Rgb) -> Cell {
+    if cell.is_empty() {
+        return Cell::default();
+    }
+    let (ch, fg, bg) = match resolve_half_block(&cell) {
+        Some(r) => (r.ch, r.fg, r.bg),
+        None => (cell.ch, cell.fg, cell.bg),
+    };
+    if ch == ' ' {
+        return Cell::default();
+    }
+    Cell { ch, fg: fg.map(quantize), bg: bg.map(quantize) }
+}
+
+fn bounds(canvas: &Canvas) -> Option<(usize, usize)> {
+    (0..canvas.height)
+        .flat_map(|y| (0..canvas.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| !canvas.get(x, y).unwrap().is_empty())
+        .fold(None, |acc: Option<(usize, usize)>, (x, y)| match acc {
+            Some((mx, my)) => Some((mx.min(x), my.min(y))),
+            None => Some((x, y)),
+        })
+}
+
+fn check_ansi_roundtrip(original: &Canvas, format: ColorFormat, quantize: fn(Rgb) -> Rgb) {
+    check_ansi_roundtrip_with(original, format, quantize, &AnsiOptions::default());
+}
+
+fn check_ansi_roundtrip_with(original: &Canvas, format: ColorFormat, quantize: fn(Rgb) -> Rgb, options: &AnsiOptions) {
+    let text = export::to_ansi_with(original, format, options);
+    let parsed = import::from_ansi(&text);
+    let Some((min_x, min_y)) = bounds(original) else {
+        assert!(text.is_empty());
+        return;
+    };
+    for y in min_y..original.height {
+        for x in min_x..original.width {
+            let want = expected(original.get(x, y).unwrap(), quantize);
+            let got = parsed.get(x - min_x, y - min_y).unwrap_or_default();
+            assert_eq!(got, want, "cell ({}, {})", x, y);
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn ansi_truecolor_roundtrip(original in canvas(32)) {
+        check_ansi_roundtrip(&original, ColorFormat::TrueColor, |c| c);
+    }
+
+    #[test]
+    fn ansi_cursor_forward_roundtrip(original in canvas(32)) {
+        let options = AnsiOptions { cursor_forward: true, ..Default::default() };
+        check_ansi_roundtrip_with(&original, ColorFormat::TrueColor, |c| c, &options);
+    }
+
+    #[test]
+    fn ansi_256_roundtrip_quantizes(original in canvas(32)) {
+        check_ansi_roundtrip(&original, ColorFormat::Color256, |c| color256_to_rgb(nearest_256(&c)));
+    }
+
+    #[test]
+    fn project_save_load_roundtrip(
+        name in "\\PC{0,24}",
+        canvas in canvas(24),
+        color in rgb(),
+        sym in symmetry(),
+        recent in prop::collection::vec(rgb(), 0..8),
+    ) {
+        let path = std::env::temp_dir().join(format!(
+            "kaku_prop_roundtrip_{}.kaku",
+            std::process::id()
+        ));
+        let mut project = Project::new(&name, canvas, color, sym);
+        project.recent_colors = recent;
+        project.save_to_file(&path).unwrap();
+        let loaded = Project::load_from_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        prop_assert_eq!(&loaded.name, &project.name);
+        prop_assert_eq!(loaded.color, project.color);
+        prop_assert_eq!(loaded.symmetry, project.symmetry);
+        prop_assert_eq!(&loaded.recent_colors, &project.recent_colors);
+        prop_assert_eq!(&loaded.canvas, &project.canvas);
+    }
+}
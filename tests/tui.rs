@@ -0,0 +1,970 @@
+//! End-to-end TUI tests: drive the app with key and mouse events and assert
+//! on what `ui::render` puts in a `TestBackend` buffer.
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use kakukuma::app::{App, AppMode};
+use kakukuma::cell::Rgb;
+use kakukuma::export::ColorFormat;
+use kakukuma::input::{self, CanvasArea};
+use kakukuma::settings::CursorStyle;
+use kakukuma::symmetry::SymmetryMode;
+use kakukuma::ui;
+use ratatui::backend::TestBackend;
+use ratatui::style::Color;
+use ratatui::Terminal;
+
+struct Harness {
+    terminal: Terminal<TestBackend>,
+    app: App,
+    area: CanvasArea,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self::with_size(120, 40)
+    }
+
+    fn with_size(width: u16, height: u16) -> Self {
+        let terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let area = CanvasArea { left: 0, top: 0, width: 0, height: 0, viewport_w: 0, viewport_h: 0, tiles: None, minimap: None };
+        let mut h = Harness { terminal, app: App::new(), area };
+        h.render();
+        h
+    }
+
+    fn render(&mut self) {
+        let app = &self.app;
+        let mut area = None;
+        self.terminal.draw(|f| area = Some(ui::render(f, app))).unwrap();
+        self.area = area.unwrap();
+        self.app.viewport_w = self.area.viewport_w;
+        self.app.viewport_h = self.area.viewport_h;
+    }
+
+    fn send(&mut self, event: Event) {
+        input::handle_event(&mut self.app, event, &self.area);
+        self.render();
+    }
+
+    /// Press a key. Upper case letters come with Shift, as crossterm reports
+    /// them.
+    fn key(&mut self, code: KeyCode) {
+        let shifted = matches!(code, KeyCode::Char(c) if c.is_ascii_uppercase());
+        let modifiers = if shifted { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+        self.send(Event::Key(KeyEvent::new(code, modifiers)));
+    }
+
+    fn ctrl(&mut self, c: char) {
+        self.send(Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)));
+    }
+
+    fn keys(&mut self, s: &str) {
+        for c in s.chars() {
+            self.key(KeyCode::Char(c));
+        }
+    }
+
+    fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) {
+        self.send(Event::Mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }));
+    }
+
+    /// Click the canvas cell at (x, y), at zoom 1 with no scrolling.
+    fn click_cell(&mut self, x: u16, y: u16) {
+        let (col, row) = (self.area.left + x, self.area.top + y);
+        self.mouse(MouseEventKind::Down(MouseButton::Left), col, row);
+        self.mouse(MouseEventKind::Up(MouseButton::Left), col, row);
+    }
+
+    /// Buffer cell on screen for canvas cell (x, y).
+    fn screen_cell(&self, x: u16, y: u16) -> &ratatui::buffer::Cell {
+        &self.terminal.backend().buffer()[(self.area.left + x, self.area.top + y)]
+    }
+
+    fn screen_text(&self) -> String {
+        let buf = self.terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buf.area.height {
+            for x in 0..buf.area.width {
+                text.push_str(buf[(x, y)].symbol());
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn assert_shows(&self, needle: &str) {
+        let text = self.screen_text();
+        assert!(text.contains(needle), "expected {:?} on screen:\n{}", needle, text);
+    }
+}
+
+#[test]
+fn startup_renders_editor() {
+    let h = Harness::new();
+    assert!(h.area.width > 0 && h.area.height > 0);
+    h.assert_shows("Pencil");
+}
+
+#[test]
+fn small_terminal_shows_resize_hint() {
+    let h = Harness::with_size(50, 16);
+    h.assert_shows("please resize your terminal!");
+}
+
+#[test]
+fn compact_layout_keeps_canvas_usable() {
+    let mut h = Harness::with_size(60, 20);
+    assert!(!h.screen_text().contains("please resize"));
+    assert!(!h.screen_text().contains("Tools"));
+    h.keys("e");
+    h.assert_shows("Eraser");
+    h.keys("p");
+    h.click_cell(2, 1);
+    assert!(!h.app.canvas.get(2, 1).unwrap().is_empty());
+}
+
+#[test]
+fn every_mode_fits_the_compact_layout() {
+    use kakukuma::export::{ColorMerge, DepthReport};
+    use kakukuma::palette::CustomPalette;
+    use kakukuma::update::Release;
+    use kakukuma::versions::Version;
+
+    const MODES: [AppMode; 29] = [
+        AppMode::Normal, AppMode::ExportDialog, AppMode::FileDialog, AppMode::FileRename,
+        AppMode::FileDeleteConfirm, AppMode::SaveAs, AppMode::ExportFile, AppMode::Help,
+        AppMode::Quitting, AppMode::RevertConfirm, AppMode::Recovery, AppMode::ColorSliders,
+        AppMode::PaletteDialog, AppMode::PaletteNameInput, AppMode::LospecSlugInput,
+        AppMode::ReleaseNotes, AppMode::PaletteRename, AppMode::PaletteExport, AppMode::NewCanvas,
+        AppMode::HexColorInput, AppMode::BlockPicker, AppMode::RunInput, AppMode::RectSizeInput,
+        AppMode::ImportDialog, AppMode::SymmetryPicker, AppMode::HistoryScrubber,
+        AppMode::Versions, AppMode::Inspector, AppMode::ResizeCanvas,
+    ];
+    for (width, height) in [(60, 20), (69, 21), (100, 20)] {
+        let mut h = Harness::with_size(width, height);
+        let names: Vec<String> = (0..30).map(|i| format!("project-{}.kaku", i)).collect();
+        h.app.file_dialog_files = names.clone();
+        h.app.file_dialog_selected = 29;
+        h.app.import_dialog_files = names.clone();
+        h.app.palette_dialog_files = names;
+        h.app.palette_dialog_selected = 29;
+        h.app.custom_palette = Some(CustomPalette { name: "warm".into(), colors: vec![Rgb::new(200, 80, 0)] });
+        h.app.versions = vec![Version::new(&h.app.canvas)];
+        h.app.available_update = Some(Release { version: "9.9.9".into(), notes: "New".into(), url: String::new() });
+        h.app.export_format = 1;
+        h.app.export_cursor = 2;
+        let merge = ColorMerge { color: Rgb::new(1, 2, 3), cells: 4, into: Rgb::new(0, 0, 0) };
+        h.app.export_analysis = Some(DepthReport { distinct: 9, limit: 16, inexact: 8, merges: vec![merge; 8] });
+        for mode in MODES {
+            h.app.mode = mode;
+            h.render();
+        }
+        // Lists and the focused export row scroll into view
+        for mode in [AppMode::FileDialog, AppMode::PaletteDialog] {
+            h.app.mode = mode;
+            h.render();
+            h.assert_shows("> project-29.kaku");
+        }
+        h.app.mode = AppMode::ExportDialog;
+        h.render();
+        h.assert_shows("Clipboard");
+    }
+}
+
+#[test]
+fn click_draws_pixel() {
+    let mut h = Harness::new();
+    h.click_cell(3, 2);
+    // Off the cell, so it shows without the cursor
+    h.mouse(MouseEventKind::Moved, 0, 0);
+    let cell = h.screen_cell(3, 2);
+    assert_eq!(cell.symbol(), "█");
+    assert_eq!(cell.fg, h.app.display_color(Rgb::WHITE));
+    assert!(h.app.dirty);
+}
+
+#[test]
+fn shift_wasd_and_middle_drag_pan_large_canvas() {
+    let mut h = Harness::new();
+    assert!(!h.screen_text().contains('\u{2503}'), "no scrollbar when the canvas fits");
+    h.app.canvas = kakukuma::canvas::Canvas::new_with_size(128, 128);
+    h.render();
+    h.keys("SD");
+    assert_eq!((h.app.viewport_x, h.app.viewport_y), (1, 1));
+    assert!(!h.app.canvas_cursor_active);
+    h.assert_shows("\u{2501}");
+    h.assert_shows("\u{2503}");
+
+    let (left, top) = (h.area.left + 10, h.area.top + 10);
+    h.mouse(MouseEventKind::Down(MouseButton::Middle), left, top);
+    h.mouse(MouseEventKind::Drag(MouseButton::Middle), left - 4, top - 2);
+    assert_eq!((h.app.viewport_x, h.app.viewport_y), (5, 3));
+    assert!(!h.app.dirty);
+}
+
+#[test]
+fn minimap_shows_for_large_canvas_and_toggles() {
+    let mut h = Harness::new();
+    let corner = |h: &Harness| {
+        let (x, y) = (h.area.left + h.area.width - 2, h.area.top + h.area.height - 2);
+        h.terminal.backend().buffer()[(x, y)].symbol().to_string()
+    };
+    h.app.canvas = kakukuma::canvas::Canvas::new_with_size(128, 128);
+    h.render();
+    assert_ne!(corner(&h), "\u{2580}", "off by default");
+    h.keys("o");
+    assert!(h.app.show_minimap);
+    h.app.canvas = kakukuma::canvas::Canvas::new();
+    h.render();
+    assert_ne!(corner(&h), "\u{2580}", "no minimap when the canvas fits");
+    h.app.canvas = kakukuma::canvas::Canvas::new_with_size(128, 128);
+    h.render();
+    assert_eq!(corner(&h), "\u{2580}");
+
+    // Clicking the minimap moves the view there and paints nothing
+    let (col, row) = (h.area.left + h.area.width - 2, h.area.top + h.area.height - 2);
+    h.mouse(MouseEventKind::Down(MouseButton::Left), col, row);
+    h.mouse(MouseEventKind::Up(MouseButton::Left), col, row);
+    assert_eq!(h.app.viewport_x, 128 - h.area.viewport_w);
+    assert_eq!(h.app.viewport_y, 128 - h.area.viewport_h);
+    assert!(h.app.canvas.get(127, 127).unwrap().is_empty());
+    assert!(!h.app.dirty);
+
+    h.keys("o");
+    assert!(!h.app.show_minimap);
+    assert_ne!(corner(&h), "\u{2580}");
+}
+
+#[test]
+fn wheel_zooms_around_pointer() {
+    let mut h = Harness::new();
+    h.app.canvas = kakukuma::canvas::Canvas::new_with_size(128, 128);
+    h.render();
+    h.mouse(MouseEventKind::ScrollUp, h.area.left + 40, h.area.top + 12);
+    assert_eq!(h.app.zoom, 2);
+    // Canvas cell 40 stays under the pointer, 20 double-width cells in
+    assert_eq!(h.app.viewport_x, 20);
+    h.mouse(MouseEventKind::Moved, h.area.left + 40, h.area.top + 12);
+    assert_eq!(h.app.cursor, Some((40, 12)));
+    h.mouse(MouseEventKind::ScrollDown, h.area.left + 40, h.area.top + 12);
+    assert_eq!((h.app.zoom, h.app.viewport_x), (1, 0));
+}
+
+#[test]
+fn inspector_edits_hovered_cell_exactly() {
+    let mut h = Harness::new();
+    h.mouse(MouseEventKind::Moved, h.area.left + 2, h.area.top + 1);
+    h.keys("C");
+    assert_eq!(h.app.mode, AppMode::Inspector);
+    h.assert_shows("Cell (2,1)");
+    h.assert_shows("U+0020");
+
+    for _ in 0..6 {
+        h.key(KeyCode::Backspace);
+    }
+    h.keys("U+2580");
+    h.key(KeyCode::Tab);
+    for _ in 0..7 {
+        h.key(KeyCode::Backspace);
+    }
+    h.keys("#12345");
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Inspector, "bad color keeps the dialog open");
+    h.assert_shows("Invalid color");
+    h.keys("6");
+    h.key(KeyCode::Tab);
+    h.keys("#0A0B0C");
+    h.key(KeyCode::Enter);
+
+    assert_eq!(h.app.mode, AppMode::Normal);
+    let cell = h.app.canvas.get(2, 1).unwrap();
+    assert_eq!(cell.ch, '\u{2580}');
+    assert_eq!(cell.fg, Some(Rgb::new(0x12, 0x34, 0x56)));
+    assert_eq!(cell.bg, Some(Rgb::new(0x0A, 0x0B, 0x0C)));
+    h.ctrl('z');
+    assert!(h.app.canvas.get(2, 1).unwrap().is_empty());
+}
+
+#[test]
+fn drag_draws_stroke_and_undo_removes_it() {
+    let mut h = Harness::new();
+    let (left, top) = (h.area.left, h.area.top);
+    h.mouse(MouseEventKind::Down(MouseButton::Left), left, top);
+    for x in 1..5 {
+        h.mouse(MouseEventKind::Drag(MouseButton::Left), left + x, top);
+    }
+    h.mouse(MouseEventKind::Up(MouseButton::Left), left + 4, top);
+    assert_eq!(h.screen_cell(4, 0).symbol(), "█");
+
+    h.ctrl('z');
+    // Move off the canvas so the pencil's ghost preview isn't drawn
+    h.mouse(MouseEventKind::Moved, 0, 0);
+    for x in 0..5 {
+        assert_ne!(h.screen_cell(x, 0).symbol(), "█");
+    }
+}
+
+#[test]
+fn pencil_hover_shows_dimmed_ghost_with_symmetry() {
+    let mut h = Harness::new();
+    h.app.color = Rgb::new(200, 100, 50);
+    h.keys("h");
+    let (col, row) = (h.area.left + 2, h.area.top + 3);
+    h.mouse(MouseEventKind::Moved, col, row);
+    let mirror = h.app.canvas.width as u16 - 1 - 2;
+    for x in [2, mirror] {
+        let cell = h.screen_cell(x, 3);
+        assert_eq!(cell.symbol(), "█");
+        assert_eq!(cell.fg, h.app.display_color(Rgb::new(120, 60, 30)));
+    }
+    assert!(h.app.canvas.get(2, 3).unwrap().is_empty(), "ghost must not paint");
+
+    h.app.settings.ghost_preview = false;
+    h.render();
+    assert_ne!(h.screen_cell(mirror, 3).symbol(), "█");
+}
+
+#[test]
+fn keyboard_cursor_draws() {
+    let mut h = Harness::new();
+    h.keys("dds ");
+    assert_eq!(h.app.canvas_cursor, (2, 1));
+    assert_eq!(h.screen_cell(2, 1).symbol(), "█");
+}
+
+#[test]
+fn eraser_tool_clears_pixel() {
+    let mut h = Harness::new();
+    h.click_cell(1, 1);
+    h.keys("e");
+    h.click_cell(1, 1);
+    assert_ne!(h.screen_cell(1, 1).symbol(), "█");
+}
+
+#[test]
+fn sixteen_color_terminal_quantizes_canvas() {
+    let mut h = Harness::new();
+    h.app.color_support = ColorFormat::Color16;
+    h.app.color = Rgb::new(250, 10, 10);
+    h.click_cell(0, 0);
+    // Off the cell, so it shows without the cursor
+    h.mouse(MouseEventKind::Moved, 0, 0);
+    assert_eq!(h.screen_cell(0, 0).fg, Color::Indexed(9));
+    h.assert_shows("16-color");
+
+    h.app.check_color_support();
+    h.render();
+    h.assert_shows("colors are approximated");
+}
+
+#[test]
+fn help_opens_and_any_key_closes() {
+    let mut h = Harness::new();
+    h.keys("?");
+    assert_eq!(h.app.mode, AppMode::Help);
+    h.assert_shows("Draw at cursor");
+    h.key(KeyCode::Esc);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert!(!h.screen_text().contains("Draw at cursor"));
+}
+
+#[test]
+fn help_scrolls_and_searches() {
+    let mut h = Harness::with_size(100, 36);
+    h.keys("?");
+    h.assert_shows("Rect fill/outline");
+    assert!(!h.screen_text().contains("Vertical mirror"), "help should need scrolling at 36 rows");
+    h.key(KeyCode::End);
+    h.assert_shows("Vertical mirror");
+    assert!(!h.screen_text().contains("Rect fill/outline"));
+
+    h.keys("/zoom");
+    h.assert_shows("Cycle zoom");
+    assert!(!h.screen_text().contains("Vertical mirror"));
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Help);
+    h.key(KeyCode::Esc);
+    assert_eq!(h.app.mode, AppMode::Normal);
+}
+
+#[test]
+fn screen_reader_mode_uses_plain_status_and_marker_cursor() {
+    let mut h = Harness::new();
+    h.app.screen_reader = true;
+    h.keys("dd");
+    assert_eq!(h.app.canvas_cursor, (2, 0));
+    let cell = h.screen_cell(2, 0);
+    assert_eq!(cell.symbol(), "+");
+    assert!(cell.modifier.contains(ratatui::style::Modifier::REVERSED));
+    h.keys("e");
+    h.assert_shows("Tool: Eraser. Tool Eraser");
+    h.assert_shows("Cursor 2,0");
+    assert!(!h.screen_text().contains("\u{2022} Tools"), "panel titles should be plain");
+    assert_eq!(h.terminal.get_cursor_position().unwrap().y, 39);
+}
+
+#[test]
+fn monochrome_mode_marks_axes_and_previews_with_characters() {
+    let mut h = Harness::new();
+    h.app.monochrome = true;
+    h.keys("h");
+    let axis = (h.app.canvas.width / 2) as u16;
+    assert_eq!(h.screen_cell(axis, 5).symbol(), "|");
+    h.assert_shows("[H]  v ");
+
+    h.keys("l");
+    h.click_cell(1, 1);
+    let (col, row) = (h.area.left + 4, h.area.top + 1);
+    h.mouse(MouseEventKind::Moved, col, row);
+    assert_eq!(h.screen_cell(2, 1).symbol(), "*");
+    assert_eq!(h.screen_cell(4, 1).symbol(), "+");
+}
+
+#[test]
+fn shape_preview_includes_mirrored_copies() {
+    let mut h = Harness::new();
+    h.app.monochrome = true;
+    h.keys("hr");
+    h.click_cell(1, 1);
+    let (col, row) = (h.area.left + 3, h.area.top + 3);
+    h.mouse(MouseEventKind::Moved, col, row);
+    let right = (h.app.canvas.width - 2) as u16;
+    assert_eq!(h.screen_cell(1, 1).symbol(), "*");
+    assert_eq!(h.screen_cell(right, 1).symbol(), "*");
+    assert_eq!(h.screen_cell(right - 2, 3).symbol(), "*");
+    assert_ne!(h.screen_cell(right - 1, 2).symbol(), "*", "outline only");
+}
+
+#[test]
+fn alt_click_paints_without_symmetry() {
+    let mut h = Harness::new();
+    h.keys("h");
+    let (col, row) = (h.area.left + 1, h.area.top + 1);
+    let down = MouseEventKind::Down(MouseButton::Left);
+    h.send(Event::Mouse(MouseEvent { kind: down, column: col, row, modifiers: KeyModifiers::ALT }));
+    h.mouse(MouseEventKind::Up(MouseButton::Left), col, row);
+    let mirror = h.app.canvas.width - 2;
+    assert!(!h.app.canvas.get(1, 1).unwrap().is_empty());
+    assert!(h.app.canvas.get(mirror, 1).unwrap().is_empty());
+
+    h.click_cell(1, 2);
+    assert!(!h.app.canvas.get(mirror, 2).unwrap().is_empty());
+}
+
+#[test]
+fn symmetry_picker_selects_radial_mode() {
+    let mut h = Harness::new();
+    h.keys("H");
+    h.assert_shows(" Symmetry ");
+    h.assert_shows("Rotate 4 ways around center");
+    for _ in 0..4 {
+        h.key(KeyCode::Down);
+    }
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert_eq!(h.app.symmetry, SymmetryMode::Radial4);
+    h.assert_shows("[Radial 4]");
+
+    h.click_cell(1, 1);
+    let (w, hgt) = (h.app.canvas.width, h.app.canvas.height);
+    assert!(!h.app.canvas.get(w - 2, hgt - 2).unwrap().is_empty());
+}
+
+#[test]
+fn history_scrubber_previews_and_jumps() {
+    let mut h = Harness::new();
+    h.keys("p");
+    for x in 1..=3 {
+        h.click_cell(x, 1);
+    }
+    h.keys("u");
+    h.assert_shows(" History ");
+    h.assert_shows("Step 3/3");
+    h.key(KeyCode::Left);
+    h.key(KeyCode::Left);
+    h.assert_shows("Step 1/3");
+    assert!(h.app.canvas.get(2, 1).unwrap().is_empty());
+    h.key(KeyCode::Esc);
+    assert!(!h.app.canvas.get(3, 1).unwrap().is_empty());
+    assert_eq!(h.app.history.position(), 3);
+
+    h.keys("u");
+    h.key(KeyCode::Home);
+    h.key(KeyCode::Right);
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert!(!h.app.canvas.get(1, 1).unwrap().is_empty());
+    assert!(h.app.canvas.get(2, 1).unwrap().is_empty());
+    // The rest is still there to redo
+    h.ctrl('y');
+    assert!(!h.app.canvas.get(2, 1).unwrap().is_empty());
+}
+
+#[test]
+fn versions_browser_restores_older_canvas() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_versions_{}.kaku", std::process::id()));
+    let versions_file = kakukuma::versions::versions_path(&path);
+    let _ = std::fs::remove_file(&versions_file);
+    let mut h = Harness::new();
+    h.app.project_path = Some(path.to_string_lossy().into_owned());
+    h.keys("U");
+    assert_eq!(h.app.mode, AppMode::Normal);
+
+    kakukuma::versions::record(&versions_file, &h.app.canvas).unwrap();
+    h.keys("p");
+    h.click_cell(2, 2);
+    kakukuma::versions::record(&versions_file, &h.app.canvas).unwrap();
+    h.click_cell(4, 2);
+
+    h.keys("U");
+    h.assert_shows(" Versions (2) ");
+    h.key(KeyCode::Down);
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert!(h.app.canvas.get(2, 2).unwrap().is_empty());
+    assert!(h.app.canvas.get(4, 2).unwrap().is_empty());
+    // Restoring is one undo step
+    h.ctrl('z');
+    assert!(!h.app.canvas.get(4, 2).unwrap().is_empty());
+    let _ = std::fs::remove_file(&versions_file);
+}
+
+#[test]
+fn export_dialog_writes_history_gif() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_history_{}.gif", std::process::id()));
+    let mut h = Harness::new();
+    h.ctrl('e');
+    h.keys("g");
+    assert_eq!(h.app.mode, AppMode::ExportDialog);
+
+    h.key(KeyCode::Esc);
+    h.keys("p");
+    h.click_cell(1, 1);
+    h.click_cell(2, 1);
+    h.ctrl('e');
+    h.assert_shows("Undo history as a GIF file (G)");
+    h.keys("g");
+    assert_eq!(h.app.mode, AppMode::ExportFile);
+    h.app.text_input = path.to_string_lossy().into_owned();
+    h.key(KeyCode::Enter);
+    h.assert_shows("Exported 2 steps");
+    assert!(std::fs::read(&path).unwrap().starts_with(b"GIF89a"));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn brush_pattern_paints_checkerboard() {
+    let mut h = Harness::new();
+    h.keys("pGGG");
+    assert_eq!(h.app.brush_pattern, kakukuma::tools::BrushPattern::Half);
+    h.assert_shows("Brush: 50%");
+    for x in 0..4 {
+        h.click_cell(x, 0);
+    }
+    let painted: Vec<bool> = (0..4).map(|x| !h.app.canvas.get(x, 0).unwrap().is_empty()).collect();
+    assert_eq!(painted, [true, false, true, false]);
+}
+
+#[test]
+fn f12_toggles_debug_overlay() {
+    let mut h = Harness::new();
+    h.keys("p");
+    h.click_cell(2, 2);
+    h.key(KeyCode::F(12));
+    h.assert_shows(" Debug ");
+    h.assert_shows("History 1 steps");
+    h.assert_shows("Canvas  48x32");
+    h.key(KeyCode::F(12));
+    assert!(!h.screen_text().contains(" Debug "));
+    assert!(h.app.keymap.sections_matching("debug").is_empty(), "hidden from help");
+}
+
+#[test]
+fn f2_toggles_mouse_capture() {
+    let mut h = Harness::new();
+    h.key(KeyCode::F(2));
+    assert!(!h.app.mouse_capture);
+    h.assert_shows("[mouse off]");
+    h.assert_shows("F2 to draw again");
+    h.key(KeyCode::F(2));
+    assert!(h.app.mouse_capture);
+    assert!(!h.screen_text().contains("[mouse off]"));
+}
+
+#[test]
+fn cursor_styles_from_settings() {
+    let mut h = Harness::new();
+    h.app.settings.cursor_style = CursorStyle::Brackets;
+    h.keys("dds");
+    assert_eq!(h.app.canvas_cursor, (2, 1));
+    // On the border, not over the neighboring cells
+    assert_eq!(h.screen_cell(1, 1).symbol(), " ");
+    assert_eq!(h.screen_cell(3, 1).symbol(), " ");
+    let buf = h.terminal.backend().buffer();
+    assert_eq!(buf[(h.area.left + 1, h.area.top - 1)].symbol(), "[");
+    assert_eq!(buf[(h.area.left + 3, h.area.top - 1)].symbol(), "]");
+    assert_eq!(buf[(h.area.left - 1, h.area.top + 1)].symbol(), "\u{25B6}");
+    // At 4x they fit inside the cell
+    h.app.zoom = 4;
+    h.render();
+    let buf = h.terminal.backend().buffer();
+    assert_eq!(buf[(h.area.left + 8, h.area.top + 2)].symbol(), "[");
+    assert_eq!(buf[(h.area.left + 11, h.area.top + 3)].symbol(), "]");
+    assert_eq!(buf[(h.area.left + 7, h.area.top + 2)].symbol(), " ");
+    h.app.zoom = 1;
+
+    h.app.settings.cursor_style = CursorStyle::Crosshair;
+    h.render();
+    assert_eq!(h.screen_cell(1, 1).symbol(), " ");
+    assert_eq!(h.screen_cell(2, 5).bg, h.app.theme().separator);
+    let buf = h.terminal.backend().buffer();
+    assert_eq!(buf[(h.area.left + 2, h.area.top - 1)].symbol(), "\u{252C}");
+    assert_eq!(buf[(h.area.left - 1, h.area.top + 1)].symbol(), "\u{251C}");
+
+    h.app.settings.cursor_style = CursorStyle::Inverse;
+    h.render();
+    let highlight = h.app.theme().highlight;
+    assert_eq!(h.screen_cell(2, 1).bg, highlight);
+}
+
+#[test]
+fn cursor_shows_on_full_blocks() {
+    let mut h = Harness::new();
+    h.app.color = Rgb::new(200, 40, 40);
+    h.keys("dds ");
+    assert!(!h.app.canvas.get(2, 1).unwrap().is_empty());
+    let highlight = h.app.theme().highlight;
+    for style in [CursorStyle::Swap, CursorStyle::Inverse, CursorStyle::Blink, CursorStyle::Crosshair] {
+        h.app.settings.cursor_style = style;
+        h.render();
+        let cell = h.screen_cell(2, 1);
+        assert_eq!((cell.fg, cell.bg), (highlight, highlight), "{:?}", style);
+    }
+
+    // Off full blocks, inverse and swap differ
+    h.keys("d");
+    h.app.settings.cursor_style = CursorStyle::Swap;
+    h.render();
+    let swapped = h.screen_cell(3, 1).clone();
+    h.app.settings.cursor_style = CursorStyle::Inverse;
+    h.render();
+    assert_ne!(h.screen_cell(3, 1), &swapped);
+}
+
+#[test]
+fn run_prompt_paints_a_column() {
+    let mut h = Harness::new();
+    h.keys("n");
+    h.assert_shows("Count and direction");
+    h.keys("4s");
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert_eq!(h.app.canvas_cursor, (0, 3));
+    for y in 0..4 {
+        assert!(!h.app.canvas.get(0, y).unwrap().is_empty());
+    }
+    h.keys("n3q");
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::RunInput);
+    h.assert_shows("Unknown direction");
+}
+
+#[test]
+fn select_active_block_highlights_and_erases() {
+    let mut h = Harness::new();
+    h.click_cell(3, 2);
+    h.click_cell(5, 4);
+    h.mouse(MouseEventKind::Moved, 0, 0);
+    h.keys("m");
+    h.assert_shows("Selected 2");
+    let cell = h.screen_cell(5, 4);
+    assert_eq!(cell.symbol(), "\u{2592}");
+    assert_eq!(cell.fg, h.app.theme().highlight);
+
+    h.key(KeyCode::Delete);
+    assert!(h.app.selection.is_none());
+    assert!(h.app.canvas.get(3, 2).unwrap().is_empty());
+    assert_ne!(h.screen_cell(5, 4).symbol(), "\u{2592}");
+}
+
+#[test]
+fn new_canvas_dialog_resizes_canvas() {
+    let mut h = Harness::new();
+    h.ctrl('n');
+    assert_eq!(h.app.mode, AppMode::NewCanvas);
+    h.key(KeyCode::Left);
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert_eq!(h.app.canvas.width, 40);
+    assert_eq!(h.area.width, 40);
+}
+
+#[test]
+fn resize_dialog_grows_canvas_around_anchor() {
+    let mut h = Harness::new();
+    let (w, height) = (h.app.canvas.width, h.app.canvas.height);
+    h.keys("R");
+    h.assert_shows(" Resize Canvas ");
+    h.assert_shows(&format!("Current: {}x{}", w, height));
+    h.key(KeyCode::Right);
+    h.key(KeyCode::Right);
+    h.keys("5");
+    h.assert_shows("center");
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert_eq!((h.app.canvas.width, h.app.canvas.height), (w + 2, height));
+    h.ctrl('z');
+    assert_eq!(h.app.canvas.width, w);
+}
+
+#[test]
+fn export_dialog_opens_and_cancels() {
+    let mut h = Harness::new();
+    h.ctrl('e');
+    h.assert_shows(" Export ");
+    // Colored output, 16 colors, then dithering on
+    h.key(KeyCode::Right);
+    h.key(KeyCode::Down);
+    h.key(KeyCode::Left);
+    h.keys("d");
+    h.assert_shows("Dither (D): on");
+    h.keys("a");
+    h.assert_shows("0 colors, all exact");
+    h.keys("bb");
+    h.assert_shows("Transparent (B): #E5E5E5");
+    h.key(KeyCode::Esc);
+    assert_eq!(h.app.mode, AppMode::Normal);
+}
+
+#[test]
+fn quit_with_unsaved_changes_prompts() {
+    let mut h = Harness::new();
+    h.click_cell(0, 0);
+    h.keys("q");
+    assert_eq!(h.app.mode, AppMode::Quitting);
+    h.assert_shows(" Quit ");
+    h.keys("n");
+    assert!(h.app.running);
+    h.keys("qy");
+    assert!(!h.app.running);
+}
+
+#[test]
+fn ctrl_r_reverts_to_saved_after_confirming() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_revert_{}.kaku", std::process::id()));
+    let mut h = Harness::new();
+    h.app.project_path = Some(path.to_string_lossy().into_owned());
+    h.click_cell(0, 0);
+    h.ctrl('s');
+    let saved = h.app.canvas.clone();
+
+    h.click_cell(3, 3);
+    h.ctrl('r');
+    assert_eq!(h.app.mode, AppMode::RevertConfirm);
+    h.assert_shows(" Revert ");
+    h.keys("n");
+    assert_ne!(h.app.canvas, saved);
+
+    h.ctrl('r');
+    h.keys("y");
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert_eq!(h.app.canvas, saved);
+    assert!(!h.app.dirty);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn palette_dialog_shows_swatches() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_swatch_{}.palette", std::process::id()));
+    let colors: Vec<Rgb> = (0..12).map(|i| Rgb::new(20 * i, 0, 255 - 20 * i)).collect();
+    let pal = kakukuma::palette::CustomPalette { name: "ramp".to_string(), colors: colors.clone() };
+    kakukuma::palette::save_palette(&pal, &path).unwrap();
+
+    let mut h = Harness::new();
+    h.app.mode = AppMode::PaletteDialog;
+    h.app.palette_dialog_files = vec![path.to_string_lossy().into_owned()];
+    h.render();
+
+    let buf = h.terminal.backend().buffer();
+    let shows = |c: Rgb| buf.content().iter().any(|cell| cell.symbol() == "█" && cell.fg == h.app.display_color(c));
+    assert!(shows(colors[0]));
+    assert!(shows(colors[7]));
+    assert!(!shows(colors[8]), "strip should stop at 8 colors");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn open_dialog_shows_project_header() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_info_{}.kaku", std::process::id()));
+    let canvas = kakukuma::canvas::Canvas::new_with_size(20, 12);
+    let mut project = kakukuma::project::Project::new(
+        "header-art", canvas, Rgb::WHITE, kakukuma::symmetry::SymmetryMode::Off,
+    );
+    project.save_to_file(&path).unwrap();
+
+    let mut h = Harness::new();
+    h.app.mode = AppMode::FileDialog;
+    h.app.file_dialog_files = vec![path.to_string_lossy().into_owned()];
+    h.render();
+    h.assert_shows("header-art");
+    h.assert_shows("20x12");
+    h.assert_shows("v5");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn tab_is_free_and_open_dialog_i_imports() {
+    let mut h = Harness::new();
+    h.key(KeyCode::Tab);
+    assert_eq!(h.app.mode, AppMode::Normal, "plain Tab doesn't open the import dialog");
+
+    h.app.mode = AppMode::FileDialog;
+    h.render();
+    h.assert_shows("I Import");
+    h.keys("i");
+    // The working directory decides whether there are images to list
+    let status = h.app.status_message.as_ref().map(|m| m.text.as_str());
+    assert!(h.app.mode == AppMode::ImportDialog || status == Some("No PNG or JPEG files found"));
+}
+
+#[test]
+fn import_dialog_draws_image_as_one_undo_step() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_import_{}.png", std::process::id()));
+    image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255])).save(&path).unwrap();
+
+    let mut h = Harness::new();
+    h.app.mode = AppMode::ImportDialog;
+    h.app.import_dialog_files = vec![path.to_string_lossy().into_owned()];
+    h.render();
+    h.assert_shows(" Import Image ");
+    h.assert_shows("Dithering: off");
+    h.keys("d");
+    h.assert_shows("Dithering: on");
+
+    h.key(KeyCode::Enter);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    let red = kakukuma::palette::nearest_color(255, 0, 0);
+    assert_eq!(h.app.canvas.get(0, 0).unwrap().fg, Some(red));
+    assert_eq!(h.app.canvas.get(h.app.canvas.width - 1, 0).unwrap().fg, Some(red));
+
+    h.ctrl('z');
+    assert!(h.app.canvas.get(0, 0).unwrap().is_empty());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn large_custom_palette_pages_in_colors_box() {
+    let colors: Vec<Rgb> = (0..30).map(|i| Rgb::new(8 * i, 100, 0)).collect();
+    let mut h = Harness::new();
+    h.app.custom_palette = Some(kakukuma::palette::CustomPalette { name: "big".to_string(), colors: colors.clone() });
+    h.app.rebuild_palette_layout();
+    h.render();
+    h.assert_shows("page 1/2");
+
+    h.key(KeyCode::PageDown);
+    h.assert_shows("page 2/2");
+    assert_eq!(h.app.palette_cursor, 24);
+    assert_eq!(h.app.color, colors[24]);
+
+    // Browsing back past the top of the page flips to the previous one
+    h.key(KeyCode::Up);
+    h.assert_shows("page 1/2");
+    h.key(KeyCode::PageUp);
+    assert_eq!(h.app.palette_cursor, 0);
+}
+
+#[test]
+fn open_dialog_t_opens_a_tab_and_ctrl_tab_cycles() {
+    let path = std::env::temp_dir().join(format!("kaku_tui_tabs_{}.kaku", std::process::id()));
+    let canvas = kakukuma::canvas::Canvas::new_with_size(20, 12);
+    kakukuma::project::Project::new("second", canvas, Rgb::WHITE, SymmetryMode::Off)
+        .save_to_file(&path)
+        .unwrap();
+
+    let mut h = Harness::new();
+    h.click_cell(0, 0);
+    h.app.mode = AppMode::FileDialog;
+    h.app.file_dialog_files = vec![path.to_string_lossy().into_owned()];
+    h.keys("t");
+    h.assert_shows("untitled* \u{2502} second");
+    assert_eq!(h.app.canvas.width, 20);
+
+    h.send(Event::Key(KeyEvent::new(KeyCode::Tab, KeyModifiers::CONTROL)));
+    assert_eq!(h.app.active_document, 0);
+    assert!(h.app.dirty);
+    h.assert_shows("Tab 1/2: untitled");
+
+    // Plain Tab is what Ctrl+Tab becomes without the kitty protocol
+    h.key(KeyCode::Tab);
+    assert_eq!(h.app.mode, AppMode::Normal);
+    assert_eq!(h.app.active_document, 0);
+    h.send(Event::Key(KeyEvent::new(KeyCode::PageDown, KeyModifiers::CONTROL)));
+    assert_eq!(h.app.active_document, 1);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn release_notes_show_after_update_check() {
+    let mut h = Harness::new();
+    h.keys("V");
+    h.assert_shows("Update checks are off");
+
+    let release = kakukuma::update::Release {
+        version: "9.9.9".to_string(),
+        notes: "- Tile preview\n- Faster export".to_string(),
+        url: "https://example.com/releases/9.9.9".to_string(),
+    };
+    h.app.update_check = Some(kakukuma::net::Pending::spawn(move || Ok(Some(release))));
+    while !h.app.tick_downloads() {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    assert!(h.app.update_check.is_none());
+    h.render();
+    h.assert_shows("kakukuma 9.9.9 is out (Shift+V for release notes)");
+
+    h.keys("V");
+    assert_eq!(h.app.mode, AppMode::ReleaseNotes);
+    h.assert_shows(" kakukuma 9.9.9 ");
+    h.assert_shows("- Faster export");
+    h.key(KeyCode::Esc);
+    assert_eq!(h.app.mode, AppMode::Normal);
+}
+
+#[test]
+fn ctrl_arrows_shift_canvas_with_wrap() {
+    let mut h = Harness::new();
+    h.app.color = Rgb::new(205, 0, 0);
+    h.click_cell(0, 0);
+    let drawn = h.app.canvas.get(0, 0);
+    h.send(Event::Key(KeyEvent::new(KeyCode::Left, KeyModifiers::CONTROL)));
+    h.assert_shows("Shifted canvas left, wrapping");
+    assert_eq!(h.app.canvas.get(h.app.canvas.width - 1, 0), drawn);
+    h.ctrl('z');
+    assert_eq!(h.app.canvas.get(0, 0), drawn);
+}
+
+#[test]
+fn tile_preview_repeats_canvas_and_draws_through_copies() {
+    let mut h = Harness::new();
+    h.app.canvas = kakukuma::canvas::Canvas::new_with_size(16, 8);
+    h.app.color = Rgb::new(205, 0, 0);
+    h.render();
+    h.keys("E");
+    h.assert_shows("Tile preview: On");
+    let zoom = h.app.zoom as u16;
+    let (w, rows) = (16 * zoom, if zoom == 4 { 16 } else { 8 });
+
+    h.click_cell(0, 0);
+    // Off the cell, so it shows without the cursor
+    h.mouse(MouseEventKind::Moved, 0, 0);
+    let drawn = h.screen_cell(0, 0).clone();
+    assert_eq!(h.screen_cell(w, 0), &drawn);
+    assert_eq!(h.screen_cell(0, rows), &drawn);
+
+    // The copy to the right maps back onto the canvas
+    h.click_cell(w + zoom, rows);
+    assert_eq!(h.app.canvas.get(1, 0), h.app.canvas.get(0, 0));
+
+    h.keys("E");
+    assert_ne!(h.screen_cell(w, 0), &drawn);
+}